@@ -0,0 +1,56 @@
+//! Pipelining benchmark.
+//!
+//! A simple client that connects to a mini-redis server and sets 1000 keys,
+//! first with one round trip per command, then with a single pipeline, and
+//! prints how long each took.
+//!
+//! You can test this out by running:
+//!
+//!     cargo run --release --bin mini-redis-server
+//!
+//! And then in another terminal run:
+//!
+//!     cargo run --release --example pipeline
+
+#![warn(rust_2018_idioms)]
+
+use mini_redis::{clients::Client, Result};
+use std::time::Instant;
+
+/// Number of `SET` commands sent by each run.
+const COUNT: usize = 1000;
+
+#[tokio::main]
+pub async fn main() -> Result<()> {
+    // Open a connection to the mini-redis address.
+    let mut client = Client::connect("127.0.0.1:6379").await?;
+
+    // Each command waits for the response to the previous one.
+    let start = Instant::now();
+    for i in 0..COUNT {
+        client
+            .set(&format!("sequential:{}", i), i.to_string().into())
+            .await?;
+    }
+    let sequential = start.elapsed();
+
+    // The commands are sent together, then the responses are read.
+    let start = Instant::now();
+    let mut pipeline = client.pipeline();
+    for i in 0..COUNT {
+        pipeline = pipeline.cmd(["SET".to_string(), format!("pipelined:{}", i), i.to_string()]);
+    }
+    let responses = pipeline.query().await?;
+    let pipelined = start.elapsed();
+
+    assert_eq!(COUNT, responses.len());
+
+    println!("{} sequential SETs: {:?}", COUNT, sequential);
+    println!("{} pipelined SETs:  {:?}", COUNT, pipelined);
+    println!(
+        "speedup: {:.1}x",
+        sequential.as_secs_f64() / pipelined.as_secs_f64()
+    );
+
+    Ok(())
+}
@@ -31,7 +31,8 @@ pub async fn main() -> Result<()> {
     if let Some(msg) = subscriber.next_message().await? {
         println!(
             "got message from the channel: {}; message = {:?}",
-            msg.channel, msg.content
+            String::from_utf8_lossy(&msg.channel),
+            msg.content
         );
     }
 
@@ -0,0 +1,455 @@
+//! Append-only file, logging every command which modifies keys.
+//!
+//! Enabled with `Config::appendonly`. Write commands are appended to the file
+//! as RESP frames, the way they were received, once applied. When the server
+//! starts, the file is replayed: each command is applied again, through the
+//! same path as commands received from clients, to rebuild the keyspace.
+//!
+//! A connection holds the log from before applying a write command until the
+//! command is appended, so commands are logged in the order they are applied.
+//! Commands which do not modify keys are not logged and do not wait for the
+//! log.
+//!
+//! Some commands are not logged as received, so that replaying them has the
+//! same effect:
+//!
+//! * Commands which replied with an error are not logged, they did not modify
+//!   anything.
+//! * `SPOP` pops random members. It is logged as `SREM` of the members
+//!   popped.
+//! * Expirations set by `EXPIRE`, or by `SET` with `EX` or `PX`, are relative
+//!   to when the command is applied. They are logged as `PEXPIREAT`, at the
+//!   time the key expires, after a `SET` without expiration. Replaying the
+//!   file does not extend them, and keys which expired while the server was
+//!   stopped are not brought back. `PEXPIREAT` is only understood by the
+//!   replay, clients cannot send it.
+//! * Elements popped by `BLPOP`, including elements handed to a client while
+//!   it was blocked, are logged as a `BLPOP` on the list at the time they are
+//!   popped.
+//! * The commands queued in a transaction are logged individually, without
+//!   `MULTI` and `EXEC`.
+//! * Keys evicted to make room under `Config::maxmemory` are logged as
+//!   `DEL`, after the command which evicted them.
+//! * `SELECT` is written whenever a command is applied to another database
+//!   than the previous one.
+//!
+//! Keys purged once expired are not logged, replaying the expiration removes
+//! them again.
+
+use crate::cmd::Set;
+use crate::{AppendFsync, Command, Connection, Db, Frame, Parse, Shutdown};
+
+use bytes::Bytes;
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::{broadcast, Mutex, MutexGuard, Notify};
+use tokio::time::{self, Duration};
+use tracing::error;
+
+/// Timeout of the `BLPOP` written when an element is popped from a list.
+///
+/// The list is never empty when the file is replayed in order. The timeout
+/// only keeps a damaged file from blocking the replay forever.
+const POP_TIMEOUT: &str = "1";
+
+/// An open append-only file.
+#[derive(Debug)]
+pub(crate) struct Aof {
+    log: Arc<Mutex<Log>>,
+}
+
+/// Appends frames to the file. Held while a command is applied and logged.
+#[derive(Debug)]
+pub(crate) struct Log {
+    /// The file, written through a `Connection` to encode the frames.
+    file: Connection,
+
+    /// Another handle to the file, used to sync it to disk.
+    sync: File,
+
+    /// When the file is synced to disk.
+    fsync: AppendFsync,
+
+    /// Database selected by the last `SELECT` written to the file, `None`
+    /// until one is written.
+    database: Option<usize>,
+}
+
+/// The socket of the connection replaying the file. Nothing is read from it,
+/// and the replies written to it are discarded.
+#[derive(Debug)]
+struct Discard;
+
+impl Aof {
+    /// Open the append-only file at `path`, creating it if needed.
+    ///
+    /// With `AppendFsync::EverySec`, a background task syncing the file to
+    /// disk is spawned. It runs until the `Aof` is dropped.
+    pub(crate) async fn open(path: &Path, fsync: AppendFsync) -> io::Result<Aof> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        let sync = file.try_clone().await?;
+
+        let log = Arc::new(Mutex::new(Log {
+            file: Connection::new(file),
+            sync,
+            fsync,
+            database: None,
+        }));
+
+        if fsync == AppendFsync::EverySec {
+            tokio::spawn(sync_every_second(Arc::downgrade(&log)));
+        }
+
+        Ok(Aof { log })
+    }
+
+    /// Waits for the log to be available, and returns it.
+    ///
+    /// No other command is logged until the returned guard is dropped. Lists
+    /// may be modified outside of any logged command, when an element handed
+    /// to a blocked client which went away is put back. Those writes are
+    /// logged first.
+    pub(crate) async fn lock(&self, db: &Db) -> io::Result<MutexGuard<'_, Log>> {
+        let mut log = self.log.lock().await;
+        log.feed_implicit_writes(db).await?;
+        Ok(log)
+    }
+
+    /// Log the pending implicit writes to `db`, and sync the file to disk
+    /// whatever the fsync policy. Called once no client is left.
+    pub(crate) async fn close(&self, db: &Db) -> io::Result<()> {
+        let mut log = self.lock(db).await?;
+        log.file.flush().await?;
+        log.sync.sync_data().await
+    }
+}
+
+impl Log {
+    /// Log `frame`, a write command applied to `db` which replied `reply`.
+    ///
+    /// The implicit writes made while the command was applied, such as
+    /// elements popped by clients blocked on a list, or keys evicted, are
+    /// logged after it.
+    pub(crate) async fn append(
+        &mut self,
+        db: &Db,
+        frame: Frame,
+        reply: Option<&Frame>,
+    ) -> io::Result<()> {
+        for frame in entries(frame, reply) {
+            self.feed(db.index(), &frame).await?;
+        }
+
+        self.feed_implicit_writes(db).await?;
+        self.flush().await
+    }
+
+    /// Log the implicit writes made since the last command was logged, see
+    /// `Db::take_implicit_writes`.
+    pub(crate) async fn append_implicit_writes(&mut self, db: &Db) -> io::Result<()> {
+        self.feed_implicit_writes(db).await?;
+        self.flush().await
+    }
+
+    async fn feed_implicit_writes(&mut self, db: &Db) -> io::Result<()> {
+        for (index, frame) in db.take_implicit_writes() {
+            self.feed(index, &frame).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Write `frame`, applied to the database at `index`, to the buffer.
+    async fn feed(&mut self, index: usize, frame: &Frame) -> io::Result<()> {
+        if self.database != Some(index) {
+            let mut select = Frame::array();
+            select.push_bulk(Bytes::from("SELECT".as_bytes()));
+            select.push_bulk(Bytes::from(index.to_string()));
+            self.file.feed_frame(&select).await?;
+            self.database = Some(index);
+        }
+
+        self.file.feed_frame(frame).await
+    }
+
+    /// Write the buffered frames to the file, and sync it to disk if the
+    /// policy asks to.
+    async fn flush(&mut self) -> io::Result<()> {
+        self.file.flush().await?;
+
+        if self.fsync == AppendFsync::Always {
+            self.sync.sync_data().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the frame logging `BLPOP` popping the first element of the list
+/// stored at `key`.
+pub(crate) fn pop_frame(key: &str) -> Frame {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from("BLPOP".as_bytes()));
+    frame.push_bulk(Bytes::from(key.to_string()));
+    frame.push_bulk(Bytes::from(POP_TIMEOUT.as_bytes()));
+    frame
+}
+
+/// Returns the frame logging `key` being evicted.
+pub(crate) fn del_frame(key: &str) -> Frame {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from("DEL".as_bytes()));
+    frame.push_bulk(Bytes::from(key.to_string()));
+    frame
+}
+
+/// Returns the frame logging `value` being put back at the head of the list
+/// stored at `key`.
+pub(crate) fn push_front_frame(key: &str, value: Bytes) -> Frame {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from("LPUSH".as_bytes()));
+    frame.push_bulk(Bytes::from(key.to_string()));
+    frame.push_bulk(value);
+    frame
+}
+
+/// Apply the commands of the append-only file at `path` to `db`.
+///
+/// Returns the number of commands applied. A missing file holds no commands.
+/// Fails if the file is truncated or holds an invalid command. Errors replied
+/// by the commands are ignored.
+pub(crate) async fn replay(db: &Db, path: &Path) -> crate::Result<u64> {
+    let file = match File::open(path).await {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut src = Connection::new(file);
+    let mut dst = Connection::new(Discard);
+
+    // The replay is never interrupted, the sender is kept until it is done.
+    let (_notify, rx) = broadcast::channel(1);
+    let mut shutdown = Shutdown::new(rx, Arc::new(Notify::new()));
+
+    let mut count = 0;
+
+    loop {
+        let frame = match src.read_frame().await {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break,
+            Err(err) => {
+                // A frame cut short, because the server stopped while writing
+                // it, is reported as a connection reset.
+                let truncated = err
+                    .downcast_ref::<io::Error>()
+                    .is_some_and(|err| err.kind() == io::ErrorKind::ConnectionReset);
+
+                if truncated {
+                    return Err("append-only file ends with a truncated command".into());
+                }
+
+                return Err(err);
+            }
+        };
+
+        // `PEXPIREAT` is not a command, it is only written to the file, see
+        // `absolute_expiration`.
+        if Command::peek_name(&frame)? == "pexpireat" {
+            expire_at(&db.select(dst.database()), frame)?;
+            count += 1;
+            continue;
+        }
+
+        let cmd = Command::from_frame(frame)?;
+
+        // Transactions are handled as by the server, although the commands
+        // of a transaction are not logged within one.
+        if let Some(transaction) = dst.transaction_mut() {
+            if !matches!(
+                cmd,
+                Command::Multi(_) | Command::Exec(_) | Command::Discard(_)
+            ) {
+                transaction.queue(cmd, None);
+                continue;
+            }
+        }
+
+        let db = db.select(dst.database());
+        cmd.apply(&db, &mut dst, &mut shutdown, 0).await?;
+
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Returns the frames to log for the write command `frame`, which replied
+/// `reply`. Nothing is logged for commands which failed.
+fn entries(frame: Frame, reply: Option<&Frame>) -> Vec<Frame> {
+    if let Some(Frame::Error(_)) = reply {
+        return vec![];
+    }
+
+    let name = match Command::peek_name(&frame) {
+        Ok(name) => name,
+        Err(_) => return vec![],
+    };
+
+    match &name[..] {
+        "spop" => reply
+            .and_then(|reply| srem_entry(frame, reply))
+            .into_iter()
+            .collect(),
+        "set" | "expire" => absolute_expiration(frame),
+        _ => vec![frame],
+    }
+}
+
+/// Returns the frame logging `SPOP` as `SREM` of the members it replied, or
+/// `None` if it popped nothing.
+fn srem_entry(frame: Frame, reply: &Frame) -> Option<Frame> {
+    let key = match &frame {
+        Frame::Array(parts) => parts.get(1)?.clone(),
+        _ => return None,
+    };
+
+    let members = match reply {
+        Frame::Bulk(member) => vec![member.clone()],
+        Frame::Array(members) => members
+            .iter()
+            .filter_map(|member| match member {
+                Frame::Bulk(member) => Some(member.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+
+    if members.is_empty() {
+        return None;
+    }
+
+    let mut srem = vec![Frame::Bulk(Bytes::from("SREM".as_bytes())), key];
+    srem.extend(members.into_iter().map(Frame::Bulk));
+
+    Some(Frame::Array(srem))
+}
+
+/// Returns the frames logging `frame`, a `SET` or `EXPIRE` command, with the
+/// expiration it sets converted to a `PEXPIREAT` at the time the key expires.
+fn absolute_expiration(frame: Frame) -> Vec<Frame> {
+    match Command::from_frame(frame.clone()) {
+        Ok(Command::Set(set)) => match set.expire() {
+            Some(ttl) => {
+                let key = set.key().to_string();
+                let set = Set::new(&key, set.value().clone(), None).into_frame();
+                vec![set, expire_at_frame(&key, ttl)]
+            }
+            None => vec![frame],
+        },
+        // A timeout which is not positive deletes the key, whenever it is
+        // replayed.
+        Ok(Command::Expire(expire)) if expire.seconds() > 0 => {
+            let ttl = Duration::from_secs(expire.seconds() as u64);
+            vec![expire_at_frame(expire.key(), ttl)]
+        }
+        _ => vec![frame],
+    }
+}
+
+/// Returns the `PEXPIREAT` frame logging `key` expiring after `ttl`.
+fn expire_at_frame(key: &str, ttl: Duration) -> Frame {
+    let millis = (SystemTime::now() + ttl)
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from("PEXPIREAT".as_bytes()));
+    frame.push_bulk(Bytes::from(key.to_string()));
+    frame.push_bulk(Bytes::from(millis.to_string()));
+    frame
+}
+
+/// Replay `frame`, a `PEXPIREAT` written by `expire_at_frame`: make the key
+/// expire at the logged time, or delete it if that time passed.
+fn expire_at(db: &Db, frame: Frame) -> crate::Result<()> {
+    let mut parse = Parse::new(frame)?;
+    parse.next_string()?;
+    let key = parse.next_string()?;
+    let millis = parse.next_int()?;
+    parse.finish()?;
+
+    let when = UNIX_EPOCH + Duration::from_millis(millis);
+
+    match when.duration_since(SystemTime::now()) {
+        Ok(ttl) if !ttl.is_zero() => {
+            db.expire(&key, ttl);
+        }
+        _ => {
+            db.del(&[key]);
+        }
+    }
+
+    Ok(())
+}
+
+/// Sync the file to disk every second, until the `Aof` is dropped.
+async fn sync_every_second(log: Weak<Mutex<Log>>) {
+    let mut interval = time::interval(Duration::from_secs(1));
+
+    loop {
+        interval.tick().await;
+
+        let log = match log.upgrade() {
+            Some(log) => log,
+            None => return,
+        };
+
+        // The data was written to the file already, a failure only means it
+        // is not on disk yet.
+        let sync = log.lock().await.sync.try_clone().await;
+        if let Err(err) = async { sync?.sync_data().await }.await {
+            error!(cause = %err, "failed to sync the append-only file");
+        }
+    }
+}
+
+impl AsyncRead for Discard {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for Discard {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -1,4 +1,8 @@
-use mini_redis::{clients::Client, DEFAULT_PORT};
+use mini_redis::{
+    clients::Client,
+    cmd::{Aggregate, ScoreBound},
+    DEFAULT_PORT,
+};
 
 use bytes::Bytes;
 use clap::{Parser, Subcommand};
@@ -23,6 +27,10 @@ struct Cli {
 
     #[clap(long, default_value_t = DEFAULT_PORT)]
     port: u16,
+
+    /// Password to authenticate with before issuing the command
+    #[clap(long)]
+    password: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -53,7 +61,8 @@ enum Command {
     ///  Publisher to send a message to a specific channel.
     Publish {
         /// Name of channel
-        channel: String,
+        #[clap(value_parser = bytes_from_str)]
+        channel: Bytes,
 
         #[clap(value_parser = bytes_from_str)]
         /// Message to publish
@@ -62,7 +71,8 @@ enum Command {
     /// Subscribe a client to a specific channel or channels.
     Subscribe {
         /// Specific channel or channels
-        channels: Vec<String>,
+        #[clap(value_parser = bytes_from_str)]
+        channels: Vec<Bytes>,
     },
 
     /// HSET key field value [field value ...]
@@ -97,6 +107,657 @@ enum Command {
         /// Name of key to set
         key: String,
     },
+
+    /// Remove one or more fields from a hash.
+    HDel {
+        /// Name of the hash
+        key: String,
+
+        /// Names of the fields to remove
+        #[clap(required = true)]
+        fields: Vec<String>,
+    },
+
+    /// Check whether a hash has a field.
+    HExists {
+        /// Name of the hash
+        key: String,
+
+        /// Name of the field to check
+        field: String,
+    },
+
+    /// Get the number of fields in a hash.
+    HLen {
+        /// Name of the hash
+        key: String,
+    },
+
+    /// Get the field names of a hash.
+    HKeys {
+        /// Name of the hash
+        key: String,
+    },
+
+    /// Get the values of a hash.
+    HVals {
+        /// Name of the hash
+        key: String,
+    },
+
+    /// Get the values of several fields of a hash.
+    HMGet {
+        /// Name of the hash
+        key: String,
+
+        /// Names of the fields to get
+        #[clap(required = true)]
+        fields: Vec<String>,
+    },
+
+    /// Increment the integer stored in a field of a hash.
+    HIncrBy {
+        /// Name of the hash
+        key: String,
+
+        /// Name of the field to increment
+        field: String,
+
+        /// Amount added to the value
+        #[clap(allow_hyphen_values = true)]
+        delta: i64,
+    },
+
+    /// Set a field of a hash, only if the field does not exist.
+    HSetNx {
+        /// Name of the hash
+        key: String,
+
+        /// Name of the field to set
+        field: String,
+
+        /// Value to set.
+        #[clap(value_parser = bytes_from_str)]
+        value: Bytes,
+    },
+
+    /// Remove one or more keys.
+    Del {
+        /// Names of the keys to remove
+        #[clap(required = true)]
+        keys: Vec<String>,
+    },
+
+    /// Count how many of the keys exist.
+    Exists {
+        /// Names of the keys to check
+        #[clap(required = true)]
+        keys: Vec<String>,
+    },
+
+    /// Increment the integer value of a key by one.
+    Incr {
+        /// Name of the key to increment
+        key: String,
+    },
+
+    /// Decrement the integer value of a key by one.
+    Decr {
+        /// Name of the key to decrement
+        key: String,
+    },
+
+    /// Increment the integer value of a key by the given amount.
+    IncrBy {
+        /// Name of the key to increment
+        key: String,
+
+        /// Amount to add, may be negative
+        #[clap(allow_hyphen_values = true)]
+        delta: i64,
+    },
+
+    /// Decrement the integer value of a key by the given amount.
+    DecrBy {
+        /// Name of the key to decrement
+        key: String,
+
+        /// Amount to subtract, may be negative
+        #[clap(allow_hyphen_values = true)]
+        delta: i64,
+    },
+
+    /// Get the internal encoding of the value of a key.
+    ObjectEncoding {
+        /// Name of the key to inspect
+        key: String,
+    },
+
+    /// Get the remaining time to live of a key, in seconds.
+    Ttl {
+        /// Name of the key to inspect
+        key: String,
+    },
+
+    /// Get the remaining time to live of a key, in milliseconds.
+    Pttl {
+        /// Name of the key to inspect
+        key: String,
+    },
+
+    /// Set a timeout on a key, in seconds.
+    Expire {
+        /// Name of the key to expire
+        key: String,
+
+        /// Seconds after which the key expires
+        #[clap(allow_hyphen_values = true)]
+        seconds: i64,
+    },
+
+    /// Remove the timeout of a key.
+    Persist {
+        /// Name of the key to persist
+        key: String,
+    },
+
+    /// Get the values of several keys.
+    Mget {
+        /// Names of the keys to get
+        #[clap(required = true)]
+        keys: Vec<String>,
+    },
+
+    /// Set several keys to their respective values.
+    Mset {
+        /// Alternating keys and values
+        #[clap(required = true)]
+        pairs: Vec<String>,
+    },
+
+    /// Append a value to the string stored at a key.
+    Append {
+        /// Name of the key to append to
+        key: String,
+
+        /// Value to append
+        #[clap(value_parser = bytes_from_str)]
+        value: Bytes,
+    },
+
+    /// Get the length of the string stored at a key.
+    Strlen {
+        /// Name of the key to inspect
+        key: String,
+    },
+
+    /// Get the number of keys in the database.
+    Dbsize,
+
+    /// Set a key to hold a value and get the value it held.
+    Getset {
+        /// Name of key to set
+        key: String,
+
+        /// Value to set.
+        #[clap(value_parser = bytes_from_str)]
+        value: Bytes,
+    },
+
+    /// Set a key to hold a value, only if the key does not exist.
+    Setnx {
+        /// Name of key to set
+        key: String,
+
+        /// Value to set.
+        #[clap(value_parser = bytes_from_str)]
+        value: Bytes,
+    },
+
+    /// Get the type of the value stored at a key.
+    Type {
+        /// Name of the key to inspect
+        key: String,
+    },
+
+    /// Insert values at the head of a list.
+    LPush {
+        /// Name of the list
+        key: String,
+
+        /// Values to insert
+        #[clap(required = true, value_parser = bytes_from_str)]
+        values: Vec<Bytes>,
+    },
+
+    /// Insert a value at the head of a list and keep its first elements only.
+    LPushCapped {
+        /// Name of the list
+        key: String,
+
+        /// Value to insert
+        #[clap(value_parser = bytes_from_str)]
+        value: Bytes,
+
+        /// Number of elements to keep
+        maxlen: u64,
+    },
+
+    /// Insert values at the tail of a list.
+    RPush {
+        /// Name of the list
+        key: String,
+
+        /// Values to insert
+        #[clap(required = true, value_parser = bytes_from_str)]
+        values: Vec<Bytes>,
+    },
+
+    /// Get a range of elements of a list.
+    LRange {
+        /// Name of the list
+        key: String,
+
+        /// Offset of the first element, negative offsets count from the end
+        #[clap(allow_hyphen_values = true)]
+        start: i64,
+
+        /// Offset of the last element, negative offsets count from the end
+        #[clap(allow_hyphen_values = true)]
+        stop: i64,
+    },
+
+    /// Get the length of a list.
+    LLen {
+        /// Name of the list
+        key: String,
+    },
+
+    /// Get an element of a list by its index.
+    LIndex {
+        /// Name of the list
+        key: String,
+
+        /// Index of the element, negative indices count from the end
+        #[clap(allow_hyphen_values = true)]
+        index: i64,
+    },
+
+    /// Pop the first element of the first non-empty list, blocking until one is
+    /// pushed.
+    BLPop {
+        /// Names of the lists, checked in order
+        #[clap(required = true)]
+        keys: Vec<String>,
+
+        /// Seconds to block for, 0 blocks forever
+        #[clap(long, default_value_t = 0.0)]
+        timeout: f64,
+    },
+
+    /// Add members to a set.
+    SAdd {
+        /// Name of the set
+        key: String,
+
+        /// Members to add
+        #[clap(required = true, value_parser = bytes_from_str)]
+        members: Vec<Bytes>,
+    },
+
+    /// Get all the members of a set.
+    SMembers {
+        /// Name of the set
+        key: String,
+    },
+
+    /// Check whether a value is a member of a set.
+    SIsMember {
+        /// Name of the set
+        key: String,
+
+        /// Value to look for
+        #[clap(value_parser = bytes_from_str)]
+        member: Bytes,
+    },
+
+    /// Remove members from a set.
+    SRem {
+        /// Name of the set
+        key: String,
+
+        /// Members to remove
+        #[clap(required = true, value_parser = bytes_from_str)]
+        members: Vec<Bytes>,
+    },
+
+    /// Get the number of members of a set.
+    SCard {
+        /// Name of the set
+        key: String,
+    },
+
+    /// Remove and return random members of a set.
+    SPop {
+        /// Name of the set
+        key: String,
+
+        /// Number of members to pop, a single one if not given
+        count: Option<u64>,
+    },
+
+    /// Get the members of the intersection of sets.
+    SInter {
+        /// Names of the sets
+        #[clap(required = true)]
+        keys: Vec<String>,
+    },
+
+    /// Get the members of the union of sets.
+    SUnion {
+        /// Names of the sets
+        #[clap(required = true)]
+        keys: Vec<String>,
+    },
+
+    /// Get the members of the first set that are in none of the others.
+    SDiff {
+        /// Names of the sets
+        #[clap(required = true)]
+        keys: Vec<String>,
+    },
+
+    /// Store the intersection of sets into a key.
+    SInterStore {
+        /// Name of the key to store the result at
+        destination: String,
+
+        /// Names of the sets
+        #[clap(required = true)]
+        keys: Vec<String>,
+    },
+
+    /// Store the union of sets into a key.
+    SUnionStore {
+        /// Name of the key to store the result at
+        destination: String,
+
+        /// Names of the sets
+        #[clap(required = true)]
+        keys: Vec<String>,
+    },
+
+    /// Store the members of the first set that are in none of the others into
+    /// a key.
+    SDiffStore {
+        /// Name of the key to store the result at
+        destination: String,
+
+        /// Names of the sets
+        #[clap(required = true)]
+        keys: Vec<String>,
+    },
+
+    /// Add a member to a sorted set, or update its score.
+    ZAdd {
+        /// Name of the sorted set
+        key: String,
+
+        /// Score of the member
+        #[clap(allow_hyphen_values = true)]
+        score: f64,
+
+        /// Member to add
+        #[clap(value_parser = bytes_from_str)]
+        member: Bytes,
+    },
+
+    /// Get the members of a sorted set between two offsets.
+    ZRange {
+        /// Name of the sorted set
+        key: String,
+
+        /// Offset of the first member, negative offsets count from the end
+        #[clap(allow_hyphen_values = true)]
+        start: i64,
+
+        /// Offset of the last member, negative offsets count from the end
+        #[clap(allow_hyphen_values = true)]
+        stop: i64,
+
+        /// Also print the scores
+        #[clap(long)]
+        withscores: bool,
+    },
+
+    /// Count the members of a sorted set scoring between two bounds.
+    ZCount {
+        /// Name of the sorted set
+        key: String,
+
+        /// Lowest score counted, prefixed with '(' to exclude it
+        #[clap(allow_hyphen_values = true)]
+        min: ScoreBound,
+
+        /// Highest score counted, prefixed with '(' to exclude it
+        #[clap(allow_hyphen_values = true)]
+        max: ScoreBound,
+    },
+
+    /// Get the score of a member of a sorted set.
+    ZScore {
+        /// Name of the sorted set
+        key: String,
+
+        /// Member to look up
+        #[clap(value_parser = bytes_from_str)]
+        member: Bytes,
+    },
+
+    /// Add to the score of a member of a sorted set.
+    ZIncrBy {
+        /// Name of the sorted set
+        key: String,
+
+        /// Amount to add to the score
+        #[clap(allow_hyphen_values = true)]
+        increment: f64,
+
+        /// Member whose score changes
+        #[clap(value_parser = bytes_from_str)]
+        member: Bytes,
+    },
+
+    /// Get the rank of a member of a sorted set.
+    ZRank {
+        /// Name of the sorted set
+        key: String,
+
+        /// Member to look up
+        #[clap(value_parser = bytes_from_str)]
+        member: Bytes,
+    },
+
+    /// Remove members from a sorted set.
+    ZRem {
+        /// Name of the sorted set
+        key: String,
+
+        /// Members to remove
+        #[clap(required = true, value_parser = bytes_from_str)]
+        members: Vec<Bytes>,
+    },
+
+    /// Remove the members of a sorted set between two ranks.
+    ZRemRangeByRank {
+        /// Name of the sorted set
+        key: String,
+
+        /// Rank of the first member, negative ranks count from the end
+        #[clap(allow_hyphen_values = true)]
+        start: i64,
+
+        /// Rank of the last member, negative ranks count from the end
+        #[clap(allow_hyphen_values = true)]
+        stop: i64,
+    },
+
+    /// Remove the members of a sorted set scoring between two bounds.
+    ZRemRangeByScore {
+        /// Name of the sorted set
+        key: String,
+
+        /// Lowest score removed, prefixed with '(' to exclude it
+        #[clap(allow_hyphen_values = true)]
+        min: ScoreBound,
+
+        /// Highest score removed, prefixed with '(' to exclude it
+        #[clap(allow_hyphen_values = true)]
+        max: ScoreBound,
+    },
+
+    /// Store the union of sorted sets into a key.
+    ZUnionStore {
+        /// Name of the key to store the result at
+        destination: String,
+
+        /// Names of the sorted sets
+        #[clap(required = true)]
+        keys: Vec<String>,
+
+        /// Multiplier of the scores of each sorted set, one per key
+        #[clap(long, num_args = 1.., allow_hyphen_values = true)]
+        weights: Option<Vec<f64>>,
+
+        /// How the scores of a member are combined: sum, min or max
+        #[clap(long, default_value = "sum")]
+        aggregate: Aggregate,
+    },
+
+    /// Store the intersection of sorted sets into a key.
+    ZInterStore {
+        /// Name of the key to store the result at
+        destination: String,
+
+        /// Names of the sorted sets
+        #[clap(required = true)]
+        keys: Vec<String>,
+
+        /// Multiplier of the scores of each sorted set, one per key
+        #[clap(long, num_args = 1.., allow_hyphen_values = true)]
+        weights: Option<Vec<f64>>,
+
+        /// How the scores of a member are combined: sum, min or max
+        #[clap(long, default_value = "sum")]
+        aggregate: Aggregate,
+    },
+
+    /// Get the union of sorted sets.
+    ZUnion {
+        /// Names of the sorted sets
+        #[clap(required = true)]
+        keys: Vec<String>,
+
+        /// Multiplier of the scores of each sorted set, one per key
+        #[clap(long, num_args = 1.., allow_hyphen_values = true)]
+        weights: Option<Vec<f64>>,
+
+        /// How the scores of a member are combined: sum, min or max
+        #[clap(long, default_value = "sum")]
+        aggregate: Aggregate,
+
+        /// Also print the scores
+        #[clap(long)]
+        withscores: bool,
+    },
+
+    /// Get the intersection of sorted sets.
+    ZInter {
+        /// Names of the sorted sets
+        #[clap(required = true)]
+        keys: Vec<String>,
+
+        /// Multiplier of the scores of each sorted set, one per key
+        #[clap(long, num_args = 1.., allow_hyphen_values = true)]
+        weights: Option<Vec<f64>>,
+
+        /// How the scores of a member are combined: sum, min or max
+        #[clap(long, default_value = "sum")]
+        aggregate: Aggregate,
+
+        /// Also print the scores
+        #[clap(long)]
+        withscores: bool,
+    },
+
+    /// Get the members of a sorted set that are in none of the other ones.
+    ZDiff {
+        /// Names of the sorted sets, the first one being the set to subtract from
+        #[clap(required = true)]
+        keys: Vec<String>,
+
+        /// Also print the scores
+        #[clap(long)]
+        withscores: bool,
+    },
+
+    /// Remove every key from the database.
+    FlushDb,
+
+    /// Remove every key from every database.
+    FlushAll,
+
+    /// Write every database to the server's dump file.
+    Save,
+
+    /// Iterate over the keys, a batch at a time.
+    Scan {
+        /// Cursor returned by the previous call, 0 to start
+        #[clap(default_value_t = 0)]
+        cursor: u64,
+
+        /// Only print keys matching this glob-style pattern
+        #[clap(long = "match")]
+        pattern: Option<String>,
+
+        /// Number of keys to examine
+        #[clap(long)]
+        count: Option<u64>,
+    },
+
+    /// Rename a key, replacing any value stored under the new name.
+    Rename {
+        /// Name of the key to rename
+        key: String,
+
+        /// New name of the key
+        new_key: String,
+
+        /// Only rename if the new name is not in use
+        #[clap(long)]
+        nx: bool,
+    },
+
+    /// Get the longest common subsequence of the values of two keys.
+    Lcs {
+        /// Name of the first key
+        key1: String,
+
+        /// Name of the second key
+        key2: String,
+
+        /// Only print the length of the subsequence
+        #[clap(long, conflicts_with = "idx")]
+        len: bool,
+
+        /// Print the position of the matches in both values
+        #[clap(long)]
+        idx: bool,
+
+        /// With --idx, skip matches shorter than this
+        #[clap(long, default_value_t = 0)]
+        min_match_len: u64,
+    },
 }
 
 /// Entry point for CLI tool.
@@ -122,6 +783,10 @@ async fn main() -> mini_redis::Result<()> {
     // Establish a connection
     let mut client = Client::connect(&addr).await?;
 
+    if let Some(password) = &cli.password {
+        client.auth(password).await?;
+    }
+
     // Process the requested command
     match cli.command {
         Command::Ping { msg } => {
@@ -160,7 +825,7 @@ async fn main() -> mini_redis::Result<()> {
             println!("OK");
         }
         Command::Publish { channel, message } => {
-            client.publish(&channel, message).await?;
+            client.publish(channel, message).await?;
             println!("Publish OK");
         }
         Command::Subscribe { channels } => {
@@ -169,13 +834,42 @@ async fn main() -> mini_redis::Result<()> {
             }
             let mut subscriber = client.subscribe(channels).await?;
 
-            // await messages on channels
-            while let Some(msg) = subscriber.next_message().await? {
+            for confirmation in subscriber.confirmations() {
                 println!(
-                    "got message from the channel: {}; message = {:?}",
-                    msg.channel, msg.content
+                    "subscribed to the channel: {}; subscriptions = {}",
+                    display_channel(&confirmation.channel),
+                    confirmation.count
                 );
             }
+
+            // await messages on channels, until interrupted
+            loop {
+                let msg = tokio::select! {
+                    msg = subscriber.next_message() => msg?,
+                    _ = tokio::signal::ctrl_c() => {
+                        subscriber.unsubscribe(&[]).await?;
+
+                        for confirmation in subscriber.confirmations() {
+                            println!(
+                                "unsubscribed from the channel: {}; subscriptions = {}",
+                                display_channel(&confirmation.channel),
+                                confirmation.count
+                            );
+                        }
+
+                        break;
+                    }
+                };
+
+                match msg {
+                    Some(msg) => println!(
+                        "got message from the channel: {}; message = {:?}",
+                        display_channel(&msg.channel),
+                        msg.content
+                    ),
+                    None => break,
+                }
+            }
         }
         Command::HSet { key, field, value } => {
             client.hset(&key, &field, value).await?;
@@ -209,11 +903,515 @@ async fn main() -> mini_redis::Result<()> {
                 return Err("key does not exist".into());
             }
         }
+        Command::HDel { key, fields } => {
+            let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+            println!("(integer) {}", client.hdel(&key, &fields).await?);
+        }
+        Command::HExists { key, field } => {
+            println!("(integer) {}", client.hexists(&key, &field).await? as u8);
+        }
+        Command::HLen { key } => {
+            println!("(integer) {}", client.hlen(&key).await?);
+        }
+        Command::HKeys { key } => {
+            for (i, field) in client.hkeys(&key).await?.iter().enumerate() {
+                println!("{}) \"{}\"", i + 1, field);
+            }
+        }
+        Command::HVals { key } => {
+            for (i, value) in client.hvals(&key).await?.iter().enumerate() {
+                if let Ok(string) = str::from_utf8(value) {
+                    println!("{}) \"{}\"", i + 1, string);
+                } else {
+                    println!("{}) {:?}", i + 1, value);
+                }
+            }
+        }
+        Command::HMGet { key, fields } => {
+            let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+            for (i, value) in client.hmget(&key, &fields).await?.iter().enumerate() {
+                match value {
+                    Some(value) => match str::from_utf8(value) {
+                        Ok(string) => println!("{}) \"{}\"", i + 1, string),
+                        Err(_) => println!("{}) {:?}", i + 1, value),
+                    },
+                    None => println!("{}) (nil)", i + 1),
+                }
+            }
+        }
+        Command::HIncrBy { key, field, delta } => {
+            println!("(integer) {}", client.hincrby(&key, &field, delta).await?);
+        }
+        Command::HSetNx { key, field, value } => {
+            let set = client.hsetnx(&key, &field, value).await?;
+            println!("(integer) {}", set as u8);
+        }
+        Command::Del { keys } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            println!("(integer) {}", client.del(&keys).await?);
+        }
+        Command::Exists { keys } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            println!("(integer) {}", client.exists(&keys).await?);
+        }
+        Command::Incr { key } => {
+            println!("(integer) {}", client.incr(&key).await?);
+        }
+        Command::Decr { key } => {
+            println!("(integer) {}", client.decr(&key).await?);
+        }
+        Command::IncrBy { key, delta } => {
+            println!("(integer) {}", client.incr_by(&key, delta).await?);
+        }
+        Command::DecrBy { key, delta } => {
+            println!("(integer) {}", client.decr_by(&key, delta).await?);
+        }
+        Command::ObjectEncoding { key } => match client.object_encoding(&key).await? {
+            Some(encoding) => println!("\"{}\"", encoding),
+            None => println!("(nil)"),
+        },
+        Command::Ttl { key } => {
+            println!("(integer) {}", client.ttl(&key).await?);
+        }
+        Command::Pttl { key } => {
+            println!("(integer) {}", client.pttl(&key).await?);
+        }
+        Command::Expire { key, seconds } => {
+            let updated = client.expire(&key, seconds).await?;
+            println!("(integer) {}", updated as u8);
+        }
+        Command::Persist { key } => {
+            let removed = client.persist(&key).await?;
+            println!("(integer) {}", removed as u8);
+        }
+        Command::Mget { keys } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+
+            for (i, value) in client.mget(&keys).await?.iter().enumerate() {
+                match value {
+                    Some(value) => match str::from_utf8(value) {
+                        Ok(string) => println!("{}) \"{}\"", i + 1, string),
+                        Err(_) => println!("{}) {:?}", i + 1, value),
+                    },
+                    None => println!("{}) (nil)", i + 1),
+                }
+            }
+        }
+        Command::Mset { pairs } => {
+            if pairs.len() % 2 != 0 {
+                return Err("each key must be followed by a value".into());
+            }
+
+            let pairs: Vec<(&str, Bytes)> = pairs
+                .chunks(2)
+                .map(|pair| (pair[0].as_str(), Bytes::from(pair[1].clone())))
+                .collect();
+
+            client.mset(&pairs).await?;
+            println!("OK");
+        }
+        Command::Append { key, value } => {
+            println!("(integer) {}", client.append(&key, value).await?);
+        }
+        Command::Strlen { key } => {
+            println!("(integer) {}", client.strlen(&key).await?);
+        }
+        Command::Dbsize => {
+            println!("(integer) {}", client.dbsize().await?);
+        }
+        Command::Getset { key, value } => {
+            if let Some(value) = client.getset(&key, value).await? {
+                if let Ok(string) = str::from_utf8(&value) {
+                    println!("\"{}\"", string);
+                } else {
+                    println!("{:?}", value);
+                }
+            } else {
+                println!("(nil)");
+            }
+        }
+        Command::Setnx { key, value } => {
+            println!("(integer) {}", client.set_nx(&key, value).await? as u8);
+        }
+        Command::Type { key } => {
+            println!("{}", client.key_type(&key).await?);
+        }
+        Command::LPush { key, values } => {
+            println!("(integer) {}", client.lpush(&key, &values).await?);
+        }
+        Command::LPushCapped { key, value, maxlen } => {
+            let len = client.lpushcapped(&key, value, maxlen).await?;
+            println!("(integer) {}", len);
+        }
+        Command::RPush { key, values } => {
+            println!("(integer) {}", client.rpush(&key, &values).await?);
+        }
+        Command::LRange { key, start, stop } => {
+            for (i, value) in client.lrange(&key, start, stop).await?.iter().enumerate() {
+                if let Ok(string) = str::from_utf8(value) {
+                    println!("{}) \"{}\"", i + 1, string);
+                } else {
+                    println!("{}) {:?}", i + 1, value);
+                }
+            }
+        }
+        Command::LLen { key } => {
+            println!("(integer) {}", client.llen(&key).await?);
+        }
+        Command::LIndex { key, index } => {
+            if let Some(value) = client.lindex(&key, index).await? {
+                if let Ok(string) = str::from_utf8(&value) {
+                    println!("\"{}\"", string);
+                } else {
+                    println!("{:?}", value);
+                }
+            } else {
+                println!("(nil)");
+            }
+        }
+        Command::BLPop { keys, timeout } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            let timeout = Duration::from_secs_f64(timeout);
+            if let Some((key, value)) = client.blpop(&keys, timeout).await? {
+                println!("1) \"{}\"", key);
+                if let Ok(string) = str::from_utf8(&value) {
+                    println!("2) \"{}\"", string);
+                } else {
+                    println!("2) {:?}", value);
+                }
+            } else {
+                println!("(nil)");
+            }
+        }
+        Command::SAdd { key, members } => {
+            println!("(integer) {}", client.sadd(&key, &members).await?);
+        }
+        Command::SMembers { key } => {
+            for (i, member) in client.smembers(&key).await?.iter().enumerate() {
+                if let Ok(string) = str::from_utf8(member) {
+                    println!("{}) \"{}\"", i + 1, string);
+                } else {
+                    println!("{}) {:?}", i + 1, member);
+                }
+            }
+        }
+        Command::SIsMember { key, member } => {
+            let found = client.sismember(&key, member).await?;
+            println!("(integer) {}", found as u8);
+        }
+        Command::SRem { key, members } => {
+            println!("(integer) {}", client.srem(&key, &members).await?);
+        }
+        Command::SCard { key } => {
+            println!("(integer) {}", client.scard(&key).await?);
+        }
+        Command::SPop { key, count: None } => {
+            if let Some(member) = client.spop(&key).await? {
+                if let Ok(string) = str::from_utf8(&member) {
+                    println!("\"{}\"", string);
+                } else {
+                    println!("{:?}", member);
+                }
+            } else {
+                println!("(nil)");
+            }
+        }
+        Command::SPop {
+            key,
+            count: Some(count),
+        } => {
+            for (i, member) in client.spop_count(&key, count).await?.iter().enumerate() {
+                if let Ok(string) = str::from_utf8(member) {
+                    println!("{}) \"{}\"", i + 1, string);
+                } else {
+                    println!("{}) {:?}", i + 1, member);
+                }
+            }
+        }
+        Command::SInter { keys } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            for (i, member) in client.sinter(&keys).await?.iter().enumerate() {
+                if let Ok(string) = str::from_utf8(member) {
+                    println!("{}) \"{}\"", i + 1, string);
+                } else {
+                    println!("{}) {:?}", i + 1, member);
+                }
+            }
+        }
+        Command::SUnion { keys } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            for (i, member) in client.sunion(&keys).await?.iter().enumerate() {
+                if let Ok(string) = str::from_utf8(member) {
+                    println!("{}) \"{}\"", i + 1, string);
+                } else {
+                    println!("{}) {:?}", i + 1, member);
+                }
+            }
+        }
+        Command::SDiff { keys } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            for (i, member) in client.sdiff(&keys).await?.iter().enumerate() {
+                if let Ok(string) = str::from_utf8(member) {
+                    println!("{}) \"{}\"", i + 1, string);
+                } else {
+                    println!("{}) {:?}", i + 1, member);
+                }
+            }
+        }
+        Command::SInterStore { destination, keys } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            let count = client.sinterstore(&destination, &keys).await?;
+            println!("(integer) {}", count);
+        }
+        Command::SUnionStore { destination, keys } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            let count = client.sunionstore(&destination, &keys).await?;
+            println!("(integer) {}", count);
+        }
+        Command::SDiffStore { destination, keys } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            let count = client.sdiffstore(&destination, &keys).await?;
+            println!("(integer) {}", count);
+        }
+        Command::ZAdd { key, score, member } => {
+            println!("(integer) {}", client.zadd(&key, &[(score, member)]).await?);
+        }
+        Command::ZRange {
+            key,
+            start,
+            stop,
+            withscores: false,
+        } => {
+            print_members(&client.zrange(&key, start, stop).await?);
+        }
+        Command::ZRange {
+            key,
+            start,
+            stop,
+            withscores: true,
+        } => {
+            print_scored_members(&client.zrange_with_scores(&key, start, stop).await?);
+        }
+        Command::ZCount { key, min, max } => {
+            println!("(integer) {}", client.zcount(&key, min, max).await?);
+        }
+        Command::ZScore { key, member } => {
+            if let Some(score) = client.zscore(&key, member).await? {
+                println!("\"{}\"", score);
+            } else {
+                println!("(nil)");
+            }
+        }
+        Command::ZIncrBy {
+            key,
+            increment,
+            member,
+        } => {
+            let score = client.zincrby(&key, increment, member).await?;
+            println!("\"{}\"", score);
+        }
+        Command::ZRank { key, member } => {
+            if let Some(rank) = client.zrank(&key, member).await? {
+                println!("(integer) {}", rank);
+            } else {
+                println!("(nil)");
+            }
+        }
+        Command::ZRem { key, members } => {
+            println!("(integer) {}", client.zrem(&key, &members).await?);
+        }
+        Command::ZRemRangeByRank { key, start, stop } => {
+            let removed = client.zremrangebyrank(&key, start, stop).await?;
+            println!("(integer) {}", removed);
+        }
+        Command::ZRemRangeByScore { key, min, max } => {
+            let removed = client.zremrangebyscore(&key, min, max).await?;
+            println!("(integer) {}", removed);
+        }
+        Command::ZUnionStore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+        } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            let count = client
+                .zunionstore(&destination, &keys, weights.as_deref(), aggregate)
+                .await?;
+            println!("(integer) {}", count);
+        }
+        Command::ZInterStore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+        } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            let count = client
+                .zinterstore(&destination, &keys, weights.as_deref(), aggregate)
+                .await?;
+            println!("(integer) {}", count);
+        }
+        Command::ZUnion {
+            keys,
+            weights,
+            aggregate,
+            withscores: false,
+        } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            print_members(&client.zunion(&keys, weights.as_deref(), aggregate).await?);
+        }
+        Command::ZUnion {
+            keys,
+            weights,
+            aggregate,
+            withscores: true,
+        } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            let members = client
+                .zunion_with_scores(&keys, weights.as_deref(), aggregate)
+                .await?;
+            print_scored_members(&members);
+        }
+        Command::ZInter {
+            keys,
+            weights,
+            aggregate,
+            withscores: false,
+        } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            print_members(&client.zinter(&keys, weights.as_deref(), aggregate).await?);
+        }
+        Command::ZInter {
+            keys,
+            weights,
+            aggregate,
+            withscores: true,
+        } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            let members = client
+                .zinter_with_scores(&keys, weights.as_deref(), aggregate)
+                .await?;
+            print_scored_members(&members);
+        }
+        Command::ZDiff {
+            keys,
+            withscores: false,
+        } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            print_members(&client.zdiff(&keys).await?);
+        }
+        Command::ZDiff {
+            keys,
+            withscores: true,
+        } => {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            print_scored_members(&client.zdiff_with_scores(&keys).await?);
+        }
+        Command::FlushDb => {
+            client.flushdb().await?;
+            println!("OK");
+        }
+        Command::FlushAll => {
+            client.flushall().await?;
+            println!("OK");
+        }
+        Command::Save => {
+            client.save().await?;
+            println!("OK");
+        }
+        Command::Scan {
+            cursor,
+            pattern,
+            count,
+        } => {
+            let (next, keys) = client.scan(cursor, pattern.as_deref(), count).await?;
+            for (i, key) in keys.iter().enumerate() {
+                println!("{}) \"{}\"", i + 1, key);
+            }
+            println!("cursor: {}", next);
+        }
+        Command::Rename {
+            key,
+            new_key,
+            nx: true,
+        } => {
+            let renamed = client.rename_nx(&key, &new_key).await?;
+            println!("(integer) {}", renamed as u8);
+        }
+        Command::Rename { key, new_key, .. } => {
+            client.rename(&key, &new_key).await?;
+            println!("OK");
+        }
+        Command::Lcs {
+            key1,
+            key2,
+            len: true,
+            ..
+        } => {
+            println!("(integer) {}", client.lcs_len(&key1, &key2).await?);
+        }
+        Command::Lcs {
+            key1,
+            key2,
+            idx: true,
+            min_match_len,
+            ..
+        } => {
+            let (matches, len) = client.lcs_idx(&key1, &key2, min_match_len).await?;
+            for m in matches {
+                println!(
+                    "{}-{} matches {}-{} (length {})",
+                    m.a.0, m.a.1, m.b.0, m.b.1, m.len
+                );
+            }
+            println!("len: {}", len);
+        }
+        Command::Lcs { key1, key2, .. } => {
+            let value = client.lcs(&key1, &key2).await?;
+            if let Ok(string) = str::from_utf8(&value) {
+                println!("\"{}\"", string);
+            } else {
+                println!("{:?}", value);
+            }
+        }
     }
 
     Ok(())
 }
 
+/// Channel names are binary safe. Print them as text if they are valid UTF-8,
+/// and escaped otherwise.
+fn display_channel(channel: &Bytes) -> String {
+    match str::from_utf8(channel) {
+        Ok(string) => string.to_string(),
+        Err(_) => format!("{:?}", channel),
+    }
+}
+
+/// Print sorted set members as a numbered list.
+fn print_members(members: &[Bytes]) {
+    for (i, member) in members.iter().enumerate() {
+        if let Ok(string) = str::from_utf8(member) {
+            println!("{}) \"{}\"", i + 1, string);
+        } else {
+            println!("{}) {:?}", i + 1, member);
+        }
+    }
+}
+
+/// Print sorted set members as a numbered list, each followed by its score.
+fn print_scored_members(members: &[(Bytes, f64)]) {
+    for (i, (member, score)) in members.iter().enumerate() {
+        if let Ok(string) = str::from_utf8(member) {
+            println!("{}) \"{}\"", 2 * i + 1, string);
+        } else {
+            println!("{}) {:?}", 2 * i + 1, member);
+        }
+        println!("{}) \"{}\"", 2 * i + 2, score);
+    }
+}
+
 fn duration_from_ms_str(src: &str) -> Result<Duration, ParseIntError> {
     let ms = src.parse::<u64>()?;
     Ok(Duration::from_millis(ms))
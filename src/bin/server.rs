@@ -6,10 +6,13 @@
 //!
 //! The `clap` crate is used for parsing arguments.
 
-use mini_redis::{server, DEFAULT_PORT};
+use mini_redis::{server, AppendFsync, Config, MaxmemoryPolicy, DEFAULT_PORT};
 
 use clap::Parser;
+use std::path::PathBuf;
 use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
 use tokio::signal;
 
 #[cfg(feature = "otel")]
@@ -34,11 +37,22 @@ pub async fn main() -> mini_redis::Result<()> {
 
     let cli = Cli::parse();
     let port = cli.port.unwrap_or(DEFAULT_PORT);
+    let config = cli.config();
+
+    // Bind a Unix domain socket listener instead of a TCP one if requested
+    #[cfg(unix)]
+    if let Some(path) = &cli.unixsocket {
+        let listener = UnixListener::bind(path)?;
+
+        server::run_unix_with_config(listener, config, signal::ctrl_c()).await;
+
+        return Ok(());
+    }
 
     // Bind a TCP listener
     let listener = TcpListener::bind(&format!("127.0.0.1:{}", port)).await?;
 
-    server::run(listener, signal::ctrl_c()).await;
+    server::run_with_config(listener, config, signal::ctrl_c()).await;
 
     Ok(())
 }
@@ -48,6 +62,122 @@ pub async fn main() -> mini_redis::Result<()> {
 struct Cli {
     #[clap(long)]
     port: Option<u16>,
+
+    /// Listen on a Unix domain socket at this path instead of the TCP port
+    #[cfg(unix)]
+    #[clap(long)]
+    unixsocket: Option<PathBuf>,
+
+    /// Maximum number of elements accepted in a single request array
+    #[clap(long)]
+    max_multibulk_len: Option<u64>,
+
+    /// Maximum number of channels a single connection may subscribe to
+    #[clap(long)]
+    max_subscriptions: Option<usize>,
+
+    /// Apply GET and SET through the generic command path only
+    #[clap(long)]
+    no_fast_path: bool,
+
+    /// Reject empty key names on write commands
+    #[clap(long)]
+    strict_keys: bool,
+
+    /// Number of recent messages kept per channel for SUBSCRIBE-REPLAY
+    #[clap(long)]
+    pubsub_backlog: Option<usize>,
+
+    /// Maximum number of channels keeping a backlog for SUBSCRIBE-REPLAY
+    #[clap(long)]
+    pubsub_backlog_channels: Option<usize>,
+
+    /// Require clients to authenticate with this password
+    #[clap(long)]
+    requirepass: Option<String>,
+
+    /// Reject commands from clients that did not switch to RESP3 with HELLO 3
+    #[clap(long)]
+    protocol_required: bool,
+
+    /// Number of logical databases
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    databases: Option<u64>,
+
+    /// Dump file loaded on startup, written by SAVE and on shutdown
+    #[clap(long)]
+    dbfile: Option<PathBuf>,
+
+    /// Log write commands to an append-only file, replayed on startup
+    #[clap(long)]
+    appendonly: bool,
+
+    /// Append-only file used with --appendonly
+    #[clap(long, default_value = "appendonly.aof")]
+    appendfilename: PathBuf,
+
+    /// When to sync the append-only file to disk: always, everysec or no
+    #[clap(long)]
+    appendfsync: Option<AppendFsync>,
+
+    /// Approximate number of bytes values of all types may use, 0 for no limit
+    #[clap(long)]
+    maxmemory: Option<usize>,
+
+    /// What happens when the limit is reached: noeviction or allkeys-lru
+    #[clap(long)]
+    maxmemory_policy: Option<MaxmemoryPolicy>,
+}
+
+impl Cli {
+    /// Build the server `Config`, using defaults for unset options.
+    fn config(&self) -> Config {
+        let mut config = Config::default();
+
+        if let Some(len) = self.max_multibulk_len {
+            config.max_multibulk_len = len;
+        }
+
+        if let Some(max) = self.max_subscriptions {
+            config.max_subscriptions = max;
+        }
+
+        if let Some(len) = self.pubsub_backlog {
+            config.pubsub_backlog = len;
+        }
+
+        if let Some(channels) = self.pubsub_backlog_channels {
+            config.pubsub_backlog_channels = channels;
+        }
+
+        if let Some(databases) = self.databases {
+            config.databases = databases as usize;
+        }
+
+        config.command_fast_path = !self.no_fast_path;
+        config.strict_keys = self.strict_keys;
+        config.requirepass = self.requirepass.clone();
+        config.protocol_required = self.protocol_required;
+        config.dbfile = self.dbfile.clone();
+
+        if self.appendonly {
+            config.appendonly = Some(self.appendfilename.clone());
+        }
+
+        if let Some(fsync) = self.appendfsync {
+            config.appendfsync = fsync;
+        }
+
+        if let Some(maxmemory) = self.maxmemory {
+            config.maxmemory = maxmemory;
+        }
+
+        if let Some(policy) = self.maxmemory_policy {
+            config.maxmemory_policy = policy;
+        }
+
+        config
+    }
 }
 
 #[cfg(not(feature = "otel"))]
@@ -200,7 +200,7 @@ impl BlockingClient {
     ///     println!("Got = {:?}", val);
     /// }
     /// ```
-    pub fn publish(&mut self, channel: &str, message: Bytes) -> crate::Result<u64> {
+    pub fn publish(&mut self, channel: impl AsRef<[u8]>, message: Bytes) -> crate::Result<u64> {
         self.rt.block_on(self.inner.publish(channel, message))
     }
 
@@ -212,7 +212,7 @@ impl BlockingClient {
     ///
     /// The `BlockingSubscriber` value is used to receive messages as well as
     /// manage the list of channels the client is subscribed to.
-    pub fn subscribe(self, channels: Vec<String>) -> crate::Result<BlockingSubscriber> {
+    pub fn subscribe(self, channels: Vec<Bytes>) -> crate::Result<BlockingSubscriber> {
         let subscriber = self.rt.block_on(self.inner.subscribe(channels))?;
         Ok(BlockingSubscriber {
             inner: subscriber,
@@ -223,10 +223,16 @@ impl BlockingClient {
 
 impl BlockingSubscriber {
     /// Returns the set of channels currently subscribed to.
-    pub fn get_subscribed(&self) -> &[String] {
+    pub fn get_subscribed(&self) -> &[Bytes] {
         self.inner.get_subscribed()
     }
 
+    /// Returns the confirmations received for the last subscribe or
+    /// unsubscribe request.
+    pub fn confirmations(&self) -> &[crate::clients::Confirmation] {
+        self.inner.confirmations()
+    }
+
     /// Receive the next message published on a subscribed channel, waiting if
     /// necessary.
     ///
@@ -245,12 +251,12 @@ impl BlockingSubscriber {
     }
 
     /// Subscribe to a list of new channels
-    pub fn subscribe(&mut self, channels: &[String]) -> crate::Result<()> {
+    pub fn subscribe(&mut self, channels: &[Bytes]) -> crate::Result<()> {
         self.rt.block_on(self.inner.subscribe(channels))
     }
 
     /// Unsubscribe to a list of new channels
-    pub fn unsubscribe(&mut self, channels: &[String]) -> crate::Result<()> {
+    pub fn unsubscribe(&mut self, channels: &[Bytes]) -> crate::Result<()> {
         self.rt.block_on(self.inner.unsubscribe(channels))
     }
 }
@@ -3,15 +3,31 @@
 //! Provides an async connect and methods for issuing the supported commands.
 
 use std::collections::HashMap;
-use crate::cmd::{Get, HGet, HGetAll, HSet, Ping, Publish, Set, Subscribe, Unsubscribe};
+use crate::clients::{Error as ClientError, RetryClient, RetryConfig};
+use crate::cmd::{
+    Aggregate, Append, Auth, BLPop, CommandGetKeys, CommandSpec, ConfigResetStat, DbSize, Decr,
+    DecrBy, Del, Discard, Exec, Exists, Expire, FlushAll, FlushDb, Get, GetRange, GetSet, HDel,
+    HExists, HGet, HGetAll, HIncrBy, HKeys, HLen, HMGet, HSet, HSetNx, HVals, Hello, Incr, IncrBy,
+    Info, LIndex, LLen, LPush, LPushCapped, LRange, Lcs, Mget, Mset, Multi, ObjectEncoding,
+    Persist, Ping, Psubscribe, Pttl, PubSub, Publish, Punsubscribe, RPush, Rename, RenameNx, SAdd,
+    SCard, SDiff, SDiffStore, SInter, SInterStore, SIsMember, SMembers, SPop, SRem, SUnion,
+    SUnionStore, Save, Scan, ScoreBound, Select, Set, SetNx, Strlen, Subscribe, Ttl, Type,
+    Unsubscribe, Unwatch, Watch, ZAdd, ZCount, ZDiff, ZIncrBy, ZInter, ZInterStore, ZRange, ZRank,
+    ZRem, ZRemRangeByRank, ZRemRangeByScore, ZScore, ZUnion, ZUnionStore,
+};
 use crate::{Connection, Frame};
 
 use async_stream::try_stream;
 use bytes::Bytes;
 use std::io::{Error, ErrorKind};
+#[cfg(unix)]
+use std::path::Path;
 use std::time::Duration;
+#[cfg(unix)]
+use tokio::net::UnixStream;
 use tokio::net::{TcpStream, ToSocketAddrs};
-use tokio_stream::Stream;
+use tokio::time;
+use tokio_stream::{Stream, StreamExt};
 use tracing::{debug, instrument};
 
 /// Established connection with a Redis server.
@@ -30,6 +46,14 @@ pub struct Client {
     /// `Connection` allows the handler to operate at the "frame" level and keep
     /// the byte level protocol parsing details encapsulated in `Connection`.
     connection: Connection,
+
+    /// How long to wait for each response, or `None` to wait indefinitely.
+    timeout: Option<Duration>,
+
+    /// Number of responses still owed by the server to requests which timed
+    /// out. They are discarded as they arrive, as responses arrive in the
+    /// order of the requests.
+    stale: usize,
 }
 
 /// A client that has entered pub/sub mode.
@@ -42,16 +66,128 @@ pub struct Subscriber {
     client: Client,
 
     /// The set of channels to which the `Subscriber` is currently subscribed.
-    subscribed_channels: Vec<String>,
+    subscribed_channels: Vec<Bytes>,
+
+    /// Confirmations received for the last subscribe or unsubscribe request.
+    confirmations: Vec<Confirmation>,
+}
+
+/// A client that has subscribed to channel patterns.
+///
+/// As with `Subscriber`, the client may then only perform pub/sub related
+/// commands.
+pub struct PatternSubscriber {
+    /// The subscribed client.
+    client: Client,
+
+    /// The set of patterns to which the `PatternSubscriber` is currently
+    /// subscribed.
+    subscribed_patterns: Vec<Bytes>,
+
+    /// Confirmations received for the last subscribe or unsubscribe request.
+    confirmations: Vec<Confirmation>,
 }
 
 /// A message received on a subscribed channel.
 #[derive(Debug, Clone)]
 pub struct Message {
-    pub channel: String,
+    /// Name of the channel, which may not be valid UTF-8.
+    pub channel: Bytes,
+    pub content: Bytes,
+}
+
+/// A message received on a channel matching a subscribed pattern.
+#[derive(Debug, Clone)]
+pub struct PatternMessage {
+    /// The subscribed pattern the channel matched.
+    pub pattern: Bytes,
+
+    /// Name of the channel the message was published on.
+    pub channel: Bytes,
     pub content: Bytes,
 }
 
+/// What a subscriber waiting for a message received.
+enum Received<T> {
+    /// A message was published.
+    Message(T),
+
+    /// No message was received before the timeout set with
+    /// `Client::set_timeout` elapsed. The subscription is still active.
+    TimedOut,
+
+    /// The server closed the connection, ending the subscription.
+    Closed,
+}
+
+impl<T> Received<T> {
+    /// Returns the message, `None` on timeout or once the subscription is
+    /// closed.
+    fn message(self) -> Option<T> {
+        match self {
+            Received::Message(message) => Some(message),
+            Received::TimedOut | Received::Closed => None,
+        }
+    }
+}
+
+/// A subscription change confirmed by the server.
+#[derive(Debug, Clone)]
+pub struct Confirmation {
+    /// Name of the channel, or pattern, subscribed to or unsubscribed from.
+    pub channel: Bytes,
+
+    /// Number of channels and patterns the client is subscribed to after the
+    /// change.
+    pub count: u64,
+}
+
+/// A contiguous part of a longest common subsequence, returned by
+/// [`Client::lcs_idx`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LcsMatch {
+    /// Offsets of the match in the first value, both inclusive.
+    pub a: (u64, u64),
+
+    /// Offsets of the match in the second value, both inclusive.
+    pub b: (u64, u64),
+
+    /// Length of the match.
+    pub len: u64,
+}
+
+/// A command rejected by the server during [`Client::bulk_load`].
+#[derive(Debug, Clone)]
+pub struct BulkLoadError {
+    /// Position of the command in the stream passed to `bulk_load`, starting
+    /// at zero.
+    pub index: usize,
+
+    /// The error message returned by the server.
+    pub message: String,
+}
+
+/// An arbitrary command, built using [`Client::cmd`].
+pub struct Cmd<'a> {
+    /// The client the command is sent with.
+    client: &'a mut Client,
+
+    /// Lower cased command name, used to look up the command metadata.
+    name: String,
+
+    /// The command name followed by the arguments added so far.
+    args: Vec<Bytes>,
+}
+
+/// A batch of commands sent together, built using [`Client::pipeline`].
+pub struct Pipeline<'a> {
+    /// The client the commands are sent with.
+    client: &'a mut Client,
+
+    /// The commands added so far, in the order they are sent.
+    frames: Vec<Frame>,
+}
+
 impl Client {
     /// Establish a connection with the Redis server located at `addr`.
     ///
@@ -85,7 +221,108 @@ impl Client {
         // perform redis protocol frame parsing.
         let connection = Connection::new(socket);
 
-        Ok(Client { connection })
+        Ok(Client {
+            connection,
+            timeout: None,
+            stale: 0,
+        })
+    }
+
+    /// Establish a connection with the Redis server listening on the Unix
+    /// domain socket at `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = match Client::connect_unix("/tmp/mini-redis.sock").await {
+    ///         Ok(client) => client,
+    ///         Err(_) => panic!("failed to establish connection"),
+    ///     };
+    /// # drop(client);
+    /// }
+    /// ```
+    #[cfg(unix)]
+    pub async fn connect_unix(path: impl AsRef<Path>) -> crate::Result<Client> {
+        let socket = UnixStream::connect(path).await?;
+        let connection = Connection::new(socket);
+
+        Ok(Client {
+            connection,
+            timeout: None,
+            stale: 0,
+        })
+    }
+
+    /// Set how long to wait for the response to each request.
+    ///
+    /// Once `timeout` elapses, the request fails with
+    /// [`Error::Timeout`](crate::clients::Error::Timeout). `None`, the default,
+    /// waits indefinitely. For subscribers, `next_message` returns `Ok(None)`
+    /// instead if no message is received in time.
+    ///
+    /// The response to a request which timed out may still arrive later. It
+    /// is then discarded, so that it is not mistaken for the response to the
+    /// next request. Requests themselves are always written in full, as a
+    /// partially written request would corrupt the connection.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::{self, Client};
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///     client.set_timeout(Some(Duration::from_secs(1)));
+    ///
+    ///     match client.get("foo").await {
+    ///         Ok(val) => println!("Got = {:?}", val),
+    ///         Err(err) if err.downcast_ref() == Some(&clients::Error::Timeout) => {
+    ///             println!("timed out")
+    ///         }
+    ///         Err(err) => panic!("{}", err),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Establish a connection with the Redis server located at `addr`, which
+    /// is re-established if lost.
+    ///
+    /// When a command fails because the server reset or closed the
+    /// connection, the returned `RetryClient` reconnects with exponential
+    /// backoff, as set by `config`, and sends the command once more.
+    /// Subscribers created from it subscribe to their channels again after
+    /// reconnecting.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::{Client, RetryConfig};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let config = RetryConfig::default();
+    ///     let mut client = Client::connect_with_retry("localhost:6379", config)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let val = client.get("foo").await.unwrap();
+    ///     println!("Got = {:?}", val);
+    /// }
+    /// ```
+    pub async fn connect_with_retry<T: ToSocketAddrs>(
+        addr: T,
+        config: RetryConfig,
+    ) -> crate::Result<RetryClient> {
+        RetryClient::connect(addr, config).await
     }
 
     /// Ping to the server.
@@ -123,9 +360,10 @@ impl Client {
         }
     }
 
-    /// Get the value of key.
+    /// Describe the server with `HELLO`, without changing the protocol.
     ///
-    /// If the key does not exist the special value `None` is returned.
+    /// The client always speaks RESP2, so the server metadata map is received
+    /// as a flat array of fields and values.
     ///
     /// # Examples
     ///
@@ -138,40 +376,74 @@ impl Client {
     /// async fn main() {
     ///     let mut client = Client::connect("localhost:6379").await.unwrap();
     ///
-    ///     let val = client.get("foo").await.unwrap();
-    ///     println!("Got = {:?}", val);
+    ///     let info = client.hello().await.unwrap();
+    ///     println!("server = {:?}", info["server"]);
     /// }
     /// ```
     #[instrument(skip(self))]
-    pub async fn get(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
-        // Create a `Get` command for the `key` and convert it to a frame.
-        let frame = Get::new(key).into_frame();
+    pub async fn hello(&mut self) -> crate::Result<HashMap<String, Frame>> {
+        let frame = Hello::new(None).into_frame();
 
         debug!(request = ?frame);
 
-        // Write the frame to the socket. This writes the full frame to the
-        // socket, waiting if necessary.
         self.connection.write_frame(&frame).await?;
 
-        // Wait for the response from the server
-        //
-        // Both `Simple` and `Bulk` frames are accepted. `Null` represents the
-        // key not being present and `None` is returned.
+        let fields = match self.read_response().await? {
+            Frame::Map(entries) => entries,
+            Frame::Array(frames) if frames.len() % 2 == 0 => {
+                let mut iter = frames.into_iter();
+                std::iter::from_fn(|| Some((iter.next()?, iter.next()?))).collect()
+            }
+            frame => return Err(frame.to_error()),
+        };
+
+        fields
+            .into_iter()
+            .map(|(name, value)| match name {
+                Frame::Bulk(name) => Ok((String::from_utf8(name.to_vec())?, value)),
+                frame => Err(frame.to_error()),
+            })
+            .collect()
+    }
+
+    /// Authenticate the connection with the password the server was started
+    /// with.
+    ///
+    /// A server started with `--requirepass` rejects every other command,
+    /// except `HELLO`, until the connection has authenticated. An error is
+    /// returned if the password is wrong or the server does not require one.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.auth("secret").await.unwrap();
+    /// }
+    /// ```
+    #[instrument(skip(self, password))]
+    pub async fn auth(&mut self, password: &str) -> crate::Result<()> {
+        let frame = Auth::new(password).into_frame();
+
+        // The request is not logged, as it holds the password.
+        self.connection.write_frame(&frame).await?;
+
         match self.read_response().await? {
-            Frame::Simple(value) => Ok(Some(value.into())),
-            Frame::Bulk(value) => Ok(Some(value)),
-            Frame::Null => Ok(None),
+            Frame::Simple(response) if response == "OK" => Ok(()),
             frame => Err(frame.to_error()),
         }
     }
 
-    /// Set `key` to hold the given `value`.
-    ///
-    /// The `value` is associated with `key` until it is overwritten by the next
-    /// call to `set` or it is removed.
+    /// Select the logical database the following commands are applied to.
     ///
-    /// If key already holds a value, it is overwritten. Any previous time to
-    /// live associated with the key is discarded on successful SET operation.
+    /// Each database is an independent keyspace. The connection starts on
+    /// database `0`. An error is returned if `index` is out of range.
     ///
     /// # Examples
     ///
@@ -184,98 +456,59 @@ impl Client {
     /// async fn main() {
     ///     let mut client = Client::connect("localhost:6379").await.unwrap();
     ///
+    ///     client.select(1).await.unwrap();
     ///     client.set("foo", "bar".into()).await.unwrap();
-    ///
-    ///     // Getting the value immediately works
-    ///     let val = client.get("foo").await.unwrap().unwrap();
-    ///     assert_eq!(val, "bar");
     /// }
     /// ```
     #[instrument(skip(self))]
-    pub async fn set(&mut self, key: &str, value: Bytes) -> crate::Result<()> {
-        // Create a `Set` command and pass it to `set_cmd`. A separate method is
-        // used to set a value with an expiration. The common parts of both
-        // functions are implemented by `set_cmd`.
-        self.set_cmd(Set::new(key, value, None)).await
+    pub async fn select(&mut self, index: u64) -> crate::Result<()> {
+        let frame = Select::new(index).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
     }
 
-    /// Set `key` to hold the given `value`. The value expires after `expiration`
-    ///
-    /// The `value` is associated with `key` until one of the following:
-    /// - it expires.
-    /// - it is overwritten by the next call to `set`.
-    /// - it is removed.
+    /// Remove every key from the selected database.
     ///
-    /// If key already holds a value, it is overwritten. Any previous time to
-    /// live associated with the key is discarded on a successful SET operation.
+    /// Pub/sub subscriptions are not affected.
     ///
     /// # Examples
     ///
-    /// Demonstrates basic usage. This example is not **guaranteed** to always
-    /// work as it relies on time based logic and assumes the client and server
-    /// stay relatively synchronized in time. The real world tends to not be so
-    /// favorable.
+    /// Demonstrates basic usage.
     ///
     /// ```no_run
     /// use mini_redis::clients::Client;
-    /// use tokio::time;
-    /// use std::time::Duration;
     ///
     /// #[tokio::main]
     /// async fn main() {
-    ///     let ttl = Duration::from_millis(500);
     ///     let mut client = Client::connect("localhost:6379").await.unwrap();
     ///
-    ///     client.set_expires("foo", "bar".into(), ttl).await.unwrap();
-    ///
-    ///     // Getting the value immediately works
-    ///     let val = client.get("foo").await.unwrap().unwrap();
-    ///     assert_eq!(val, "bar");
-    ///
-    ///     // Wait for the TTL to expire
-    ///     time::sleep(ttl).await;
-    ///
-    ///     let val = client.get("foo").await.unwrap();
-    ///     assert!(val.is_some());
+    ///     client.flushdb().await.unwrap();
     /// }
     /// ```
     #[instrument(skip(self))]
-    pub async fn set_expires(
-        &mut self,
-        key: &str,
-        value: Bytes,
-        expiration: Duration,
-    ) -> crate::Result<()> {
-        // Create a `Set` command and pass it to `set_cmd`. A separate method is
-        // used to set a value with an expiration. The common parts of both
-        // functions are implemented by `set_cmd`.
-        self.set_cmd(Set::new(key, value, Some(expiration))).await
-    }
-
-    /// The core `SET` logic, used by both `set` and `set_expires.
-    async fn set_cmd(&mut self, cmd: Set) -> crate::Result<()> {
-        // Convert the `Set` command into a frame
-        let frame = cmd.into_frame();
+    pub async fn flushdb(&mut self) -> crate::Result<()> {
+        let frame = FlushDb::new().into_frame();
 
         debug!(request = ?frame);
 
-        // Write the frame to the socket. This writes the full frame to the
-        // socket, waiting if necessary.
         self.connection.write_frame(&frame).await?;
 
-        // Wait for the response from the server. On success, the server
-        // responds simply with `OK`. Any other response indicates an error.
         match self.read_response().await? {
             Frame::Simple(response) if response == "OK" => Ok(()),
             frame => Err(frame.to_error()),
         }
     }
 
-    /// Posts `message` to the given `channel`.
+    /// Remove every key from every database.
     ///
-    /// Returns the number of subscribers currently listening on the channel.
-    /// There is no guarantee that these subscribers receive the message as they
-    /// may disconnect at any time.
+    /// Pub/sub subscriptions are not affected.
     ///
     /// # Examples
     ///
@@ -288,65 +521,3208 @@ impl Client {
     /// async fn main() {
     ///     let mut client = Client::connect("localhost:6379").await.unwrap();
     ///
-    ///     let val = client.publish("foo", "bar".into()).await.unwrap();
-    ///     println!("Got = {:?}", val);
+    ///     client.flushall().await.unwrap();
     /// }
     /// ```
     #[instrument(skip(self))]
-    pub async fn publish(&mut self, channel: &str, message: Bytes) -> crate::Result<u64> {
-        // Convert the `Publish` command into a frame
-        let frame = Publish::new(channel, message).into_frame();
+    pub async fn flushall(&mut self) -> crate::Result<()> {
+        let frame = FlushAll::new().into_frame();
 
         debug!(request = ?frame);
 
-        // Write the frame to the socket
         self.connection.write_frame(&frame).await?;
 
-        // Read the response
         match self.read_response().await? {
-            Frame::Integer(response) => Ok(response),
+            Frame::Simple(response) if response == "OK" => Ok(()),
             frame => Err(frame.to_error()),
         }
     }
 
-    /// Subscribes the client to the specified channels.
+    /// Write every database to the server's dump file.
     ///
-    /// Once a client issues a subscribe command, it may no longer issue any
-    /// non-pub/sub commands. The function consumes `self` and returns a `Subscriber`.
+    /// Returns once the file is written. Fails if the server has no dump file
+    /// configured.
     ///
-    /// The `Subscriber` value is used to receive messages as well as manage the
-    /// list of channels the client is subscribed to.
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.save().await.unwrap();
+    /// }
+    /// ```
     #[instrument(skip(self))]
-    pub async fn subscribe(mut self, channels: Vec<String>) -> crate::Result<Subscriber> {
-        // Issue the subscribe command to the server and wait for confirmation.
-        // The client will then have been transitioned into the "subscriber"
-        // state and may only issue pub/sub commands from that point on.
-        self.subscribe_cmd(&channels).await?;
-
-        // Return the `Subscriber` type
-        Ok(Subscriber {
-            client: self,
-            subscribed_channels: channels,
-        })
-    }
-
-    /// The core `SUBSCRIBE` logic, used by misc subscribe fns
-    async fn subscribe_cmd(&mut self, channels: &[String]) -> crate::Result<()> {
-        // Convert the `Subscribe` command into a frame
-        let frame = Subscribe::new(channels.to_vec()).into_frame();
+    pub async fn save(&mut self) -> crate::Result<()> {
+        let frame = Save::new().into_frame();
 
         debug!(request = ?frame);
 
-        // Write the frame to the socket
         self.connection.write_frame(&frame).await?;
 
-        // For each channel being subscribed to, the server responds with a
-        // message confirming subscription to that channel.
-        for channel in channels {
-            // Read the response
-            let response = self.read_response().await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
 
-            // Verify it is confirmation of subscription.
+    /// Iterate over the keys of the selected database, returning the cursor to
+    /// pass to the next call and a batch of keys.
+    ///
+    /// A scan starts with cursor `0` and is complete once the returned cursor
+    /// is `0` again. Every key present for the whole scan is returned, keys
+    /// added or removed meanwhile may not be. `count` is the number of keys
+    /// examined by a call, those not matching `pattern` are then left out.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let mut cursor = 0;
+    ///     loop {
+    ///         let (next, keys) = client.scan(cursor, Some("user:*"), None).await.unwrap();
+    ///         println!("{:?}", keys);
+    ///
+    ///         if next == 0 {
+    ///             break;
+    ///         }
+    ///         cursor = next;
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn scan(
+        &mut self,
+        cursor: u64,
+        pattern: Option<&str>,
+        count: Option<u64>,
+    ) -> crate::Result<(u64, Vec<String>)> {
+        let frame = Scan::new(cursor, pattern, count).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        let (cursor, keys) = match self.read_response().await? {
+            Frame::Array(frames) => match frames.as_slice() {
+                [Frame::Bulk(cursor), Frame::Array(keys)] => (cursor.clone(), keys.clone()),
+                _ => return Err(Frame::Array(frames).to_error()),
+            },
+            frame => return Err(frame.to_error()),
+        };
+
+        let cursor = str::from_utf8(&cursor)?.parse()?;
+        let keys = keys
+            .into_iter()
+            .map(|key| match key {
+                Frame::Bulk(key) => Ok(String::from_utf8(key.to_vec())?),
+                frame => Err(frame.to_error()),
+            })
+            .collect::<crate::Result<_>>()?;
+
+        Ok((cursor, keys))
+    }
+
+    /// Rename `key` to `new_key`, replacing any value stored at `new_key`.
+    ///
+    /// Keys of any type can be renamed, and their expiration moves with them.
+    /// An error is returned if `key` does not exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.set("old", "value".into()).await.unwrap();
+    ///     client.rename("old", "new").await.unwrap();
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn rename(&mut self, key: &str, new_key: &str) -> crate::Result<()> {
+        let frame = Rename::new(key, new_key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Rename `key` to `new_key`, only if `new_key` does not exist.
+    ///
+    /// Returns `true` if the key was renamed. An error is returned if `key`
+    /// does not exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     if !client.rename_nx("old", "new").await.unwrap() {
+    ///         println!("new is already in use");
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn rename_nx(&mut self, key: &str, new_key: &str) -> crate::Result<bool> {
+        let frame = RenameNx::new(key, new_key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(renamed) => Ok(renamed == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Watch `keys`, so that the next transaction is only applied if none of
+    /// them is modified before [`exec`](Client::exec).
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.watch(&["foo"]).await.unwrap();
+    ///     let val = client.get("foo").await.unwrap();
+    ///
+    ///     client.multi().await.unwrap();
+    ///     client.cmd("SET").arg("foo").arg("bar").query().await.unwrap();
+    ///
+    ///     match client.exec().await.unwrap() {
+    ///         Some(_) => println!("Replaced = {:?}", val),
+    ///         None => println!("foo was modified, not replaced"),
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn watch(&mut self, keys: &[&str]) -> crate::Result<()> {
+        let frame = Watch::new(keys).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Stop watching the keys watched with [`watch`](Client::watch).
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.watch(&["foo"]).await.unwrap();
+    ///     client.unwatch().await.unwrap();
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn unwatch(&mut self) -> crate::Result<()> {
+        let frame = Unwatch::new().into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Start a transaction.
+    ///
+    /// Until [`exec`](Client::exec) or [`discard`](Client::discard) is called,
+    /// the server queues the commands sent on the connection and replies
+    /// `QUEUED` to each of them. Queued commands are therefore sent with
+    /// [`cmd`](Client::cmd), as the dedicated methods expect the reply of the
+    /// command itself.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.multi().await.unwrap();
+    ///     client.cmd("SET").arg("foo").arg("bar").query().await.unwrap();
+    ///     client.cmd("GET").arg("foo").query().await.unwrap();
+    ///
+    ///     let replies = client.exec().await.unwrap();
+    ///     println!("Got = {:?}", replies);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn multi(&mut self) -> crate::Result<()> {
+        let frame = Multi::new().into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Apply the commands queued since [`multi`](Client::multi).
+    ///
+    /// Returns the reply to each queued command, in order. Error replies are
+    /// returned as `Frame::Error` values, as they do not stop the other
+    /// commands from being applied. If a command could not be queued, the
+    /// whole transaction is discarded and an error is returned.
+    ///
+    /// `None` is returned if a key watched with [`watch`](Client::watch) was
+    /// modified, in which case none of the commands were applied.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.multi().await.unwrap();
+    ///     client.cmd("INCR").arg("counter").query().await.unwrap();
+    ///     client.cmd("INCR").arg("counter").query().await.unwrap();
+    ///
+    ///     if let Some(replies) = client.exec().await.unwrap() {
+    ///         println!("Got = {:?}", replies);
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn exec(&mut self) -> crate::Result<Option<Vec<Frame>>> {
+        let frame = Exec::new().into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(replies) => Ok(Some(replies)),
+            Frame::Null | Frame::NullArray => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Drop the commands queued since [`multi`](Client::multi), ending the
+    /// transaction.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.multi().await.unwrap();
+    ///     client.cmd("DEL").arg("foo").query().await.unwrap();
+    ///     client.discard().await.unwrap();
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn discard(&mut self) -> crate::Result<()> {
+        let frame = Discard::new().into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Get the value of key.
+    ///
+    /// If the key does not exist the special value `None` is returned.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let val = client.get("foo").await.unwrap();
+    ///     println!("Got = {:?}", val);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn get(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        // Create a `Get` command for the `key` and convert it to a frame.
+        let frame = Get::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        // Write the frame to the socket. This writes the full frame to the
+        // socket, waiting if necessary.
+        self.connection.write_frame(&frame).await?;
+
+        // Wait for the response from the server
+        //
+        // Both `Simple` and `Bulk` frames are accepted. `Null` represents the
+        // key not being present and `None` is returned.
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(Some(value.into())),
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Get the part of the value of key between the offsets `start` and `end`
+    /// (both inclusive).
+    ///
+    /// Negative offsets count back from the end of the value. If the key does
+    /// not exist, an empty value is returned.
+    ///
+    /// Offsets are byte indices unless `chars` is `true`, in which case they
+    /// are character indices and the value must be valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.set("foo", "héllo".into()).await.unwrap();
+    ///
+    ///     // The first two characters, "hé", rather than the first two bytes.
+    ///     let val = client.getrange("foo", 0, 1, true).await.unwrap();
+    ///     println!("Got = {:?}", val);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn getrange(
+        &mut self,
+        key: &str,
+        start: i64,
+        end: i64,
+        chars: bool,
+    ) -> crate::Result<Bytes> {
+        let frame = GetRange::new(key, start, end, chars).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(value.into()),
+            Frame::Bulk(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Remove the specified keys.
+    ///
+    /// Returns the number of keys that were removed. Keys that do not exist
+    /// are ignored.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let removed = client.del(&["foo", "bar"]).await.unwrap();
+    ///     println!("Removed {} keys", removed);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn del(&mut self, keys: &[&str]) -> crate::Result<u64> {
+        let frame = Del::new(keys).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(removed) => Ok(removed as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns how many of the specified keys exist.
+    ///
+    /// A key listed more than once is counted each time. The values are not
+    /// transferred.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     if client.exists(&["foo"]).await.unwrap() == 1 {
+    ///         println!("foo exists");
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn exists(&mut self, keys: &[&str]) -> crate::Result<u64> {
+        let frame = Exists::new(keys).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Increment the integer stored at `key` by one and return the new value.
+    ///
+    /// A missing key is treated as `0`. An error is returned if the value is
+    /// not an integer, or if the result would overflow a 64 bit signed
+    /// integer.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let val = client.incr("counter").await.unwrap();
+    ///     println!("Incremented = {}", val);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn incr(&mut self, key: &str) -> crate::Result<i64> {
+        let frame = Incr::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Decrement the integer stored at `key` by one and return the new value.
+    ///
+    /// A missing key is treated as `0`. An error is returned if the value is
+    /// not an integer, or if the result would overflow a 64 bit signed
+    /// integer.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let val = client.decr("counter").await.unwrap();
+    ///     println!("Decremented = {}", val);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn decr(&mut self, key: &str) -> crate::Result<i64> {
+        let frame = Decr::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Increment the integer stored at `key` by `delta` and return the new
+    /// value.
+    ///
+    /// A missing key is treated as `0`. An error is returned if the value is
+    /// not an integer, or if the result would overflow a 64 bit signed
+    /// integer.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let val = client.incr_by("counter", 10).await.unwrap();
+    ///     println!("Incremented = {}", val);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn incr_by(&mut self, key: &str, delta: i64) -> crate::Result<i64> {
+        let frame = IncrBy::new(key, delta).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Decrement the integer stored at `key` by `delta` and return the new
+    /// value.
+    ///
+    /// A missing key is treated as `0`. An error is returned if the value is
+    /// not an integer, or if the result would overflow a 64 bit signed
+    /// integer.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let val = client.decr_by("counter", 10).await.unwrap();
+    ///     println!("Decremented = {}", val);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn decr_by(&mut self, key: &str, delta: i64) -> crate::Result<i64> {
+        let frame = DecrBy::new(key, delta).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the internal encoding of the value stored at `key`.
+    ///
+    /// Strings are `int`, `embstr` or `raw`, hashes and sets `hashtable`, lists
+    /// `quicklist` and sorted sets `skiplist`. `None` is returned if the key
+    /// does not exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let encoding = client.object_encoding("foo").await.unwrap();
+    ///     println!("Encoding = {:?}", encoding);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn object_encoding(&mut self, key: &str) -> crate::Result<Option<String>> {
+        let frame = ObjectEncoding::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(Some(value)),
+            Frame::Bulk(value) => Ok(Some(String::from_utf8_lossy(&value).into_owned())),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the remaining time to live of `key`, in seconds.
+    ///
+    /// As with Redis, `-2` is returned if the key does not exist and `-1` if
+    /// it has no associated expiration.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let ttl = client.ttl("foo").await.unwrap();
+    ///     println!("TTL = {}", ttl);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn ttl(&mut self, key: &str) -> crate::Result<i64> {
+        let frame = Ttl::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(ttl) => Ok(ttl),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the remaining time to live of `key`, in milliseconds.
+    ///
+    /// As with Redis, `-2` is returned if the key does not exist and `-1` if
+    /// it has no associated expiration.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let ttl = client.pttl("foo").await.unwrap();
+    ///     println!("TTL = {}", ttl);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn pttl(&mut self, key: &str) -> crate::Result<i64> {
+        let frame = Pttl::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(ttl) => Ok(ttl),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Make `key` expire after `seconds`, replacing any previous expiration.
+    ///
+    /// A timeout which is not positive deletes the key. Returns `false` if the
+    /// key does not exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     if client.expire("foo", 60).await.unwrap() {
+    ///         println!("foo expires in a minute");
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn expire(&mut self, key: &str, seconds: i64) -> crate::Result<bool> {
+        let frame = Expire::new(key, seconds).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(updated) => Ok(updated == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Remove the expiration of `key`.
+    ///
+    /// Returns `false` if the key does not exist or has no expiration.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     if client.persist("foo").await.unwrap() {
+    ///         println!("foo no longer expires");
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn persist(&mut self, key: &str) -> crate::Result<bool> {
+        let frame = Persist::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(removed) => Ok(removed == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Get the values of all the specified keys, in one round trip.
+    ///
+    /// The values are returned in the order of `keys`. `None` is returned in
+    /// place of a key which does not exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let values = client.mget(&["foo", "bar"]).await.unwrap();
+    ///     println!("Got = {:?}", values);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn mget(&mut self, keys: &[&str]) -> crate::Result<Vec<Option<Bytes>>> {
+        let frame = Mget::new(keys).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(value) => Ok(Some(value)),
+                    Frame::Null => Ok(None),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Set each key of `pairs` to its value, in one round trip.
+    ///
+    /// All the keys are set at once, other clients never see only some of
+    /// them updated. Existing values are replaced, along with their
+    /// expiration.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client
+    ///         .mset(&[("foo", "1".into()), ("bar", "2".into())])
+    ///         .await
+    ///         .unwrap();
+    /// }
+    /// ```
+    #[instrument(skip(self, pairs))]
+    pub async fn mset(&mut self, pairs: &[(&str, Bytes)]) -> crate::Result<()> {
+        let frame = Mset::new(pairs).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Append `value` to the string stored at `key` and return the new length
+    /// of the string.
+    ///
+    /// If the key does not exist, it is created holding `value`.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let len = client.append("log", "line\n".into()).await.unwrap();
+    ///     println!("Length = {}", len);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn append(&mut self, key: &str, value: Bytes) -> crate::Result<u64> {
+        let frame = Append::new(key, value).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the length, in bytes, of the string stored at `key`.
+    ///
+    /// `0` is returned if the key does not exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let len = client.strlen("foo").await.unwrap();
+    ///     println!("Length = {}", len);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn strlen(&mut self, key: &str) -> crate::Result<u64> {
+        let frame = Strlen::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the number of keys in the database.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let size = client.dbsize().await.unwrap();
+    ///     println!("{} keys", size);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn dbsize(&mut self) -> crate::Result<u64> {
+        let frame = DbSize::new().into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(size) => Ok(size as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Set `key` to hold `value` and return the value it held.
+    ///
+    /// `None` is returned if the key did not exist. Any expiration of the key
+    /// is removed.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let prev = client.getset("leader", "node-2".into()).await.unwrap();
+    ///     println!("previous leader = {:?}", prev);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn getset(&mut self, key: &str, value: Bytes) -> crate::Result<Option<Bytes>> {
+        let frame = GetSet::new(key, value).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Set `key` to hold `value`, only if `key` does not exist.
+    ///
+    /// Returns `true` if the key was set. A key which expired is treated as
+    /// absent, even if the server did not remove it yet.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     if client.set_nx("lock", "owner".into()).await.unwrap() {
+    ///         println!("lock acquired");
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn set_nx(&mut self, key: &str, value: Bytes) -> crate::Result<bool> {
+        let frame = SetNx::new(key, value).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(set) => Ok(set == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the type of the value stored at `key`.
+    ///
+    /// This is `"string"`, `"hash"`, `"list"`, `"set"`, `"zset"`, or `"none"`
+    /// if the key does not exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let kind = client.key_type("foo").await.unwrap();
+    ///     println!("foo is a {}", kind);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn key_type(&mut self, key: &str) -> crate::Result<String> {
+        let frame = Type::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(kind) => Ok(kind),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Insert `values` at the head of the list stored at `key` and return the
+    /// length of the list.
+    ///
+    /// The values are inserted one after the other, so the last one ends up
+    /// first. A missing list is created.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let len = client.lpush("events", &["login".into()]).await.unwrap();
+    ///     println!("{} events", len);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn lpush(&mut self, key: &str, values: &[Bytes]) -> crate::Result<u64> {
+        let frame = LPush::new(key, values).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Insert `value` at the head of the list stored at `key`, then drop the
+    /// elements past the first `maxlen`, and return the length of the list.
+    ///
+    /// Both happen atomically, so the list keeps the `maxlen` most recent
+    /// values. A missing list is created, and the key is removed if `maxlen`
+    /// is `0`.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let len = client
+    ///         .lpushcapped("events", "login".into(), 100)
+    ///         .await
+    ///         .unwrap();
+    ///     println!("{} recent events", len);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn lpushcapped(
+        &mut self,
+        key: &str,
+        value: Bytes,
+        maxlen: u64,
+    ) -> crate::Result<u64> {
+        let frame = LPushCapped::new(key, value, maxlen).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Insert `values` at the tail of the list stored at `key` and return the
+    /// length of the list.
+    ///
+    /// A missing list is created.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let len = client.rpush("queue", &["job".into()]).await.unwrap();
+    ///     println!("{} jobs", len);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn rpush(&mut self, key: &str, values: &[Bytes]) -> crate::Result<u64> {
+        let frame = RPush::new(key, values).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the elements of the list stored at `key` between `start` and
+    /// `stop`, both inclusive.
+    ///
+    /// Negative offsets count back from the end of the list, `-1` being the
+    /// last element. A missing list is empty.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     for event in client.lrange("events", 0, -1).await.unwrap() {
+    ///         println!("{:?}", event);
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn lrange(&mut self, key: &str, start: i64, stop: i64) -> crate::Result<Vec<Bytes>> {
+        let frame = LRange::new(key, start, stop).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(value) => Ok(value),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the length of the list stored at `key`.
+    ///
+    /// `0` is returned if the key does not exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let len = client.llen("queue").await.unwrap();
+    ///     println!("{} jobs", len);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn llen(&mut self, key: &str) -> crate::Result<u64> {
+        let frame = LLen::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the element at `index` in the list stored at `key`.
+    ///
+    /// A negative index counts back from the end of the list, `-1` being the
+    /// last element. `None` is returned if the index is out of range.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let last = client.lindex("events", -1).await.unwrap();
+    ///     println!("{:?}", last);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn lindex(&mut self, key: &str, index: i64) -> crate::Result<Option<Bytes>> {
+        let frame = LIndex::new(key, index).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Remove and return the first element of the first non-empty list among
+    /// `keys`, along with the key it was popped from.
+    ///
+    /// If all the lists are empty, the call blocks until an element is pushed
+    /// to one of them or `timeout` elapses, in which case `None` is returned. A
+    /// zero `timeout` blocks forever. Clients blocked on the same list are
+    /// served in the order they blocked.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let timeout = Duration::from_secs(5);
+    ///     let popped = client.blpop(&["jobs"], timeout).await.unwrap();
+    ///     if let Some((key, job)) = popped {
+    ///         println!("Got {:?} from {}", job, key);
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn blpop(
+        &mut self,
+        keys: &[&str],
+        timeout: Duration,
+    ) -> crate::Result<Option<(String, Bytes)>> {
+        let frame = BLPop::new(keys, timeout).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(frames) => match frames.as_slice() {
+                [Frame::Bulk(key), Frame::Bulk(value)] => {
+                    Ok(Some((String::from_utf8(key.to_vec())?, value.clone())))
+                }
+                _ => Err(Frame::Array(frames.to_vec()).to_error()),
+            },
+            Frame::Null | Frame::NullArray => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Add `members` to the set stored at `key` and return how many were not
+    /// already members.
+    ///
+    /// A missing set is created.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let added = client.sadd("tags", &["rust".into()]).await.unwrap();
+    ///     println!("{} new tags", added);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn sadd(&mut self, key: &str, members: &[Bytes]) -> crate::Result<u64> {
+        let frame = SAdd::new(key, members).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(added) => Ok(added as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the members of the set stored at `key`, in no particular order.
+    ///
+    /// A missing set is empty.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     for tag in client.smembers("tags").await.unwrap() {
+    ///         println!("{:?}", tag);
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn smembers(&mut self, key: &str) -> crate::Result<Vec<Bytes>> {
+        let frame = SMembers::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(member) => Ok(member),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns `true` if `member` is a member of the set stored at `key`.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     if client.sismember("tags", "rust".into()).await.unwrap() {
+    ///         println!("tagged");
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn sismember(&mut self, key: &str, member: Bytes) -> crate::Result<bool> {
+        let frame = SIsMember::new(key, member).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(found) => Ok(found == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Remove `members` from the set stored at `key` and return how many were
+    /// members.
+    ///
+    /// The key is removed once the set is empty.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let removed = client.srem("tags", &["rust".into()]).await.unwrap();
+    ///     println!("{} tags removed", removed);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn srem(&mut self, key: &str, members: &[Bytes]) -> crate::Result<u64> {
+        let frame = SRem::new(key, members).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(removed) => Ok(removed as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the number of members of the set stored at `key`.
+    ///
+    /// `0` is returned if the key does not exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let count = client.scard("tags").await.unwrap();
+    ///     println!("{} tags", count);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn scard(&mut self, key: &str) -> crate::Result<u64> {
+        let frame = SCard::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Remove and return a random member of the set stored at `key`.
+    ///
+    /// `None` is returned if the key does not exist. The key is removed once
+    /// the set is empty.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let winner = client.spop("players").await.unwrap();
+    ///     println!("{:?}", winner);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn spop(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        let frame = SPop::new(key, None).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(member) => Ok(Some(member)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Remove and return up to `count` random members of the set stored at
+    /// `key`.
+    ///
+    /// Which members are popped, and their order, is not deterministic. The
+    /// key is removed once the set is empty.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let winners = client.spop_count("players", 3).await.unwrap();
+    ///     println!("{:?}", winners);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn spop_count(&mut self, key: &str, count: u64) -> crate::Result<Vec<Bytes>> {
+        let frame = SPop::new(key, Some(count)).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(member) => Ok(member),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the members of the intersection of the sets stored at `keys`.
+    ///
+    /// Missing keys are treated as empty sets. The members are returned in no
+    /// particular order.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let tags = client.sinter(&["tags:1", "tags:2"]).await.unwrap();
+    ///     println!("Shared tags: {:?}", tags);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn sinter(&mut self, keys: &[&str]) -> crate::Result<Vec<Bytes>> {
+        let frame = SInter::new(keys).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(member) => Ok(member),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the members of the union of the sets stored at `keys`.
+    ///
+    /// Missing keys are treated as empty sets. The members are returned in no
+    /// particular order.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let tags = client.sunion(&["tags:1", "tags:2"]).await.unwrap();
+    ///     println!("All tags: {:?}", tags);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn sunion(&mut self, keys: &[&str]) -> crate::Result<Vec<Bytes>> {
+        let frame = SUnion::new(keys).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(member) => Ok(member),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the members of the set stored at the first of `keys` that are in
+    /// none of the sets stored at the others.
+    ///
+    /// Missing keys are treated as empty sets. The members are returned in no
+    /// particular order.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let tags = client.sdiff(&["tags:1", "tags:2"]).await.unwrap();
+    ///     println!("Tags only on the first post: {:?}", tags);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn sdiff(&mut self, keys: &[&str]) -> crate::Result<Vec<Bytes>> {
+        let frame = SDiff::new(keys).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(member) => Ok(member),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Store the intersection of the sets stored at `keys` into `destination`.
+    ///
+    /// Whatever `destination` holds is replaced, or removed if the result is
+    /// empty. Returns the number of members stored.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let count = client.sinterstore("tags:all", &["tags:1", "tags:2"]).await.unwrap();
+    ///     println!("Stored {} tags", count);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn sinterstore(&mut self, destination: &str, keys: &[&str]) -> crate::Result<u64> {
+        let frame = SInterStore::new(destination, keys).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Store the union of the sets stored at `keys` into `destination`.
+    ///
+    /// Whatever `destination` holds is replaced, or removed if the result is
+    /// empty. Returns the number of members stored.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let count = client.sunionstore("tags:all", &["tags:1", "tags:2"]).await.unwrap();
+    ///     println!("Stored {} tags", count);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn sunionstore(&mut self, destination: &str, keys: &[&str]) -> crate::Result<u64> {
+        let frame = SUnionStore::new(destination, keys).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Store the members of the set stored at the first of `keys` that are in
+    /// none of the sets stored at the others into `destination`.
+    ///
+    /// Whatever `destination` holds is replaced, or removed if the result is
+    /// empty. Returns the number of members stored.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let count = client.sdiffstore("tags:all", &["tags:1", "tags:2"]).await.unwrap();
+    ///     println!("Stored {} tags", count);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn sdiffstore(&mut self, destination: &str, keys: &[&str]) -> crate::Result<u64> {
+        let frame = SDiffStore::new(destination, keys).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Add `members`, each with its score, to the sorted set stored at `key`
+    /// and return how many were not already members.
+    ///
+    /// The score of existing members is updated. A missing sorted set is
+    /// created.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let scores = [(10.0, "alice".into()), (8.5, "bob".into())];
+    ///     let added = client.zadd("scores", &scores).await.unwrap();
+    ///     println!("{} new players", added);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zadd(&mut self, key: &str, members: &[(f64, Bytes)]) -> crate::Result<u64> {
+        let frame = ZAdd::new(key, members).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(added) => Ok(added as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the members of the sorted set stored at `key` between `start`
+    /// and `stop`, both inclusive.
+    ///
+    /// Members are ordered by ascending score, and lexicographically when
+    /// scores are equal. Negative offsets count back from the end, `-1` being
+    /// the last member.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let players = client.zrange("scores", 0, -1).await.unwrap();
+    ///     println!("{:?}", players);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zrange(&mut self, key: &str, start: i64, stop: i64) -> crate::Result<Vec<Bytes>> {
+        let frame = ZRange::new(key, start, stop, false).into_frame();
+        self.members_cmd(frame).await
+    }
+
+    /// Returns the members of the sorted set stored at `key` between `start`
+    /// and `stop`, both inclusive, along with their score.
+    ///
+    /// Members are ordered as by [`zrange`](Client::zrange).
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let top = client.zrange_with_scores("scores", -3, -1).await.unwrap();
+    ///     for (player, score) in top {
+    ///         println!("{:?}: {}", player, score);
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zrange_with_scores(
+        &mut self,
+        key: &str,
+        start: i64,
+        stop: i64,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let frame = ZRange::new(key, start, stop, true).into_frame();
+        self.scored_members_cmd(frame).await
+    }
+
+    /// Returns the number of members of the sorted set stored at `key` whose
+    /// score is between `min` and `max`.
+    ///
+    /// Each bound is either inclusive or exclusive, and may be infinite. A
+    /// missing key counts no members.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    /// use mini_redis::cmd::ScoreBound;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let min = ScoreBound::Exclusive(100.0);
+    ///     let max = ScoreBound::Inclusive(f64::INFINITY);
+    ///     let count = client.zcount("scores", min, max).await.unwrap();
+    ///     println!("{} players scored over 100", count);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zcount(
+        &mut self,
+        key: &str,
+        min: ScoreBound,
+        max: ScoreBound,
+    ) -> crate::Result<u64> {
+        let frame = ZCount::new(key, min, max).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the score of `member` in the sorted set stored at `key`.
+    ///
+    /// `None` is returned if the key does not exist or `member` is not a
+    /// member.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let score = client.zscore("scores", "alice".into()).await.unwrap();
+    ///     println!("{:?}", score);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zscore(&mut self, key: &str, member: Bytes) -> crate::Result<Option<f64>> {
+        let frame = ZScore::new(key, member).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(score) => Ok(Some(str::from_utf8(&score)?.parse()?)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Add `increment` to the score of `member` in the sorted set stored at
+    /// `key` and return the new score.
+    ///
+    /// A missing member is added with `increment` as its score, and a missing
+    /// sorted set is created.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let new = client.zincrby("scores", 2.5, "alice".into()).await.unwrap();
+    ///     println!("alice now has {}", new);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zincrby(
+        &mut self,
+        key: &str,
+        increment: f64,
+        member: Bytes,
+    ) -> crate::Result<f64> {
+        let frame = ZIncrBy::new(key, increment, member).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(score) => Ok(str::from_utf8(&score)?.parse()?),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the rank of `member` in the sorted set stored at `key`, `0`
+    /// being the member with the lowest score.
+    ///
+    /// `None` is returned if the key does not exist or `member` is not a
+    /// member.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let rank = client.zrank("scores", "alice".into()).await.unwrap();
+    ///     println!("{:?}", rank);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zrank(&mut self, key: &str, member: Bytes) -> crate::Result<Option<u64>> {
+        let frame = ZRank::new(key, member).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(rank) => Ok(Some(rank as u64)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Remove `members` from the sorted set stored at `key` and return how
+    /// many were members.
+    ///
+    /// The key is removed once the sorted set is empty.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let count = client.zrem("scores", &["alice".into()]).await.unwrap();
+    ///     println!("{} players removed", count);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zrem(&mut self, key: &str, members: &[Bytes]) -> crate::Result<u64> {
+        let frame = ZRem::new(key, members).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(removed) => Ok(removed as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Remove the members of the sorted set stored at `key` ranked between
+    /// `start` and `stop`, both inclusive, and return how many were removed.
+    ///
+    /// Ranks are 0-based, in ascending order of score. Negative offsets count
+    /// back from the highest ranked member. The key is removed once the sorted
+    /// set is empty.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     // Keep the top 10 players only.
+    ///     let removed = client.zremrangebyrank("scores", 0, -11).await.unwrap();
+    ///     println!("Removed {} players", removed);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zremrangebyrank(
+        &mut self,
+        key: &str,
+        start: i64,
+        stop: i64,
+    ) -> crate::Result<u64> {
+        let frame = ZRemRangeByRank::new(key, start, stop).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Remove the members of the sorted set stored at `key` whose score is
+    /// between `min` and `max`, and return how many were removed.
+    ///
+    /// Bounds are handled as by [`zcount`](Client::zcount). The key is removed
+    /// once the sorted set is empty.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    /// use mini_redis::cmd::ScoreBound;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let min = ScoreBound::Inclusive(f64::NEG_INFINITY);
+    ///     let max = ScoreBound::Exclusive(0.0);
+    ///     let removed = client.zremrangebyscore("scores", min, max).await.unwrap();
+    ///     println!("Removed {} players", removed);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zremrangebyscore(
+        &mut self,
+        key: &str,
+        min: ScoreBound,
+        max: ScoreBound,
+    ) -> crate::Result<u64> {
+        let frame = ZRemRangeByScore::new(key, min, max).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Store the union of the sorted sets stored at `keys` into `destination`.
+    ///
+    /// Plain sets are accepted as sources, each member scoring `1`. The scores
+    /// of each source are multiplied by its weight in `weights`, `1` if not
+    /// given, and the scores of a member found in several sources are combined
+    /// with `aggregate`. Whatever `destination` holds is replaced, or removed
+    /// if the result is empty. Returns the number of members stored.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    /// use mini_redis::cmd::Aggregate;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let keys = ["scores:1", "scores:2"];
+    ///     let count = client
+    ///         .zunionstore("scores", &keys, Some(&[1.0, 2.0]), Aggregate::Max)
+    ///         .await
+    ///         .unwrap();
+    ///     println!("Stored {} players", count);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zunionstore(
+        &mut self,
+        destination: &str,
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Aggregate,
+    ) -> crate::Result<u64> {
+        let frame = ZUnionStore::new(destination, keys, weights, aggregate).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Store the intersection of the sorted sets stored at `keys` into
+    /// `destination`.
+    ///
+    /// Plain sets are accepted as sources, each member scoring `1`. The scores
+    /// of each source are multiplied by its weight in `weights`, `1` if not
+    /// given, and the scores of a member found in several sources are combined
+    /// with `aggregate`. Whatever `destination` holds is replaced, or removed
+    /// if the result is empty. Returns the number of members stored.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    /// use mini_redis::cmd::Aggregate;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let keys = ["scores:1", "scores:2"];
+    ///     let count = client
+    ///         .zinterstore("scores", &keys, Some(&[1.0, 2.0]), Aggregate::Max)
+    ///         .await
+    ///         .unwrap();
+    ///     println!("Stored {} players", count);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zinterstore(
+        &mut self,
+        destination: &str,
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Aggregate,
+    ) -> crate::Result<u64> {
+        let frame = ZInterStore::new(destination, keys, weights, aggregate).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the union of the sorted sets stored at `keys`, in ascending
+    /// order of score.
+    ///
+    /// Plain sets are accepted as sources, each member scoring `1`. The scores
+    /// of each source are multiplied by its weight in `weights`, `1` if not
+    /// given, and the scores of a member found in several sources are combined
+    /// with `aggregate`.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    /// use mini_redis::cmd::Aggregate;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let keys = ["scores:1", "scores:2"];
+    ///     let players = client.zunion(&keys, None, Aggregate::Sum).await.unwrap();
+    ///     println!("{:?}", players);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zunion(
+        &mut self,
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Aggregate,
+    ) -> crate::Result<Vec<Bytes>> {
+        let frame = ZUnion::new(keys, weights, aggregate, false).into_frame();
+        self.members_cmd(frame).await
+    }
+
+    /// Returns the union of the sorted sets stored at `keys` along with
+    /// their score.
+    ///
+    /// Members and scores are computed as by [`zunion`](Client::zunion).
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    /// use mini_redis::cmd::Aggregate;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let keys = ["scores:1", "scores:2"];
+    ///     let players = client
+    ///         .zunion_with_scores(&keys, Some(&[1.0, 2.0]), Aggregate::Max)
+    ///         .await
+    ///         .unwrap();
+    ///     for (player, score) in players {
+    ///         println!("{:?}: {}", player, score);
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zunion_with_scores(
+        &mut self,
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Aggregate,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let frame = ZUnion::new(keys, weights, aggregate, true).into_frame();
+        self.scored_members_cmd(frame).await
+    }
+
+    /// Returns the intersection of the sorted sets stored at `keys`, in ascending
+    /// order of score.
+    ///
+    /// Sources, weights and scores are handled as by
+    /// [`zunion`](Client::zunion).
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    /// use mini_redis::cmd::Aggregate;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let keys = ["scores:1", "scores:2"];
+    ///     let players = client.zinter(&keys, None, Aggregate::Sum).await.unwrap();
+    ///     println!("{:?}", players);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zinter(
+        &mut self,
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Aggregate,
+    ) -> crate::Result<Vec<Bytes>> {
+        let frame = ZInter::new(keys, weights, aggregate, false).into_frame();
+        self.members_cmd(frame).await
+    }
+
+    /// Returns the intersection of the sorted sets stored at `keys` along with
+    /// their score.
+    ///
+    /// Members and scores are computed as by [`zinter`](Client::zinter).
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    /// use mini_redis::cmd::Aggregate;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let keys = ["scores:1", "scores:2"];
+    ///     let players = client
+    ///         .zinter_with_scores(&keys, Some(&[1.0, 2.0]), Aggregate::Max)
+    ///         .await
+    ///         .unwrap();
+    ///     for (player, score) in players {
+    ///         println!("{:?}: {}", player, score);
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zinter_with_scores(
+        &mut self,
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Aggregate,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let frame = ZInter::new(keys, weights, aggregate, true).into_frame();
+        self.scored_members_cmd(frame).await
+    }
+
+    /// Returns the members of the sorted set stored at the first of `keys` that
+    /// are in none of the sorted sets stored at the others, in ascending order
+    /// of score.
+    ///
+    /// Plain sets are accepted as sources.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let players = client.zdiff(&["scores", "banned"]).await.unwrap();
+    ///     println!("{:?}", players);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zdiff(&mut self, keys: &[&str]) -> crate::Result<Vec<Bytes>> {
+        let frame = ZDiff::new(keys, false).into_frame();
+        self.members_cmd(frame).await
+    }
+
+    /// Returns the members of the sorted set stored at the first of `keys` that
+    /// are in none of the sorted sets stored at the others, along with their
+    /// score.
+    ///
+    /// Members are computed as by [`zdiff`](Client::zdiff).
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let players = client
+    ///         .zdiff_with_scores(&["scores", "banned"])
+    ///         .await
+    ///         .unwrap();
+    ///     for (player, score) in players {
+    ///         println!("{:?}: {}", player, score);
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn zdiff_with_scores(&mut self, keys: &[&str]) -> crate::Result<Vec<(Bytes, f64)>> {
+        let frame = ZDiff::new(keys, true).into_frame();
+        self.scored_members_cmd(frame).await
+    }
+
+    /// Issue a command replying with sorted set members and return them.
+    async fn members_cmd(&mut self, frame: Frame) -> crate::Result<Vec<Bytes>> {
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(member) => Ok(member),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Issue a command replying with sorted set members, each followed by its
+    /// score, and return them.
+    async fn scored_members_cmd(&mut self, frame: Frame) -> crate::Result<Vec<(Bytes, f64)>> {
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        let frames = match self.read_response().await? {
+            Frame::Array(frames) if frames.len() % 2 == 0 => frames,
+            frame => return Err(frame.to_error()),
+        };
+
+        let mut members = Vec::with_capacity(frames.len() / 2);
+        let mut frames = frames.into_iter();
+
+        while let (Some(member), Some(score)) = (frames.next(), frames.next()) {
+            match (member, score) {
+                (Frame::Bulk(member), Frame::Bulk(score)) => {
+                    members.push((member, str::from_utf8(&score)?.parse()?))
+                }
+                (_, frame) => return Err(frame.to_error()),
+            }
+        }
+
+        Ok(members)
+    }
+
+    /// Get the longest common subsequence of the values of `key1` and `key2`.
+    ///
+    /// Missing keys are treated as empty values.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.set("key1", "ohmytext".into()).await.unwrap();
+    ///     client.set("key2", "mynewtext".into()).await.unwrap();
+    ///
+    ///     // "mytext"
+    ///     let val = client.lcs("key1", "key2").await.unwrap();
+    ///     println!("Got = {:?}", val);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn lcs(&mut self, key1: &str, key2: &str) -> crate::Result<Bytes> {
+        match self.lcs_cmd(Lcs::new(key1, key2)).await? {
+            Frame::Simple(value) => Ok(value.into()),
+            Frame::Bulk(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Get the length of the longest common subsequence of the values of
+    /// `key1` and `key2`.
+    #[instrument(skip(self))]
+    pub async fn lcs_len(&mut self, key1: &str, key2: &str) -> crate::Result<u64> {
+        match self.lcs_cmd(Lcs::new(key1, key2).len_only()).await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Get the positions of the longest common subsequence of the values of
+    /// `key1` and `key2`.
+    ///
+    /// Returns the contiguous matches of at least `min_match_len` bytes, from
+    /// the last one to the first one, and the length of the subsequence.
+    #[instrument(skip(self))]
+    pub async fn lcs_idx(
+        &mut self,
+        key1: &str,
+        key2: &str,
+        min_match_len: u64,
+    ) -> crate::Result<(Vec<LcsMatch>, u64)> {
+        let response = self
+            .lcs_cmd(Lcs::new(key1, key2).with_idx(min_match_len))
+            .await?;
+
+        // The server responds with an array frame in the form of:
+        //
+        // ```
+        // [ "matches", [ [ [a_start, a_end], [b_start, b_end], len ], ... ], "len", len ]
+        // ```
+        let parts = match response {
+            Frame::Array(ref parts) => parts,
+            frame => return Err(frame.to_error()),
+        };
+
+        match parts.as_slice() {
+            [matches_key, Frame::Array(matches), len_key, Frame::Integer(len)]
+                if *matches_key == "matches" && *len_key == "len" =>
+            {
+                let matches = matches
+                    .iter()
+                    .map(lcs_match)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| response.to_error())?;
+
+                Ok((matches, *len as u64))
+            }
+            _ => Err(response.to_error()),
+        }
+    }
+
+    /// Issue an `LCS` command and return the response.
+    async fn lcs_cmd(&mut self, cmd: Lcs) -> crate::Result<Frame> {
+        let frame = cmd.into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        self.read_response().await
+    }
+
+    /// Set `key` to hold the given `value`.
+    ///
+    /// The `value` is associated with `key` until it is overwritten by the next
+    /// call to `set` or it is removed.
+    ///
+    /// If key already holds a value, it is overwritten. Any previous time to
+    /// live associated with the key is discarded on successful SET operation.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.set("foo", "bar".into()).await.unwrap();
+    ///
+    ///     // Getting the value immediately works
+    ///     let val = client.get("foo").await.unwrap().unwrap();
+    ///     assert_eq!(val, "bar");
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn set(&mut self, key: &str, value: Bytes) -> crate::Result<()> {
+        // Create a `Set` command and pass it to `set_cmd`. A separate method is
+        // used to set a value with an expiration. The common parts of both
+        // functions are implemented by `set_cmd`.
+        self.set_cmd(Set::new(key, value, None)).await
+    }
+
+    /// Set `key` to hold the given `value`. The value expires after `expiration`
+    ///
+    /// The `value` is associated with `key` until one of the following:
+    /// - it expires.
+    /// - it is overwritten by the next call to `set`.
+    /// - it is removed.
+    ///
+    /// If key already holds a value, it is overwritten. Any previous time to
+    /// live associated with the key is discarded on a successful SET operation.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage. This example is not **guaranteed** to always
+    /// work as it relies on time based logic and assumes the client and server
+    /// stay relatively synchronized in time. The real world tends to not be so
+    /// favorable.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    /// use tokio::time;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let ttl = Duration::from_millis(500);
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.set_expires("foo", "bar".into(), ttl).await.unwrap();
+    ///
+    ///     // Getting the value immediately works
+    ///     let val = client.get("foo").await.unwrap().unwrap();
+    ///     assert_eq!(val, "bar");
+    ///
+    ///     // Wait for the TTL to expire
+    ///     time::sleep(ttl).await;
+    ///
+    ///     let val = client.get("foo").await.unwrap();
+    ///     assert!(val.is_some());
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn set_expires(
+        &mut self,
+        key: &str,
+        value: Bytes,
+        expiration: Duration,
+    ) -> crate::Result<()> {
+        // Create a `Set` command and pass it to `set_cmd`. A separate method is
+        // used to set a value with an expiration. The common parts of both
+        // functions are implemented by `set_cmd`.
+        self.set_cmd(Set::new(key, value, Some(expiration))).await
+    }
+
+    /// The core `SET` logic, used by both `set` and `set_expires.
+    async fn set_cmd(&mut self, cmd: Set) -> crate::Result<()> {
+        // Convert the `Set` command into a frame
+        let frame = cmd.into_frame();
+
+        debug!(request = ?frame);
+
+        // Write the frame to the socket. This writes the full frame to the
+        // socket, waiting if necessary.
+        self.connection.write_frame(&frame).await?;
+
+        // Wait for the response from the server. On success, the server
+        // responds simply with `OK`. Any other response indicates an error.
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the keys of the command invocation `args`, without executing it.
+    ///
+    /// `args` is a full invocation, starting with the command name. An error is
+    /// returned if the command is unknown or has no key arguments.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let args = vec!["set".into(), "foo".into(), "bar".into()];
+    ///     let keys = client.command_getkeys(args).await.unwrap();
+    ///     println!("Got = {:?}", keys);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn command_getkeys(&mut self, args: Vec<Bytes>) -> crate::Result<Vec<Bytes>> {
+        let frame = CommandGetKeys::new(args).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(key) => Ok(key),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns information and statistics about the server.
+    ///
+    /// Only `section` is returned if provided, otherwise all sections are.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let info = client.info(Some("commandstats")).await.unwrap();
+    ///     println!("{}", String::from_utf8_lossy(&info));
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn info(&mut self, section: Option<&str>) -> crate::Result<Bytes> {
+        let frame = Info::new(section.map(str::to_string)).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(value.into()),
+            Frame::Bulk(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Resets the statistics reported by `info`.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     client.config_resetstat().await.unwrap();
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn config_resetstat(&mut self) -> crate::Result<()> {
+        let frame = ConfigResetStat::new().into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Posts `message` to the given `channel`.
+    ///
+    /// Returns the number of subscribers currently listening on the channel.
+    /// There is no guarantee that these subscribers receive the message as they
+    /// may disconnect at any time.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let val = client.publish("foo", "bar".into()).await.unwrap();
+    ///     println!("Got = {:?}", val);
+    /// }
+    /// ```
+    #[instrument(skip(self, channel))]
+    pub async fn publish(
+        &mut self,
+        channel: impl AsRef<[u8]>,
+        message: Bytes,
+    ) -> crate::Result<u64> {
+        // Convert the `Publish` command into a frame. Channel names are binary
+        // safe.
+        let channel = Bytes::copy_from_slice(channel.as_ref());
+        let frame = Publish::new(channel, message).into_frame();
+
+        debug!(request = ?frame);
+
+        // Write the frame to the socket
+        self.connection.write_frame(&frame).await?;
+
+        // Read the response
+        match self.read_response().await? {
+            Frame::Integer(response) => Ok(response as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the channels with at least one subscriber, in byte order.
+    ///
+    /// Only the channels matching `pattern`, a glob-style pattern, are
+    /// returned if provided. Pattern subscriptions are not reported.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let channels = client.pubsub_channels(Some("news.*")).await.unwrap();
+    ///     println!("Got = {:?}", channels);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn pubsub_channels(&mut self, pattern: Option<&str>) -> crate::Result<Vec<Bytes>> {
+        let pattern = pattern.map(|pattern| Bytes::copy_from_slice(pattern.as_bytes()));
+        let frame = PubSub::channels(pattern).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(channel) => Ok(channel),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the number of subscribers of each of `channels`, in order.
+    ///
+    /// Channels without subscribers are reported with a count of `0`.
+    /// Pattern subscriptions are not counted.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let counts = client.pubsub_numsub(&["news"]).await.unwrap();
+    ///     println!("Got = {:?}", counts);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn pubsub_numsub(&mut self, channels: &[&str]) -> crate::Result<Vec<(Bytes, u64)>> {
+        let channels = channels
+            .iter()
+            .map(|channel| Bytes::copy_from_slice(channel.as_bytes()))
+            .collect();
+        let frame = PubSub::numsub(channels).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        // The response alternates channels and counts.
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .chunks(2)
+                .map(|pair| match pair {
+                    [Frame::Bulk(channel), Frame::Integer(count)] => {
+                        Ok((channel.clone(), *count as u64))
+                    }
+                    _ => Err(Frame::Array(pair.to_vec()).to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Subscribes the client to the specified channels.
+    ///
+    /// Once a client issues a subscribe command, it may no longer issue any
+    /// non-pub/sub commands. The function consumes `self` and returns a `Subscriber`.
+    ///
+    /// The `Subscriber` value is used to receive messages as well as manage the
+    /// list of channels the client is subscribed to.
+    #[instrument(skip(self))]
+    pub async fn subscribe(mut self, channels: Vec<Bytes>) -> crate::Result<Subscriber> {
+        // Issue the subscribe command to the server and wait for confirmation.
+        // The client will then have been transitioned into the "subscriber"
+        // state and may only issue pub/sub commands from that point on.
+        let mut subscribed_channels = vec![];
+        let mut confirmations = vec![];
+        self.subscribe_cmd(
+            Subscribe::new(channels),
+            &mut subscribed_channels,
+            &mut confirmations,
+        )
+        .await?;
+
+        // Return the `Subscriber` type
+        Ok(Subscriber {
+            client: self,
+            subscribed_channels,
+            confirmations,
+        })
+    }
+
+    /// Subscribes the client to `channel`, first receiving up to `count` of
+    /// the messages last published on it.
+    ///
+    /// The backlogged messages are returned by `Subscriber::next_message`,
+    /// oldest first, before any message published after subscribing. No
+    /// message is replayed unless the server keeps a backlog.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let mut subscriber = client.subscribe_replay("news".into(), 10).await.unwrap();
+    ///     let msg = subscriber.next_message().await.unwrap();
+    ///     println!("Got = {:?}", msg);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn subscribe_replay(
+        mut self,
+        channel: Bytes,
+        count: u64,
+    ) -> crate::Result<Subscriber> {
+        let mut subscribed_channels = vec![];
+        let mut confirmations = vec![];
+        self.subscribe_cmd(
+            Subscribe::replay(channel, count as usize),
+            &mut subscribed_channels,
+            &mut confirmations,
+        )
+        .await?;
+
+        Ok(Subscriber {
+            client: self,
+            subscribed_channels,
+            confirmations,
+        })
+    }
+
+    /// Subscribes the client to every channel matching one of `patterns`.
+    ///
+    /// Patterns use the glob-style syntax of `SCAN MATCH`, for example
+    /// `news.*`. As with `subscribe`, the function consumes `self` and returns
+    /// a `PatternSubscriber`, which yields each message along with the pattern
+    /// and the channel it matched.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let mut subscriber = client.psubscribe(vec!["news.*".into()]).await.unwrap();
+    ///     let msg = subscriber.next_message().await.unwrap();
+    ///     println!("Got = {:?}", msg);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn psubscribe(mut self, patterns: Vec<Bytes>) -> crate::Result<PatternSubscriber> {
+        let mut subscribed_patterns = vec![];
+        let mut confirmations = vec![];
+        self.subscribe_frame(
+            "psubscribe",
+            Psubscribe::new(patterns.clone()).into_frame(),
+            &patterns,
+            &mut subscribed_patterns,
+            &mut confirmations,
+        )
+        .await?;
+
+        Ok(PatternSubscriber {
+            client: self,
+            subscribed_patterns,
+            confirmations,
+        })
+    }
+
+    /// The core `SUBSCRIBE` logic, used by misc subscribe fns
+    ///
+    /// Each channel confirmed by the server is pushed onto `subscribed`, and
+    /// its confirmation onto `confirmations`. If the server rejects a channel,
+    /// the remaining responses are still consumed and the first error is
+    /// returned.
+    async fn subscribe_cmd(
+        &mut self,
+        cmd: Subscribe,
+        subscribed: &mut Vec<Bytes>,
+        confirmations: &mut Vec<Confirmation>,
+    ) -> crate::Result<()> {
+        let channels = cmd.channels().to_vec();
+
+        // Convert the `Subscribe` command into a frame
+        let frame = cmd.into_frame();
+
+        self.subscribe_frame("subscribe", frame, &channels, subscribed, confirmations)
+            .await
+    }
+
+    /// Send a `SUBSCRIBE` or `PSUBSCRIBE` request, then read the confirmation
+    /// of each of `channels`, which are patterns for `PSUBSCRIBE`.
+    ///
+    /// `kind` is the name of the request, which the server repeats in each
+    /// confirmation.
+    async fn subscribe_frame(
+        &mut self,
+        kind: &str,
+        frame: Frame,
+        channels: &[Bytes],
+        subscribed: &mut Vec<Bytes>,
+        confirmations: &mut Vec<Confirmation>,
+    ) -> crate::Result<()> {
+        debug!(request = ?frame);
+
+        // Write the frame to the socket
+        self.connection.write_frame(&frame).await?;
+
+        let mut result = Ok(());
+
+        // For each channel being subscribed to, the server responds with a
+        // message confirming subscription to that channel.
+        for channel in channels {
+            // Read the response
+            let response = match self.read_response().await {
+                Ok(response) => response,
+                // The server refused this channel, for example because the
+                // connection reached its subscription limit. Other channels
+                // may still have been accepted, so keep reading.
+                Err(err) => {
+                    if result.is_ok() {
+                        result = Err(err);
+                    }
+                    continue;
+                }
+            };
+
+            // Verify it is confirmation of subscription.
             match response {
                 Frame::Array(ref frame) => match frame.as_slice() {
                     // The server responds with an array frame in the form of:
@@ -358,143 +3734,890 @@ impl Client {
                     // where channel is the name of the channel and
                     // num-subscribed is the number of channels that the client
                     // is currently subscribed to.
-                    [subscribe, schannel, ..]
-                        if *subscribe == "subscribe" && *schannel == channel => {}
+                    [subscribe, Frame::Bulk(schannel), Frame::Integer(count)]
+                        if *subscribe == kind && schannel == channel =>
+                    {
+                        subscribed.push(channel.clone());
+                        confirmations.push(Confirmation {
+                            channel: channel.clone(),
+                            count: *count as u64,
+                        });
+                    }
+                    _ => return Err(response.to_error()),
+                },
+                frame => return Err(frame.to_error()),
+            };
+        }
+
+        result
+    }
+
+    /// Send an `UNSUBSCRIBE` or `PUNSUBSCRIBE` request, then read `num`
+    /// confirmations, removing each unsubscribed channel, or pattern, from
+    /// `subscribed`.
+    ///
+    /// `kind` is the name of the request, which the server repeats in each
+    /// confirmation.
+    async fn unsubscribe_frame(
+        &mut self,
+        kind: &str,
+        frame: Frame,
+        num: usize,
+        subscribed: &mut Vec<Bytes>,
+        confirmations: &mut Vec<Confirmation>,
+    ) -> crate::Result<()> {
+        debug!(request = ?frame);
+
+        // Write the frame to the socket
+        self.connection.write_frame(&frame).await?;
+
+        // Read the response
+        for _ in 0..num {
+            let response = self.read_response().await?;
+
+            match response {
+                Frame::Array(ref frame) => match frame.as_slice() {
+                    [unsubscribe, Frame::Bulk(channel), Frame::Integer(count)]
+                        if *unsubscribe == kind =>
+                    {
+                        // The server also confirms channels which were not
+                        // subscribed to, leaving the list unchanged.
+                        subscribed.retain(|c| c != channel);
+
+                        confirmations.push(Confirmation {
+                            channel: channel.clone(),
+                            count: *count as u64,
+                        });
+                    }
                     _ => return Err(response.to_error()),
                 },
                 frame => return Err(frame.to_error()),
             };
         }
 
-        Ok(())
+        Ok(())
+    }
+
+    /// Send every command produced by `commands` to the server, keeping at
+    /// most `max_in_flight` commands awaiting a response at any time.
+    ///
+    /// Commands are written back to back without waiting for each response,
+    /// which is much faster than issuing them one by one. Once `max_in_flight`
+    /// responses are outstanding, the next response is read before another
+    /// command is sent. This bounds the memory used on both ends, so streams
+    /// of millions of commands can be loaded. A `max_in_flight` of zero is
+    /// treated as one.
+    ///
+    /// Each command is a request `Frame`, as sent by the other `Client`
+    /// methods. Successful responses are discarded.
+    ///
+    /// # Returns
+    ///
+    /// The commands rejected by the server, with their position in the
+    /// stream. `Err` is returned if the connection fails, in which case the
+    /// remaining commands are not sent.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mini_redis::{clients::Client, Frame};
+    /// use tokio_stream::StreamExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let commands = tokio_stream::iter(0..1000).map(|i| {
+    ///         Frame::Array(vec![
+    ///             Frame::Bulk("set".into()),
+    ///             Frame::Bulk(format!("key:{}", i).into()),
+    ///             Frame::Bulk("value".into()),
+    ///         ])
+    ///     });
+    ///
+    ///     let errors = client.bulk_load(commands, 64).await.unwrap();
+    ///     assert!(errors.is_empty());
+    /// }
+    /// ```
+    #[instrument(skip(self, commands))]
+    pub async fn bulk_load(
+        &mut self,
+        commands: impl Stream<Item = Frame>,
+        max_in_flight: usize,
+    ) -> crate::Result<Vec<BulkLoadError>> {
+        let max_in_flight = max_in_flight.max(1);
+
+        let mut errors = vec![];
+
+        // Index of the next command whose response is expected. Responses
+        // arrive in the same order the commands are sent.
+        let mut next_response = 0;
+        let mut sent = 0;
+
+        tokio::pin!(commands);
+
+        while let Some(frame) = commands.next().await {
+            if sent - next_response == max_in_flight {
+                // The window is full. Send the buffered commands and wait for
+                // the oldest one to complete before sending more.
+                self.connection.flush().await?;
+                let read = self.read_bulk_load_response(next_response, &mut errors);
+                if let Err(err) = read.await {
+                    self.abandon(&err, sent - next_response);
+                    return Err(err);
+                }
+                next_response += 1;
+            }
+
+            self.connection.feed_frame(&frame).await?;
+            sent += 1;
+        }
+
+        // Drain the responses to the remaining commands.
+        self.connection.flush().await?;
+
+        while next_response < sent {
+            let read = self.read_bulk_load_response(next_response, &mut errors);
+            if let Err(err) = read.await {
+                self.abandon(&err, sent - next_response);
+                return Err(err);
+            }
+            next_response += 1;
+        }
+
+        Ok(errors)
+    }
+
+    /// Read the response to the `index`th command sent by `bulk_load`,
+    /// recording it in `errors` if the server rejected the command.
+    async fn read_bulk_load_response(
+        &mut self,
+        index: usize,
+        errors: &mut Vec<BulkLoadError>,
+    ) -> crate::Result<()> {
+        let response = self.read_frame().await?;
+
+        debug!(?response);
+
+        match response {
+            Some(Frame::Error(message)) => {
+                errors.push(BulkLoadError { index, message });
+                Ok(())
+            }
+            Some(_) => Ok(()),
+            None => {
+                // The server closed the connection before responding to every
+                // command.
+                let err = Error::new(ErrorKind::ConnectionReset, "connection reset by server");
+
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Reads a frame from the socket, waiting at most for the timeout set with
+    /// `set_timeout`.
+    ///
+    /// The responses owed to requests which timed out arrive first. They are
+    /// discarded.
+    async fn read_frame(&mut self) -> crate::Result<Option<Frame>> {
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return self.read_current_frame().await,
+        };
+
+        // `read_current_frame` may be cancelled at any point: frames are only
+        // removed from the read buffer once fully received.
+        match time::timeout(timeout, self.read_current_frame()).await {
+            Ok(frame) => frame,
+            Err(_) => Err(ClientError::Timeout.into()),
+        }
+    }
+
+    /// Reads the next frame which is not owed to a request which timed out.
+    async fn read_current_frame(&mut self) -> crate::Result<Option<Frame>> {
+        loop {
+            let frame = self.connection.read_frame().await?;
+
+            if frame.is_some() && self.stale > 0 {
+                debug!(discarded = ?frame);
+                self.stale -= 1;
+                continue;
+            }
+
+            return Ok(frame);
+        }
+    }
+
+    /// Records that `count` responses are still owed by the server if `err`
+    /// is a timeout, so that they are discarded once they arrive.
+    fn abandon(&mut self, err: &crate::Error, count: usize) {
+        if is_timeout(err) {
+            self.stale += count;
+        }
+    }
+
+    /// Start building an arbitrary command named `name`.
+    ///
+    /// Arguments are added with [`Cmd::arg`] and the command is sent with
+    /// [`Cmd::query`]. This allows issuing commands which do not have a
+    /// dedicated method on `Client`.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let val = client.cmd("GET").arg("foo").query().await.unwrap();
+    ///     println!("Got = {:?}", val);
+    /// }
+    /// ```
+    pub fn cmd(&mut self, name: &str) -> Cmd<'_> {
+        Cmd {
+            client: self,
+            name: name.to_lowercase(),
+            args: vec![Bytes::copy_from_slice(name.as_bytes())],
+        }
+    }
+
+    /// Start building a pipeline, a batch of commands sent together.
+    ///
+    /// Commands are added with [`Pipeline::cmd`] or [`Pipeline::frame`] and
+    /// sent with [`Pipeline::query`]. All of them are written to the socket at
+    /// once, then the responses are read, which saves a round trip per
+    /// command.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let responses = client
+    ///         .pipeline()
+    ///         .cmd(["SET", "foo", "bar"])
+    ///         .cmd(["GET", "foo"])
+    ///         .query()
+    ///         .await
+    ///         .unwrap();
+    ///     println!("Got = {:?}", responses);
+    /// }
+    /// ```
+    pub fn pipeline(&mut self) -> Pipeline<'_> {
+        Pipeline {
+            client: self,
+            frames: vec![],
+        }
+    }
+
+    /// Reads a response frame from the socket.
+    ///
+    /// If an `Error` frame is received, it is converted to `Err`.
+    async fn read_response(&mut self) -> crate::Result<Frame> {
+        let response = match self.read_frame().await {
+            Ok(response) => response,
+            Err(err) => {
+                self.abandon(&err, 1);
+                return Err(err);
+            }
+        };
+
+        debug!(?response);
+
+        match response {
+            // Error frames are converted to `Err`
+            Some(Frame::Error(msg)) => Err(msg.into()),
+            Some(frame) => Ok(frame),
+            None => {
+                // Receiving `None` here indicates the server has closed the
+                // connection without sending a frame. This is unexpected and is
+                // represented as a "connection reset by peer" error.
+                let err = Error::new(ErrorKind::ConnectionReset, "connection reset by server");
+
+                Err(err.into())
+            }
+        }
+    }
+
+
+    pub async fn hset(&mut self, p0: &String, p1: &String, p2: Bytes) -> crate::Result<()> {
+        let frame = HSet::new(p0, p1, p2).into_frame();
+
+        debug!(request = ?frame);
+
+        // Write the frame to the socket
+        self.connection.write_frame(&frame).await?;
+
+        // Read the response from the server
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    ///hget
+    pub async fn hget(&mut self, p0: &String, p1: &String) -> crate::Result<Option<Bytes>> {
+        let frame = HGet::new(p0.to_string(), p1.to_string()).into_frame();
+
+        debug!(request = ?frame);
+
+        // Write the frame to the socket
+        self.connection.write_frame(&frame).await?;
+
+        // Read the response from the server
+        match self.read_response().await? {
+            Frame::Bulk(response) => Ok(Some(response)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+
+    }
+
+    ///hgetall
+    pub async fn hgetall(&mut self, p0: &String) -> crate::Result<Option<HashMap<String, Bytes>>> {
+        let frame = HGetAll::new(p0.to_string()).into_frame();
+
+        debug!(request = ?frame);
+
+        // Write the frame to the socket
+        self.connection.write_frame(&frame).await?;
+
+        // Read the response from the server
+        match self.read_response().await? {
+            // The server replies with an empty array when the key is missing.
+            // Hashes are never empty, so there is no ambiguity.
+            Frame::Array(response) if response.is_empty() => Ok(None),
+            Frame::Array(response) => {
+                let mut hash_map = HashMap::new();
+                let mut iter = response.into_iter();
+
+                // Since HGETALL returns pairs of frames, we iterate two at a time.
+                while let (Some(Frame::Bulk(key)), Some(Frame::Bulk(value))) = (iter.next(), iter.next()) {
+                    let key = String::from_utf8(key.to_vec()).map_err(|_| "Invalid UTF-8 sequence in key")?;
+                    hash_map.insert(key, value);
+                }
+
+                Ok(Some(hash_map))
+            },
+            Frame::Null => {
+                Ok(None)
+            },
+            frame => Err(frame.to_error()),
+        }
+    }
+
+
+    /// Remove `fields` from the hash stored at `key` and return the number of
+    /// fields that were removed.
+    ///
+    /// The key is removed along with its last field.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let removed = client.hdel("user:1", &["email"]).await.unwrap();
+    ///     println!("removed {} fields", removed);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn hdel(&mut self, key: &str, fields: &[&str]) -> crate::Result<u64> {
+        let frame = HDel::new(key, fields).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(removed) => Ok(removed as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns `true` if the hash stored at `key` has `field`.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     if client.hexists("user:1", "email").await.unwrap() {
+    ///         println!("email is set");
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn hexists(&mut self, key: &str, field: &str) -> crate::Result<bool> {
+        let frame = HExists::new(key, field).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(exists) => Ok(exists == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the number of fields in the hash stored at `key`.
+    ///
+    /// `0` is returned if the key does not exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let len = client.hlen("user:1").await.unwrap();
+    ///     println!("{} fields", len);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn hlen(&mut self, key: &str) -> crate::Result<u64> {
+        let frame = HLen::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the field names of the hash stored at `key`.
+    ///
+    /// Fields are in the order they were first set. The list is empty if the
+    /// key does not exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     for field in client.hkeys("user:1").await.unwrap() {
+    ///         println!("{}", field);
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn hkeys(&mut self, key: &str) -> crate::Result<Vec<String>> {
+        let frame = HKeys::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(field) => String::from_utf8(field.to_vec())
+                        .map_err(|_| "Invalid UTF-8 sequence in field".into()),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
     }
 
-    /// Reads a response frame from the socket.
+    /// Returns the values of the hash stored at `key`.
     ///
-    /// If an `Error` frame is received, it is converted to `Err`.
-    async fn read_response(&mut self) -> crate::Result<Frame> {
-        let response = self.connection.read_frame().await?;
+    /// Values are in the order their fields were first set. The list is empty
+    /// if the key does not exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     for value in client.hvals("user:1").await.unwrap() {
+    ///         println!("{:?}", value);
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn hvals(&mut self, key: &str) -> crate::Result<Vec<Bytes>> {
+        let frame = HVals::new(key).into_frame();
 
-        debug!(?response);
+        debug!(request = ?frame);
 
-        match response {
-            // Error frames are converted to `Err`
-            Some(Frame::Error(msg)) => Err(msg.into()),
-            Some(frame) => Ok(frame),
-            None => {
-                // Receiving `None` here indicates the server has closed the
-                // connection without sending a frame. This is unexpected and is
-                // represented as a "connection reset by peer" error.
-                let err = Error::new(ErrorKind::ConnectionReset, "connection reset by server");
+        self.connection.write_frame(&frame).await?;
 
-                Err(err.into())
-            }
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(value) => Ok(value),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
         }
     }
 
-
-    pub async fn hset(&mut self, p0: &String, p1: &String, p2: Bytes) -> crate::Result<()> {
-        let frame = HSet::new(p0, p1, p2).into_frame();
+    /// Returns the values of `fields` in the hash stored at `key`.
+    ///
+    /// Values are in the same order as `fields`, `None` for fields which do not
+    /// exist.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let values = client.hmget("user:1", &["name", "email"]).await.unwrap();
+    ///     println!("{:?}", values);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn hmget(&mut self, key: &str, fields: &[&str]) -> crate::Result<Vec<Option<Bytes>>> {
+        let frame = HMGet::new(key, fields).into_frame();
 
         debug!(request = ?frame);
 
-        // Write the frame to the socket
-        self.connection.write_frame(&frame).await.unwrap();
+        self.connection.write_frame(&frame).await?;
 
-        // Read the response from the server
-        match self.read_response().await.unwrap() {
-            Frame::Simple(response) if response == "OK" => {
-                println!("response {:?}",response);
-                Ok(())
-            },
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(value) => Ok(Some(value)),
+                    Frame::Null => Ok(None),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
             frame => Err(frame.to_error()),
         }
     }
 
-    ///hget
-    pub async fn hget(&mut self, p0: &String, p1: &String) -> crate::Result<Option<Bytes>> {
-        let frame = HGet::new(p0.to_string(), p1.to_string()).into_frame();
+    /// Increment the integer stored in `field` of the hash at `key` by `delta`
+    /// and return the new value.
+    ///
+    /// A missing field is treated as `0`. An error is returned if the field
+    /// does not hold an integer, or if the result would overflow.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     let visits = client.hincrby("user:1", "visits", 1).await.unwrap();
+    ///     println!("visits = {}", visits);
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn hincrby(&mut self, key: &str, field: &str, delta: i64) -> crate::Result<i64> {
+        let frame = HIncrBy::new(key, field, delta).into_frame();
 
         debug!(request = ?frame);
 
-        // Write the frame to the socket
-        self.connection.write_frame(&frame).await.unwrap();
+        self.connection.write_frame(&frame).await?;
 
-        // Read the response from the server
-        match self.read_response().await.unwrap() {
-            Frame::Bulk(response) => {
-                println!("response {:?}", response);
-                Ok(Some(response))
-            },
-            Frame::Null => Ok(None),
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value),
             frame => Err(frame.to_error()),
         }
-
     }
 
-    ///hgetall
-    pub async fn hgetall(&mut self, p0: &String) -> crate::Result<Option<HashMap<String, Bytes>>> {
-        let frame = HGetAll::new(p0.to_string()).into_frame();
+    /// Set `field` in the hash stored at `key` to `value`, only if the field
+    /// does not exist.
+    ///
+    /// Returns `true` if the field was set. A missing hash is created.
+    ///
+    /// # Examples
+    ///
+    /// Demonstrates basic usage.
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::connect("localhost:6379").await.unwrap();
+    ///
+    ///     if client.hsetnx("user:1", "created", "now".into()).await.unwrap() {
+    ///         println!("created is set");
+    ///     }
+    /// }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn hsetnx(&mut self, key: &str, field: &str, value: Bytes) -> crate::Result<bool> {
+        let frame = HSetNx::new(key, field, value).into_frame();
 
         debug!(request = ?frame);
 
-        // Write the frame to the socket
         self.connection.write_frame(&frame).await?;
 
-        // Read the response from the server
         match self.read_response().await? {
-            Frame::Array(response) => {
-                println!("response {:?}", response);
+            Frame::Integer(set) => Ok(set == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
 
-                let mut hash_map = HashMap::new();
-                let mut iter = response.into_iter();
 
-                // Since HGETALL returns pairs of frames, we iterate two at a time.
-                while let (Some(Frame::Bulk(key)), Some(Frame::Bulk(value))) = (iter.next(), iter.next()) {
-                    let key = String::from_utf8(key.to_vec()).map_err(|_| "Invalid UTF-8 sequence in key")?;
-                    hash_map.insert(key, value);
+}
+
+impl Cmd<'_> {
+    /// Append an argument to the command.
+    pub fn arg(mut self, arg: impl Into<Bytes>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Send the command and wait for the response.
+    ///
+    /// Commands known to `mini-redis` are checked against their expected
+    /// number of arguments first. A command with the wrong number of arguments
+    /// is rejected locally with the error Redis returns in that case, and
+    /// nothing is sent. Unknown commands are sent as is, leaving validation to
+    /// the server.
+    #[instrument(skip(self), fields(name = %self.name))]
+    pub async fn query(self) -> crate::Result<Frame> {
+        if let Some(spec) = CommandSpec::lookup(&self.name) {
+            if !spec.check_arity(self.args.len()) {
+                return Err(
+                    format!("ERR wrong number of arguments for '{}' command", self.name).into(),
+                );
+            }
+        }
+
+        let mut frame = Frame::array();
+        for arg in self.args {
+            frame.push_bulk(arg);
+        }
+
+        debug!(request = ?frame);
+
+        self.client.connection.write_frame(&frame).await?;
+
+        self.client.read_response().await
+    }
+}
+
+impl Pipeline<'_> {
+    /// Append a command made of `args`, the command name followed by its
+    /// arguments.
+    pub fn cmd<I, A>(self, args: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<Bytes>,
+    {
+        let args = args
+            .into_iter()
+            .map(|arg| Frame::Bulk(arg.into()))
+            .collect();
+        self.frame(Frame::Array(args))
+    }
+
+    /// Append a command already encoded as a request `Frame`.
+    pub fn frame(mut self, frame: Frame) -> Self {
+        self.frames.push(frame);
+        self
+    }
+
+    /// Returns the number of commands in the pipeline.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns `true` if the pipeline holds no command.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Send the commands and wait for all of their responses.
+    ///
+    /// The commands are written with a single flush, then one response is
+    /// read per command. The responses are returned in the order the commands
+    /// were added.
+    ///
+    /// A command rejected by the server does not fail the pipeline: its
+    /// response is an `Error` frame, and the following commands are still
+    /// applied. `Err` is only returned if the connection fails, as the
+    /// responses can then no longer be matched with the commands.
+    ///
+    /// As with [`Cmd::query`], commands known to `mini-redis` are checked
+    /// against their expected number of arguments first. If any has the wrong
+    /// number, `Err` is returned and nothing is sent, since the server would
+    /// close the connection on such a command.
+    ///
+    /// All the commands are buffered before any response is read, so very
+    /// large batches are better sent with [`Client::bulk_load`].
+    #[instrument(skip(self), fields(len = self.frames.len()))]
+    pub async fn query(self) -> crate::Result<Vec<Frame>> {
+        for frame in &self.frames {
+            check_arity(frame)?;
+        }
+
+        for frame in &self.frames {
+            debug!(request = ?frame);
+
+            self.client.connection.feed_frame(frame).await?;
+        }
+
+        self.client.connection.flush().await?;
+
+        // The server responds to commands in the order it receives them.
+        let mut responses = Vec::with_capacity(self.frames.len());
+
+        for i in 0..self.frames.len() {
+            let response = match self.client.read_frame().await {
+                Ok(response) => response,
+                Err(err) => {
+                    self.client.abandon(&err, self.frames.len() - i);
+                    return Err(err);
                 }
+            };
 
-                Ok(Some(hash_map))
-            },
-            Frame::Null => {
-                Ok(None)
-            },
-            frame => Err(frame.to_error()),
+            debug!(?response);
+
+            match response {
+                Some(frame) => responses.push(frame),
+                None => {
+                    let err = Error::new(ErrorKind::ConnectionReset, "connection reset by server");
+
+                    return Err(err.into());
+                }
+            }
         }
+
+        Ok(responses)
     }
+}
 
+/// Check the number of arguments of a request frame, if it is a command known
+/// to `mini-redis`.
+fn check_arity(frame: &Frame) -> crate::Result<()> {
+    let args = match frame {
+        Frame::Array(args) => args,
+        _ => return Ok(()),
+    };
 
+    let name = match args.first() {
+        Some(Frame::Bulk(name)) => String::from_utf8_lossy(name).to_lowercase(),
+        _ => return Ok(()),
+    };
 
+    match CommandSpec::lookup(&name) {
+        Some(spec) if !spec.check_arity(args.len()) => {
+            Err(format!("ERR wrong number of arguments for '{}' command", name).into())
+        }
+        _ => Ok(()),
+    }
 }
 
 impl Subscriber {
     /// Returns the set of channels currently subscribed to.
-    pub fn get_subscribed(&self) -> &[String] {
+    pub fn get_subscribed(&self) -> &[Bytes] {
         &self.subscribed_channels
     }
 
+    /// Returns the confirmations received for the last subscribe or
+    /// unsubscribe request, including the one which created the `Subscriber`.
+    ///
+    /// There is one confirmation per channel, in the order the server sent
+    /// them.
+    pub fn confirmations(&self) -> &[Confirmation] {
+        &self.confirmations
+    }
+
     /// Receive the next message published on a subscribed channel, waiting if
     /// necessary.
     ///
-    /// `None` indicates the subscription has been terminated.
+    /// `None` indicates the subscription has been terminated or, if a timeout
+    /// is set with `Client::set_timeout`, that no message was received in
+    /// time.
     pub async fn next_message(&mut self) -> crate::Result<Option<Message>> {
-        match self.client.connection.read_frame().await? {
+        Ok(self.read_message().await?.message())
+    }
+
+    /// Receive the next message, telling a timeout apart from the end of the
+    /// subscription.
+    async fn read_message(&mut self) -> crate::Result<Received<Message>> {
+        let frame = match self.client.read_frame().await {
+            // No message was received in time. Unlike for a request, no frame
+            // is owed by the server.
+            Err(err) if is_timeout(&err) => return Ok(Received::TimedOut),
+            frame => frame?,
+        };
+
+        match frame {
             Some(mframe) => {
                 debug!(?mframe);
 
                 match mframe {
                     Frame::Array(ref frame) => match frame.as_slice() {
-                        [message, channel, content] if *message == "message" => Ok(Some(Message {
-                            channel: channel.to_string(),
-                            content: Bytes::from(content.to_string()),
-                        })),
+                        [message, Frame::Bulk(channel), Frame::Bulk(content)]
+                            if *message == "message" =>
+                        {
+                            Ok(Received::Message(Message {
+                                channel: channel.clone(),
+                                content: content.clone(),
+                            }))
+                        }
                         _ => Err(mframe.to_error()),
                     },
                     frame => Err(frame.to_error()),
                 }
             }
-            None => Ok(None),
+            None => Ok(Received::Closed),
         }
     }
 
@@ -506,41 +4629,49 @@ impl Subscriber {
     /// implementation to use `unsafe` code. Instead, a conversion function is
     /// provided and the returned stream is implemented with the help of the
     /// `async-stream` crate.
+    ///
+    /// The stream only ends with the subscription. Timeouts set with
+    /// `Client::set_timeout` do not end it, waiting for a message just starts
+    /// over.
     pub fn into_stream(mut self) -> impl Stream<Item = crate::Result<Message>> {
         // Uses the `try_stream` macro from the `async-stream` crate. Generators
         // are not stable in Rust. The crate uses a macro to simulate generators
         // on top of async/await. There are limitations, so read the
         // documentation there.
         try_stream! {
-            while let Some(message) = self.next_message().await? {
-                yield message;
+            loop {
+                match self.read_message().await? {
+                    Received::Message(message) => yield message,
+                    Received::TimedOut => continue,
+                    Received::Closed => break,
+                }
             }
         }
     }
 
     /// Subscribe to a list of new channels
     #[instrument(skip(self))]
-    pub async fn subscribe(&mut self, channels: &[String]) -> crate::Result<()> {
-        // Issue the subscribe command
-        self.client.subscribe_cmd(channels).await?;
-
-        // Update the set of subscribed channels.
-        self.subscribed_channels
-            .extend(channels.iter().map(Clone::clone));
-
-        Ok(())
+    pub async fn subscribe(&mut self, channels: &[Bytes]) -> crate::Result<()> {
+        // Issue the subscribe command. The set of subscribed channels is
+        // updated with each confirmation received from the server.
+        self.confirmations.clear();
+        self.client
+            .subscribe_cmd(
+                Subscribe::new(channels.to_vec()),
+                &mut self.subscribed_channels,
+                &mut self.confirmations,
+            )
+            .await
     }
 
-    /// Unsubscribe to a list of new channels
+    /// Unsubscribe from a list of channels, or from every channel if the list
+    /// is empty
+    ///
+    /// Messages keep being received on the other subscribed channels.
     #[instrument(skip(self))]
-    pub async fn unsubscribe(&mut self, channels: &[String]) -> crate::Result<()> {
+    pub async fn unsubscribe(&mut self, channels: &[Bytes]) -> crate::Result<()> {
         let frame = Unsubscribe::new(channels).into_frame();
 
-        debug!(request = ?frame);
-
-        // Write the frame to the socket
-        self.client.connection.write_frame(&frame).await?;
-
         // if the input channel list is empty, server acknowledges as unsubscribing
         // from all subscribed channels, so we assert that the unsubscribe list received
         // matches the client subscribed one
@@ -550,35 +4681,163 @@ impl Subscriber {
             channels.len()
         };
 
-        // Read the response
-        for _ in 0..num {
-            let response = self.client.read_response().await?;
+        self.confirmations.clear();
 
-            match response {
-                Frame::Array(ref frame) => match frame.as_slice() {
-                    [unsubscribe, channel, ..] if *unsubscribe == "unsubscribe" => {
-                        let len = self.subscribed_channels.len();
+        self.client
+            .unsubscribe_frame(
+                "unsubscribe",
+                frame,
+                num,
+                &mut self.subscribed_channels,
+                &mut self.confirmations,
+            )
+            .await
+    }
+}
 
-                        if len == 0 {
-                            // There must be at least one channel
-                            return Err(response.to_error());
-                        }
+impl PatternSubscriber {
+    /// Returns the set of patterns currently subscribed to.
+    pub fn get_subscribed(&self) -> &[Bytes] {
+        &self.subscribed_patterns
+    }
+
+    /// Returns the confirmations received for the last subscribe or
+    /// unsubscribe request, including the one which created the
+    /// `PatternSubscriber`.
+    ///
+    /// There is one confirmation per pattern, in the order the server sent
+    /// them.
+    pub fn confirmations(&self) -> &[Confirmation] {
+        &self.confirmations
+    }
+
+    /// Receive the next message published on a channel matching a subscribed
+    /// pattern, waiting if necessary.
+    ///
+    /// `None` indicates the subscription has been terminated or, if a timeout
+    /// is set with `Client::set_timeout`, that no message was received in
+    /// time.
+    pub async fn next_message(&mut self) -> crate::Result<Option<PatternMessage>> {
+        Ok(self.read_message().await?.message())
+    }
+
+    /// Receive the next message, see `Subscriber::read_message`.
+    async fn read_message(&mut self) -> crate::Result<Received<PatternMessage>> {
+        let frame = match self.client.read_frame().await {
+            // No message was received in time. Unlike for a request, no frame
+            // is owed by the server.
+            Err(err) if is_timeout(&err) => return Ok(Received::TimedOut),
+            frame => frame?,
+        };
 
-                        // unsubscribed channel should exist in the subscribed list at this point
-                        self.subscribed_channels.retain(|c| *channel != &c[..]);
+        match frame {
+            Some(mframe) => {
+                debug!(?mframe);
 
-                        // Only a single channel should be removed from the
-                        // list of subscribed channels.
-                        if self.subscribed_channels.len() != len - 1 {
-                            return Err(response.to_error());
+                match mframe {
+                    Frame::Array(ref frame) => match frame.as_slice() {
+                        [message, Frame::Bulk(pattern), Frame::Bulk(channel), Frame::Bulk(content)]
+                            if *message == "pmessage" =>
+                        {
+                            Ok(Received::Message(PatternMessage {
+                                pattern: pattern.clone(),
+                                channel: channel.clone(),
+                                content: content.clone(),
+                            }))
                         }
-                    }
-                    _ => return Err(response.to_error()),
-                },
-                frame => return Err(frame.to_error()),
-            };
+                        _ => Err(mframe.to_error()),
+                    },
+                    frame => Err(frame.to_error()),
+                }
+            }
+            None => Ok(Received::Closed),
         }
+    }
 
-        Ok(())
+    /// Convert the subscriber into a `Stream` yielding new messages published
+    /// on channels matching subscribed patterns.
+    ///
+    /// See `Subscriber::into_stream`.
+    pub fn into_stream(mut self) -> impl Stream<Item = crate::Result<PatternMessage>> {
+        try_stream! {
+            loop {
+                match self.read_message().await? {
+                    Received::Message(message) => yield message,
+                    Received::TimedOut => continue,
+                    Received::Closed => break,
+                }
+            }
+        }
+    }
+
+    /// Subscribe to a list of new patterns
+    #[instrument(skip(self))]
+    pub async fn psubscribe(&mut self, patterns: &[Bytes]) -> crate::Result<()> {
+        self.confirmations.clear();
+        self.client
+            .subscribe_frame(
+                "psubscribe",
+                Psubscribe::new(patterns.to_vec()).into_frame(),
+                patterns,
+                &mut self.subscribed_patterns,
+                &mut self.confirmations,
+            )
+            .await
+    }
+
+    /// Unsubscribe from a list of patterns, or from every pattern if the list
+    /// is empty
+    #[instrument(skip(self))]
+    pub async fn punsubscribe(&mut self, patterns: &[Bytes]) -> crate::Result<()> {
+        let frame = Punsubscribe::new(patterns).into_frame();
+
+        let num = if patterns.is_empty() {
+            self.subscribed_patterns.len()
+        } else {
+            patterns.len()
+        };
+
+        self.confirmations.clear();
+
+        self.client
+            .unsubscribe_frame(
+                "punsubscribe",
+                frame,
+                num,
+                &mut self.subscribed_patterns,
+                &mut self.confirmations,
+            )
+            .await
+    }
+}
+
+/// Returns `true` if `err` is a request timing out.
+fn is_timeout(err: &crate::Error) -> bool {
+    err.downcast_ref() == Some(&ClientError::Timeout)
+}
+
+/// Decode a match of an `LCS ... IDX WITHMATCHLEN` response, in the form of:
+///
+/// ```text
+/// [ [a_start, a_end], [b_start, b_end], len ]
+/// ```
+fn lcs_match(frame: &Frame) -> Option<LcsMatch> {
+    let (a, b, len) = match frame {
+        Frame::Array(parts) => match parts.as_slice() {
+            [Frame::Array(a), Frame::Array(b), Frame::Integer(len)] => (a, b, *len as u64),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    match (a.as_slice(), b.as_slice()) {
+        ([Frame::Integer(a0), Frame::Integer(a1)], [Frame::Integer(b0), Frame::Integer(b1)]) => {
+            Some(LcsMatch {
+                a: (*a0 as u64, *a1 as u64),
+                b: (*b0 as u64, *b1 as u64),
+                len,
+            })
+        }
+        _ => None,
     }
 }
@@ -0,0 +1,22 @@
+use std::fmt;
+
+/// Errors returned by the clients, in addition to I/O and protocol errors.
+///
+/// They are returned boxed in `crate::Error`, and can be told apart with
+/// `downcast_ref`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The server did not respond within the timeout set with
+    /// `Client::set_timeout`.
+    Timeout,
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Timeout => "request timed out".fmt(fmt),
+        }
+    }
+}
@@ -1,8 +1,20 @@
+mod error;
+pub use error::Error;
+
 mod client;
-pub use client::{Client, Message, Subscriber};
+pub use client::{
+    BulkLoadError, Client, Cmd, Confirmation, LcsMatch, Message, PatternMessage, PatternSubscriber,
+    Pipeline, Subscriber,
+};
 
 mod blocking_client;
 pub use blocking_client::BlockingClient;
 
 mod buffered_client;
 pub use buffered_client::BufferedClient;
+
+mod pool;
+pub use pool::{Pool, PooledClient};
+
+mod retry;
+pub use retry::{RetryClient, RetryConfig, RetrySubscriber};
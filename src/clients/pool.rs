@@ -0,0 +1,343 @@
+use crate::clients::{self, Client};
+use crate::{frame, Result};
+
+use bytes::Bytes;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::net::{lookup_host, ToSocketAddrs};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time;
+
+/// A fixed size pool of connections to a Redis server.
+///
+/// A `Client` only has a single request in flight at a time and requires
+/// mutable access, so it cannot be shared by concurrent tasks. A `Pool` holds
+/// several connections instead, and hands one out to each task calling
+/// [`get`](Pool::get). At most `size` connections are handed out at a time,
+/// further tasks wait until one is returned.
+///
+/// The `Pool` handle may be cloned before passing the new handle to separate
+/// tasks. All clones share the same connections.
+///
+/// A pool created with [`connect_with_heartbeat`](Pool::connect_with_heartbeat)
+/// also checks its idle connections periodically, so that connections closed
+/// while idle, for instance by a load balancer, are replaced before they are
+/// handed out.
+#[derive(Clone)]
+pub struct Pool {
+    shared: Arc<Shared>,
+}
+
+/// A connection handed out by a `Pool`, returned to it on drop.
+///
+/// A connection is only returned to the pool if its last command received a
+/// response, even an error reply such as `WRONGTYPE`. If a command fails with
+/// an I/O error or times out, or its future is dropped before completing, the
+/// stream may be closed or hold a partial request or an unread response. The
+/// connection is then discarded, and a new one is established by the next
+/// command.
+///
+/// Each `PooledClient` starts on database `0`, whichever database a previous
+/// borrower left the connection on with [`select`](PooledClient::select).
+pub struct PooledClient {
+    /// The connection, or `None` if it was discarded and not yet replaced.
+    client: Option<Client>,
+
+    /// Database selected on the connection.
+    selected: u64,
+
+    /// Database the commands apply to, changed by `select`. The connection is
+    /// switched to it before the next command if it is on another one.
+    database: u64,
+
+    /// The pool the connection is returned to.
+    pool: Arc<Shared>,
+
+    /// Counts the connection as handed out until dropped.
+    _permit: OwnedSemaphorePermit,
+}
+
+struct Shared {
+    /// Addresses of the server, used to replace discarded connections.
+    addrs: Vec<SocketAddr>,
+
+    /// Connections not currently handed out, along with the database selected
+    /// on each of them. Discarded connections are missing from the list, so it
+    /// may hold fewer than `size` connections.
+    idle: Mutex<Vec<(Client, u64)>>,
+
+    /// One permit per connection of the pool, held by each `PooledClient`.
+    permits: Arc<Semaphore>,
+}
+
+impl Pool {
+    /// Establish a pool of `size` connections with the Redis server located at
+    /// `addr`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Pool;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let pool = Pool::connect("localhost:6379", 4).await.unwrap();
+    ///
+    ///     let mut client = pool.get().await;
+    ///     let val = client.get("foo").await.unwrap();
+    ///     println!("Got = {:?}", val);
+    /// }
+    /// ```
+    pub async fn connect<T: ToSocketAddrs>(addr: T, size: usize) -> Result<Pool> {
+        assert!(size > 0, "a pool needs at least one connection");
+
+        // The address is resolved once, so that replacing a connection does
+        // not need the original `addr`.
+        let addrs: Vec<SocketAddr> = lookup_host(addr).await?.collect();
+
+        let mut idle = Vec::with_capacity(size);
+        for _ in 0..size {
+            idle.push((Client::connect(&addrs[..]).await?, 0));
+        }
+
+        Ok(Pool {
+            shared: Arc::new(Shared {
+                addrs,
+                idle: Mutex::new(idle),
+                permits: Arc::new(Semaphore::new(size)),
+            }),
+        })
+    }
+
+    /// Establish a pool of `size` connections with the Redis server located at
+    /// `addr`, and check its idle connections every `interval`.
+    ///
+    /// Each idle connection is sent a `PING`. A connection which fails to
+    /// answer within `interval` is replaced by a new one, or discarded if the
+    /// server cannot be reached, in which case the next command replaces it.
+    /// Connections handed out are not checked. A connection being checked is
+    /// counted as handed out, so a task calling [`get`](Pool::get) meanwhile
+    /// waits for the check to complete if no other connection is available.
+    ///
+    /// The checks stop once every handle to the pool is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` or `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mini_redis::clients::Pool;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let interval = Duration::from_secs(30);
+    ///     let pool = Pool::connect_with_heartbeat("localhost:6379", 4, interval)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let mut client = pool.get().await;
+    ///     let val = client.get("foo").await.unwrap();
+    ///     println!("Got = {:?}", val);
+    /// }
+    /// ```
+    pub async fn connect_with_heartbeat<T: ToSocketAddrs>(
+        addr: T,
+        size: usize,
+        interval: Duration,
+    ) -> Result<Pool> {
+        assert!(!interval.is_zero(), "the interval must not be zero");
+
+        let pool = Pool::connect(addr, size).await?;
+        tokio::spawn(heartbeat(Arc::downgrade(&pool.shared), interval));
+
+        Ok(pool)
+    }
+
+    /// Take a connection from the pool, waiting for one to be returned if all
+    /// of them are handed out.
+    ///
+    /// The connection is returned to the pool when the `PooledClient` is
+    /// dropped.
+    pub async fn get(&self) -> PooledClient {
+        // The semaphore is never closed.
+        let permit = self.shared.permits.clone().acquire_owned().await.unwrap();
+
+        // A permit guarantees a connection is available, unless it was
+        // discarded, in which case the first command replaces it.
+        let (client, selected) = match self.shared.idle.lock().unwrap().pop() {
+            Some((client, selected)) => (Some(client), selected),
+            None => (None, 0),
+        };
+
+        PooledClient {
+            client,
+            selected,
+            database: 0,
+            pool: self.shared.clone(),
+            _permit: permit,
+        }
+    }
+}
+
+impl PooledClient {
+    /// Get the value of key.
+    ///
+    /// Same as `Client::get`, on a pooled connection.
+    pub async fn get(&mut self, key: &str) -> Result<Option<Bytes>> {
+        let mut client = self.take_client().await?;
+        let value = client.get(key).await;
+        self.put_client(client, value)
+    }
+
+    /// Set `key` to hold the given `value`.
+    ///
+    /// Same as `Client::set`, on a pooled connection.
+    pub async fn set(&mut self, key: &str, value: Bytes) -> Result<()> {
+        let mut client = self.take_client().await?;
+        let res = client.set(key, value).await;
+        self.put_client(client, res)
+    }
+
+    /// Get the value of `field` in the hash stored at `key`.
+    ///
+    /// Same as `Client::hget`, on a pooled connection.
+    pub async fn hget(&mut self, key: &str, field: &str) -> Result<Option<Bytes>> {
+        let mut client = self.take_client().await?;
+        let value = client.hget(&key.to_string(), &field.to_string()).await;
+        self.put_client(client, value)
+    }
+
+    /// Select the database commands apply to.
+    ///
+    /// Same as `Client::select`, on a pooled connection. The next borrower of
+    /// the connection starts on database `0` again.
+    pub async fn select(&mut self, index: u64) -> Result<()> {
+        let mut client = self.take_client().await?;
+        let res = client.select(index).await;
+        if res.is_ok() {
+            self.selected = index;
+            self.database = index;
+        }
+        self.put_client(client, res)
+    }
+
+    /// Take the connection out for the duration of a command, establishing a
+    /// new one if it was discarded, and switch it to `database`.
+    ///
+    /// The caller puts the connection back with `put_client` once the command
+    /// completed. Until then, dropping the future discards it.
+    async fn take_client(&mut self) -> Result<Client> {
+        let mut client = match self.client.take() {
+            Some(client) => client,
+            None => {
+                self.selected = 0;
+                Client::connect(&self.pool.addrs[..]).await?
+            }
+        };
+
+        if self.selected != self.database {
+            if let Err(err) = client.select(self.database).await {
+                return self.put_client(client, Err(err));
+            }
+            self.selected = self.database;
+        }
+
+        Ok(client)
+    }
+
+    /// Put the connection back after a command completed with `res`, unless
+    /// the command failed in a way that leaves the connection unusable.
+    ///
+    /// An error reply from the server is a complete response, so the
+    /// connection is kept. I/O errors, malformed frames and timeouts discard
+    /// it.
+    fn put_client<T>(&mut self, client: Client, res: Result<T>) -> Result<T> {
+        match &res {
+            Err(err) if err.is::<io::Error>() => {}
+            Err(err) if err.is::<frame::Error>() => {}
+            Err(err) if err.is::<clients::Error>() => {}
+            _ => self.client = Some(client),
+        }
+
+        res
+    }
+}
+
+/// Check the idle connections of the pool every `interval`, until the pool is
+/// dropped.
+async fn heartbeat(shared: Weak<Shared>, interval: Duration) {
+    let mut ticks = time::interval(interval);
+    ticks.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+
+    // The first tick completes immediately, the connections were just
+    // established.
+    ticks.tick().await;
+
+    loop {
+        ticks.tick().await;
+
+        let shared = match shared.upgrade() {
+            Some(shared) => shared,
+            None => return,
+        };
+
+        // Connections are taken from the front and put back at the back, so
+        // each idle connection is checked once.
+        let idle = shared.idle.lock().unwrap().len();
+
+        for _ in 0..idle {
+            // Holding a permit keeps the connection from being handed out
+            // while it is checked. If none is available, every connection is
+            // in use.
+            let _permit = match shared.permits.clone().try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) => break,
+            };
+
+            let (client, selected) = {
+                let mut idle = shared.idle.lock().unwrap();
+                if idle.is_empty() {
+                    break;
+                }
+                idle.remove(0)
+            };
+
+            let client = match check(client, interval).await {
+                Some(client) => Some((client, selected)),
+                None => Client::connect(&shared.addrs[..])
+                    .await
+                    .ok()
+                    .map(|client| (client, 0)),
+            };
+
+            if let Some(client) = client {
+                shared.idle.lock().unwrap().push(client);
+            }
+        }
+    }
+}
+
+/// Returns `client` if it answers a `PING` within `timeout`.
+async fn check(mut client: Client, timeout: Duration) -> Option<Client> {
+    match time::timeout(timeout, client.ping(None)).await {
+        Ok(Ok(_)) => Some(client),
+        _ => None,
+    }
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take() {
+            self.pool.idle.lock().unwrap().push((client, self.selected));
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use crate::clients::{Client, Confirmation, Message, Subscriber};
+use crate::Result;
+
+use bytes::Bytes;
+use std::io::{Error, ErrorKind};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::{lookup_host, ToSocketAddrs};
+use tokio::time;
+
+/// Settings for re-establishing the connection of a `RetryClient`.
+///
+/// After losing the connection, the client waits `base_delay` before trying
+/// to connect again. The delay doubles after each failed attempt, up to
+/// `max_delay`, and the client gives up after `max_retries` attempts.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// Maximum number of connection attempts after losing the connection. No
+    /// attempt is made when zero.
+    pub max_retries: u32,
+
+    /// Delay before the first connection attempt.
+    pub base_delay: Duration,
+
+    /// Upper bound of the delay between connection attempts.
+    pub max_delay: Duration,
+}
+
+/// A `Client` which reconnects when the connection is lost.
+///
+/// Created by [`Client::connect_with_retry`]. When a command fails because
+/// the server reset or closed the connection, a new connection is established
+/// according to the `RetryConfig`, and the command is sent once more. Other
+/// errors, including errors replied by the server, are returned as is.
+///
+/// The password accepted by [`auth`](RetryClient::auth) and the database
+/// chosen with [`select`](RetryClient::select) are sent again on each new
+/// connection, before the command is retried.
+///
+/// A command whose connection was lost may or may not have been applied by
+/// the server before it closed, so retried commands may be applied twice.
+pub struct RetryClient {
+    /// The current connection.
+    client: Client,
+
+    /// Addresses of the server, resolved when first connecting.
+    addrs: Vec<SocketAddr>,
+
+    /// When and how often to try reconnecting.
+    config: RetryConfig,
+
+    /// State restored on each new connection.
+    session: Session,
+}
+
+/// A `Subscriber` which reconnects when the connection is lost.
+///
+/// After reconnecting, the channels subscribed to are subscribed to again.
+/// Messages published while the connection was down are not received.
+pub struct RetrySubscriber {
+    /// The subscription on the current connection.
+    subscriber: Subscriber,
+
+    /// Addresses of the server, resolved when first connecting.
+    addrs: Vec<SocketAddr>,
+
+    /// When and how often to try reconnecting.
+    config: RetryConfig,
+
+    /// State restored on each new connection.
+    session: Session,
+}
+
+/// Connection state set by past commands, which a new connection must be
+/// brought back to.
+#[derive(Debug, Clone, Default)]
+struct Session {
+    /// The password last accepted by `AUTH`, if any.
+    password: Option<String>,
+
+    /// The database last chosen with `SELECT`.
+    database: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> RetryConfig {
+        RetryConfig {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryClient {
+    /// Establish a connection with the Redis server located at `addr`, see
+    /// `Client::connect_with_retry`.
+    pub(crate) async fn connect<T: ToSocketAddrs>(
+        addr: T,
+        config: RetryConfig,
+    ) -> Result<RetryClient> {
+        // The address is resolved once, so that reconnecting does not need the
+        // original `addr`.
+        let addrs: Vec<SocketAddr> = lookup_host(addr).await?.collect();
+        let client = Client::connect(&addrs[..]).await?;
+
+        Ok(RetryClient {
+            client,
+            addrs,
+            config,
+            session: Session::default(),
+        })
+    }
+
+    /// Authenticate the connection with `password`.
+    ///
+    /// Same as `Client::auth`, reconnecting if the connection is lost. Once
+    /// accepted, the password is sent again on every new connection.
+    pub async fn auth(&mut self, password: &str) -> Result<()> {
+        match self.client.auth(password).await {
+            Err(err) if is_disconnect(&err) => {
+                self.client = reconnect(&self.addrs, &self.config, &self.session, err).await?;
+                self.client.auth(password).await?;
+            }
+            result => result?,
+        }
+
+        self.session.password = Some(password.to_string());
+        Ok(())
+    }
+
+    /// Select the logical database the following commands are applied to.
+    ///
+    /// Same as `Client::select`, reconnecting if the connection is lost. Once
+    /// selected, the database is selected again on every new connection.
+    pub async fn select(&mut self, index: u64) -> Result<()> {
+        match self.client.select(index).await {
+            Err(err) if is_disconnect(&err) => {
+                self.client = reconnect(&self.addrs, &self.config, &self.session, err).await?;
+                self.client.select(index).await?;
+            }
+            result => result?,
+        }
+
+        self.session.database = index;
+        Ok(())
+    }
+
+    /// Get the value of key.
+    ///
+    /// Same as `Client::get`, reconnecting if the connection is lost.
+    pub async fn get(&mut self, key: &str) -> Result<Option<Bytes>> {
+        match self.client.get(key).await {
+            Err(err) if is_disconnect(&err) => {
+                self.client = reconnect(&self.addrs, &self.config, &self.session, err).await?;
+                self.client.get(key).await
+            }
+            result => result,
+        }
+    }
+
+    /// Set `key` to hold the given `value`.
+    ///
+    /// Same as `Client::set`, reconnecting if the connection is lost.
+    pub async fn set(&mut self, key: &str, value: Bytes) -> Result<()> {
+        match self.client.set(key, value.clone()).await {
+            Err(err) if is_disconnect(&err) => {
+                self.client = reconnect(&self.addrs, &self.config, &self.session, err).await?;
+                self.client.set(key, value).await
+            }
+            result => result,
+        }
+    }
+
+    /// Set `key` to hold the given `value`. The value expires after
+    /// `expiration`.
+    ///
+    /// Same as `Client::set_expires`, reconnecting if the connection is lost.
+    pub async fn set_expires(
+        &mut self,
+        key: &str,
+        value: Bytes,
+        expiration: Duration,
+    ) -> Result<()> {
+        match self
+            .client
+            .set_expires(key, value.clone(), expiration)
+            .await
+        {
+            Err(err) if is_disconnect(&err) => {
+                self.client = reconnect(&self.addrs, &self.config, &self.session, err).await?;
+                self.client.set_expires(key, value, expiration).await
+            }
+            result => result,
+        }
+    }
+
+    /// Posts `message` to the given `channel`.
+    ///
+    /// Same as `Client::publish`, reconnecting if the connection is lost.
+    pub async fn publish(&mut self, channel: impl AsRef<[u8]>, message: Bytes) -> Result<u64> {
+        let channel = channel.as_ref();
+
+        match self.client.publish(channel, message.clone()).await {
+            Err(err) if is_disconnect(&err) => {
+                self.client = reconnect(&self.addrs, &self.config, &self.session, err).await?;
+                self.client.publish(channel, message).await
+            }
+            result => result,
+        }
+    }
+
+    /// Subscribes the client to the specified channels.
+    ///
+    /// Same as `Client::subscribe`, reconnecting if the connection is lost.
+    /// The returned `RetrySubscriber` keeps reconnecting as needed.
+    pub async fn subscribe(self, channels: Vec<Bytes>) -> Result<RetrySubscriber> {
+        let RetryClient {
+            client,
+            addrs,
+            config,
+            session,
+        } = self;
+
+        let subscriber = match client.subscribe(channels.clone()).await {
+            Err(err) if is_disconnect(&err) => {
+                let client = reconnect(&addrs, &config, &session, err).await?;
+                client.subscribe(channels).await?
+            }
+            result => result?,
+        };
+
+        Ok(RetrySubscriber {
+            subscriber,
+            addrs,
+            config,
+            session,
+        })
+    }
+}
+
+impl RetrySubscriber {
+    /// Returns the set of channels currently subscribed to.
+    pub fn get_subscribed(&self) -> &[Bytes] {
+        self.subscriber.get_subscribed()
+    }
+
+    /// Returns the confirmations received for the last subscribe or
+    /// unsubscribe request, including the ones received when subscribing
+    /// again after reconnecting.
+    pub fn confirmations(&self) -> &[Confirmation] {
+        self.subscriber.confirmations()
+    }
+
+    /// Receive the next message published on a subscribed channel, waiting if
+    /// necessary.
+    ///
+    /// If the connection is lost, a new one is established and subscribed to
+    /// the same channels before waiting for the next message. `None` is only
+    /// returned if the connection is closed while no channel is subscribed
+    /// to.
+    pub async fn next_message(&mut self) -> Result<Option<Message>> {
+        loop {
+            let err = match self.subscriber.next_message().await {
+                Ok(None) if self.get_subscribed().is_empty() => return Ok(None),
+                // The server closed the connection.
+                Ok(None) => Error::from(ErrorKind::ConnectionReset).into(),
+                Err(err) if is_disconnect(&err) => err,
+                result => return result,
+            };
+
+            let channels = self.get_subscribed().to_vec();
+            self.resubscribe(channels, err).await?;
+        }
+    }
+
+    /// Subscribe to a list of new channels
+    ///
+    /// Same as `Subscriber::subscribe`, reconnecting if the connection is
+    /// lost.
+    pub async fn subscribe(&mut self, channels: &[Bytes]) -> Result<()> {
+        match self.subscriber.subscribe(channels).await {
+            Err(err) if is_disconnect(&err) => {
+                // Some of `channels` may have been confirmed before the
+                // connection was lost, so they are only added once.
+                let mut all = self.get_subscribed().to_vec();
+                for channel in channels {
+                    if !all.contains(channel) {
+                        all.push(channel.clone());
+                    }
+                }
+
+                self.resubscribe(all, err).await
+            }
+            result => result,
+        }
+    }
+
+    /// Unsubscribe from a list of channels, or from every channel if the list
+    /// is empty
+    ///
+    /// Same as `Subscriber::unsubscribe`, reconnecting if the connection is
+    /// lost.
+    pub async fn unsubscribe(&mut self, channels: &[Bytes]) -> Result<()> {
+        match self.subscriber.unsubscribe(channels).await {
+            Err(err) if is_disconnect(&err) => {
+                let subscribed = self.get_subscribed().to_vec();
+                self.resubscribe(subscribed, err).await?;
+                self.subscriber.unsubscribe(channels).await
+            }
+            result => result,
+        }
+    }
+
+    /// Establish a new connection, subscribed to `channels`.
+    ///
+    /// `err` is returned if no attempt to reconnect is made. A subscriber
+    /// cannot be created without any channel, so this includes when
+    /// `channels` is empty.
+    async fn resubscribe(&mut self, channels: Vec<Bytes>, err: crate::Error) -> Result<()> {
+        if channels.is_empty() {
+            return Err(err);
+        }
+
+        let client = reconnect(&self.addrs, &self.config, &self.session, err).await?;
+
+        self.subscriber = client.subscribe(channels).await?;
+
+        Ok(())
+    }
+}
+
+/// Establish a new connection to `addrs`, waiting with exponential backoff
+/// before each attempt, and restore `session` on it.
+///
+/// If every attempt fails, the error of the last one is returned, or `err` if
+/// no attempt is made. An attempt whose connection is lost while restoring
+/// the session fails, other errors are returned immediately.
+async fn reconnect(
+    addrs: &[SocketAddr],
+    config: &RetryConfig,
+    session: &Session,
+    err: crate::Error,
+) -> Result<Client> {
+    let mut err = err;
+    let mut delay = config.base_delay;
+
+    for _ in 0..config.max_retries {
+        time::sleep(delay).await;
+
+        match Client::connect(addrs).await {
+            Ok(mut client) => match restore(&mut client, session).await {
+                Ok(()) => return Ok(client),
+                Err(e) if is_disconnect(&e) => err = e,
+                Err(e) => return Err(e),
+            },
+            Err(e) => err = e,
+        }
+
+        delay = (delay * 2).min(config.max_delay);
+    }
+
+    Err(err)
+}
+
+/// Bring a new connection back to `session`.
+async fn restore(client: &mut Client, session: &Session) -> Result<()> {
+    if let Some(password) = &session.password {
+        client.auth(password).await?;
+    }
+
+    // Connections start on the first database.
+    if session.database != 0 {
+        client.select(session.database).await?;
+    }
+
+    Ok(())
+}
+
+/// Returns `true` if `err` indicates the connection to the server was lost.
+fn is_disconnect(err: &crate::Error) -> bool {
+    err.downcast_ref::<Error>().is_some_and(|err| {
+        matches!(
+            err.kind(),
+            ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::BrokenPipe
+                | ErrorKind::UnexpectedEof
+        )
+    })
+}
@@ -0,0 +1,90 @@
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Authenticate the connection with the password set by `requirepass`.
+///
+/// Until the connection has authenticated, the server answers every command
+/// other than `AUTH` and `HELLO` with `NOAUTH Authentication required`. A
+/// wrong password leaves the connection as it was.
+#[derive(Debug)]
+pub struct Auth {
+    /// Password to check against the server configuration
+    password: String,
+}
+
+impl Auth {
+    /// Create a new `Auth` command sending `password`.
+    pub fn new(password: impl ToString) -> Auth {
+        Auth {
+            password: password.to_string(),
+        }
+    }
+
+    /// Get the password
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// Parse an `Auth` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `AUTH` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Auth` value on success. If the frame is malformed, `Err` is
+    /// returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two entries.
+    ///
+    /// ```text
+    /// AUTH password
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Auth> {
+        let password = parse.next_string()?;
+
+        Ok(Auth { password })
+    }
+
+    /// Apply the `Auth` command to the connection.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match &db.config().requirepass {
+            None => Frame::Error(
+                "ERR AUTH <password> called without any password configured".to_string(),
+            ),
+            Some(password) if *password == self.password => {
+                dst.set_authenticated(true);
+                Frame::Simple("OK".to_string())
+            }
+            Some(_) => Frame::Error("WRONGPASS invalid password".to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding an `Auth` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("auth".as_bytes()));
+        frame.push_bulk(Bytes::from(self.password.into_bytes()));
+        frame
+    }
+}
@@ -0,0 +1,86 @@
+use crate::cmd::Parse;
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Cluster introspection.
+///
+/// `mini-redis` does not support clustering. These subcommands exist so that
+/// cluster aware clients probing the server detect a standalone instance
+/// instead of failing on an unknown command.
+///
+/// # Subcommands
+///
+/// Currently, the following subcommands are supported:
+///
+/// * INFO -- Returns the cluster state, with `cluster_enabled:0`.
+/// * MYID -- Returns the 40 character id of this server.
+#[derive(Debug)]
+pub enum Cluster {
+    /// `CLUSTER INFO`
+    Info,
+
+    /// `CLUSTER MYID`
+    MyId,
+}
+
+impl Cluster {
+    /// Parse a `Cluster` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `CLUSTER` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Cluster` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two entries.
+    ///
+    /// ```text
+    /// CLUSTER INFO|MYID
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Cluster> {
+        match &parse.next_string()?.to_uppercase()[..] {
+            "INFO" => Ok(Cluster::Info),
+            "MYID" => Ok(Cluster::MyId),
+            _ => Err("currently `CLUSTER` only supports the INFO and MYID subcommands".into()),
+        }
+    }
+
+    /// Apply the `Cluster` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self {
+            // The fields a single node without any slots would report.
+            Cluster::Info => Frame::Bulk(Bytes::from_static(
+                b"cluster_enabled:0\r\n\
+                  cluster_state:ok\r\n\
+                  cluster_slots_assigned:0\r\n\
+                  cluster_slots_ok:0\r\n\
+                  cluster_slots_pfail:0\r\n\
+                  cluster_slots_fail:0\r\n\
+                  cluster_known_nodes:1\r\n\
+                  cluster_size:0\r\n\
+                  cluster_current_epoch:0\r\n\
+                  cluster_my_epoch:0\r\n",
+            )),
+            Cluster::MyId => Frame::Bulk(Bytes::from(db.node_id().to_string())),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use crate::cmd::{CommandSpec, Parse, ParseError};
+use crate::{Connection, Frame};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Returns the keys of a full command invocation, without executing it.
+///
+/// The keys are found using the key positions recorded for each command in
+/// `CommandSpec`. Cluster aware clients use this to route commands they do not
+/// know about.
+#[derive(Debug)]
+pub struct CommandGetKeys {
+    /// The invocation to inspect, starting with the command name
+    args: Vec<Bytes>,
+}
+
+impl CommandGetKeys {
+    /// Create a new `CommandGetKeys` command which returns the keys of `args`.
+    ///
+    /// `args` is a full command invocation, starting with the command name.
+    pub fn new(args: Vec<Bytes>) -> CommandGetKeys {
+        CommandGetKeys { args }
+    }
+
+    /// Get the inspected invocation
+    pub fn args(&self) -> &[Bytes] {
+        &self.args
+    }
+
+    /// Parse a `CommandGetKeys` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `COMMAND` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `CommandGetKeys` value on success. If the frame is
+    /// malformed, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 3 entries.
+    ///
+    /// ```text
+    /// COMMAND GETKEYS command [arg ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<CommandGetKeys> {
+        use ParseError::EndOfStream;
+
+        // Currently, mini-redis does not support any of the other COMMAND
+        // subcommands.
+        let subcommand = parse.next_string()?;
+        if subcommand.to_uppercase() != "GETKEYS" {
+            return Err("currently `COMMAND` only supports the GETKEYS subcommand".into());
+        }
+
+        // At least the command name must be provided.
+        let mut args = vec![parse.next_bytes()?];
+
+        loop {
+            match parse.next_bytes() {
+                Ok(arg) => args.push(arg),
+                Err(EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(CommandGetKeys { args })
+    }
+
+    /// Apply the `CommandGetKeys` command.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, dst))]
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        let spec = std::str::from_utf8(&self.args[0])
+            .ok()
+            .and_then(|name| CommandSpec::lookup(&name.to_lowercase()));
+
+        let response = match spec {
+            None => Frame::Error("ERR Invalid command specified".to_string()),
+            Some(spec) if !spec.check_arity(self.args.len()) => {
+                Frame::Error("ERR Invalid number of arguments specified for command".to_string())
+            }
+            Some(spec) if !spec.has_keys() => {
+                Frame::Error("ERR The command has no key arguments".to_string())
+            }
+            Some(spec) => match spec.keys(&self.args) {
+                Some(keys) => {
+                    let mut response = Frame::array();
+                    for key in keys {
+                        response.push_bulk(key.clone());
+                    }
+                    response
+                }
+                None => Frame::Error("ERR Invalid arguments specified for command".to_string()),
+            },
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `CommandGetKeys` command
+    /// to send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("command".as_bytes()));
+        frame.push_bulk(Bytes::from("getkeys".as_bytes()));
+        for arg in self.args {
+            frame.push_bulk(arg);
+        }
+        frame
+    }
+}
@@ -0,0 +1,76 @@
+use crate::cmd::Parse;
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Resets the statistics reported by `INFO`.
+///
+/// Currently, this clears the per command statistics of the `commandstats`
+/// section and the counters of the `stats` section.
+#[derive(Debug, Default)]
+pub struct ConfigResetStat;
+
+impl ConfigResetStat {
+    /// Create a new `ConfigResetStat` command.
+    pub fn new() -> ConfigResetStat {
+        ConfigResetStat
+    }
+
+    /// Parse a `ConfigResetStat` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `CONFIG` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ConfigResetStat` value on success. If the frame is
+    /// malformed, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two entries.
+    ///
+    /// ```text
+    /// CONFIG RESETSTAT
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ConfigResetStat> {
+        // Currently, mini-redis does not support any of the other CONFIG
+        // subcommands.
+        let subcommand = parse.next_string()?;
+        if subcommand.to_uppercase() != "RESETSTAT" {
+            return Err("currently `CONFIG` only supports the RESETSTAT subcommand".into());
+        }
+
+        Ok(ConfigResetStat)
+    }
+
+    /// Apply the `ConfigResetStat` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        db.reset_stats();
+
+        let response = Frame::Simple("OK".to_string());
+        debug!(?response);
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ConfigResetStat` command
+    /// to send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("config".as_bytes()));
+        frame.push_bulk(Bytes::from("resetstat".as_bytes()));
+        frame
+    }
+}
@@ -0,0 +1,164 @@
+use crate::cmd::{Parse, ParseError};
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Administrative commands, intended for testing.
+///
+/// # Subcommands
+///
+/// Currently, the following subcommands are supported:
+///
+/// * QUIT-ALL -- Close every connection except the one issuing the command.
+///   Replies with the number of connections closed.
+/// * PROTOCOL type -- Reply with an example value of the given RESP type, so
+///   clients can test their decoding. `mini-redis` only speaks RESP2, so
+///   RESP3 types are sent as their RESP2 equivalent, as Redis does.
+/// * POPULATE count [prefix] [size] -- Create `count` keys named
+///   `prefix:0`, `prefix:1`... holding `value:0`, `value:1`... for load
+///   tests. The prefix defaults to `key`. If `size` is given, the values are
+///   padded with zero bytes, or truncated, to `size` bytes. Existing keys are
+///   not overwritten.
+#[derive(Debug)]
+pub enum Debug {
+    /// `DEBUG QUIT-ALL`
+    QuitAll,
+
+    /// `DEBUG PROTOCOL type`
+    Protocol(String),
+
+    /// `DEBUG POPULATE count [prefix] [size]`
+    Populate {
+        count: u64,
+        prefix: String,
+        size: Option<u64>,
+    },
+}
+
+impl Debug {
+    /// Parse a `Debug` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `DEBUG` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Debug` value on success. If the frame is malformed, `Err` is
+    /// returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two to five entries.
+    ///
+    /// ```text
+    /// DEBUG QUIT-ALL
+    /// DEBUG PROTOCOL type
+    /// DEBUG POPULATE count [prefix] [size]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Debug> {
+        match &parse.next_string()?.to_uppercase()[..] {
+            "QUIT-ALL" => Ok(Debug::QuitAll),
+            "PROTOCOL" => Ok(Debug::Protocol(parse.next_string()?.to_lowercase())),
+            "POPULATE" => {
+                let count = parse.next_int()?;
+
+                let prefix = match parse.next_string() {
+                    Ok(prefix) => prefix,
+                    Err(ParseError::EndOfStream) => "key".to_string(),
+                    Err(err) => return Err(err.into()),
+                };
+
+                let size = match parse.next_int() {
+                    Ok(size) => Some(size),
+                    Err(ParseError::EndOfStream) => None,
+                    Err(err) => return Err(err.into()),
+                };
+
+                Ok(Debug::Populate {
+                    count,
+                    prefix,
+                    size,
+                })
+            }
+            _ => Err(
+                "currently `DEBUG` only supports the QUIT-ALL, PROTOCOL and POPULATE subcommands"
+                    .into(),
+            ),
+        }
+    }
+
+    /// Apply the `Debug` command to the specified `Db` instance.
+    ///
+    /// `client_id` identifies the connection which issued the command. The
+    /// response is written to `dst`. This is called by the server in order to
+    /// execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        client_id: u64,
+    ) -> crate::Result<()> {
+        let response = match self {
+            Debug::QuitAll => {
+                // The other connections finish the command they are processing,
+                // if any, before closing. This does not wait for them.
+                let closed = db.close_clients_except(client_id);
+                Frame::Integer(closed as i64)
+            }
+            Debug::Protocol(kind) => protocol_example(&kind),
+            Debug::Populate {
+                count,
+                prefix,
+                size,
+            } => {
+                db.populate(count, &prefix, size.map(|size| size as usize));
+                Frame::Simple("OK".to_string())
+            }
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+/// Returns the example value replied by `DEBUG PROTOCOL kind`.
+///
+/// The values are the ones Redis uses, encoded as Redis does for RESP2
+/// clients: doubles, big numbers and verbatim strings are bulk strings, sets
+/// are arrays, maps are arrays of alternating keys and values, booleans are
+/// the integers `1` and `0` and attributes are omitted.
+fn protocol_example(kind: &str) -> Frame {
+    match kind {
+        "string" => Frame::Bulk(Bytes::from_static(b"Hello World")),
+        "integer" => Frame::Integer(12345),
+        "double" => Frame::Bulk(Bytes::from_static(b"3.141")),
+        "bignum" => Frame::Bulk(Bytes::from_static(b"1234567999999999999999999999999999999")),
+        "null" => Frame::Null,
+        "array" | "set" => Frame::Array((0..3).map(Frame::Integer).collect()),
+        "map" => Frame::Array(
+            (0..3)
+                .flat_map(|i| vec![Frame::Integer(i), Frame::Integer((i == 1) as i64)])
+                .collect(),
+        ),
+        "attrib" => Frame::Bulk(Bytes::from_static(
+            b"Some real reply following the attribute",
+        )),
+        "verbatim" => Frame::Bulk(Bytes::from_static(b"This is a verbatim\nstring")),
+        "true" => Frame::Integer(1),
+        "false" => Frame::Integer(0),
+        "err" => Frame::Error("ERR This is an error".to_string()),
+        _ => Frame::Error(
+            "ERR Wrong protocol type name. Please use one of the following: \
+             string|integer|double|bignum|null|array|set|map|attrib|verbatim|true|false|err"
+                .to_string(),
+        ),
+    }
+}
@@ -0,0 +1,77 @@
+use crate::cmd::incr::apply_delta;
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::instrument;
+
+/// Decrement the integer stored at key by one.
+///
+/// A missing key is set to `0` before the operation. An error is returned if
+/// the value is not the string representation of a 64 bit signed integer, or
+/// if the result would not fit in one. The reply is the value after the
+/// decrement.
+#[derive(Debug)]
+pub struct Decr {
+    /// Name of the key to decrement
+    key: String,
+}
+
+impl Decr {
+    /// Create a new `Decr` command which decrements `key`.
+    pub fn new(key: impl ToString) -> Decr {
+        Decr {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Parse a `Decr` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `DECR` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Decr` value on success. If the frame is malformed, `Err` is
+    /// returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two entries.
+    ///
+    /// ```text
+    /// DECR key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Decr> {
+        let key = parse.next_string()?;
+
+        Ok(Decr { key })
+    }
+
+    /// Apply the `Decr` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        apply_delta(db, dst, &self.key, -1).await
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Decr` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("decr".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
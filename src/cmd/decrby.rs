@@ -0,0 +1,97 @@
+use crate::cmd::incr::apply_delta;
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Decrement the integer stored at key by `delta`.
+///
+/// `delta` may be negative. A missing key is set to `0` before the operation.
+/// An error is returned if the value is not the string representation of a 64
+/// bit signed integer, or if the result would not fit in one. The reply is the
+/// value after the decrement.
+#[derive(Debug)]
+pub struct DecrBy {
+    /// Name of the key to decrement
+    key: String,
+
+    /// Amount subtracted from the value
+    delta: i64,
+}
+
+impl DecrBy {
+    /// Create a new `DecrBy` command which decrements `key` by `delta`.
+    pub fn new(key: impl ToString, delta: i64) -> DecrBy {
+        DecrBy {
+            key: key.to_string(),
+            delta,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the delta
+    pub fn delta(&self) -> i64 {
+        self.delta
+    }
+
+    /// Parse a `DecrBy` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `DECRBY` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `DecrBy` value on success. If the frame is malformed or
+    /// `delta` is not an integer, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// DECRBY key delta
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<DecrBy> {
+        let key = parse.next_string()?;
+        let delta = parse.next_signed_int()?;
+
+        Ok(DecrBy { key, delta })
+    }
+
+    /// Apply the `DecrBy` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        // `i64::MIN` cannot be negated.
+        match self.delta.checked_neg() {
+            Some(delta) => apply_delta(db, dst, &self.key, delta).await,
+            None => {
+                let response = Frame::Error("ERR decrement would overflow".to_string());
+                debug!(?response);
+                dst.write_frame(&response).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `DecrBy` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("decrby".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.delta.to_string()));
+        frame
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use std::time::Duration;
+use tracing::{debug, instrument};
+
+/// Set a timeout on key, in seconds.
+///
+/// Any previous timeout is replaced. A timeout which is not positive deletes
+/// the key right away. The reply is `1` if the timeout was set and `0` if the
+/// key does not exist. Keys of any type may expire.
+#[derive(Debug)]
+pub struct Expire {
+    /// Name of the key to expire
+    key: String,
+
+    /// Seconds after which the key expires
+    seconds: i64,
+}
+
+impl Expire {
+    /// Create a new `Expire` command which makes `key` expire after `seconds`.
+    pub fn new(key: impl ToString, seconds: i64) -> Expire {
+        Expire {
+            key: key.to_string(),
+            seconds,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the timeout, in seconds
+    pub fn seconds(&self) -> i64 {
+        self.seconds
+    }
+
+    /// Parse an `Expire` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `EXPIRE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Expire` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// EXPIRE key seconds
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Expire> {
+        let key = parse.next_string()?;
+        let seconds = parse.next_signed_int()?;
+
+        Ok(Expire { key, seconds })
+    }
+
+    /// Apply the `Expire` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let updated = if self.seconds > 0 {
+            db.expire(&self.key, Duration::from_secs(self.seconds as u64))
+        } else {
+            // The key would expire immediately.
+            db.del(&[self.key]) > 0
+        };
+
+        let response = Frame::Integer(updated as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding an `Expire` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("expire".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.seconds.to_string()));
+        frame
+    }
+}
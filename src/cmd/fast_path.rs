@@ -0,0 +1,82 @@
+use crate::{Db, Frame};
+
+use bytes::Bytes;
+use std::str;
+
+/// Apply `GET` and `SET` requests directly from the received frame.
+///
+/// These are by far the most frequently issued commands. Going through
+/// `Command::from_frame` allocates the lower cased command name, a `Parse`
+/// cursor and the command struct before any work is done. Here, the frame is
+/// inspected in place and the `Db` is called directly.
+///
+/// Only the plain forms are handled: `GET key` and `SET key value` with no
+/// options. Anything else, including malformed requests, returns `None` and is
+/// left to the generic path so that replies and errors are identical.
+///
+/// # Returns
+///
+/// The command name and response frame if the request was applied, `None`
+/// otherwise.
+pub(crate) fn apply(frame: &Frame, db: &Db) -> Option<(&'static str, Frame)> {
+    let parts = match frame {
+        Frame::Array(parts) => parts,
+        _ => return None,
+    };
+
+    match parts.as_slice() {
+        [name, key] if is_name(name, b"get") => {
+            let key = to_str(key)?;
+
+            let response = match db.get(key) {
+                Some(value) => Frame::Bulk(value),
+                None => Frame::Null,
+            };
+
+            Some(("get", response))
+        }
+        [name, key, value] if is_name(name, b"set") => {
+            let key = to_str(key)?;
+            let value = to_bytes(value)?;
+
+            // Rejected keys are left to the generic path, which reports the
+            // error.
+            db.check_write_key(key).ok()?;
+
+            let response = match db.set(key.to_string(), value, None) {
+                Ok(()) => Frame::Simple("OK".to_string()),
+                Err(msg) => Frame::Error(msg.to_string()),
+            };
+
+            Some(("set", response))
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` if `frame` is the command name `name`, ignoring case.
+fn is_name(frame: &Frame, name: &[u8]) -> bool {
+    match frame {
+        Frame::Simple(s) => s.as_bytes().eq_ignore_ascii_case(name),
+        Frame::Bulk(data) => data.eq_ignore_ascii_case(name),
+        _ => false,
+    }
+}
+
+/// Borrow a string argument, matching `Parse::next_string`.
+fn to_str(frame: &Frame) -> Option<&str> {
+    match frame {
+        Frame::Simple(s) => Some(s),
+        Frame::Bulk(data) => str::from_utf8(data).ok(),
+        _ => None,
+    }
+}
+
+/// Extract a bytes argument, matching `Parse::next_bytes`.
+fn to_bytes(frame: &Frame) -> Option<Bytes> {
+    match frame {
+        Frame::Simple(s) => Some(Bytes::copy_from_slice(s.as_bytes())),
+        Frame::Bulk(data) => Some(data.clone()),
+        _ => None,
+    }
+}
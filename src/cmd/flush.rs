@@ -0,0 +1,148 @@
+//! Commands removing every key, from the selected database with `FLUSHDB` or
+//! from all of them with `FLUSHALL`.
+//!
+//! The keyspaces are copied on write, so clearing them only drops their
+//! top level maps. The data is freed once no snapshot holds it anymore. The
+//! `ASYNC` and `SYNC` modifiers are accepted for compatibility and behave the
+//! same.
+//!
+//! Pub/sub channels and their subscribers are not keys and are not affected.
+
+use crate::{Connection, Db, Frame, Parse, ParseError};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Remove every key from the selected database.
+#[derive(Debug, Default)]
+pub struct FlushDb {}
+
+/// Remove every key from every database.
+#[derive(Debug, Default)]
+pub struct FlushAll {}
+
+impl FlushDb {
+    /// Create a new `FlushDb` command.
+    pub fn new() -> FlushDb {
+        FlushDb {}
+    }
+
+    /// Parse a `FlushDb` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `FLUSHDB` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `FlushDb` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing `FLUSHDB` and an optional modifier.
+    ///
+    /// ```text
+    /// FLUSHDB [ASYNC | SYNC]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<FlushDb> {
+        parse_modifier(parse)?;
+
+        Ok(FlushDb {})
+    }
+
+    /// Apply the `FlushDb` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        db.flush();
+
+        let response = Frame::Simple("OK".to_string());
+        debug!(?response);
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `FlushDb` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("flushdb".as_bytes()));
+        frame
+    }
+}
+
+impl FlushAll {
+    /// Create a new `FlushAll` command.
+    pub fn new() -> FlushAll {
+        FlushAll {}
+    }
+
+    /// Parse a `FlushAll` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `FLUSHALL` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `FlushAll` value on success. If the frame is malformed,
+    /// `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing `FLUSHALL` and an optional modifier.
+    ///
+    /// ```text
+    /// FLUSHALL [ASYNC | SYNC]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<FlushAll> {
+        parse_modifier(parse)?;
+
+        Ok(FlushAll {})
+    }
+
+    /// Apply the `FlushAll` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        db.flush_all();
+
+        let response = Frame::Simple("OK".to_string());
+        debug!(?response);
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `FlushAll` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("flushall".as_bytes()));
+        frame
+    }
+}
+
+/// Consume the optional `ASYNC` or `SYNC` modifier.
+fn parse_modifier(parse: &mut Parse) -> crate::Result<()> {
+    match parse.next_string() {
+        Ok(modifier) if ["ASYNC", "SYNC"].contains(&&modifier.to_uppercase()[..]) => Ok(()),
+        Ok(_) => Err("ERR syntax error".into()),
+        Err(ParseError::EndOfStream) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
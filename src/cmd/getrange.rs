@@ -0,0 +1,149 @@
+use crate::cmd::{Parse, ParseError};
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Get the substring of the string value stored at key, determined by the
+/// offsets `start` and `end` (both are inclusive).
+///
+/// Negative offsets can be used in order to provide an offset starting from the
+/// end of the string. So -1 means the last character, -2 the penultimate and so
+/// forth. Out of range requests are clamped to the actual length of the string.
+///
+/// # Options
+///
+/// Currently, the following options are supported:
+///
+/// * CHARS -- Interpret `start` and `end` as character indices over the value
+///   decoded as UTF-8 instead of byte indices. An error is returned if the
+///   value is not valid UTF-8.
+#[derive(Debug)]
+pub struct GetRange {
+    /// Name of the key to get
+    key: String,
+
+    /// Offset of the first element to return
+    start: i64,
+
+    /// Offset of the last element to return
+    end: i64,
+
+    /// Whether the offsets are character indices instead of byte indices
+    chars: bool,
+}
+
+impl GetRange {
+    /// Create a new `GetRange` command which fetches the part of `key` between
+    /// `start` and `end`.
+    ///
+    /// If `chars` is `true`, the offsets are character indices.
+    pub fn new(key: impl ToString, start: i64, end: i64, chars: bool) -> GetRange {
+        GetRange {
+            key: key.to_string(),
+            start,
+            end,
+            chars,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the start offset
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    /// Get the end offset
+    pub fn end(&self) -> i64 {
+        self.end
+    }
+
+    /// Returns `true` if the offsets are character indices
+    pub fn chars(&self) -> bool {
+        self.chars
+    }
+
+    /// Parse a `GetRange` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `GETRANGE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `GetRange` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 4 entries.
+    ///
+    /// ```text
+    /// GETRANGE key start end [CHARS]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<GetRange> {
+        use ParseError::EndOfStream;
+
+        let key = parse.next_string()?;
+        let start = parse.next_signed_int()?;
+        let end = parse.next_signed_int()?;
+
+        // The `CHARS` option is optional. If nothing else follows, offsets are
+        // byte indices.
+        let chars = match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "CHARS" => true,
+            Ok(_) => return Err("currently `GETRANGE` only supports the CHARS option".into()),
+            Err(EndOfStream) => false,
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(GetRange {
+            key,
+            start,
+            end,
+            chars,
+        })
+    }
+
+    /// Apply the `GetRange` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.getrange(&self.key, self.start, self.end, self.chars) {
+            Ok(value) => Frame::Bulk(value),
+            // The value cannot be indexed by character. The connection remains
+            // usable, so the error is reported to the client.
+            Err(_) => Frame::Error("ERR value is not valid UTF-8".to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `GetRange` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("getrange".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.start.to_string()));
+        frame.push_bulk(Bytes::from(self.end.to_string()));
+        if self.chars {
+            frame.push_bulk(Bytes::from("chars".as_bytes()));
+        }
+        frame
+    }
+}
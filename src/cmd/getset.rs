@@ -0,0 +1,99 @@
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Set `key` to hold the string `value` and return the value it held.
+///
+/// The reply is `Null` if the key did not exist. The previous expiration of the
+/// key, if any, is removed. An error is returned if the key holds another type
+/// of value.
+#[derive(Debug)]
+pub struct GetSet {
+    /// Name of the key to set
+    key: String,
+
+    /// Value to store
+    value: Bytes,
+}
+
+impl GetSet {
+    /// Create a new `GetSet` command which sets `key` to `value`.
+    pub fn new(key: impl ToString, value: Bytes) -> GetSet {
+        GetSet {
+            key: key.to_string(),
+            value,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the value
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    /// Parse a `GetSet` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `GETSET` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `GetSet` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// GETSET key value
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<GetSet> {
+        let key = parse.next_string()?;
+        let value = parse.next_bytes()?;
+
+        Ok(GetSet { key, value })
+    }
+
+    /// Apply the `GetSet` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.check_write_key(&self.key) {
+            Ok(()) => match db.getset(self.key, self.value) {
+                Ok(Some(value)) => Frame::Bulk(value),
+                Ok(None) => Frame::Null,
+                Err(msg) => Frame::Error(msg.to_string()),
+            },
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `GetSet` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("getset".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.value);
+        frame
+    }
+}
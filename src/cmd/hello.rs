@@ -0,0 +1,107 @@
+use crate::{Connection, Frame, Parse, ParseError};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Switch the connection to another protocol version and describe the
+/// server.
+///
+/// With `3`, replies are encoded with RESP3 from then on, using maps, doubles,
+/// booleans and push frames. With `2`, or until `HELLO 3` is sent, replies are
+/// encoded with RESP2. Without a version, the protocol is left unchanged.
+///
+/// The reply is a map of server metadata, encoded with the new protocol.
+#[derive(Debug, Default)]
+pub struct Hello {
+    /// Protocol version to switch to
+    protocol: Option<u64>,
+}
+
+impl Hello {
+    /// Create a new `Hello` command switching to `protocol`, if given.
+    pub fn new(protocol: Option<u64>) -> Hello {
+        Hello { protocol }
+    }
+
+    /// Get the protocol version
+    pub fn protocol(&self) -> Option<u64> {
+        self.protocol
+    }
+
+    /// Parse a `Hello` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `HELLO` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Hello` value on success. If the frame is malformed or the
+    /// version is not an integer, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing `HELLO` and an optional version.
+    ///
+    /// ```text
+    /// HELLO [protover]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Hello> {
+        match parse.next_int() {
+            Ok(protocol) => Ok(Hello::new(Some(protocol))),
+            Err(ParseError::EndOfStream) => Ok(Hello::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Apply the `Hello` command to the connection.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, dst))]
+    pub(crate) async fn apply(self, dst: &mut Connection, client_id: u64) -> crate::Result<()> {
+        let response = match self.protocol {
+            Some(protocol @ 2..=3) => {
+                dst.set_protocol(protocol as u8);
+                server_info(dst.protocol(), client_id)
+            }
+            None => server_info(dst.protocol(), client_id),
+            Some(_) => Frame::Error("NOPROTO unsupported protocol version".to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Hello` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hello".as_bytes()));
+        if let Some(protocol) = self.protocol {
+            frame.push_bulk(Bytes::from(protocol.to_string()));
+        }
+        frame
+    }
+}
+
+/// Build the server metadata replied to `HELLO`.
+fn server_info(protocol: u8, client_id: u64) -> Frame {
+    let field = |name: &'static str| Frame::Bulk(Bytes::from_static(name.as_bytes()));
+
+    Frame::Map(vec![
+        (field("server"), field("mini-redis")),
+        (field("version"), field(env!("CARGO_PKG_VERSION"))),
+        (field("proto"), Frame::Integer(protocol as i64)),
+        (field("id"), Frame::Integer(client_id as i64)),
+        (field("mode"), field("standalone")),
+        (field("role"), field("master")),
+    ])
+}
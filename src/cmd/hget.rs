@@ -24,14 +24,14 @@ impl HGet {
         &self.field
     }
 
-    pub fn parse_frames(parse: &mut Parse) -> crate::Result<HGet> {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HGet> {
         let key = parse.next_string()?;
         let field = parse.next_string()?;
 
         Ok(HGet { key, field })
     }
 
-    pub async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
         // get the value from the shared database state.
         let response = match db.hget(&self.key, &self.field) {
             Some(value) => Frame::Bulk(value),
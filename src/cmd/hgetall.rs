@@ -20,24 +20,24 @@ impl HGetAll {
         &self.key
     }
 
-    pub fn parse_frames(parse: &mut Parse) -> crate::Result<HGetAll> {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HGetAll> {
         let key = parse.next_string()?;
         Ok(HGetAll { key })
     }
 
-    pub async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
         // get the value from the shared database state.
         let response = match db.hgetall(&self.key) {
+            // RESP2 connections receive the fields and values as a flat array.
             Some(hash_map) => {
-                let mut frame = Frame::Array(Vec::with_capacity(hash_map.len() * 2));
-                for (key, value) in hash_map {
-                    // For each key-value pair, push the key and value as bulk strings
-                    frame.push_bulk(Bytes::from(key.into_bytes()));
-                    frame.push_bulk(value);
-                }
-                frame
+                let entries = hash_map.into_iter().map(|(key, value)| {
+                    let key = Frame::Bulk(Bytes::from(key.into_bytes()));
+                    (key, Frame::Bulk(value))
+                });
+                Frame::Map(entries.collect())
             }
-            None => Frame::Null,
+            // A missing key is an empty hash, as in Redis.
+            None => Frame::Map(vec![]),
         };
 
         debug!(?response);
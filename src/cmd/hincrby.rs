@@ -0,0 +1,112 @@
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Increment the integer stored in a field of the hash stored at key by
+/// `delta`.
+///
+/// `delta` may be negative. A missing field is set to `0` before the operation,
+/// and a missing key is created. An error is returned if the field does not
+/// hold the string representation of a 64 bit signed integer, or if the result
+/// would not fit in one. The reply is the value after the increment.
+#[derive(Debug)]
+pub struct HIncrBy {
+    /// Name of the hash
+    key: String,
+
+    /// Name of the field to increment
+    field: String,
+
+    /// Amount added to the value
+    delta: i64,
+}
+
+impl HIncrBy {
+    /// Create a new `HIncrBy` command which increments `field` in the hash at
+    /// `key` by `delta`.
+    pub fn new(key: impl ToString, field: impl ToString, delta: i64) -> HIncrBy {
+        HIncrBy {
+            key: key.to_string(),
+            field: field.to_string(),
+            delta,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the field
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Get the delta
+    pub fn delta(&self) -> i64 {
+        self.delta
+    }
+
+    /// Parse a `HIncrBy` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `HINCRBY` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `HIncrBy` value on success. If the frame is malformed or
+    /// `delta` is not an integer, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing four entries.
+    ///
+    /// ```text
+    /// HINCRBY key field delta
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HIncrBy> {
+        let key = parse.next_string()?;
+        let field = parse.next_string()?;
+        let delta = parse.next_signed_int()?;
+
+        Ok(HIncrBy { key, field, delta })
+    }
+
+    /// Apply the `HIncrBy` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db
+            .check_write_key(&self.key)
+            .and_then(|()| db.hincrby(self.key, self.field, self.delta))
+        {
+            Ok(value) => Frame::Integer(value),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `HIncrBy` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hincrby".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.field.into_bytes()));
+        frame.push_bulk(Bytes::from(self.delta.to_string()));
+        frame
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Returns all field names in the hash stored at key.
+///
+/// Fields are returned in the order they were first set, as with `HGETALL`.
+/// The reply is an empty array if the key does not exist.
+#[derive(Debug)]
+pub struct HKeys {
+    /// Name of the hash
+    key: String,
+}
+
+impl HKeys {
+    /// Create a new `HKeys` command which lists the fields of the hash at
+    /// `key`.
+    pub fn new(key: impl ToString) -> HKeys {
+        HKeys {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Parse a `HKeys` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `HKEYS` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `HKeys` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two entries.
+    ///
+    /// ```text
+    /// HKEYS key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HKeys> {
+        let key = parse.next_string()?;
+
+        Ok(HKeys { key })
+    }
+
+    /// Apply the `HKeys` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let fields = db.hkeys(&self.key);
+        let response = Frame::Array(
+            fields
+                .into_iter()
+                .map(|field| Frame::Bulk(Bytes::from(field)))
+                .collect(),
+        );
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `HKeys` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hkeys".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
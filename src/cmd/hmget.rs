@@ -0,0 +1,115 @@
+use crate::cmd::{Parse, ParseError};
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Returns the values of the specified fields in the hash stored at key.
+///
+/// The reply is an array with the value of each field, in the requested order,
+/// or `Null` for fields which do not exist. If the key does not exist, every
+/// field is `Null`.
+#[derive(Debug)]
+pub struct HMGet {
+    /// Name of the hash
+    key: String,
+
+    /// Names of the fields to get
+    fields: Vec<String>,
+}
+
+impl HMGet {
+    /// Create a new `HMGet` command which gets `fields` from the hash at
+    /// `key`.
+    pub fn new(key: impl ToString, fields: &[&str]) -> HMGet {
+        HMGet {
+            key: key.to_string(),
+            fields: fields.iter().map(|field| field.to_string()).collect(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the fields
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    /// Parse a `HMGet` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `HMGET` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `HMGet` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 3 entries.
+    ///
+    /// ```text
+    /// HMGET key field [field ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HMGet> {
+        let key = parse.next_string()?;
+
+        // At least one field is required.
+        let mut fields = vec![parse.next_string()?];
+
+        loop {
+            match parse.next_string() {
+                Ok(field) => fields.push(field),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(HMGet { key, fields })
+    }
+
+    /// Apply the `HMGet` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let values = db.hmget(&self.key, &self.fields);
+        let response = Frame::Array(
+            values
+                .into_iter()
+                .map(|value| match value {
+                    Some(value) => Frame::Bulk(value),
+                    None => Frame::Null,
+                })
+                .collect(),
+        );
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `HMGet` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hmget".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for field in self.fields {
+            frame.push_bulk(Bytes::from(field.into_bytes()));
+        }
+        frame
+    }
+}
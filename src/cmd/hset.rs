@@ -56,10 +56,16 @@ impl HSet {
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
         // Set the value in the shared database state.
-        db.hset(self.key, self.field, self.value);
-
-        // Create a success response and write it to `dst`.
-        let response = Frame::Simple("OK".to_string());
+        let response = match db
+            .check_write_key(&self.key)
+            .and_then(|()| db.hset(self.key, self.field, self.value))
+        {
+            // Create a success response.
+            Ok(_) => Frame::Simple("OK".to_string()),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        // Write the response to `dst`.
         debug!(?response);
         dst.write_frame(&response).await?;
 
@@ -68,7 +74,7 @@ impl HSet {
 
 
     /// Parse a `HSet` instance from a received frame.
-    pub fn parse_frames(parse: &mut crate::Parse) -> crate::Result<HSet> {
+    pub(crate) fn parse_frames(parse: &mut crate::Parse) -> crate::Result<HSet> {
         // The `HSET` string has already been consumed. Extract the `key`
         // and `value` values from the frame.
         //
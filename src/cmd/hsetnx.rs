@@ -0,0 +1,110 @@
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Set a field in the hash stored at key to `value`, only if the field does
+/// not exist.
+///
+/// A missing key is created, holding a hash with the field. The reply is `1` if
+/// the field was set and `0` otherwise.
+#[derive(Debug)]
+pub struct HSetNx {
+    /// Name of the hash
+    key: String,
+
+    /// Name of the field to set
+    field: String,
+
+    /// Value to store
+    value: Bytes,
+}
+
+impl HSetNx {
+    /// Create a new `HSetNx` command which sets `field` in the hash at `key` to
+    /// `value` unless it exists.
+    pub fn new(key: impl ToString, field: impl ToString, value: Bytes) -> HSetNx {
+        HSetNx {
+            key: key.to_string(),
+            field: field.to_string(),
+            value,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the field
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Get the value
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    /// Parse a `HSetNx` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `HSETNX` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `HSetNx` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing four entries.
+    ///
+    /// ```text
+    /// HSETNX key field value
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HSetNx> {
+        let key = parse.next_string()?;
+        let field = parse.next_string()?;
+        let value = parse.next_bytes()?;
+
+        Ok(HSetNx { key, field, value })
+    }
+
+    /// Apply the `HSetNx` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db
+            .check_write_key(&self.key)
+            .and_then(|()| db.hsetnx(self.key, self.field, self.value))
+        {
+            Ok(set) => Frame::Integer(set as i64),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `HSetNx` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hsetnx".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.field.into_bytes()));
+        frame.push_bulk(self.value);
+        frame
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Returns all values in the hash stored at key.
+///
+/// Values are returned in the order their fields were first set, as with
+/// `HGETALL`. The reply is an empty array if the key does not exist.
+#[derive(Debug)]
+pub struct HVals {
+    /// Name of the hash
+    key: String,
+}
+
+impl HVals {
+    /// Create a new `HVals` command which lists the values of the hash at
+    /// `key`.
+    pub fn new(key: impl ToString) -> HVals {
+        HVals {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Parse a `HVals` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `HVALS` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `HVals` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two entries.
+    ///
+    /// ```text
+    /// HVALS key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HVals> {
+        let key = parse.next_string()?;
+
+        Ok(HVals { key })
+    }
+
+    /// Apply the `HVals` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let values = db.hvals(&self.key);
+        let response = Frame::Array(values.into_iter().map(Frame::Bulk).collect());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `HVals` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hvals".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
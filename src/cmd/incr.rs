@@ -0,0 +1,103 @@
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Increment the integer stored at key by one.
+///
+/// A missing key is set to `0` before the operation. An error is returned if
+/// the value is not the string representation of a 64 bit signed integer, or
+/// if the result would not fit in one. The reply is the value after the
+/// increment.
+#[derive(Debug)]
+pub struct Incr {
+    /// Name of the key to increment
+    key: String,
+}
+
+impl Incr {
+    /// Create a new `Incr` command which increments `key`.
+    pub fn new(key: impl ToString) -> Incr {
+        Incr {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Parse an `Incr` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `INCR` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Incr` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two entries.
+    ///
+    /// ```text
+    /// INCR key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Incr> {
+        let key = parse.next_string()?;
+
+        Ok(Incr { key })
+    }
+
+    /// Apply the `Incr` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        apply_delta(db, dst, &self.key, 1).await
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding an `Incr` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("incr".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+/// Add `delta` to the integer stored at `key` and write the new value to
+/// `dst`.
+///
+/// This is shared by `INCR`, `DECR`, `INCRBY` and `DECRBY`. If the value is
+/// not an integer or the result would overflow, an error frame is written
+/// instead.
+pub(super) async fn apply_delta(
+    db: &Db,
+    dst: &mut Connection,
+    key: &str,
+    delta: i64,
+) -> crate::Result<()> {
+    let response = match db
+        .check_write_key(key)
+        .and_then(|()| db.incr_by(key, delta))
+    {
+        Ok(value) => Frame::Integer(value),
+        Err(msg) => Frame::Error(msg.to_string()),
+    };
+
+    debug!(?response);
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use crate::cmd::incr::apply_delta;
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::instrument;
+
+/// Increment the integer stored at key by `delta`.
+///
+/// `delta` may be negative. A missing key is set to `0` before the operation.
+/// An error is returned if the value is not the string representation of a 64
+/// bit signed integer, or if the result would not fit in one. The reply is the
+/// value after the increment.
+#[derive(Debug)]
+pub struct IncrBy {
+    /// Name of the key to increment
+    key: String,
+
+    /// Amount added to the value
+    delta: i64,
+}
+
+impl IncrBy {
+    /// Create a new `IncrBy` command which increments `key` by `delta`.
+    pub fn new(key: impl ToString, delta: i64) -> IncrBy {
+        IncrBy {
+            key: key.to_string(),
+            delta,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the delta
+    pub fn delta(&self) -> i64 {
+        self.delta
+    }
+
+    /// Parse an `IncrBy` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `INCRBY` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `IncrBy` value on success. If the frame is malformed or
+    /// `delta` is not an integer, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// INCRBY key delta
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<IncrBy> {
+        let key = parse.next_string()?;
+        let delta = parse.next_signed_int()?;
+
+        Ok(IncrBy { key, delta })
+    }
+
+    /// Apply the `IncrBy` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        apply_delta(db, dst, &self.key, self.delta).await
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding an `IncrBy` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("incrby".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.delta.to_string()));
+        frame
+    }
+}
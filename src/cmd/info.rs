@@ -0,0 +1,247 @@
+use crate::cmd::{Parse, ParseError};
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use std::fmt::Write;
+use tokio::time::Instant;
+use tracing::{debug, instrument};
+
+/// Returns information and statistics about the server.
+///
+/// The reply is a bulk string made of sections, each starting with a
+/// `# Name` header followed by `field:value` lines. Sections are separated by
+/// an empty line.
+///
+/// Currently, the following sections are supported:
+///
+/// * clients -- The number of clients blocked in `BLPOP`, the number of keys
+///   they are blocked on, then one line per key with the number of clients
+///   blocked on it:
+///
+///   ```text
+///   blocked_clients:<count>
+///   blocking_keys:<count>
+///   blocked_db<index>:key=<key>,clients=<count>
+///   ```
+///
+/// * memory -- The approximate number of bytes used by values of all types,
+///   counted against the limit, and the limit along with the eviction policy:
+///
+///   ```text
+///   used_memory:<bytes>
+///   maxmemory:<bytes>
+///   maxmemory_policy:<noeviction|allkeys-lru>
+///   ```
+///
+/// * stats -- The number of times a connection stopped reading requests
+///   pipelined by its client until those already received were processed,
+///   leaving the client held back by TCP flow control:
+///
+///   ```text
+///   pipeline_backpressure_events:<count>
+///   ```
+///
+/// * commandstats -- One line per command called since the server started or
+///   `CONFIG RESETSTAT` was last issued:
+///
+///   ```text
+///   cmdstat_<name>:calls=<count>,usec=<total>,usec_per_call=<average>
+///   ```
+///
+/// * keyspace -- One line per database, with the number of keys, the number of
+///   keys with an expiration and their average remaining time to live in
+///   milliseconds. Databases without keys are omitted:
+///
+///   ```text
+///   db<index>:keys=<count>,expires=<count>,avg_ttl=<ms>
+///   ```
+#[derive(Debug)]
+pub struct Info {
+    /// The requested section. All sections are returned if `None`.
+    section: Option<String>,
+}
+
+impl Info {
+    /// Create a new `Info` command returning `section`, or all sections if
+    /// `None`.
+    pub fn new(section: Option<String>) -> Info {
+        Info { section }
+    }
+
+    /// Get the requested section
+    pub fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    /// Parse an `Info` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `INFO` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Info` value on success. If the frame is malformed, `Err` is
+    /// returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing one or two entries.
+    ///
+    /// ```text
+    /// INFO [section]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Info> {
+        match parse.next_string() {
+            Ok(section) => Ok(Info::new(Some(section))),
+            Err(ParseError::EndOfStream) => Ok(Info::new(None)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Apply the `Info` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let section = self.section.map(|section| section.to_lowercase());
+        let all = matches!(
+            section.as_deref(),
+            None | Some("all") | Some("everything") | Some("default")
+        );
+
+        let mut info = String::new();
+
+        // Unknown sections are ignored, as Redis does, resulting in an empty
+        // reply if nothing else was requested.
+        if all || section.as_deref() == Some("clients") {
+            let blocked = db.blocked_clients();
+
+            info.push_str("# Clients\r\n");
+            let _ = write!(
+                info,
+                "blocked_clients:{}\r\nblocking_keys:{}\r\n",
+                blocked.clients,
+                blocked.keys.len()
+            );
+
+            for (index, key, clients) in blocked.keys {
+                let _ = write!(
+                    info,
+                    "blocked_db{}:key={},clients={}\r\n",
+                    index, key, clients
+                );
+            }
+        }
+
+        if all || section.as_deref() == Some("memory") {
+            if !info.is_empty() {
+                info.push_str("\r\n");
+            }
+
+            let config = db.config();
+
+            info.push_str("# Memory\r\n");
+            let _ = write!(
+                info,
+                "used_memory:{}\r\nmaxmemory:{}\r\nmaxmemory_policy:{}\r\n",
+                db.used_memory(),
+                config.maxmemory,
+                config.maxmemory_policy.as_str()
+            );
+        }
+
+        if all || section.as_deref() == Some("stats") {
+            if !info.is_empty() {
+                info.push_str("\r\n");
+            }
+
+            info.push_str("# Stats\r\n");
+            let _ = write!(
+                info,
+                "pipeline_backpressure_events:{}\r\n",
+                db.backpressure_events()
+            );
+        }
+
+        if all || section.as_deref() == Some("commandstats") {
+            if !info.is_empty() {
+                info.push_str("\r\n");
+            }
+
+            info.push_str("# Commandstats\r\n");
+
+            for (name, stats) in db.command_stats() {
+                let per_call = stats.usec as f64 / stats.calls as f64;
+                let _ = write!(
+                    info,
+                    "cmdstat_{}:calls={},usec={},usec_per_call={:.2}\r\n",
+                    name, stats.calls, stats.usec, per_call
+                );
+            }
+        }
+
+        if all || section.as_deref() == Some("keyspace") {
+            if !info.is_empty() {
+                info.push_str("\r\n");
+            }
+
+            info.push_str("# Keyspace\r\n");
+
+            for index in 0..db.databases() {
+                // Counting the expirations requires visiting every key. This
+                // is done on a snapshot so connections are not blocked
+                // meanwhile.
+                let snapshot = db.select(index).snapshot();
+
+                if snapshot.is_empty() {
+                    continue;
+                }
+
+                let now = Instant::now();
+                let mut expires = 0;
+                let mut total_ttl = 0;
+
+                for when in snapshot.expirations() {
+                    expires += 1;
+                    total_ttl += when.saturating_duration_since(now).as_millis();
+                }
+
+                let avg_ttl = total_ttl.checked_div(expires).unwrap_or(0);
+
+                let _ = write!(
+                    info,
+                    "db{}:keys={},expires={},avg_ttl={}\r\n",
+                    index,
+                    snapshot.len(),
+                    expires,
+                    avg_ttl
+                );
+            }
+        }
+
+        let response = Frame::Bulk(Bytes::from(info));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding an `Info` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("info".as_bytes()));
+        if let Some(section) = self.section {
+            frame.push_bulk(Bytes::from(section.into_bytes()));
+        }
+        frame
+    }
+}
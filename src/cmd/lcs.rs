@@ -0,0 +1,359 @@
+use crate::cmd::{Parse, ParseError};
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Largest DP table, in bytes, `LCS` allocates. This is the default
+/// `proto-max-bulk-len` Redis bounds it with.
+const MAX_TABLE_SIZE: usize = 512 * 1024 * 1024;
+
+/// Find the longest common subsequence of the values stored at two keys.
+///
+/// Missing keys are treated as empty values. By default, the subsequence
+/// itself is returned.
+///
+/// # Options
+///
+/// * LEN -- Return the length of the subsequence instead.
+/// * IDX -- Return the ranges of the subsequence in both values, from the
+///   last match to the first, along with its length.
+/// * MINMATCHLEN `len` -- With IDX, only return matching ranges of at least
+///   `len` bytes.
+/// * WITHMATCHLEN -- With IDX, return the length of each matching range.
+#[derive(Debug)]
+pub struct Lcs {
+    /// Name of the first key
+    key1: String,
+
+    /// Name of the second key
+    key2: String,
+
+    /// Only return the length of the subsequence
+    len: bool,
+
+    /// Return the matching ranges
+    idx: bool,
+
+    /// Minimum length of the returned matching ranges
+    min_match_len: u64,
+
+    /// Return the length of each matching range
+    with_match_len: bool,
+}
+
+/// A contiguous range of the subsequence, as found by `matches`.
+struct Match {
+    /// Offsets in the first value, both inclusive
+    a: (usize, usize),
+
+    /// Offsets in the second value, both inclusive
+    b: (usize, usize),
+}
+
+impl Lcs {
+    /// Create a new `Lcs` command which returns the longest common subsequence
+    /// of the values at `key1` and `key2`.
+    pub fn new(key1: impl ToString, key2: impl ToString) -> Lcs {
+        Lcs {
+            key1: key1.to_string(),
+            key2: key2.to_string(),
+            len: false,
+            idx: false,
+            min_match_len: 0,
+            with_match_len: false,
+        }
+    }
+
+    /// Only return the length of the subsequence.
+    pub fn len_only(mut self) -> Lcs {
+        self.len = true;
+        self
+    }
+
+    /// Return the matching ranges of at least `min_match_len` bytes, each with
+    /// its length.
+    pub fn with_idx(mut self, min_match_len: u64) -> Lcs {
+        self.idx = true;
+        self.min_match_len = min_match_len;
+        self.with_match_len = true;
+        self
+    }
+
+    /// Parse a `Lcs` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `LCS` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Lcs` value on success. If the frame is malformed, `Err` is
+    /// returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 3 entries.
+    ///
+    /// ```text
+    /// LCS key1 key2 [LEN] [IDX] [MINMATCHLEN len] [WITHMATCHLEN]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Lcs> {
+        let mut lcs = Lcs::new(parse.next_string()?, parse.next_string()?);
+
+        loop {
+            match parse.next_string() {
+                Ok(s) => match &s.to_uppercase()[..] {
+                    "LEN" => lcs.len = true,
+                    "IDX" => lcs.idx = true,
+                    "MINMATCHLEN" => lcs.min_match_len = parse.next_int()?,
+                    "WITHMATCHLEN" => lcs.with_match_len = true,
+                    _ => return Err(
+                        "`LCS` only supports the LEN, IDX, MINMATCHLEN and WITHMATCHLEN options"
+                            .into(),
+                    ),
+                },
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(lcs)
+    }
+
+    /// Apply the `Lcs` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let a = db.get(&self.key1).unwrap_or_default();
+        let b = db.get(&self.key2).unwrap_or_default();
+
+        let response = if self.len && self.idx {
+            Frame::Error(
+                "ERR If you want both the length and indexes, please just use IDX.".to_string(),
+            )
+        } else if self.len {
+            Frame::Integer(lcs_len(&a, &b) as i64)
+        } else {
+            match Table::new(&a, &b) {
+                Some(table) if self.idx => self.idx_frame(table.matches(&a, &b), table.len()),
+                Some(table) => Frame::Bulk(table.subsequence(&a, &b)),
+                None => Frame::Error(
+                    "ERR Insufficient memory, transient memory for LCS exceeds \
+                     proto-max-bulk-len"
+                        .to_string(),
+                ),
+            }
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Build the `IDX` reply from the matching ranges and the subsequence
+    /// length.
+    fn idx_frame(&self, ranges: Vec<Match>, len: usize) -> Frame {
+        let mut matches = vec![];
+
+        for m in ranges {
+            let len = m.a.1 - m.a.0 + 1;
+
+            if (len as u64) < self.min_match_len {
+                continue;
+            }
+
+            let mut frame = Frame::Array(vec![
+                Frame::Array(vec![
+                    Frame::Integer(m.a.0 as i64),
+                    Frame::Integer(m.a.1 as i64),
+                ]),
+                Frame::Array(vec![
+                    Frame::Integer(m.b.0 as i64),
+                    Frame::Integer(m.b.1 as i64),
+                ]),
+            ]);
+
+            if self.with_match_len {
+                frame.push_int(len as i64);
+            }
+
+            matches.push(frame);
+        }
+
+        Frame::Array(vec![
+            Frame::Bulk(Bytes::from_static(b"matches")),
+            Frame::Array(matches),
+            Frame::Bulk(Bytes::from_static(b"len")),
+            Frame::Integer(len as i64),
+        ])
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Lcs` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lcs".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key1.into_bytes()));
+        frame.push_bulk(Bytes::from(self.key2.into_bytes()));
+        if self.len {
+            frame.push_bulk(Bytes::from("len".as_bytes()));
+        }
+        if self.idx {
+            frame.push_bulk(Bytes::from("idx".as_bytes()));
+        }
+        if self.min_match_len > 0 {
+            frame.push_bulk(Bytes::from("minmatchlen".as_bytes()));
+            frame.push_bulk(Bytes::from(self.min_match_len.to_string()));
+        }
+        if self.with_match_len {
+            frame.push_bulk(Bytes::from("withmatchlen".as_bytes()));
+        }
+        frame
+    }
+}
+
+/// Returns the length of the longest common subsequence of `a` and `b`.
+///
+/// Only two rows of the DP table are kept, so this needs memory proportional
+/// to the shorter value only.
+fn lcs_len(a: &[u8], b: &[u8]) -> usize {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+
+    let mut prev = vec![0; short.len() + 1];
+    let mut cur = vec![0; short.len() + 1];
+
+    for &x in long {
+        for (j, &y) in short.iter().enumerate() {
+            cur[j + 1] = if x == y {
+                prev[j] + 1
+            } else {
+                cur[j].max(prev[j + 1])
+            };
+        }
+
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev[short.len()]
+}
+
+/// The DP table of the longest common subsequence of two values.
+///
+/// The cell at `(i, j)` holds the length of the subsequence of the first `i`
+/// bytes of the first value and the first `j` bytes of the second one. Cells
+/// are `u32` to halve the size of the table, values larger than that cannot
+/// fit under `MAX_TABLE_SIZE` anyway.
+struct Table {
+    /// Length of the second value, plus one
+    width: usize,
+
+    /// The cells, row by row
+    cells: Vec<u32>,
+}
+
+impl Table {
+    /// Fill the table for `a` and `b`, or return `None` if it would be larger
+    /// than `MAX_TABLE_SIZE`.
+    fn new(a: &[u8], b: &[u8]) -> Option<Table> {
+        let width = b.len() + 1;
+        let size = (a.len() + 1)
+            .checked_mul(width)?
+            .checked_mul(std::mem::size_of::<u32>())?;
+
+        if size > MAX_TABLE_SIZE {
+            return None;
+        }
+
+        let mut table = Table {
+            width,
+            cells: vec![0; (a.len() + 1) * width],
+        };
+
+        for (i, &x) in a.iter().enumerate() {
+            for (j, &y) in b.iter().enumerate() {
+                let cell = if x == y {
+                    table.get(i, j) + 1
+                } else {
+                    table.get(i, j + 1).max(table.get(i + 1, j))
+                };
+
+                table.cells[(i + 1) * width + j + 1] = cell;
+            }
+        }
+
+        Some(table)
+    }
+
+    fn get(&self, i: usize, j: usize) -> u32 {
+        self.cells[i * self.width + j]
+    }
+
+    /// Length of the subsequence, held by the last cell
+    fn len(&self) -> usize {
+        self.cells[self.cells.len() - 1] as usize
+    }
+
+    /// Walk the table for `a` and `b` back from their end, calling `f` with
+    /// the offsets in each value of every byte of the subsequence, last first.
+    ///
+    /// On a mismatch, the walk moves back in the second value unless that
+    /// loses part of the subsequence, which is how Redis picks between
+    /// subsequences of equal length.
+    fn walk(&self, a: &[u8], b: &[u8], mut f: impl FnMut(usize, usize)) {
+        let (mut i, mut j) = (a.len(), b.len());
+
+        while i > 0 && j > 0 {
+            if a[i - 1] == b[j - 1] {
+                f(i - 1, j - 1);
+                i -= 1;
+                j -= 1;
+            } else if self.get(i - 1, j) > self.get(i, j - 1) {
+                i -= 1;
+            } else {
+                j -= 1;
+            }
+        }
+    }
+
+    /// Returns the subsequence of `a` and `b`.
+    fn subsequence(&self, a: &[u8], b: &[u8]) -> Bytes {
+        let mut out = vec![0; self.len()];
+        let mut idx = out.len();
+
+        self.walk(a, b, |i, _| {
+            idx -= 1;
+            out[idx] = a[i];
+        });
+
+        Bytes::from(out)
+    }
+
+    /// Returns the contiguous ranges of the subsequence of `a` and `b`, last
+    /// first.
+    fn matches(&self, a: &[u8], b: &[u8]) -> Vec<Match> {
+        let mut matches: Vec<Match> = vec![];
+
+        self.walk(a, b, |i, j| match matches.last_mut() {
+            // The byte directly precedes the current range in both values.
+            Some(m) if m.a.0 == i + 1 && m.b.0 == j + 1 => {
+                m.a.0 = i;
+                m.b.0 = j;
+            }
+            _ => matches.push(Match {
+                a: (i, i),
+                b: (j, j),
+            }),
+        });
+
+        matches
+    }
+}
@@ -0,0 +1,744 @@
+//! List commands.
+//!
+//! Lists are kept in their own keyspace in `Db`, as a `VecDeque` per key, so
+//! elements can be pushed and popped at both ends in constant time.
+
+use crate::cmd::{Parse, ParseError};
+use crate::{Connection, Db, Frame, Shutdown};
+
+use bytes::Bytes;
+use std::time::Duration;
+use tokio::select;
+use tracing::{debug, instrument};
+
+/// Insert values at the head of the list stored at key.
+///
+/// The values are inserted one after the other, so the last one ends up first.
+/// If the key does not exist, an empty list is created first. The reply is the
+/// length of the list after the push.
+#[derive(Debug)]
+pub struct LPush {
+    /// Name of the list
+    key: String,
+
+    /// Values to insert
+    values: Vec<Bytes>,
+}
+
+/// Insert values at the tail of the list stored at key.
+///
+/// If the key does not exist, an empty list is created first. The reply is the
+/// length of the list after the push.
+#[derive(Debug)]
+pub struct RPush {
+    /// Name of the list
+    key: String,
+
+    /// Values to insert
+    values: Vec<Bytes>,
+}
+
+/// Insert a value at the head of the list stored at key, then trim the list to
+/// a maximum length.
+///
+/// Both happen atomically, so the list can be used as a fixed-size log of the
+/// most recent values. Elements past the maximum length, the oldest ones, are
+/// dropped, and a maximum length of `0` removes the key. If the key does not
+/// exist, an empty list is created first. The reply is the length of the list
+/// after the trim.
+#[derive(Debug)]
+pub struct LPushCapped {
+    /// Name of the list
+    key: String,
+
+    /// Value to insert
+    value: Bytes,
+
+    /// Number of elements to keep
+    maxlen: u64,
+}
+
+/// Returns the elements of the list stored at key between two offsets.
+///
+/// Both offsets are inclusive. Negative offsets count back from the end of the
+/// list, `-1` being the last element. Offsets out of range are clamped, so a
+/// missing key or an empty range replies with an empty array.
+#[derive(Debug)]
+pub struct LRange {
+    /// Name of the list
+    key: String,
+
+    /// Offset of the first element to return
+    start: i64,
+
+    /// Offset of the last element to return
+    stop: i64,
+}
+
+impl LPush {
+    /// Create a new `LPush` command which inserts `values` at the head of the
+    /// list at `key`.
+    pub fn new(key: impl ToString, values: &[Bytes]) -> LPush {
+        LPush {
+            key: key.to_string(),
+            values: values.to_vec(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the values
+    pub fn values(&self) -> &[Bytes] {
+        &self.values
+    }
+
+    /// Parse a `LPush` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `LPUSH` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LPush` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 3 entries.
+    ///
+    /// ```text
+    /// LPUSH key value [value ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LPush> {
+        let (key, values) = parse_push(parse)?;
+
+        Ok(LPush { key, values })
+    }
+
+    /// Apply the `LPush` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = len_frame(
+            db.check_write_key(&self.key)
+                .and_then(|()| db.lpush(self.key, self.values)),
+        );
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `LPush` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        push_frame("lpush", self.key, self.values)
+    }
+}
+
+impl RPush {
+    /// Create a new `RPush` command which inserts `values` at the tail of the
+    /// list at `key`.
+    pub fn new(key: impl ToString, values: &[Bytes]) -> RPush {
+        RPush {
+            key: key.to_string(),
+            values: values.to_vec(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the values
+    pub fn values(&self) -> &[Bytes] {
+        &self.values
+    }
+
+    /// Parse a `RPush` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `RPUSH` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `RPush` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 3 entries.
+    ///
+    /// ```text
+    /// RPUSH key value [value ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<RPush> {
+        let (key, values) = parse_push(parse)?;
+
+        Ok(RPush { key, values })
+    }
+
+    /// Apply the `RPush` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = len_frame(
+            db.check_write_key(&self.key)
+                .and_then(|()| db.rpush(self.key, self.values)),
+        );
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `RPush` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        push_frame("rpush", self.key, self.values)
+    }
+}
+
+impl LPushCapped {
+    /// Create a new `LPushCapped` command which inserts `value` at the head of
+    /// the list at `key`, then keeps its first `maxlen` elements.
+    pub fn new(key: impl ToString, value: Bytes, maxlen: u64) -> LPushCapped {
+        LPushCapped {
+            key: key.to_string(),
+            value,
+            maxlen,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the value
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    /// Get the maximum length
+    pub fn maxlen(&self) -> u64 {
+        self.maxlen
+    }
+
+    /// Parse a `LPushCapped` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `LPUSHCAPPED` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LPushCapped` value on success. If the frame is malformed,
+    /// `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing four entries.
+    ///
+    /// ```text
+    /// LPUSHCAPPED key value maxlen
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LPushCapped> {
+        let key = parse.next_string()?;
+        let value = parse.next_bytes()?;
+        let maxlen = parse.next_int()?;
+
+        Ok(LPushCapped { key, value, maxlen })
+    }
+
+    /// Apply the `LPushCapped` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = len_frame(
+            db.check_write_key(&self.key)
+                .and_then(|()| db.lpush_capped(self.key, self.value, self.maxlen as usize)),
+        );
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `LPushCapped` command to
+    /// send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lpushcapped".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.value);
+        frame.push_bulk(Bytes::from(self.maxlen.to_string()));
+        frame
+    }
+}
+
+impl LRange {
+    /// Create a new `LRange` command which returns the elements of the list at
+    /// `key` between `start` and `stop`.
+    pub fn new(key: impl ToString, start: i64, stop: i64) -> LRange {
+        LRange {
+            key: key.to_string(),
+            start,
+            stop,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the start offset
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    /// Get the stop offset
+    pub fn stop(&self) -> i64 {
+        self.stop
+    }
+
+    /// Parse a `LRange` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `LRANGE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LRange` value on success. If the frame is malformed or an
+    /// offset is not an integer, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing four entries.
+    ///
+    /// ```text
+    /// LRANGE key start stop
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LRange> {
+        let key = parse.next_string()?;
+        let start = parse.next_signed_int()?;
+        let stop = parse.next_signed_int()?;
+
+        Ok(LRange { key, start, stop })
+    }
+
+    /// Apply the `LRange` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.lrange(&self.key, self.start, self.stop) {
+            Ok(values) => Frame::Array(values.into_iter().map(Frame::Bulk).collect()),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `LRange` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lrange".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.start.to_string()));
+        frame.push_bulk(Bytes::from(self.stop.to_string()));
+        frame
+    }
+}
+
+/// Returns the length of the list stored at key.
+///
+/// The reply is `0` if the key does not exist.
+#[derive(Debug)]
+pub struct LLen {
+    /// Name of the list
+    key: String,
+}
+
+/// Returns the element at an index in the list stored at key.
+///
+/// A negative index counts back from the end of the list, `-1` being the last
+/// element. The reply is `Null` if the index is out of range or the key does
+/// not exist.
+#[derive(Debug)]
+pub struct LIndex {
+    /// Name of the list
+    key: String,
+
+    /// Index of the element
+    index: i64,
+}
+
+impl LLen {
+    /// Create a new `LLen` command which returns the length of the list at
+    /// `key`.
+    pub fn new(key: impl ToString) -> LLen {
+        LLen {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Parse a `LLen` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `LLEN` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LLen` value on success. If the frame is malformed, `Err` is
+    /// returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two entries.
+    ///
+    /// ```text
+    /// LLEN key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LLen> {
+        let key = parse.next_string()?;
+
+        Ok(LLen { key })
+    }
+
+    /// Apply the `LLen` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = len_frame(db.llen(&self.key));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `LLen` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("llen".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl LIndex {
+    /// Create a new `LIndex` command which returns the element at `index` in
+    /// the list at `key`.
+    pub fn new(key: impl ToString, index: i64) -> LIndex {
+        LIndex {
+            key: key.to_string(),
+            index,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the index
+    pub fn index(&self) -> i64 {
+        self.index
+    }
+
+    /// Parse a `LIndex` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `LINDEX` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LIndex` value on success. If the frame is malformed or the
+    /// index is not an integer, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// LINDEX key index
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LIndex> {
+        let key = parse.next_string()?;
+        let index = parse.next_signed_int()?;
+
+        Ok(LIndex { key, index })
+    }
+
+    /// Apply the `LIndex` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.lindex(&self.key, self.index) {
+            Ok(Some(value)) => Frame::Bulk(value),
+            Ok(None) => Frame::Null,
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `LIndex` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lindex".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.index.to_string()));
+        frame
+    }
+}
+
+/// Remove and return the first element of the first non-empty list among the
+/// given keys, blocking until one is pushed if they are all empty.
+///
+/// The reply is the key the element was popped from followed by the element.
+/// If the timeout elapses first, a null reply is sent instead. When several
+/// clients are blocked on the same list, the one that has been waiting for the
+/// longest is served first.
+#[derive(Debug)]
+pub struct BLPop {
+    /// Names of the lists, checked in order
+    keys: Vec<String>,
+
+    /// How long to block for. Zero blocks forever.
+    timeout: Duration,
+}
+
+impl BLPop {
+    /// Create a new `BLPop` command which pops from the first non-empty list
+    /// among `keys`, blocking for up to `timeout`. A zero `timeout` blocks
+    /// forever.
+    pub fn new(keys: &[&str], timeout: Duration) -> BLPop {
+        BLPop {
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+            timeout,
+        }
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Get the timeout
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Parse a `BLPop` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `BLPOP` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `BLPop` value on success. If the frame is malformed or the
+    /// timeout is not a positive number of seconds, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 3 entries. The timeout is
+    /// in seconds and may have a fractional part.
+    ///
+    /// ```text
+    /// BLPOP key [key ...] timeout
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<BLPop> {
+        // At least one key is required, followed by the timeout.
+        let mut keys = vec![parse.next_string()?, parse.next_string()?];
+
+        loop {
+            match parse.next_string() {
+                Ok(key) => keys.push(key),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        // The timeout is the last argument.
+        let timeout = keys
+            .pop()
+            .unwrap()
+            .parse::<f64>()
+            .map_err(|_| "timeout is not a float or out of range")?;
+
+        if timeout < 0.0 {
+            return Err("timeout is negative".into());
+        }
+
+        let timeout =
+            Duration::try_from_secs_f64(timeout).map_err(|_| "timeout is out of range")?;
+
+        Ok(BLPop { keys, timeout })
+    }
+
+    /// Apply the `BLPop` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command. If the server shuts down while the
+    /// client is blocked, no response is written.
+    #[instrument(skip(self, db, dst, shutdown))]
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let timeout = if self.timeout.is_zero() {
+            None
+        } else {
+            Some(self.timeout)
+        };
+
+        let popped = select! {
+            popped = db.blpop(&self.keys, timeout) => popped,
+            _ = shutdown.recv() => return Ok(()),
+        };
+
+        let response = match popped {
+            Ok(Some((key, value))) => {
+                let mut frame = Frame::array();
+                frame.push_bulk(Bytes::from(key.into_bytes()));
+                frame.push_bulk(value);
+                frame
+            }
+            // Redis replies with a null array on timeout.
+            Ok(None) => Frame::NullArray,
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `BLPop` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("blpop".as_bytes()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        frame.push_bulk(Bytes::from(self.timeout.as_secs_f64().to_string()));
+        frame
+    }
+}
+
+/// Parse the key and the values of a push command.
+fn parse_push(parse: &mut Parse) -> crate::Result<(String, Vec<Bytes>)> {
+    let key = parse.next_string()?;
+
+    // At least one value is required.
+    let mut values = vec![parse.next_bytes()?];
+
+    loop {
+        match parse.next_bytes() {
+            Ok(value) => values.push(value),
+            Err(ParseError::EndOfStream) => break,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok((key, values))
+}
+
+/// Build the reply of a command returning the length of a list.
+fn len_frame(len: Result<u64, &'static str>) -> Frame {
+    match len {
+        Ok(len) => Frame::Integer(len as i64),
+        Err(msg) => Frame::Error(msg.to_string()),
+    }
+}
+
+/// Encode a push command named `name`.
+fn push_frame(name: &'static str, key: String, values: Vec<Bytes>) -> Frame {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from(name.as_bytes()));
+    frame.push_bulk(Bytes::from(key.into_bytes()));
+    for value in values {
+        frame.push_bulk(value);
+    }
+    frame
+}
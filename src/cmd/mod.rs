@@ -1,27 +1,182 @@
+mod command;
+pub use command::CommandGetKeys;
+
+mod cluster;
+pub use cluster::Cluster;
+
+mod config;
+pub use config::ConfigResetStat;
+
+mod debug;
+pub use debug::Debug;
+
+mod del;
+pub use del::Del;
+
+mod exists;
+pub use exists::Exists;
+
+mod incr;
+pub use incr::Incr;
+
+mod decr;
+pub use decr::Decr;
+
+mod incrby;
+pub use incrby::IncrBy;
+
+mod decrby;
+pub use decrby::DecrBy;
+
 mod get;
 pub use get::Get;
 
+mod mget;
+pub use mget::Mget;
+
+mod mset;
+pub use mset::Mset;
+
+mod append;
+pub use append::Append;
+
+mod strlen;
+pub use strlen::Strlen;
+
+mod dbsize;
+pub use dbsize::DbSize;
+
+mod getset;
+pub use getset::GetSet;
+
+mod setnx;
+pub use setnx::SetNx;
+
+mod key_type;
+pub use key_type::Type;
+
+mod object;
+pub use object::ObjectEncoding;
+
+mod ttl;
+pub use ttl::Ttl;
+
+mod pttl;
+pub use pttl::Pttl;
+
+mod expire;
+pub use expire::Expire;
+
+mod persist;
+pub use persist::Persist;
+
+mod getrange;
+pub use getrange::GetRange;
+
 mod publish;
 pub use publish::Publish;
 
+mod pubsub;
+pub use pubsub::PubSub;
+
 mod set;
 pub use set::Set;
 
 mod subscribe;
-pub use subscribe::{Subscribe, Unsubscribe};
+pub use subscribe::{Psubscribe, Punsubscribe, Subscribe, Unsubscribe};
 
 mod ping;
 pub use ping::Ping;
 
+mod hello;
+pub use hello::Hello;
+
+mod auth;
+pub use auth::Auth;
+
+mod select;
+pub use select::Select;
+
+mod flush;
+pub use flush::{FlushAll, FlushDb};
+
+mod save;
+pub use save::Save;
+
+mod scan;
+pub use scan::Scan;
+
+mod rename;
+pub use rename::{Rename, RenameNx};
+
+mod multi;
+pub(crate) use multi::Transaction;
+pub use multi::{Discard, Exec, Multi};
+
+mod watch;
+pub(crate) use watch::{unwatch_all, WatchedKey};
+pub use watch::{Unwatch, Watch};
+
 mod unknown;
+
+pub(crate) mod fast_path;
+
 mod hset;
 pub use hset::HSet;
 
 mod hget;
 pub use hget::HGet;
 
+mod hdel;
+pub use hdel::HDel;
+
+mod hexists;
+pub use hexists::HExists;
+
+mod hlen;
+pub use hlen::HLen;
+
+mod hkeys;
+pub use hkeys::HKeys;
+
+mod hvals;
+pub use hvals::HVals;
+
+mod hmget;
+pub use hmget::HMGet;
+
+mod hincrby;
+pub use hincrby::HIncrBy;
+
+mod hsetnx;
+pub use hsetnx::HSetNx;
+
+mod list;
+pub use list::{BLPop, LIndex, LLen, LPush, LPushCapped, LRange, RPush};
+
+mod sets;
+pub use sets::{
+    SAdd, SCard, SDiff, SDiffStore, SInter, SInterStore, SIsMember, SMembers, SPop, SRem, SUnion,
+    SUnionStore,
+};
+
+mod zset;
+pub use zset::{
+    Aggregate, ScoreBound, ZAdd, ZCount, ZDiff, ZIncrBy, ZInter, ZInterStore, ZRange, ZRank, ZRem,
+    ZRemRangeByRank, ZRemRangeByScore, ZScore, ZUnion, ZUnionStore,
+};
+
 mod hgetall;
 
+mod info;
+pub use info::Info;
+
+mod lcs;
+pub use lcs::Lcs;
+
+mod spec;
+pub(crate) use spec::CommandSpec;
+
 pub use hgetall::HGetAll;
 pub use unknown::Unknown;
 
@@ -32,16 +187,100 @@ use crate::{Connection, Db, Frame, Parse, ParseError, Shutdown};
 /// Methods called on `Command` are delegated to the command implementation.
 #[derive(Debug)]
 pub enum Command {
+    Cluster(Cluster),
+    CommandGetKeys(CommandGetKeys),
+    ConfigResetStat(ConfigResetStat),
+    Debug(Debug),
+    Del(Del),
+    Exists(Exists),
+    Incr(Incr),
+    Decr(Decr),
+    IncrBy(IncrBy),
+    DecrBy(DecrBy),
+    ObjectEncoding(ObjectEncoding),
+    Ttl(Ttl),
+    Pttl(Pttl),
+    Expire(Expire),
+    Persist(Persist),
+    Mget(Mget),
+    Mset(Mset),
+    Append(Append),
+    Strlen(Strlen),
+    DbSize(DbSize),
+    GetSet(GetSet),
+    SetNx(SetNx),
+    Type(Type),
     Get(Get),
+    GetRange(GetRange),
     Publish(Publish),
+    PubSub(PubSub),
     Set(Set),
     Subscribe(Subscribe),
     Unsubscribe(Unsubscribe),
+    Psubscribe(Psubscribe),
+    Punsubscribe(Punsubscribe),
     Ping(Ping),
+    Hello(Hello),
+    Auth(Auth),
+    Select(Select),
+    FlushDb(FlushDb),
+    FlushAll(FlushAll),
+    Save(Save),
+    Scan(Scan),
+    Rename(Rename),
+    RenameNx(RenameNx),
+    Multi(Multi),
+    Exec(Exec),
+    Discard(Discard),
+    Watch(Watch),
+    Unwatch(Unwatch),
     Unknown(Unknown),
     HSet(HSet),
     HGet(HGet),
+    HDel(HDel),
+    HExists(HExists),
+    HLen(HLen),
+    HKeys(HKeys),
+    HVals(HVals),
+    HMGet(HMGet),
+    HIncrBy(HIncrBy),
+    HSetNx(HSetNx),
+    LPush(LPush),
+    LPushCapped(LPushCapped),
+    RPush(RPush),
+    LRange(LRange),
+    LLen(LLen),
+    LIndex(LIndex),
+    BLPop(BLPop),
+    SAdd(SAdd),
+    SMembers(SMembers),
+    SIsMember(SIsMember),
+    SRem(SRem),
+    SCard(SCard),
+    SPop(SPop),
+    SInter(SInter),
+    SUnion(SUnion),
+    SDiff(SDiff),
+    SInterStore(SInterStore),
+    SUnionStore(SUnionStore),
+    SDiffStore(SDiffStore),
+    ZAdd(ZAdd),
+    ZRange(ZRange),
+    ZScore(ZScore),
+    ZIncrBy(ZIncrBy),
+    ZRank(ZRank),
+    ZRem(ZRem),
+    ZRemRangeByRank(ZRemRangeByRank),
+    ZRemRangeByScore(ZRemRangeByScore),
+    ZUnionStore(ZUnionStore),
+    ZInterStore(ZInterStore),
+    ZUnion(ZUnion),
+    ZInter(ZInter),
+    ZDiff(ZDiff),
+    ZCount(ZCount),
     HGGetAll(HGetAll),
+    Info(Info),
+    Lcs(Lcs),
 }
 
 impl Command {
@@ -54,30 +293,119 @@ impl Command {
     ///
     /// On success, the command value is returned, otherwise, `Err` is returned.
     pub fn from_frame(frame: Frame) -> crate::Result<Command> {
+        // Read the command name first. This only inspects the first entry of
+        // the frame.
+        let command_name = Command::peek_name(&frame)?;
+
         // The frame value is decorated with `Parse`. `Parse` provides a
         // "cursor" like API which makes parsing the command easier.
-        //
-        // The frame value must be an array variant. Any other frame variants
-        // result in an error being returned.
         let mut parse = Parse::new(frame)?;
 
-        // All redis commands begin with the command name as a string. The name
-        // is read and converted to lower cases in order to do case sensitive
-        // matching.
-        let command_name = parse.next_string()?.to_lowercase();
+        // The command name has already been read by `peek_name`. Skip over it
+        // so the command specific parsing starts at the first argument.
+        parse.next_bytes()?;
 
         // Match the command name, delegating the rest of the parsing to the
         // specific command.
         let command = match &command_name[..] {
+            "cluster" => Command::Cluster(Cluster::parse_frames(&mut parse)?),
+            "command" => Command::CommandGetKeys(CommandGetKeys::parse_frames(&mut parse)?),
+            "config" => Command::ConfigResetStat(ConfigResetStat::parse_frames(&mut parse)?),
+            "debug" => Command::Debug(Debug::parse_frames(&mut parse)?),
+            "del" => Command::Del(Del::parse_frames(&mut parse)?),
+            "exists" => Command::Exists(Exists::parse_frames(&mut parse)?),
+            "incr" => Command::Incr(Incr::parse_frames(&mut parse)?),
+            "decr" => Command::Decr(Decr::parse_frames(&mut parse)?),
+            "incrby" => Command::IncrBy(IncrBy::parse_frames(&mut parse)?),
+            "decrby" => Command::DecrBy(DecrBy::parse_frames(&mut parse)?),
+            "object" => Command::ObjectEncoding(ObjectEncoding::parse_frames(&mut parse)?),
+            "ttl" => Command::Ttl(Ttl::parse_frames(&mut parse)?),
+            "pttl" => Command::Pttl(Pttl::parse_frames(&mut parse)?),
+            "expire" => Command::Expire(Expire::parse_frames(&mut parse)?),
+            "persist" => Command::Persist(Persist::parse_frames(&mut parse)?),
+            "mget" => Command::Mget(Mget::parse_frames(&mut parse)?),
+            "mset" => Command::Mset(Mset::parse_frames(&mut parse)?),
+            "append" => Command::Append(Append::parse_frames(&mut parse)?),
+            "strlen" => Command::Strlen(Strlen::parse_frames(&mut parse)?),
+            "dbsize" => Command::DbSize(DbSize::parse_frames(&mut parse)?),
+            "getset" => Command::GetSet(GetSet::parse_frames(&mut parse)?),
+            "setnx" => Command::SetNx(SetNx::parse_frames(&mut parse)?),
+            "type" => Command::Type(Type::parse_frames(&mut parse)?),
             "get" => Command::Get(Get::parse_frames(&mut parse)?),
+            "getrange" => Command::GetRange(GetRange::parse_frames(&mut parse)?),
             "publish" => Command::Publish(Publish::parse_frames(&mut parse)?),
+            "pubsub" => Command::PubSub(PubSub::parse_frames(&mut parse)?),
             "set" => Command::Set(Set::parse_frames(&mut parse)?),
             "subscribe" => Command::Subscribe(Subscribe::parse_frames(&mut parse)?),
+            "subscribe-replay" => Command::Subscribe(Subscribe::parse_replay_frames(&mut parse)?),
             "unsubscribe" => Command::Unsubscribe(Unsubscribe::parse_frames(&mut parse)?),
+            "psubscribe" => Command::Psubscribe(Psubscribe::parse_frames(&mut parse)?),
+            "punsubscribe" => Command::Punsubscribe(Punsubscribe::parse_frames(&mut parse)?),
             "ping" => Command::Ping(Ping::parse_frames(&mut parse)?),
+            "hello" => Command::Hello(Hello::parse_frames(&mut parse)?),
+            "auth" => Command::Auth(Auth::parse_frames(&mut parse)?),
+            "select" => Command::Select(Select::parse_frames(&mut parse)?),
+            "flushdb" => Command::FlushDb(FlushDb::parse_frames(&mut parse)?),
+            "flushall" => Command::FlushAll(FlushAll::parse_frames(&mut parse)?),
+            "save" => Command::Save(Save::parse_frames(&mut parse)?),
+            "scan" => Command::Scan(Scan::parse_frames(&mut parse)?),
+            "rename" => Command::Rename(Rename::parse_frames(&mut parse)?),
+            "renamenx" => Command::RenameNx(RenameNx::parse_frames(&mut parse)?),
+            "multi" => Command::Multi(Multi::parse_frames(&mut parse)?),
+            "exec" => Command::Exec(Exec::parse_frames(&mut parse)?),
+            "discard" => Command::Discard(Discard::parse_frames(&mut parse)?),
+            "watch" => Command::Watch(Watch::parse_frames(&mut parse)?),
+            "unwatch" => Command::Unwatch(Unwatch::parse_frames(&mut parse)?),
             "hset" => Command::HSet(HSet::parse_frames(&mut parse)?),
             "hget" => Command::HGet(HGet::parse_frames(&mut parse)?),
+            "hdel" => Command::HDel(HDel::parse_frames(&mut parse)?),
+            "hexists" => Command::HExists(HExists::parse_frames(&mut parse)?),
+            "hlen" => Command::HLen(HLen::parse_frames(&mut parse)?),
+            "hkeys" => Command::HKeys(HKeys::parse_frames(&mut parse)?),
+            "hvals" => Command::HVals(HVals::parse_frames(&mut parse)?),
+            "hmget" => Command::HMGet(HMGet::parse_frames(&mut parse)?),
+            "hincrby" => Command::HIncrBy(HIncrBy::parse_frames(&mut parse)?),
+            "hsetnx" => Command::HSetNx(HSetNx::parse_frames(&mut parse)?),
+            "lpush" => Command::LPush(LPush::parse_frames(&mut parse)?),
+            "lpushcapped" => Command::LPushCapped(LPushCapped::parse_frames(&mut parse)?),
+            "rpush" => Command::RPush(RPush::parse_frames(&mut parse)?),
+            "lrange" => Command::LRange(LRange::parse_frames(&mut parse)?),
+            "llen" => Command::LLen(LLen::parse_frames(&mut parse)?),
+            "lindex" => Command::LIndex(LIndex::parse_frames(&mut parse)?),
+            "blpop" => Command::BLPop(BLPop::parse_frames(&mut parse)?),
+            "sadd" => Command::SAdd(SAdd::parse_frames(&mut parse)?),
+            "smembers" => Command::SMembers(SMembers::parse_frames(&mut parse)?),
+            "sismember" => Command::SIsMember(SIsMember::parse_frames(&mut parse)?),
+            "srem" => Command::SRem(SRem::parse_frames(&mut parse)?),
+            "scard" => Command::SCard(SCard::parse_frames(&mut parse)?),
+            "spop" => Command::SPop(SPop::parse_frames(&mut parse)?),
+            "sinter" => Command::SInter(SInter::parse_frames(&mut parse)?),
+            "sunion" => Command::SUnion(SUnion::parse_frames(&mut parse)?),
+            "sdiff" => Command::SDiff(SDiff::parse_frames(&mut parse)?),
+            "sinterstore" => Command::SInterStore(SInterStore::parse_frames(&mut parse)?),
+            "sunionstore" => Command::SUnionStore(SUnionStore::parse_frames(&mut parse)?),
+            "sdiffstore" => Command::SDiffStore(SDiffStore::parse_frames(&mut parse)?),
+            "zadd" => Command::ZAdd(ZAdd::parse_frames(&mut parse)?),
+            "zrange" => Command::ZRange(ZRange::parse_frames(&mut parse)?),
+            "zscore" => Command::ZScore(ZScore::parse_frames(&mut parse)?),
+            "zincrby" => Command::ZIncrBy(ZIncrBy::parse_frames(&mut parse)?),
+            "zrank" => Command::ZRank(ZRank::parse_frames(&mut parse)?),
+            "zrem" => Command::ZRem(ZRem::parse_frames(&mut parse)?),
+            "zremrangebyrank" => {
+                Command::ZRemRangeByRank(ZRemRangeByRank::parse_frames(&mut parse)?)
+            }
+            "zremrangebyscore" => {
+                Command::ZRemRangeByScore(ZRemRangeByScore::parse_frames(&mut parse)?)
+            }
+            "zunionstore" => Command::ZUnionStore(ZUnionStore::parse_frames(&mut parse)?),
+            "zinterstore" => Command::ZInterStore(ZInterStore::parse_frames(&mut parse)?),
+            "zunion" => Command::ZUnion(ZUnion::parse_frames(&mut parse)?),
+            "zinter" => Command::ZInter(ZInter::parse_frames(&mut parse)?),
+            "zdiff" => Command::ZDiff(ZDiff::parse_frames(&mut parse)?),
+            "zcount" => Command::ZCount(ZCount::parse_frames(&mut parse)?),
             "hgetall" => Command::HGGetAll(HGetAll::parse_frames(&mut parse)?),
+            "info" => Command::Info(Info::parse_frames(&mut parse)?),
+            "lcs" => Command::Lcs(Lcs::parse_frames(&mut parse)?),
             _ => {
                 // The command is not recognized and an Unknown command is
                 // returned.
@@ -98,47 +426,300 @@ impl Command {
         Ok(command)
     }
 
+    /// Returns the lower cased name of the command encoded in `frame` without
+    /// parsing the command's arguments.
+    ///
+    /// This is cheap compared to `from_frame` and allows a caller to make
+    /// decisions based on the command (for example, rejecting it) before the
+    /// full command value is constructed.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Err` if `frame` is not an array frame or if its first entry is
+    /// not a valid string.
+    pub fn peek_name(frame: &Frame) -> crate::Result<String> {
+        // All redis commands are array frames beginning with the command name
+        // as a string. The name is converted to lower case in order to do case
+        // insensitive matching.
+        let name = match frame {
+            Frame::Array(parts) => match parts.first() {
+                Some(Frame::Simple(name)) => name.to_lowercase(),
+                Some(Frame::Bulk(name)) => std::str::from_utf8(name)
+                    .map_err(|_| "protocol error; invalid string")?
+                    .to_lowercase(),
+                Some(frame) => {
+                    return Err(format!(
+                        "protocol error; expected simple frame or bulk frame, got {:?}",
+                        frame
+                    )
+                    .into())
+                }
+                None => return Err(ParseError::EndOfStream.into()),
+            },
+            frame => return Err(format!("protocol error; expected array, got {:?}", frame).into()),
+        };
+
+        Ok(name)
+    }
+
     /// Apply the command to the specified `Db` instance.
     ///
-    /// The response is written to `dst`. This is called by the server in order
-    /// to execute a received command.
+    /// The response is written to `dst`. `client_id` identifies the connection
+    /// in the `Db` client registry. This is called by the server in order to
+    /// execute a received command.
     pub(crate) async fn apply(
         self,
         db: &Db,
         dst: &mut Connection,
         shutdown: &mut Shutdown,
+        client_id: u64,
     ) -> crate::Result<()> {
         use Command::*;
 
         match self {
+            Cluster(cmd) => cmd.apply(db, dst).await,
+            CommandGetKeys(cmd) => cmd.apply(dst).await,
+            ConfigResetStat(cmd) => cmd.apply(db, dst).await,
+            Debug(cmd) => cmd.apply(db, dst, client_id).await,
+            Del(cmd) => cmd.apply(db, dst).await,
+            Exists(cmd) => cmd.apply(db, dst).await,
+            Incr(cmd) => cmd.apply(db, dst).await,
+            Decr(cmd) => cmd.apply(db, dst).await,
+            IncrBy(cmd) => cmd.apply(db, dst).await,
+            DecrBy(cmd) => cmd.apply(db, dst).await,
+            ObjectEncoding(cmd) => cmd.apply(db, dst).await,
+            Ttl(cmd) => cmd.apply(db, dst).await,
+            Pttl(cmd) => cmd.apply(db, dst).await,
+            Expire(cmd) => cmd.apply(db, dst).await,
+            Persist(cmd) => cmd.apply(db, dst).await,
+            Mget(cmd) => cmd.apply(db, dst).await,
+            Mset(cmd) => cmd.apply(db, dst).await,
+            Append(cmd) => cmd.apply(db, dst).await,
+            Strlen(cmd) => cmd.apply(db, dst).await,
+            DbSize(cmd) => cmd.apply(db, dst).await,
+            GetSet(cmd) => cmd.apply(db, dst).await,
+            SetNx(cmd) => cmd.apply(db, dst).await,
+            Type(cmd) => cmd.apply(db, dst).await,
             Get(cmd) => cmd.apply(db, dst).await,
+            GetRange(cmd) => cmd.apply(db, dst).await,
             Publish(cmd) => cmd.apply(db, dst).await,
+            PubSub(cmd) => cmd.apply(db, dst).await,
             Set(cmd) => cmd.apply(db, dst).await,
             Subscribe(cmd) => cmd.apply(db, dst, shutdown).await,
+            Psubscribe(cmd) => cmd.apply(db, dst, shutdown).await,
             Ping(cmd) => cmd.apply(dst).await,
+            Hello(cmd) => cmd.apply(dst, client_id).await,
+            Auth(cmd) => cmd.apply(db, dst).await,
+            Select(cmd) => cmd.apply(db, dst).await,
+            FlushDb(cmd) => cmd.apply(db, dst).await,
+            FlushAll(cmd) => cmd.apply(db, dst).await,
+            Save(cmd) => cmd.apply(db, dst).await,
+            Scan(cmd) => cmd.apply(db, dst).await,
+            Rename(cmd) => cmd.apply(db, dst).await,
+            RenameNx(cmd) => cmd.apply(db, dst).await,
+            Multi(cmd) => cmd.apply(dst).await,
+            Exec(cmd) => cmd.apply(db, dst, shutdown, client_id).await,
+            Discard(cmd) => cmd.apply(db, dst).await,
+            Watch(cmd) => cmd.apply(db, dst).await,
+            Unwatch(cmd) => cmd.apply(db, dst).await,
             Unknown(cmd) => cmd.apply(dst).await,
             // `Unsubscribe` cannot be applied. It may only be received from the
             // context of a `Subscribe` command.
             Unsubscribe(_) => Err("`Unsubscribe` is unsupported in this context".into()),
+            Punsubscribe(_) => Err("`Punsubscribe` is unsupported in this context".into()),
             HSet(cmd) => cmd.apply(db, dst).await,
             HGet(cmd) => cmd.apply(db, dst).await,
+            HDel(cmd) => cmd.apply(db, dst).await,
+            HExists(cmd) => cmd.apply(db, dst).await,
+            HLen(cmd) => cmd.apply(db, dst).await,
+            HKeys(cmd) => cmd.apply(db, dst).await,
+            HVals(cmd) => cmd.apply(db, dst).await,
+            HMGet(cmd) => cmd.apply(db, dst).await,
+            HIncrBy(cmd) => cmd.apply(db, dst).await,
+            HSetNx(cmd) => cmd.apply(db, dst).await,
+            LPush(cmd) => cmd.apply(db, dst).await,
+            LPushCapped(cmd) => cmd.apply(db, dst).await,
+            RPush(cmd) => cmd.apply(db, dst).await,
+            LRange(cmd) => cmd.apply(db, dst).await,
+            LLen(cmd) => cmd.apply(db, dst).await,
+            LIndex(cmd) => cmd.apply(db, dst).await,
+            BLPop(cmd) => cmd.apply(db, dst, shutdown).await,
+            SAdd(cmd) => cmd.apply(db, dst).await,
+            SMembers(cmd) => cmd.apply(db, dst).await,
+            SIsMember(cmd) => cmd.apply(db, dst).await,
+            SRem(cmd) => cmd.apply(db, dst).await,
+            SCard(cmd) => cmd.apply(db, dst).await,
+            SPop(cmd) => cmd.apply(db, dst).await,
+            SInter(cmd) => cmd.apply(db, dst).await,
+            SUnion(cmd) => cmd.apply(db, dst).await,
+            SDiff(cmd) => cmd.apply(db, dst).await,
+            SInterStore(cmd) => cmd.apply(db, dst).await,
+            SUnionStore(cmd) => cmd.apply(db, dst).await,
+            SDiffStore(cmd) => cmd.apply(db, dst).await,
+            ZAdd(cmd) => cmd.apply(db, dst).await,
+            ZRange(cmd) => cmd.apply(db, dst).await,
+            ZScore(cmd) => cmd.apply(db, dst).await,
+            ZIncrBy(cmd) => cmd.apply(db, dst).await,
+            ZRank(cmd) => cmd.apply(db, dst).await,
+            ZRem(cmd) => cmd.apply(db, dst).await,
+            ZRemRangeByRank(cmd) => cmd.apply(db, dst).await,
+            ZRemRangeByScore(cmd) => cmd.apply(db, dst).await,
+            ZUnionStore(cmd) => cmd.apply(db, dst).await,
+            ZInterStore(cmd) => cmd.apply(db, dst).await,
+            ZUnion(cmd) => cmd.apply(db, dst).await,
+            ZInter(cmd) => cmd.apply(db, dst).await,
+            ZDiff(cmd) => cmd.apply(db, dst).await,
+            ZCount(cmd) => cmd.apply(db, dst).await,
             HGGetAll(cmd) => cmd.apply(db, dst).await,
+            Info(cmd) => cmd.apply(db, dst).await,
+            Lcs(cmd) => cmd.apply(db, dst).await,
         }
     }
 
     /// Returns the command name
     pub(crate) fn get_name(&self) -> &str {
         match self {
+            Command::Cluster(_) => "cluster",
+            Command::CommandGetKeys(_) => "command",
+            Command::ConfigResetStat(_) => "config",
+            Command::Debug(_) => "debug",
+            Command::Del(_) => "del",
+            Command::Exists(_) => "exists",
+            Command::Incr(_) => "incr",
+            Command::Decr(_) => "decr",
+            Command::IncrBy(_) => "incrby",
+            Command::DecrBy(_) => "decrby",
+            Command::ObjectEncoding(_) => "object",
+            Command::Ttl(_) => "ttl",
+            Command::Pttl(_) => "pttl",
+            Command::Expire(_) => "expire",
+            Command::Persist(_) => "persist",
+            Command::Mget(_) => "mget",
+            Command::Mset(_) => "mset",
+            Command::Append(_) => "append",
+            Command::Strlen(_) => "strlen",
+            Command::DbSize(_) => "dbsize",
+            Command::GetSet(_) => "getset",
+            Command::SetNx(_) => "setnx",
+            Command::Type(_) => "type",
             Command::Get(_) => "get",
-            Command::Publish(_) => "pub",
+            Command::GetRange(_) => "getrange",
+            Command::Publish(_) => "publish",
+            Command::PubSub(_) => "pubsub",
             Command::Set(_) => "set",
-            Command::Subscribe(_) => "subscribe",
+            Command::Subscribe(cmd) => cmd.get_name(),
             Command::Unsubscribe(_) => "unsubscribe",
+            Command::Psubscribe(_) => "psubscribe",
+            Command::Punsubscribe(_) => "punsubscribe",
             Command::Ping(_) => "ping",
+            Command::Hello(_) => "hello",
+            Command::Auth(_) => "auth",
+            Command::Select(_) => "select",
+            Command::FlushDb(_) => "flushdb",
+            Command::FlushAll(_) => "flushall",
+            Command::Save(_) => "save",
+            Command::Scan(_) => "scan",
+            Command::Rename(_) => "rename",
+            Command::RenameNx(_) => "renamenx",
+            Command::Multi(_) => "multi",
+            Command::Exec(_) => "exec",
+            Command::Discard(_) => "discard",
+            Command::Watch(_) => "watch",
+            Command::Unwatch(_) => "unwatch",
             Command::Unknown(cmd) => cmd.get_name(),
             Command::HSet(_) => "hset",
             Command::HGet(_) => "hget",
+            Command::HDel(_) => "hdel",
+            Command::HExists(_) => "hexists",
+            Command::HLen(_) => "hlen",
+            Command::HKeys(_) => "hkeys",
+            Command::HVals(_) => "hvals",
+            Command::HMGet(_) => "hmget",
+            Command::HIncrBy(_) => "hincrby",
+            Command::HSetNx(_) => "hsetnx",
+            Command::LPush(_) => "lpush",
+            Command::LPushCapped(_) => "lpushcapped",
+            Command::RPush(_) => "rpush",
+            Command::LRange(_) => "lrange",
+            Command::LLen(_) => "llen",
+            Command::LIndex(_) => "lindex",
+            Command::BLPop(_) => "blpop",
+            Command::SAdd(_) => "sadd",
+            Command::SMembers(_) => "smembers",
+            Command::SIsMember(_) => "sismember",
+            Command::SRem(_) => "srem",
+            Command::SCard(_) => "scard",
+            Command::SPop(_) => "spop",
+            Command::SInter(_) => "sinter",
+            Command::SUnion(_) => "sunion",
+            Command::SDiff(_) => "sdiff",
+            Command::SInterStore(_) => "sinterstore",
+            Command::SUnionStore(_) => "sunionstore",
+            Command::SDiffStore(_) => "sdiffstore",
+            Command::ZAdd(_) => "zadd",
+            Command::ZRange(_) => "zrange",
+            Command::ZScore(_) => "zscore",
+            Command::ZIncrBy(_) => "zincrby",
+            Command::ZRank(_) => "zrank",
+            Command::ZRem(_) => "zrem",
+            Command::ZRemRangeByRank(_) => "zremrangebyrank",
+            Command::ZRemRangeByScore(_) => "zremrangebyscore",
+            Command::ZUnionStore(_) => "zunionstore",
+            Command::ZInterStore(_) => "zinterstore",
+            Command::ZUnion(_) => "zunion",
+            Command::ZInter(_) => "zinter",
+            Command::ZDiff(_) => "zdiff",
+            Command::ZCount(_) => "zcount",
             Command::HGGetAll(_) => "hgetall",
+            Command::Info(_) => "info",
+            Command::Lcs(_) => "lcs",
         }
     }
+
+    /// Returns `true` if the command may modify keys, and is to be logged to
+    /// the append-only file.
+    ///
+    /// `BLPOP` and `EXEC` are not, they log the writes they make themselves.
+    pub(crate) fn is_write(&self) -> bool {
+        matches!(
+            self,
+            Command::Append(_)
+                | Command::Debug(Debug::Populate { .. })
+                | Command::Decr(_)
+                | Command::DecrBy(_)
+                | Command::Del(_)
+                | Command::Expire(_)
+                | Command::FlushAll(_)
+                | Command::FlushDb(_)
+                | Command::GetSet(_)
+                | Command::HDel(_)
+                | Command::HIncrBy(_)
+                | Command::HSet(_)
+                | Command::HSetNx(_)
+                | Command::Incr(_)
+                | Command::IncrBy(_)
+                | Command::LPush(_)
+                | Command::LPushCapped(_)
+                | Command::Mset(_)
+                | Command::Persist(_)
+                | Command::Rename(_)
+                | Command::RenameNx(_)
+                | Command::RPush(_)
+                | Command::SAdd(_)
+                | Command::SDiffStore(_)
+                | Command::SInterStore(_)
+                | Command::Set(_)
+                | Command::SetNx(_)
+                | Command::SPop(_)
+                | Command::SRem(_)
+                | Command::SUnionStore(_)
+                | Command::ZAdd(_)
+                | Command::ZIncrBy(_)
+                | Command::ZInterStore(_)
+                | Command::ZRem(_)
+                | Command::ZRemRangeByRank(_)
+                | Command::ZRemRangeByScore(_)
+                | Command::ZUnionStore(_)
+        )
+    }
 }
@@ -0,0 +1,115 @@
+use crate::cmd::{Parse, ParseError};
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Set the given keys to their respective values.
+///
+/// All the keys are set at once, other clients never see only some of them
+/// updated. Existing values are replaced, along with their expiration.
+#[derive(Debug)]
+pub struct Mset {
+    /// The keys to set along with their values
+    pairs: Vec<(String, Bytes)>,
+}
+
+impl Mset {
+    /// Create a new `Mset` command which sets each key of `pairs` to its value.
+    pub fn new(pairs: &[(&str, Bytes)]) -> Mset {
+        Mset {
+            pairs: pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        }
+    }
+
+    /// Get the keys along with their values
+    pub fn pairs(&self) -> &[(String, Bytes)] {
+        &self.pairs
+    }
+
+    /// Parse a `Mset` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `MSET` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Mset` value on success. If the frame is malformed, or a key
+    /// is missing its value, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing an odd number of entries, at least 3.
+    ///
+    /// ```text
+    /// MSET key value [key value ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Mset> {
+        let mut pairs = vec![];
+
+        loop {
+            let key = match parse.next_string() {
+                Ok(key) => key,
+                // At least one pair is required.
+                Err(ParseError::EndOfStream) if !pairs.is_empty() => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            let value = match parse.next_bytes() {
+                Ok(value) => value,
+                Err(ParseError::EndOfStream) => {
+                    return Err("`MSET` requires an even number of arguments".into())
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            pairs.push((key, value));
+        }
+
+        Ok(Mset { pairs })
+    }
+
+    /// Apply the `Mset` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        // Nothing is set if any of the keys is rejected.
+        let checked = self
+            .pairs
+            .iter()
+            .try_for_each(|(key, _)| db.check_write_key(key));
+
+        let response = match checked.and_then(|()| db.mset(self.pairs)) {
+            Ok(()) => Frame::Simple("OK".to_string()),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Mset` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("mset".as_bytes()));
+        for (key, value) in self.pairs {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+            frame.push_bulk(value);
+        }
+        frame
+    }
+}
@@ -0,0 +1,356 @@
+//! Transactions, started with `MULTI` and completed with `EXEC` or `DISCARD`.
+//!
+//! After `MULTI`, the commands received on the connection are parsed but not
+//! applied. The server replies `QUEUED` to each of them instead, until `EXEC`
+//! applies them all in order or `DISCARD` drops them.
+//!
+//! `EXEC` applies the queued commands while holding the database write gate,
+//! so no command from another connection is applied between them. Commands
+//! which may wait on other connections, `SUBSCRIBE` and `BLPOP`, cannot be
+//! queued.
+//!
+//! If a key watched with `WATCH` was modified, `EXEC` does not apply the
+//! queued commands and replies with a null array instead. The watched keys
+//! are checked while holding the write gate, so none can be modified between
+//! the check and the queued commands being applied.
+//!
+//! A command which fails to parse is not queued and aborts the transaction:
+//! `EXEC` then discards it and replies with an `EXECABORT` error. Errors
+//! replied by queued commands when applied do not abort the transaction, they
+//! are part of the `EXEC` reply like any other reply.
+
+use crate::cmd::unwatch_all;
+use crate::{Command, Connection, Db, Frame, Parse, Shutdown};
+
+use bytes::Bytes;
+use std::future::Future;
+use std::pin::Pin;
+use tracing::{debug, instrument};
+
+/// Start a transaction.
+///
+/// Commands received until `EXEC` or `DISCARD` are queued, see the module
+/// documentation.
+#[derive(Debug, Default)]
+pub struct Multi {}
+
+/// Apply the commands queued since `MULTI`.
+///
+/// The reply is an array holding the reply to each queued command, in order,
+/// or a null array if a watched key was modified.
+#[derive(Debug, Default)]
+pub struct Exec {}
+
+/// Drop the commands queued since `MULTI`, and stop watching keys.
+#[derive(Debug, Default)]
+pub struct Discard {}
+
+/// Commands queued on a connection between `MULTI` and `EXEC`.
+#[derive(Debug, Default)]
+pub(crate) struct Transaction {
+    /// Commands to apply on `EXEC`, in the order they were received. Write
+    /// commands come along with their frame, to log to the append-only file
+    /// once applied.
+    commands: Vec<(Command, Option<Frame>)>,
+
+    /// Set when a command could not be queued. `EXEC` then discards the
+    /// transaction instead of applying it.
+    failed: bool,
+}
+
+impl Multi {
+    /// Create a new `Multi` command.
+    pub fn new() -> Multi {
+        Multi {}
+    }
+
+    /// Parse a `Multi` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `MULTI` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Multi` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing a single entry.
+    ///
+    /// ```text
+    /// MULTI
+    /// ```
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Multi> {
+        Ok(Multi {})
+    }
+
+    /// Apply the `Multi` command to the connection.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, dst))]
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        let response = if dst.in_transaction() {
+            Frame::Error("ERR MULTI calls can not be nested".to_string())
+        } else {
+            dst.begin_transaction();
+            Frame::Simple("OK".to_string())
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Multi` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("multi".as_bytes()));
+        frame
+    }
+}
+
+impl Exec {
+    /// Create a new `Exec` command.
+    pub fn new() -> Exec {
+        Exec {}
+    }
+
+    /// Parse an `Exec` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `EXEC` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Exec` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing a single entry.
+    ///
+    /// ```text
+    /// EXEC
+    /// ```
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Exec> {
+        Ok(Exec {})
+    }
+
+    /// Apply the queued commands to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    ///
+    /// The queued commands are applied through `Command::apply`, which in turn
+    /// calls this function. The returned future is boxed so that its type,
+    /// and the type of the `Command::apply` future, are not recursive.
+    pub(crate) fn apply<'a>(
+        self,
+        db: &'a Db,
+        dst: &'a mut Connection,
+        shutdown: &'a mut Shutdown,
+        client_id: u64,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<()>> + Send + 'a>> {
+        Box::pin(self.exec(db, dst, shutdown, client_id))
+    }
+
+    #[instrument(name = "apply", skip(self, db, dst, shutdown))]
+    async fn exec(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+        client_id: u64,
+    ) -> crate::Result<()> {
+        let transaction = match dst.take_transaction() {
+            Some(transaction) if transaction.failed => {
+                unwatch_all(db, dst);
+
+                let response = Frame::Error(
+                    "EXECABORT Transaction discarded because of previous errors.".to_string(),
+                );
+                debug!(?response);
+                dst.write_frame(&response).await?;
+                return Ok(());
+            }
+            Some(transaction) => transaction,
+            None => {
+                let response = Frame::Error("ERR EXEC without MULTI".to_string());
+                debug!(?response);
+                dst.write_frame(&response).await?;
+                return Ok(());
+            }
+        };
+
+        // No command from another connection is applied until the guard is
+        // dropped.
+        let guard = db.transaction_guard().await;
+
+        // The watched keys are checked holding the guard, so they cannot be
+        // modified before the queued commands are applied.
+        let modified = dst.watched_keys().iter().any(|key| key.is_modified(db));
+        unwatch_all(db, dst);
+
+        if modified {
+            drop(guard);
+
+            let response = Frame::NullArray;
+            debug!(?response);
+            dst.write_frame(&response).await?;
+            return Ok(());
+        }
+
+        // The replies of the queued commands are collected instead of being
+        // written, to be sent as a single array.
+        dst.start_capture();
+
+        // Each write command is logged as it is applied, without the
+        // transaction around them. The log is held until all are.
+        let mut log = match db.aof() {
+            Some(aof) => Some(aof.lock(db).await?),
+            None => None,
+        };
+
+        for (cmd, frame) in transaction.commands {
+            // A queued `SELECT` applies to the commands queued after it.
+            let db = db.select(dst.database());
+
+            cmd.apply(&db, dst, shutdown, client_id).await?;
+
+            if let (Some(log), Some(frame)) = (&mut log, frame) {
+                log.append(&db, frame, dst.last_captured()).await?;
+            }
+        }
+
+        drop(log);
+
+        let response = Frame::Array(dst.finish_capture());
+        drop(guard);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding an `Exec` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("exec".as_bytes()));
+        frame
+    }
+}
+
+impl Discard {
+    /// Create a new `Discard` command.
+    pub fn new() -> Discard {
+        Discard {}
+    }
+
+    /// Parse a `Discard` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `DISCARD` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Discard` value on success. If the frame is malformed,
+    /// `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing a single entry.
+    ///
+    /// ```text
+    /// DISCARD
+    /// ```
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Discard> {
+        Ok(Discard {})
+    }
+
+    /// Apply the `Discard` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match dst.take_transaction() {
+            Some(_) => {
+                unwatch_all(db, dst);
+                Frame::Simple("OK".to_string())
+            }
+            None => Frame::Error("ERR DISCARD without MULTI".to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Discard` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("discard".as_bytes()));
+        frame
+    }
+}
+
+impl Transaction {
+    /// Queue `cmd` to be applied on `EXEC`, returning the reply to send.
+    /// `frame` is logged to the append-only file once the command is applied.
+    ///
+    /// Commands which cannot be part of a transaction are not queued and
+    /// abort it.
+    pub(crate) fn queue(&mut self, cmd: Command, frame: Option<Frame>) -> Frame {
+        match cmd {
+            Command::Unknown(cmd) => {
+                self.failed = true;
+                Frame::Error(format!("ERR unknown command '{}'", cmd.get_name()))
+            }
+            // As with `MULTI`, the error does not abort the transaction.
+            Command::Watch(_) => Frame::Error("ERR WATCH inside MULTI is not allowed".to_string()),
+            Command::Subscribe(_)
+            | Command::Unsubscribe(_)
+            | Command::Psubscribe(_)
+            | Command::Punsubscribe(_)
+            | Command::BLPop(_) => {
+                self.failed = true;
+                Frame::Error("ERR Command not allowed inside a transaction".to_string())
+            }
+            cmd => {
+                self.commands.push((cmd, frame));
+                Frame::Simple("QUEUED".to_string())
+            }
+        }
+    }
+
+    /// Abort the transaction, after a command failed to parse.
+    pub(crate) fn fail(&mut self) {
+        self.failed = true;
+    }
+}
@@ -0,0 +1,93 @@
+use crate::cmd::Parse;
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Returns the internal encoding of the value stored at key.
+///
+/// Strings report `int`, `embstr` or `raw`, hashes and sets `hashtable`, lists
+/// `quicklist` and sorted sets `skiplist`, matching Redis. If the key does not
+/// exist, nil is returned.
+#[derive(Debug)]
+pub struct ObjectEncoding {
+    /// Name of the key to inspect
+    key: String,
+}
+
+impl ObjectEncoding {
+    /// Create a new `ObjectEncoding` command which inspects `key`.
+    pub fn new(key: impl ToString) -> ObjectEncoding {
+        ObjectEncoding {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Parse an `ObjectEncoding` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `OBJECT` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ObjectEncoding` value on success. If the frame is
+    /// malformed, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// OBJECT ENCODING key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ObjectEncoding> {
+        // Currently, mini-redis does not support any of the other OBJECT
+        // subcommands.
+        let subcommand = parse.next_string()?;
+        if subcommand.to_uppercase() != "ENCODING" {
+            return Err("currently `OBJECT` only supports the ENCODING subcommand".into());
+        }
+
+        let key = parse.next_string()?;
+
+        Ok(ObjectEncoding { key })
+    }
+
+    /// Apply the `ObjectEncoding` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.object_encoding(&self.key) {
+            Some(encoding) => Frame::Bulk(Bytes::from_static(encoding.as_bytes())),
+            None => Frame::Null,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding an `ObjectEncoding` command
+    /// to send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("object".as_bytes()));
+        frame.push_bulk(Bytes::from("encoding".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
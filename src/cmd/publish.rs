@@ -11,8 +11,9 @@ use bytes::Bytes;
 /// channel named "foo" has no relation to setting the "foo" key.
 #[derive(Debug)]
 pub struct Publish {
-    /// Name of the channel on which the message should be published.
-    channel: String,
+    /// Name of the channel on which the message should be published. Channel
+    /// names are binary safe.
+    channel: Bytes,
 
     /// The message to publish.
     message: Bytes,
@@ -20,9 +21,9 @@ pub struct Publish {
 
 impl Publish {
     /// Create a new `Publish` command which sends `message` on `channel`.
-    pub(crate) fn new(channel: impl ToString, message: Bytes) -> Publish {
+    pub(crate) fn new(channel: impl Into<Bytes>, message: Bytes) -> Publish {
         Publish {
-            channel: channel.to_string(),
+            channel: channel.into(),
             message,
         }
     }
@@ -51,8 +52,8 @@ impl Publish {
         // The `PUBLISH` string has already been consumed. Extract the `channel`
         // and `message` values from the frame.
         //
-        // The `channel` must be a valid string.
-        let channel = parse.next_string()?;
+        // The `channel` may be any sequence of bytes.
+        let channel = parse.next_bytes()?;
 
         // The `message` is arbitrary bytes.
         let message = parse.next_bytes()?;
@@ -78,7 +79,7 @@ impl Publish {
 
         // The number of subscribers is returned as the response to the publish
         // request.
-        let response = Frame::Integer(num_subscribers as u64);
+        let response = Frame::Integer(num_subscribers as i64);
 
         // Write the frame to the client.
         dst.write_frame(&response).await?;
@@ -93,7 +94,7 @@ impl Publish {
     pub(crate) fn into_frame(self) -> Frame {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("publish".as_bytes()));
-        frame.push_bulk(Bytes::from(self.channel.into_bytes()));
+        frame.push_bulk(self.channel);
         frame.push_bulk(self.message);
 
         frame
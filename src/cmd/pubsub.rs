@@ -0,0 +1,154 @@
+use crate::{glob, Connection, Db, Frame, Parse, ParseError};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Pub/sub introspection.
+///
+/// Only subscriptions to channels are reported. Subscriptions made with
+/// `PSUBSCRIBE` are not counted, as in Redis.
+///
+/// # Subcommands
+///
+/// Currently, the following subcommands are supported:
+///
+/// * CHANNELS [pattern] -- Returns the channels with at least one subscriber,
+///   optionally only those matching a glob-style pattern.
+/// * NUMSUB [channel ...] -- Returns each channel followed by its number of
+///   subscribers.
+#[derive(Debug)]
+pub enum PubSub {
+    /// `PUBSUB CHANNELS [pattern]`
+    Channels(Option<Bytes>),
+
+    /// `PUBSUB NUMSUB [channel ...]`
+    NumSub(Vec<Bytes>),
+}
+
+impl PubSub {
+    /// Create a new `PUBSUB CHANNELS` command, listing the channels matching
+    /// `pattern`, or all channels.
+    pub fn channels(pattern: Option<Bytes>) -> PubSub {
+        PubSub::Channels(pattern)
+    }
+
+    /// Create a new `PUBSUB NUMSUB` command, counting the subscribers of
+    /// `channels`.
+    pub fn numsub(channels: Vec<Bytes>) -> PubSub {
+        PubSub::NumSub(channels)
+    }
+
+    /// Parse a `PubSub` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `PUBSUB` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `PubSub` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least two entries.
+    ///
+    /// ```text
+    /// PUBSUB CHANNELS [pattern]
+    /// PUBSUB NUMSUB [channel [channel ...]]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<PubSub> {
+        match &parse.next_string()?.to_uppercase()[..] {
+            "CHANNELS" => match parse.next_bytes() {
+                Ok(pattern) => Ok(PubSub::Channels(Some(pattern))),
+                Err(ParseError::EndOfStream) => Ok(PubSub::Channels(None)),
+                Err(err) => Err(err.into()),
+            },
+            "NUMSUB" => {
+                let mut channels = vec![];
+
+                loop {
+                    match parse.next_bytes() {
+                        Ok(channel) => channels.push(channel),
+                        Err(ParseError::EndOfStream) => break,
+                        Err(err) => return Err(err.into()),
+                    }
+                }
+
+                Ok(PubSub::NumSub(channels))
+            }
+            _ => Err("currently `PUBSUB` only supports the CHANNELS and NUMSUB subcommands".into()),
+        }
+    }
+
+    /// Apply the `PubSub` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let subscribers = db.channel_subscribers();
+
+        let mut response = Frame::array();
+
+        match self {
+            PubSub::Channels(pattern) => {
+                let mut channels: Vec<Bytes> = subscribers
+                    .into_keys()
+                    .filter(|channel| match &pattern {
+                        Some(pattern) => glob::matches(pattern, channel),
+                        None => true,
+                    })
+                    .collect();
+
+                // Sorted so that the reply does not depend on the order of
+                // the `HashMap`.
+                channels.sort_unstable();
+
+                for channel in channels {
+                    response.push_bulk(channel);
+                }
+            }
+            PubSub::NumSub(channels) => {
+                for channel in channels {
+                    let count = subscribers.get(&channel).copied().unwrap_or(0);
+
+                    response.push_bulk(channel);
+                    response.push_int(count as i64);
+                }
+            }
+        }
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `PubSub` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("pubsub".as_bytes()));
+        match self {
+            PubSub::Channels(pattern) => {
+                frame.push_bulk(Bytes::from("channels".as_bytes()));
+                if let Some(pattern) = pattern {
+                    frame.push_bulk(pattern);
+                }
+            }
+            PubSub::NumSub(channels) => {
+                frame.push_bulk(Bytes::from("numsub".as_bytes()));
+                for channel in channels {
+                    frame.push_bulk(channel);
+                }
+            }
+        }
+        frame
+    }
+}
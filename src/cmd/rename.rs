@@ -0,0 +1,194 @@
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Rename a key, replacing any value stored under the new name.
+///
+/// Keys of any type can be renamed, and their expiration moves with them.
+/// Renaming a key that does not exist is an error. Renaming a key to itself
+/// replies `OK` and leaves the key untouched.
+#[derive(Debug)]
+pub struct Rename {
+    /// Name of the key to rename
+    key: String,
+
+    /// New name of the key
+    new_key: String,
+}
+
+/// Rename a key, only if the new name is not in use.
+///
+/// The reply is `1` if the key was renamed and `0` if the new name is in use,
+/// including when it is the same as the current one. Renaming a key that does
+/// not exist is an error.
+#[derive(Debug)]
+pub struct RenameNx {
+    /// Name of the key to rename
+    key: String,
+
+    /// New name of the key
+    new_key: String,
+}
+
+impl Rename {
+    /// Create a new `Rename` command which renames `key` to `new_key`.
+    pub fn new(key: impl ToString, new_key: impl ToString) -> Rename {
+        Rename {
+            key: key.to_string(),
+            new_key: new_key.to_string(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the new key
+    pub fn new_key(&self) -> &str {
+        &self.new_key
+    }
+
+    /// Parse a `Rename` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `RENAME` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Rename` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// RENAME key newkey
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Rename> {
+        let key = parse.next_string()?;
+        let new_key = parse.next_string()?;
+
+        Ok(Rename { key, new_key })
+    }
+
+    /// Apply the `Rename` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let renamed = db
+            .check_write_key(&self.new_key)
+            .and_then(|()| db.rename(&self.key, &self.new_key, false));
+
+        let response = match renamed {
+            Ok(_) => Frame::Simple("OK".to_string()),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Rename` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("rename".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.new_key.into_bytes()));
+        frame
+    }
+}
+
+impl RenameNx {
+    /// Create a new `RenameNx` command which renames `key` to `new_key`.
+    pub fn new(key: impl ToString, new_key: impl ToString) -> RenameNx {
+        RenameNx {
+            key: key.to_string(),
+            new_key: new_key.to_string(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the new key
+    pub fn new_key(&self) -> &str {
+        &self.new_key
+    }
+
+    /// Parse a `RenameNx` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `RENAMENX` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `RenameNx` value on success. If the frame is malformed,
+    /// `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// RENAMENX key newkey
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<RenameNx> {
+        let key = parse.next_string()?;
+        let new_key = parse.next_string()?;
+
+        Ok(RenameNx { key, new_key })
+    }
+
+    /// Apply the `RenameNx` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let renamed = db
+            .check_write_key(&self.new_key)
+            .and_then(|()| db.rename(&self.key, &self.new_key, true));
+
+        let response = match renamed {
+            Ok(renamed) => Frame::Integer(renamed as i64),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `RenameNx` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("renamenx".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.new_key.into_bytes()));
+        frame
+    }
+}
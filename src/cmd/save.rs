@@ -0,0 +1,74 @@
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Write every database to the dump file configured with `Config::dbfile`.
+///
+/// The reply is only sent once the file is written. Other commands are
+/// applied in the meantime, the file holds the keys as they were when `SAVE`
+/// was applied.
+#[derive(Debug, Default)]
+pub struct Save {}
+
+impl Save {
+    /// Create a new `Save` command.
+    pub fn new() -> Save {
+        Save {}
+    }
+
+    /// Parse a `Save` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SAVE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Save` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing a single entry.
+    ///
+    /// ```text
+    /// SAVE
+    /// ```
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Save> {
+        Ok(Save {})
+    }
+
+    /// Apply the `Save` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match &db.config().dbfile {
+            Some(path) => match db.save_to(path).await {
+                Ok(()) => Frame::Simple("OK".to_string()),
+                Err(err) => Frame::Error(format!("ERR failed to save: {}", err)),
+            },
+            None => Frame::Error("ERR no dbfile configured".to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Save` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("save".as_bytes()));
+        frame
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{glob, Connection, Db, Frame, Parse, ParseError};
+
+use bytes::Bytes;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use tokio::time::Instant;
+use tracing::{debug, instrument};
+
+/// Number of keys examined by a call when `COUNT` is not given.
+const DEFAULT_COUNT: u64 = 10;
+
+/// Incrementally iterate over the keys of the selected database.
+///
+/// A scan starts with cursor `0`. Each call returns a batch of keys and the
+/// cursor to pass to the next call, until the returned cursor is `0` again.
+///
+/// Keys are visited in the order of a hash of their name, and the cursor is
+/// the hash of the next key to visit. Unlike a position in a list, this does
+/// not shift when other keys are added or removed, so every key present for
+/// the whole scan is returned. Keys added or removed during the scan may or may
+/// not be returned.
+///
+/// Each call walks a snapshot of the keyspace, so the database is only locked
+/// for as long as it takes to take the snapshot.
+///
+/// `COUNT` is the number of keys examined by a call, `MATCH` then filters them.
+/// A call may therefore return fewer keys, or none, before the scan completes.
+#[derive(Debug)]
+pub struct Scan {
+    /// Cursor returned by the previous call, or `0` to start a scan
+    cursor: u64,
+
+    /// Only return keys matching this glob-style pattern
+    pattern: Option<String>,
+
+    /// Number of keys to examine
+    count: u64,
+}
+
+impl Scan {
+    /// Create a new `Scan` command resuming from `cursor`.
+    pub fn new(cursor: u64, pattern: Option<&str>, count: Option<u64>) -> Scan {
+        Scan {
+            cursor,
+            pattern: pattern.map(str::to_string),
+            count: count.unwrap_or(DEFAULT_COUNT),
+        }
+    }
+
+    /// Get the cursor
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+
+    /// Get the pattern
+    pub fn pattern(&self) -> Option<&str> {
+        self.pattern.as_deref()
+    }
+
+    /// Get the count
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Parse a `Scan` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SCAN` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Scan` value on success. If the frame is malformed, `Err` is
+    /// returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 2 entries.
+    ///
+    /// ```text
+    /// SCAN cursor [MATCH pattern] [COUNT count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Scan> {
+        let cursor = parse.next_int()?;
+
+        let mut pattern = None;
+        let mut count = DEFAULT_COUNT;
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "MATCH" => {
+                    pattern = Some(parse.next_string()?);
+                }
+                Ok(s) if s.to_uppercase() == "COUNT" => {
+                    count = parse.next_int()?;
+
+                    if count == 0 {
+                        return Err("ERR syntax error".into());
+                    }
+                }
+                Ok(_) => {
+                    return Err("currently `SCAN` only supports the MATCH and COUNT options".into())
+                }
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(Scan {
+            cursor,
+            pattern,
+            count,
+        })
+    }
+
+    /// Apply the `Scan` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let snapshot = db.snapshot();
+
+        // The keys left to visit, in visiting order.
+        let mut keys: Vec<(u64, &str)> = snapshot
+            .keys(Instant::now())
+            .map(|key| (key_hash(key), key))
+            .filter(|(hash, _)| *hash >= self.cursor)
+            .collect();
+        keys.sort_unstable();
+
+        let mut batch = keys.len().min(self.count as usize);
+
+        // Keys sharing a hash cannot be told apart by the cursor, so they are
+        // returned together.
+        while batch > 0 && batch < keys.len() && keys[batch].0 == keys[batch - 1].0 {
+            batch += 1;
+        }
+
+        let next = keys.get(batch).map_or(0, |(hash, _)| *hash);
+
+        let mut names = Frame::array();
+        for (_, key) in &keys[..batch] {
+            let matched = match &self.pattern {
+                Some(pattern) => glob::matches(pattern.as_bytes(), key.as_bytes()),
+                None => true,
+            };
+
+            if matched {
+                names.push_bulk(Bytes::copy_from_slice(key.as_bytes()));
+            }
+        }
+
+        let response = Frame::Array(vec![Frame::Bulk(Bytes::from(next.to_string())), names]);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Scan` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("scan".as_bytes()));
+        frame.push_bulk(Bytes::from(self.cursor.to_string()));
+        if let Some(pattern) = self.pattern {
+            frame.push_bulk(Bytes::from("match".as_bytes()));
+            frame.push_bulk(Bytes::from(pattern.into_bytes()));
+        }
+        frame.push_bulk(Bytes::from("count".as_bytes()));
+        frame.push_bulk(Bytes::from(self.count.to_string()));
+        frame
+    }
+}
+
+/// Returns the position of `key` in the scan order.
+///
+/// `DefaultHasher::new` always uses the same keys, so the hash of a key does
+/// not change while the server runs.
+fn key_hash(key: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
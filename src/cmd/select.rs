@@ -0,0 +1,83 @@
+use crate::{Connection, Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Select the logical database the connection's commands are applied to.
+///
+/// Each database is an independent keyspace. Connections start on database
+/// `0`. Pub/sub channels are shared by all databases.
+#[derive(Debug)]
+pub struct Select {
+    /// Index of the database
+    index: u64,
+}
+
+impl Select {
+    /// Create a new `Select` command switching to the database at `index`.
+    pub fn new(index: u64) -> Select {
+        Select { index }
+    }
+
+    /// Get the database index
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Parse a `Select` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SELECT` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Select` value on success. If the frame is malformed or the
+    /// index is not an integer, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two entries.
+    ///
+    /// ```text
+    /// SELECT index
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Select> {
+        let index = parse.next_int()?;
+
+        Ok(Select { index })
+    }
+
+    /// Apply the `Select` command to the connection.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = if self.index < db.databases() as u64 {
+            dst.select_database(self.index as usize);
+            Frame::Simple("OK".to_string())
+        } else {
+            Frame::Error("ERR DB index is out of range".to_string())
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Select` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("select".as_bytes()));
+        frame.push_bulk(Bytes::from(self.index.to_string()));
+        frame
+    }
+}
@@ -17,6 +17,8 @@ use tracing::{debug, instrument};
 ///
 /// * EX `seconds` -- Set the specified expire time, in seconds.
 /// * PX `milliseconds` -- Set the specified expire time, in milliseconds.
+///
+/// An expire time of zero is refused with an error.
 #[derive(Debug)]
 pub struct Set {
     /// the lookup key
@@ -126,11 +128,25 @@ impl Set {
     /// to execute a received command.
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
-        // Set the value in the shared database state.
-        db.set(self.key, self.value, self.expire);
+        // As Redis does, a key which would expire right away is refused
+        // rather than set.
+        let valid = if self.expire == Some(Duration::ZERO) {
+            Err("ERR invalid expire time in 'set' command")
+        } else {
+            Ok(())
+        };
 
-        // Create a success response and write it to `dst`.
-        let response = Frame::Simple("OK".to_string());
+        // Set the value in the shared database state.
+        let response = match valid
+            .and_then(|()| db.check_write_key(&self.key))
+            .and_then(|()| db.set(self.key, self.value, self.expire))
+        {
+            // Create a success response.
+            Ok(()) => Frame::Simple("OK".to_string()),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        // Write the response to `dst`.
         debug!(?response);
         dst.write_frame(&response).await?;
 
@@ -154,7 +170,7 @@ impl Set {
             // src/bin/cli.rs parses the expiration argument as milliseconds
             // in duration_from_ms_str()
             frame.push_bulk(Bytes::from("px".as_bytes()));
-            frame.push_int(ms.as_millis() as u64);
+            frame.push_int(ms.as_millis() as i64);
         }
         frame
     }
@@ -0,0 +1,1158 @@
+//! Set commands.
+//!
+//! Sets are kept in their own keyspace in `Db`, as a `HashSet` per key, so
+//! members are unique and membership checks take constant time. Members are
+//! returned in no particular order.
+
+use crate::cmd::{Parse, ParseError};
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+
+/// Add members to the set stored at key.
+///
+/// If the key does not exist, an empty set is created first. The reply is the
+/// number of members that were not already in the set.
+#[derive(Debug)]
+pub struct SAdd {
+    /// Name of the set
+    key: String,
+
+    /// Members to add
+    members: Vec<Bytes>,
+}
+
+/// Returns all the members of the set stored at key.
+///
+/// A missing key replies with an empty array.
+#[derive(Debug)]
+pub struct SMembers {
+    /// Name of the set
+    key: String,
+}
+
+/// Returns whether a value is a member of the set stored at key.
+///
+/// The reply is `1` if it is, `0` if it is not or the key does not exist.
+#[derive(Debug)]
+pub struct SIsMember {
+    /// Name of the set
+    key: String,
+
+    /// Value to look for
+    member: Bytes,
+}
+
+impl SAdd {
+    /// Create a new `SAdd` command which adds `members` to the set at `key`.
+    pub fn new(key: impl ToString, members: &[Bytes]) -> SAdd {
+        SAdd {
+            key: key.to_string(),
+            members: members.to_vec(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the members
+    pub fn members(&self) -> &[Bytes] {
+        &self.members
+    }
+
+    /// Parse a `SAdd` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SADD` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SAdd` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 3 entries.
+    ///
+    /// ```text
+    /// SADD key member [member ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SAdd> {
+        let (key, members) = parse_members(parse)?;
+
+        Ok(SAdd { key, members })
+    }
+
+    /// Apply the `SAdd` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = count_frame(
+            db.check_write_key(&self.key)
+                .and_then(|()| db.sadd(self.key, self.members)),
+        );
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `SAdd` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("sadd".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for member in self.members {
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl SMembers {
+    /// Create a new `SMembers` command which returns the members of the set at
+    /// `key`.
+    pub fn new(key: impl ToString) -> SMembers {
+        SMembers {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Parse a `SMembers` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SMEMBERS` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SMembers` value on success. If the frame is malformed,
+    /// `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two entries.
+    ///
+    /// ```text
+    /// SMEMBERS key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SMembers> {
+        let key = parse.next_string()?;
+
+        Ok(SMembers { key })
+    }
+
+    /// Apply the `SMembers` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = members_frame(db.smembers(&self.key));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `SMembers` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("smembers".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl SIsMember {
+    /// Create a new `SIsMember` command which checks whether `member` is in
+    /// the set at `key`.
+    pub fn new(key: impl ToString, member: Bytes) -> SIsMember {
+        SIsMember {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the member
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+
+    /// Parse a `SIsMember` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SISMEMBER` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SIsMember` value on success. If the frame is malformed,
+    /// `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// SISMEMBER key member
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SIsMember> {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+
+        Ok(SIsMember { key, member })
+    }
+
+    /// Apply the `SIsMember` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.sismember(&self.key, &self.member) {
+            Ok(found) => Frame::Integer(found as i64),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `SIsMember` command to
+    /// send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("sismember".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
+
+/// Remove members from the set stored at key.
+///
+/// The reply is the number of members that were removed. The key is removed
+/// once the set is empty.
+#[derive(Debug)]
+pub struct SRem {
+    /// Name of the set
+    key: String,
+
+    /// Members to remove
+    members: Vec<Bytes>,
+}
+
+/// Returns the number of members of the set stored at key.
+///
+/// A missing key replies with `0`.
+#[derive(Debug)]
+pub struct SCard {
+    /// Name of the set
+    key: String,
+}
+
+/// Remove and return random members of the set stored at key.
+///
+/// Without a count, a single member is replied, or nil if the key does not
+/// exist. With a count, an array of up to that many members is replied. The
+/// members are picked at random, so neither which ones are popped nor their
+/// order is deterministic. The key is removed once the set is empty.
+#[derive(Debug)]
+pub struct SPop {
+    /// Name of the set
+    key: String,
+
+    /// How many members to pop, if given
+    count: Option<u64>,
+}
+
+impl SRem {
+    /// Create a new `SRem` command which removes `members` from the set at
+    /// `key`.
+    pub fn new(key: impl ToString, members: &[Bytes]) -> SRem {
+        SRem {
+            key: key.to_string(),
+            members: members.to_vec(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the members
+    pub fn members(&self) -> &[Bytes] {
+        &self.members
+    }
+
+    /// Parse a `SRem` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SREM` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SRem` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 3 entries.
+    ///
+    /// ```text
+    /// SREM key member [member ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SRem> {
+        let (key, members) = parse_members(parse)?;
+
+        Ok(SRem { key, members })
+    }
+
+    /// Apply the `SRem` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = count_frame(db.srem(&self.key, &self.members));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `SRem` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("srem".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for member in self.members {
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl SCard {
+    /// Create a new `SCard` command which returns the number of members of the
+    /// set at `key`.
+    pub fn new(key: impl ToString) -> SCard {
+        SCard {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Parse a `SCard` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SCARD` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SCard` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two entries.
+    ///
+    /// ```text
+    /// SCARD key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SCard> {
+        let key = parse.next_string()?;
+
+        Ok(SCard { key })
+    }
+
+    /// Apply the `SCard` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = count_frame(db.scard(&self.key));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `SCard` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("scard".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl SPop {
+    /// Create a new `SPop` command which pops random members of the set at
+    /// `key`.
+    ///
+    /// Without a `count`, a single member is popped.
+    pub fn new(key: impl ToString, count: Option<u64>) -> SPop {
+        SPop {
+            key: key.to_string(),
+            count,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the count
+    pub fn count(&self) -> Option<u64> {
+        self.count
+    }
+
+    /// Parse a `SPop` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SPOP` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SPop` value on success. If the frame is malformed or the
+    /// count is not a positive integer, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two or three entries.
+    ///
+    /// ```text
+    /// SPOP key [count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SPop> {
+        let key = parse.next_string()?;
+
+        let count = match parse.next_int() {
+            Ok(count) => Some(count),
+            Err(ParseError::EndOfStream) => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(SPop { key, count })
+    }
+
+    /// Apply the `SPop` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.count {
+            Some(count) => members_frame(db.spop(&self.key, count as usize)),
+            None => match db.spop(&self.key, 1) {
+                Ok(mut popped) => match popped.pop() {
+                    Some(member) => Frame::Bulk(member),
+                    None => Frame::Null,
+                },
+                Err(msg) => Frame::Error(msg.to_string()),
+            },
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `SPop` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("spop".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from(count.to_string()));
+        }
+        frame
+    }
+}
+
+/// Returns the members of the intersection of the sets stored at the given
+/// keys.
+///
+/// Missing keys are treated as empty sets, so the intersection is empty if
+/// any of the keys is missing.
+#[derive(Debug)]
+pub struct SInter {
+    /// Names of the sets
+    keys: Vec<String>,
+}
+
+/// Returns the members of the union of the sets stored at the given keys.
+///
+/// Missing keys are treated as empty sets.
+#[derive(Debug)]
+pub struct SUnion {
+    /// Names of the sets
+    keys: Vec<String>,
+}
+
+/// Returns the members of the set stored at the first key that are in none of
+/// the sets stored at the other keys.
+///
+/// Missing keys are treated as empty sets.
+#[derive(Debug)]
+pub struct SDiff {
+    /// Names of the sets, the first one being the set to subtract from
+    keys: Vec<String>,
+}
+
+/// Stores the intersection of the sets stored at the given keys into a
+/// destination key.
+///
+/// The destination is overwritten, whatever its type, or removed if the
+/// intersection is empty. The reply is the number of members stored.
+#[derive(Debug)]
+pub struct SInterStore {
+    /// Name of the key to store the result at
+    destination: String,
+
+    /// Names of the sets
+    keys: Vec<String>,
+}
+
+/// Stores the union of the sets stored at the given keys into a destination
+/// key.
+///
+/// The destination is handled as by `SINTERSTORE`.
+#[derive(Debug)]
+pub struct SUnionStore {
+    /// Name of the key to store the result at
+    destination: String,
+
+    /// Names of the sets
+    keys: Vec<String>,
+}
+
+/// Stores the members of the set stored at the first key that are in none of
+/// the sets stored at the other keys into a destination key.
+///
+/// The destination is handled as by `SINTERSTORE`.
+#[derive(Debug)]
+pub struct SDiffStore {
+    /// Name of the key to store the result at
+    destination: String,
+
+    /// Names of the sets, the first one being the set to subtract from
+    keys: Vec<String>,
+}
+
+/// Operation applied to the sets by `SINTER`, `SUNION` and `SDIFF`, and their
+/// `STORE` variants.
+#[derive(Debug, Clone, Copy)]
+enum SetOp {
+    Inter,
+    Union,
+    Diff,
+}
+
+impl SInter {
+    /// Create a new `SInter` command which intersects the sets at `keys`.
+    pub fn new(keys: &[&str]) -> SInter {
+        SInter {
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+        }
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Parse a `SInter` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SINTER` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SInter` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least two entries.
+    ///
+    /// ```text
+    /// SINTER key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SInter> {
+        let keys = parse_keys(parse)?;
+
+        Ok(SInter { keys })
+    }
+
+    /// Apply the `SInter` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = members_frame(combine(db, &self.keys, SetOp::Inter));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `SInter` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        keys_frame("sinter", self.keys)
+    }
+}
+
+impl SUnion {
+    /// Create a new `SUnion` command which merges the sets at `keys`.
+    pub fn new(keys: &[&str]) -> SUnion {
+        SUnion {
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+        }
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Parse a `SUnion` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SUNION` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SUnion` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least two entries.
+    ///
+    /// ```text
+    /// SUNION key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SUnion> {
+        let keys = parse_keys(parse)?;
+
+        Ok(SUnion { keys })
+    }
+
+    /// Apply the `SUnion` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = members_frame(combine(db, &self.keys, SetOp::Union));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `SUnion` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        keys_frame("sunion", self.keys)
+    }
+}
+
+impl SDiff {
+    /// Create a new `SDiff` command which subtracts the sets at the other
+    /// `keys` from the set at the first one.
+    pub fn new(keys: &[&str]) -> SDiff {
+        SDiff {
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+        }
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Parse a `SDiff` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SDIFF` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SDiff` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least two entries.
+    ///
+    /// ```text
+    /// SDIFF key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SDiff> {
+        let keys = parse_keys(parse)?;
+
+        Ok(SDiff { keys })
+    }
+
+    /// Apply the `SDiff` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = members_frame(combine(db, &self.keys, SetOp::Diff));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `SDiff` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        keys_frame("sdiff", self.keys)
+    }
+}
+
+impl SInterStore {
+    /// Create a new `SInterStore` command which stores the intersection of the
+    /// sets at `keys` into `destination`.
+    pub fn new(destination: impl ToString, keys: &[&str]) -> SInterStore {
+        SInterStore {
+            destination: destination.to_string(),
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+        }
+    }
+
+    /// Get the destination key
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Parse a `SInterStore` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SINTERSTORE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SInterStore` value on success. If the frame is malformed,
+    /// `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least three entries.
+    ///
+    /// ```text
+    /// SINTERSTORE destination key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SInterStore> {
+        let destination = parse.next_string()?;
+        let keys = parse_keys(parse)?;
+
+        Ok(SInterStore { destination, keys })
+    }
+
+    /// Apply the `SInterStore` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = count_frame(store(db, self.destination, &self.keys, SetOp::Inter));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `SInterStore` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        store_frame("sinterstore", self.destination, self.keys)
+    }
+}
+
+impl SUnionStore {
+    /// Create a new `SUnionStore` command which stores the union of the sets at
+    /// `keys` into `destination`.
+    pub fn new(destination: impl ToString, keys: &[&str]) -> SUnionStore {
+        SUnionStore {
+            destination: destination.to_string(),
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+        }
+    }
+
+    /// Get the destination key
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Parse a `SUnionStore` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SUNIONSTORE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SUnionStore` value on success. If the frame is malformed,
+    /// `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least three entries.
+    ///
+    /// ```text
+    /// SUNIONSTORE destination key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SUnionStore> {
+        let destination = parse.next_string()?;
+        let keys = parse_keys(parse)?;
+
+        Ok(SUnionStore { destination, keys })
+    }
+
+    /// Apply the `SUnionStore` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = count_frame(store(db, self.destination, &self.keys, SetOp::Union));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `SUnionStore` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        store_frame("sunionstore", self.destination, self.keys)
+    }
+}
+
+impl SDiffStore {
+    /// Create a new `SDiffStore` command which stores into `destination` the
+    /// members of the set at the first of `keys` that are in none of the
+    /// others.
+    pub fn new(destination: impl ToString, keys: &[&str]) -> SDiffStore {
+        SDiffStore {
+            destination: destination.to_string(),
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+        }
+    }
+
+    /// Get the destination key
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Parse a `SDiffStore` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `SDIFFSTORE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SDiffStore` value on success. If the frame is malformed,
+    /// `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least three entries.
+    ///
+    /// ```text
+    /// SDIFFSTORE destination key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SDiffStore> {
+        let destination = parse.next_string()?;
+        let keys = parse_keys(parse)?;
+
+        Ok(SDiffStore { destination, keys })
+    }
+
+    /// Apply the `SDiffStore` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = count_frame(store(db, self.destination, &self.keys, SetOp::Diff));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `SDiffStore` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        store_frame("sdiffstore", self.destination, self.keys)
+    }
+}
+
+impl SetOp {
+    /// Apply the operation to `sets`, of which there is at least one.
+    fn apply(self, sets: &[Arc<HashSet<Bytes>>]) -> Vec<Bytes> {
+        match self {
+            // No need to look at the members if one of the sets is empty.
+            SetOp::Inter if sets.iter().any(|set| set.is_empty()) => vec![],
+            SetOp::Inter => {
+                // Only the members of the smallest set can be in all of them.
+                let smallest = sets.iter().min_by_key(|set| set.len()).unwrap();
+                smallest
+                    .iter()
+                    .filter(|member| sets.iter().all(|set| set.contains(*member)))
+                    .cloned()
+                    .collect()
+            }
+            SetOp::Union => {
+                let mut union = HashSet::new();
+                for set in sets {
+                    union.extend(set.iter().cloned());
+                }
+                union.into_iter().collect()
+            }
+            SetOp::Diff => {
+                let (first, others) = sets.split_first().unwrap();
+                first
+                    .iter()
+                    .filter(|member| !others.iter().any(|set| set.contains(*member)))
+                    .cloned()
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Load the sets stored at `keys` and apply `op` to them.
+///
+/// The sets are shared with the `Db`, so the work is done without holding the
+/// `Db` lock.
+fn combine(db: &Db, keys: &[String], op: SetOp) -> Result<Vec<Bytes>, &'static str> {
+    let sets = db.load_sets(keys)?;
+
+    Ok(op.apply(&sets))
+}
+
+/// Apply `op` to the sets stored at `keys` and store the result at
+/// `destination`, returning its number of members.
+///
+/// Unlike `combine`, the work is done while holding the `Db` lock, so that no
+/// other write happens between reading the sets and storing the result.
+fn store(db: &Db, destination: String, keys: &[String], op: SetOp) -> Result<u64, &'static str> {
+    db.check_write_key(&destination)?;
+    db.store_set(destination, keys, |sets| op.apply(sets))
+}
+
+/// Parse the keys of a command taking one or more keys.
+fn parse_keys(parse: &mut Parse) -> crate::Result<Vec<String>> {
+    // At least one key is required.
+    let mut keys = vec![parse.next_string()?];
+
+    loop {
+        match parse.next_string() {
+            Ok(key) => keys.push(key),
+            Err(ParseError::EndOfStream) => break,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(keys)
+}
+
+/// Encode a command named `name` taking `keys` as its only arguments.
+fn keys_frame(name: &'static str, keys: Vec<String>) -> Frame {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from(name.as_bytes()));
+    for key in keys {
+        frame.push_bulk(Bytes::from(key.into_bytes()));
+    }
+    frame
+}
+
+/// Encode a command named `name` storing the result of an operation on the
+/// sets at `keys` into `destination`.
+fn store_frame(name: &'static str, destination: String, keys: Vec<String>) -> Frame {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from(name.as_bytes()));
+    frame.push_bulk(Bytes::from(destination.into_bytes()));
+    for key in keys {
+        frame.push_bulk(Bytes::from(key.into_bytes()));
+    }
+    frame
+}
+
+/// Parse the key and the members of a command taking one or more members.
+fn parse_members(parse: &mut Parse) -> crate::Result<(String, Vec<Bytes>)> {
+    let key = parse.next_string()?;
+
+    // At least one member is required.
+    let mut members = vec![parse.next_bytes()?];
+
+    loop {
+        match parse.next_bytes() {
+            Ok(member) => members.push(member),
+            Err(ParseError::EndOfStream) => break,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok((key, members))
+}
+
+/// Build the reply of a command returning a number of members.
+fn count_frame(count: Result<u64, &'static str>) -> Frame {
+    match count {
+        Ok(count) => Frame::Integer(count as i64),
+        Err(msg) => Frame::Error(msg.to_string()),
+    }
+}
+
+/// Build the reply of a command returning set members.
+fn members_frame(members: Result<Vec<Bytes>, &'static str>) -> Frame {
+    match members {
+        Ok(members) => {
+            let mut frame = Frame::array();
+            for member in members {
+                frame.push_bulk(member);
+            }
+            frame
+        }
+        Err(msg) => Frame::Error(msg.to_string()),
+    }
+}
@@ -0,0 +1,198 @@
+use bytes::Bytes;
+
+/// Static metadata about a command supported by `mini-redis`.
+///
+/// `arity` is the number of entries in an invocation, including the command
+/// name. A negative arity `-n` means at least `n` entries.
+///
+/// Key positions follow the convention used by Redis' `COMMAND` output. The
+/// invocation is indexed with the command name at position 0, and keys are
+/// found at `first_key`, `first_key + step`, ... up to and including
+/// `last_key`. A negative `last_key` counts back from the end of the
+/// invocation, so `-1` means the last argument. Commands without keys have
+/// `first_key` set to 0.
+#[derive(Debug)]
+pub(crate) struct CommandSpec {
+    /// Lower cased command name
+    pub(crate) name: &'static str,
+
+    /// Expected number of entries in an invocation
+    pub(crate) arity: isize,
+
+    /// Position of the first key
+    pub(crate) first_key: usize,
+
+    /// Position of the last key
+    pub(crate) last_key: isize,
+
+    /// Distance between two consecutive keys
+    pub(crate) step: usize,
+}
+
+/// Metadata for every command supported by `mini-redis`.
+///
+/// When adding a command, an entry must be added here as well.
+const SPECS: &[CommandSpec] = &[
+    CommandSpec::new("append", 3, 1, 1, 1),
+    CommandSpec::new("auth", 2, 0, 0, 0),
+    CommandSpec::new("blpop", -3, 1, -2, 1),
+    CommandSpec::new("cluster", -2, 0, 0, 0),
+    CommandSpec::new("command", -3, 0, 0, 0),
+    CommandSpec::new("config", -2, 0, 0, 0),
+    CommandSpec::new("dbsize", 1, 0, 0, 0),
+    CommandSpec::new("debug", -2, 0, 0, 0),
+    CommandSpec::new("decr", 2, 1, 1, 1),
+    CommandSpec::new("decrby", 3, 1, 1, 1),
+    CommandSpec::new("del", -2, 1, -1, 1),
+    CommandSpec::new("discard", 1, 0, 0, 0),
+    CommandSpec::new("exec", 1, 0, 0, 0),
+    CommandSpec::new("exists", -2, 1, -1, 1),
+    CommandSpec::new("expire", 3, 1, 1, 1),
+    CommandSpec::new("flushall", -1, 0, 0, 0),
+    CommandSpec::new("flushdb", -1, 0, 0, 0),
+    CommandSpec::new("get", 2, 1, 1, 1),
+    CommandSpec::new("getrange", -4, 1, 1, 1),
+    CommandSpec::new("getset", 3, 1, 1, 1),
+    CommandSpec::new("hdel", -3, 1, 1, 1),
+    CommandSpec::new("hello", -1, 0, 0, 0),
+    CommandSpec::new("hexists", 3, 1, 1, 1),
+    CommandSpec::new("hget", 3, 1, 1, 1),
+    CommandSpec::new("hgetall", 2, 1, 1, 1),
+    CommandSpec::new("hincrby", 4, 1, 1, 1),
+    CommandSpec::new("hkeys", 2, 1, 1, 1),
+    CommandSpec::new("hlen", 2, 1, 1, 1),
+    CommandSpec::new("hmget", -3, 1, 1, 1),
+    CommandSpec::new("hset", 4, 1, 1, 1),
+    CommandSpec::new("hsetnx", 4, 1, 1, 1),
+    CommandSpec::new("hvals", 2, 1, 1, 1),
+    CommandSpec::new("incr", 2, 1, 1, 1),
+    CommandSpec::new("incrby", 3, 1, 1, 1),
+    CommandSpec::new("info", -1, 0, 0, 0),
+    CommandSpec::new("lcs", -3, 1, 2, 1),
+    CommandSpec::new("lindex", 3, 1, 1, 1),
+    CommandSpec::new("llen", 2, 1, 1, 1),
+    CommandSpec::new("lpush", -3, 1, 1, 1),
+    CommandSpec::new("lpushcapped", 4, 1, 1, 1),
+    CommandSpec::new("lrange", 4, 1, 1, 1),
+    CommandSpec::new("mget", -2, 1, -1, 1),
+    CommandSpec::new("mset", -3, 1, -1, 2),
+    CommandSpec::new("multi", 1, 0, 0, 0),
+    CommandSpec::new("object", -2, 2, 2, 1),
+    CommandSpec::new("persist", 2, 1, 1, 1),
+    CommandSpec::new("ping", -1, 0, 0, 0),
+    CommandSpec::new("psubscribe", -2, 0, 0, 0),
+    CommandSpec::new("pttl", 2, 1, 1, 1),
+    CommandSpec::new("publish", 3, 0, 0, 0),
+    CommandSpec::new("pubsub", -2, 0, 0, 0),
+    CommandSpec::new("punsubscribe", -1, 0, 0, 0),
+    CommandSpec::new("rename", 3, 1, 2, 1),
+    CommandSpec::new("renamenx", 3, 1, 2, 1),
+    CommandSpec::new("rpush", -3, 1, 1, 1),
+    CommandSpec::new("sadd", -3, 1, 1, 1),
+    CommandSpec::new("save", 1, 0, 0, 0),
+    CommandSpec::new("scan", -2, 0, 0, 0),
+    CommandSpec::new("scard", 2, 1, 1, 1),
+    CommandSpec::new("sdiff", -2, 1, -1, 1),
+    CommandSpec::new("sdiffstore", -3, 1, -1, 1),
+    CommandSpec::new("select", 2, 0, 0, 0),
+    CommandSpec::new("set", -3, 1, 1, 1),
+    CommandSpec::new("setnx", 3, 1, 1, 1),
+    CommandSpec::new("sinter", -2, 1, -1, 1),
+    CommandSpec::new("sinterstore", -3, 1, -1, 1),
+    CommandSpec::new("sismember", 3, 1, 1, 1),
+    CommandSpec::new("smembers", 2, 1, 1, 1),
+    CommandSpec::new("spop", -2, 1, 1, 1),
+    CommandSpec::new("srem", -3, 1, 1, 1),
+    CommandSpec::new("strlen", 2, 1, 1, 1),
+    CommandSpec::new("subscribe", -2, 0, 0, 0),
+    CommandSpec::new("subscribe-replay", 3, 0, 0, 0),
+    CommandSpec::new("sunion", -2, 1, -1, 1),
+    CommandSpec::new("sunionstore", -3, 1, -1, 1),
+    CommandSpec::new("ttl", 2, 1, 1, 1),
+    CommandSpec::new("type", 2, 1, 1, 1),
+    CommandSpec::new("unsubscribe", -1, 0, 0, 0),
+    CommandSpec::new("unwatch", 1, 0, 0, 0),
+    CommandSpec::new("watch", -2, 1, -1, 1),
+    CommandSpec::new("zadd", -4, 1, 1, 1),
+    CommandSpec::new("zcount", 4, 1, 1, 1),
+    CommandSpec::new("zdiff", -3, 0, 0, 0),
+    CommandSpec::new("zincrby", 4, 1, 1, 1),
+    CommandSpec::new("zinter", -3, 0, 0, 0),
+    CommandSpec::new("zinterstore", -4, 1, 1, 1),
+    CommandSpec::new("zrange", -4, 1, 1, 1),
+    CommandSpec::new("zrank", 3, 1, 1, 1),
+    CommandSpec::new("zrem", -3, 1, 1, 1),
+    CommandSpec::new("zremrangebyrank", 4, 1, 1, 1),
+    CommandSpec::new("zremrangebyscore", 4, 1, 1, 1),
+    CommandSpec::new("zscore", 3, 1, 1, 1),
+    CommandSpec::new("zunion", -3, 0, 0, 0),
+    CommandSpec::new("zunionstore", -4, 1, 1, 1),
+];
+
+impl CommandSpec {
+    const fn new(
+        name: &'static str,
+        arity: isize,
+        first_key: usize,
+        last_key: isize,
+        step: usize,
+    ) -> CommandSpec {
+        CommandSpec {
+            name,
+            arity,
+            first_key,
+            last_key,
+            step,
+        }
+    }
+
+    /// Returns the metadata of the command named `name`, or `None` if the
+    /// command is not supported.
+    ///
+    /// `name` must be lower cased.
+    pub(crate) fn lookup(name: &str) -> Option<&'static CommandSpec> {
+        SPECS.iter().find(|spec| spec.name == name)
+    }
+
+    /// Returns `true` if an invocation with `len` entries, including the
+    /// command name, has a valid number of arguments.
+    pub(crate) fn check_arity(&self, len: usize) -> bool {
+        if self.arity < 0 {
+            len as isize >= -self.arity
+        } else {
+            len as isize == self.arity
+        }
+    }
+
+    /// Returns `true` if invocations of the command contain keys.
+    pub(crate) fn has_keys(&self) -> bool {
+        self.first_key > 0
+    }
+
+    /// Returns the keys found in `args`, a full invocation of the command
+    /// including its name.
+    ///
+    /// Returns `None` if `args` is too short to contain the keys.
+    pub(crate) fn keys<'a>(&self, args: &'a [Bytes]) -> Option<Vec<&'a Bytes>> {
+        if !self.has_keys() {
+            return Some(vec![]);
+        }
+
+        let last_key = if self.last_key < 0 {
+            args.len() as isize + self.last_key
+        } else {
+            self.last_key
+        };
+
+        if last_key < self.first_key as isize || last_key as usize >= args.len() {
+            return None;
+        }
+
+        let keys = args[self.first_key..=last_key as usize]
+            .iter()
+            .step_by(self.step)
+            .collect();
+
+        Some(keys)
+    }
+}
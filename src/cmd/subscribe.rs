@@ -12,9 +12,22 @@ use tokio_stream::{Stream, StreamExt, StreamMap};
 /// Once the client enters the subscribed state, it is not supposed to issue any
 /// other commands, except for additional SUBSCRIBE, PSUBSCRIBE, UNSUBSCRIBE,
 /// PUNSUBSCRIBE, PING and QUIT commands.
+///
+/// `SUBSCRIBE-REPLAY channel count` subscribes to a single channel and first
+/// delivers up to `count` of the messages last published on it, as kept by the
+/// backlog configured with `Config::pubsub_backlog`. Live messages follow.
 #[derive(Debug)]
 pub struct Subscribe {
-    channels: Vec<String>,
+    /// Names of the channels. Channel names are binary safe.
+    channels: Vec<Bytes>,
+
+    /// Number of backlogged messages to deliver on subscribing. `0` for a plain
+    /// `SUBSCRIBE`.
+    replay: usize,
+
+    /// Patterns to subscribe to, when the subscribed state is entered or
+    /// extended by `PSUBSCRIBE`.
+    patterns: Vec<Bytes>,
 }
 
 /// Unsubscribes the client from one or more channels.
@@ -23,7 +36,28 @@ pub struct Subscribe {
 /// previously subscribed channels.
 #[derive(Clone, Debug)]
 pub struct Unsubscribe {
-    channels: Vec<String>,
+    channels: Vec<Bytes>,
+}
+
+/// Subscribes the client to one or more glob-style patterns.
+///
+/// The client receives the messages published on every channel matching one
+/// of the patterns, along with the pattern and the channel. As with
+/// `SUBSCRIBE`, the client then enters the subscribed state, and may subscribe
+/// to channels and patterns alike.
+#[derive(Debug)]
+pub struct Psubscribe {
+    /// Glob-style patterns, matched against channel names.
+    patterns: Vec<Bytes>,
+}
+
+/// Unsubscribes the client from one or more patterns.
+///
+/// When no patterns are specified, the client is unsubscribed from all the
+/// previously subscribed patterns.
+#[derive(Clone, Debug)]
+pub struct Punsubscribe {
+    patterns: Vec<Bytes>,
 }
 
 /// Stream of messages. The stream receives messages from the
@@ -32,10 +66,57 @@ pub struct Unsubscribe {
 /// a trait object.
 type Messages = Pin<Box<dyn Stream<Item = Bytes> + Send>>;
 
+/// Stream of messages received through a pattern subscription, along with the
+/// channel each of them was published on.
+type PatternMessages = Pin<Box<dyn Stream<Item = (Bytes, Bytes)> + Send>>;
+
+/// The channels and patterns a client is subscribed to.
+struct Subscriptions {
+    channels: StreamMap<Bytes, Messages>,
+    patterns: StreamMap<Bytes, PatternMessages>,
+}
+
+impl Subscriptions {
+    /// Returns the number of channels and patterns subscribed to. This is the
+    /// count reported to the client, and checked against
+    /// `Config::max_subscriptions`.
+    fn len(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
+}
+
 impl Subscribe {
     /// Creates a new `Subscribe` command to listen on the specified channels.
-    pub(crate) fn new(channels: Vec<String>) -> Subscribe {
-        Subscribe { channels }
+    pub(crate) fn new(channels: Vec<Bytes>) -> Subscribe {
+        Subscribe {
+            channels,
+            replay: 0,
+            patterns: vec![],
+        }
+    }
+
+    /// Creates a new `Subscribe` command to listen on `channel`, first
+    /// receiving up to `count` backlogged messages.
+    pub(crate) fn replay(channel: Bytes, count: usize) -> Subscribe {
+        Subscribe {
+            channels: vec![channel],
+            replay: count,
+            patterns: vec![],
+        }
+    }
+
+    /// Get the channels to subscribe to
+    pub(crate) fn channels(&self) -> &[Bytes] {
+        &self.channels
+    }
+
+    /// Returns the name of the command, `subscribe` or `subscribe-replay`.
+    pub(crate) fn get_name(&self) -> &'static str {
+        if self.replay > 0 {
+            "subscribe-replay"
+        } else {
+            "subscribe"
+        }
     }
 
     /// Parse a `Subscribe` instance from a received frame.
@@ -67,13 +148,13 @@ impl Subscribe {
         //
         // Extract the first string. If there is none, the the frame is
         // malformed and the error is bubbled up.
-        let mut channels = vec![parse.next_string()?];
+        let mut channels = vec![parse.next_bytes()?];
 
         // Now, the remainder of the frame is consumed. Each value must be a
         // string or the frame is malformed. Once all values in the frame have
         // been consumed, the command is fully parsed.
         loop {
-            match parse.next_string() {
+            match parse.next_bytes() {
                 // A string has been consumed from the `parse`, push it into the
                 // list of channels to subscribe to.
                 Ok(s) => channels.push(s),
@@ -86,7 +167,25 @@ impl Subscribe {
             }
         }
 
-        Ok(Subscribe { channels })
+        Ok(Subscribe::new(channels))
+    }
+
+    /// Parse a `SUBSCRIBE-REPLAY` instance from a received frame.
+    ///
+    /// The `SUBSCRIBE-REPLAY` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// SUBSCRIBE-REPLAY channel count
+    /// ```
+    pub(crate) fn parse_replay_frames(parse: &mut Parse) -> crate::Result<Subscribe> {
+        let channel = parse.next_bytes()?;
+        let count = parse.next_int()?;
+
+        Ok(Subscribe::replay(channel, count as usize))
     }
 
     /// Apply the `Subscribe` command to the specified `Db` instance.
@@ -111,15 +210,25 @@ impl Subscribe {
         // dynamically add and remove channels from its subscription set. To
         // handle this, a `StreamMap` is used to track active subscriptions. The
         // `StreamMap` merges messages from individual broadcast channels as
-        // they are received.
-        let mut subscriptions = StreamMap::new();
+        // they are received. Pattern subscriptions are tracked in a second
+        // one.
+        let mut subscriptions = Subscriptions {
+            channels: StreamMap::new(),
+            patterns: StreamMap::new(),
+        };
 
         loop {
             // `self.channels` is used to track additional channels to subscribe
             // to. When new `SUBSCRIBE` commands are received during the
             // execution of `apply`, the new channels are pushed onto this vec.
             for channel_name in self.channels.drain(..) {
-                subscribe_to_channel(channel_name, &mut subscriptions, db, dst).await?;
+                subscribe_to_channel(channel_name, self.replay, &mut subscriptions, db, dst)
+                    .await?;
+            }
+
+            // Likewise for patterns, pushed by `PSUBSCRIBE` commands.
+            for pattern in self.patterns.drain(..) {
+                subscribe_to_pattern(pattern, &mut subscriptions, db, dst).await?;
             }
 
             // Wait for one of the following to happen:
@@ -129,9 +238,14 @@ impl Subscribe {
             // - A server shutdown signal.
             select! {
                 // Receive messages from subscribed channels
-                Some((channel_name, msg)) = subscriptions.next() => {
+                Some((channel_name, msg)) = subscriptions.channels.next() => {
                     dst.write_frame(&make_message_frame(channel_name, msg)).await?;
                 }
+                // Receive messages from channels matching subscribed patterns
+                Some((pattern, (channel_name, msg))) = subscriptions.patterns.next() => {
+                    let frame = make_pmessage_frame(pattern, channel_name, msg);
+                    dst.write_frame(&frame).await?;
+                }
                 res = dst.read_frame() => {
                     let frame = match res? {
                         Some(frame) => frame,
@@ -141,7 +255,7 @@ impl Subscribe {
 
                     handle_command(
                         frame,
-                        &mut self.channels,
+                        &mut self,
                         &mut subscriptions,
                         dst,
                     ).await?;
@@ -159,21 +273,43 @@ impl Subscribe {
     /// to the server.
     pub(crate) fn into_frame(self) -> Frame {
         let mut frame = Frame::array();
-        frame.push_bulk(Bytes::from("subscribe".as_bytes()));
+        frame.push_bulk(Bytes::from(self.get_name().as_bytes()));
         for channel in self.channels {
-            frame.push_bulk(Bytes::from(channel.into_bytes()));
+            frame.push_bulk(channel);
+        }
+        if self.replay > 0 {
+            frame.push_bulk(Bytes::from(self.replay.to_string()));
         }
         frame
     }
 }
 
 async fn subscribe_to_channel(
-    channel_name: String,
-    subscriptions: &mut StreamMap<String, Messages>,
+    channel_name: Bytes,
+    replay: usize,
+    subscriptions: &mut Subscriptions,
     db: &Db,
     dst: &mut Connection,
 ) -> crate::Result<()> {
-    let mut rx = db.subscribe(channel_name.clone());
+    // Refuse new channels once the connection holds the maximum number of
+    // subscriptions. Re-subscribing to a channel already in the set does not
+    // count against the limit.
+    let limit = db.config().max_subscriptions;
+
+    if subscriptions.len() >= limit && !subscriptions.channels.contains_key(&channel_name) {
+        let response = Frame::Error(format!(
+            "ERR maximum number of subscriptions ({}) reached",
+            limit
+        ));
+        dst.write_frame(&response).await?;
+        return Ok(());
+    }
+
+    let (mut rx, backlog) = if replay > 0 {
+        db.subscribe_replay(channel_name.clone(), replay)
+    } else {
+        (db.subscribe(channel_name.clone()), vec![])
+    };
 
     // Subscribe to the channel.
     let rx = Box::pin(async_stream::stream! {
@@ -188,10 +324,56 @@ async fn subscribe_to_channel(
     });
 
     // Track subscription in this client's subscription set.
-    subscriptions.insert(channel_name.clone(), rx);
+    subscriptions.channels.insert(channel_name.clone(), rx);
 
     // Respond with the successful subscription
-    let response = make_subscribe_frame(channel_name, subscriptions.len());
+    let response = make_subscribe_frame(channel_name.clone(), subscriptions.len());
+    dst.write_frame(&response).await?;
+
+    // Deliver the backlog before any message received by `rx`, which were all
+    // published after it.
+    for msg in backlog {
+        dst.write_frame(&make_message_frame(channel_name.clone(), msg))
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn subscribe_to_pattern(
+    pattern: Bytes,
+    subscriptions: &mut Subscriptions,
+    db: &Db,
+    dst: &mut Connection,
+) -> crate::Result<()> {
+    // Patterns count against the same limit as channels.
+    let limit = db.config().max_subscriptions;
+
+    if subscriptions.len() >= limit && !subscriptions.patterns.contains_key(&pattern) {
+        let response = Frame::Error(format!(
+            "ERR maximum number of subscriptions ({}) reached",
+            limit
+        ));
+        dst.write_frame(&response).await?;
+        return Ok(());
+    }
+
+    let mut rx = db.psubscribe(pattern.clone());
+
+    let rx = Box::pin(async_stream::stream! {
+        loop {
+            match rx.recv().await {
+                Ok(msg) => yield msg,
+                // If we lagged in consuming messages, just resume.
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(_) => break,
+            }
+        }
+    });
+
+    subscriptions.patterns.insert(pattern.clone(), rx);
+
+    let response = make_psubscribe_frame(pattern, subscriptions.len());
     dst.write_frame(&response).await?;
 
     Ok(())
@@ -204,19 +386,20 @@ async fn subscribe_to_channel(
 /// `subscriptions`.
 async fn handle_command(
     frame: Frame,
-    subscribe_to: &mut Vec<String>,
-    subscriptions: &mut StreamMap<String, Messages>,
+    subscribe_to: &mut Subscribe,
+    subscriptions: &mut Subscriptions,
     dst: &mut Connection,
 ) -> crate::Result<()> {
     // A command has been received from the client.
     //
-    // Only `SUBSCRIBE` and `UNSUBSCRIBE` commands are permitted
-    // in this context.
+    // Only `SUBSCRIBE`, `UNSUBSCRIBE` and their pattern counterparts are
+    // permitted in this context.
     match Command::from_frame(frame)? {
         Command::Subscribe(subscribe) => {
             // The `apply` method will subscribe to the channels we add to this
             // vector.
-            subscribe_to.extend(subscribe.channels.into_iter());
+            subscribe_to.channels.extend(subscribe.channels);
+            subscribe_to.replay = subscribe.replay;
         }
         Command::Unsubscribe(mut unsubscribe) => {
             // If no channels are specified, this requests unsubscribing from
@@ -224,19 +407,32 @@ async fn handle_command(
             // vec is populated with the list of channels currently subscribed
             // to.
             if unsubscribe.channels.is_empty() {
-                unsubscribe.channels = subscriptions
-                    .keys()
-                    .map(|channel_name| channel_name.to_string())
-                    .collect();
+                unsubscribe.channels = subscriptions.channels.keys().cloned().collect();
             }
 
             for channel_name in unsubscribe.channels {
-                subscriptions.remove(&channel_name);
+                subscriptions.channels.remove(&channel_name);
 
                 let response = make_unsubscribe_frame(channel_name, subscriptions.len());
                 dst.write_frame(&response).await?;
             }
         }
+        Command::Psubscribe(psubscribe) => {
+            subscribe_to.patterns.extend(psubscribe.patterns);
+        }
+        Command::Punsubscribe(mut punsubscribe) => {
+            // As with `UNSUBSCRIBE`, no patterns means all of them.
+            if punsubscribe.patterns.is_empty() {
+                punsubscribe.patterns = subscriptions.patterns.keys().cloned().collect();
+            }
+
+            for pattern in punsubscribe.patterns {
+                subscriptions.patterns.remove(&pattern);
+
+                let response = make_punsubscribe_frame(pattern, subscriptions.len());
+                dst.write_frame(&response).await?;
+            }
+        }
         command => {
             let cmd = Unknown::new(command.get_name());
             cmd.apply(dst).await?;
@@ -247,40 +443,69 @@ async fn handle_command(
 
 /// Creates the response to a subcribe request.
 ///
-/// All of these functions take the `channel_name` as a `String` instead of
-/// a `&str` since `Bytes::from` can reuse the allocation in the `String`, and
-/// taking a `&str` would require copying the data. This allows the caller to
-/// decide whether to clone the channel name or not.
-fn make_subscribe_frame(channel_name: String, num_subs: usize) -> Frame {
-    let mut response = Frame::array();
+/// All of these functions take the `channel_name` as `Bytes` instead of a
+/// `&[u8]` since `Bytes` can be cloned without copying the data, and taking a
+/// `&[u8]` would require copying it. This allows the caller to decide whether
+/// to clone the channel name or not.
+fn make_subscribe_frame(channel_name: Bytes, num_subs: usize) -> Frame {
+    let mut response = Frame::push();
     response.push_bulk(Bytes::from_static(b"subscribe"));
-    response.push_bulk(Bytes::from(channel_name));
-    response.push_int(num_subs as u64);
+    response.push_bulk(channel_name);
+    response.push_int(num_subs as i64);
     response
 }
 
 /// Creates the response to an unsubcribe request.
-fn make_unsubscribe_frame(channel_name: String, num_subs: usize) -> Frame {
-    let mut response = Frame::array();
+fn make_unsubscribe_frame(channel_name: Bytes, num_subs: usize) -> Frame {
+    let mut response = Frame::push();
     response.push_bulk(Bytes::from_static(b"unsubscribe"));
-    response.push_bulk(Bytes::from(channel_name));
-    response.push_int(num_subs as u64);
+    response.push_bulk(channel_name);
+    response.push_int(num_subs as i64);
     response
 }
 
 /// Creates a message informing the client about a new message on a channel that
 /// the client subscribes to.
-fn make_message_frame(channel_name: String, msg: Bytes) -> Frame {
-    let mut response = Frame::array();
+fn make_message_frame(channel_name: Bytes, msg: Bytes) -> Frame {
+    let mut response = Frame::push();
     response.push_bulk(Bytes::from_static(b"message"));
-    response.push_bulk(Bytes::from(channel_name));
+    response.push_bulk(channel_name);
+    response.push_bulk(msg);
+    response
+}
+
+/// Creates the response to a psubscribe request.
+fn make_psubscribe_frame(pattern: Bytes, num_subs: usize) -> Frame {
+    let mut response = Frame::push();
+    response.push_bulk(Bytes::from_static(b"psubscribe"));
+    response.push_bulk(pattern);
+    response.push_int(num_subs as i64);
+    response
+}
+
+/// Creates the response to a punsubscribe request.
+fn make_punsubscribe_frame(pattern: Bytes, num_subs: usize) -> Frame {
+    let mut response = Frame::push();
+    response.push_bulk(Bytes::from_static(b"punsubscribe"));
+    response.push_bulk(pattern);
+    response.push_int(num_subs as i64);
+    response
+}
+
+/// Creates a message informing the client about a new message on a channel
+/// matching a pattern the client subscribes to.
+fn make_pmessage_frame(pattern: Bytes, channel_name: Bytes, msg: Bytes) -> Frame {
+    let mut response = Frame::push();
+    response.push_bulk(Bytes::from_static(b"pmessage"));
+    response.push_bulk(pattern);
+    response.push_bulk(channel_name);
     response.push_bulk(msg);
     response
 }
 
 impl Unsubscribe {
     /// Create a new `Unsubscribe` command with the given `channels`.
-    pub(crate) fn new(channels: &[String]) -> Unsubscribe {
+    pub(crate) fn new(channels: &[Bytes]) -> Unsubscribe {
         Unsubscribe {
             channels: channels.to_vec(),
         }
@@ -316,7 +541,7 @@ impl Unsubscribe {
         // Once all values in the frame have been consumed, the command is fully
         // parsed.
         loop {
-            match parse.next_string() {
+            match parse.next_bytes() {
                 // A string has been consumed from the `parse`, push it into the
                 // list of channels to unsubscribe from.
                 Ok(s) => channels.push(s),
@@ -341,7 +566,145 @@ impl Unsubscribe {
         frame.push_bulk(Bytes::from("unsubscribe".as_bytes()));
 
         for channel in self.channels {
-            frame.push_bulk(Bytes::from(channel.into_bytes()));
+            frame.push_bulk(channel);
+        }
+
+        frame
+    }
+}
+
+impl Psubscribe {
+    /// Creates a new `Psubscribe` command to listen on channels matching the
+    /// specified patterns.
+    pub(crate) fn new(patterns: Vec<Bytes>) -> Psubscribe {
+        Psubscribe { patterns }
+    }
+
+    /// Parse a `Psubscribe` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `PSUBSCRIBE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// On success, the `Psubscribe` value is returned. If the frame is
+    /// malformed, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two or more entries.
+    ///
+    /// ```text
+    /// PSUBSCRIBE pattern [pattern ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Psubscribe> {
+        use ParseError::EndOfStream;
+
+        // At least one pattern is required.
+        let mut patterns = vec![parse.next_bytes()?];
+
+        loop {
+            match parse.next_bytes() {
+                Ok(s) => patterns.push(s),
+                Err(EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(Psubscribe { patterns })
+    }
+
+    /// Apply the `Psubscribe` command to the specified `Db` instance.
+    ///
+    /// The client enters the subscribed state, handled by `Subscribe::apply`,
+    /// with no channels and the patterns to subscribe to.
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let subscribe = Subscribe {
+            channels: vec![],
+            replay: 0,
+            patterns: self.patterns,
+        };
+
+        subscribe.apply(db, dst, shutdown).await
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Psubscribe` command to
+    /// send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("psubscribe".as_bytes()));
+        for pattern in self.patterns {
+            frame.push_bulk(pattern);
+        }
+        frame
+    }
+}
+
+impl Punsubscribe {
+    /// Create a new `Punsubscribe` command with the given `patterns`.
+    pub(crate) fn new(patterns: &[Bytes]) -> Punsubscribe {
+        Punsubscribe {
+            patterns: patterns.to_vec(),
+        }
+    }
+
+    /// Parse a `Punsubscribe` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `PUNSUBSCRIBE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// On success, the `Punsubscribe` value is returned. If the frame is
+    /// malformed, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least one entry.
+    ///
+    /// ```text
+    /// PUNSUBSCRIBE [pattern [pattern ...]]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Punsubscribe, ParseError> {
+        use ParseError::EndOfStream;
+
+        // There may be no patterns listed, so start with an empty vec.
+        let mut patterns = vec![];
+
+        loop {
+            match parse.next_bytes() {
+                Ok(s) => patterns.push(s),
+                Err(EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Punsubscribe { patterns })
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Punsubscribe` command to
+    /// send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("punsubscribe".as_bytes()));
+
+        for pattern in self.patterns {
+            frame.push_bulk(pattern);
         }
 
         frame
@@ -0,0 +1,210 @@
+//! Optimistic locking for transactions, with `WATCH` and `UNWATCH`.
+//!
+//! A connection watches keys before starting a transaction with `MULTI`. If
+//! any of them is written to before `EXEC`, by any connection, the transaction
+//! is not applied and `EXEC` replies with a null array. The keys are
+//! unwatched by `EXEC`, `DISCARD` and `UNWATCH`, and when the connection
+//! closes.
+//!
+//! This allows compare-and-swap: read a key after watching it, then write
+//! it back in a transaction, and retry if `EXEC` did not apply it.
+
+use crate::cmd::{Parse, ParseError};
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Watch keys, so that the next transaction is only applied if none of them
+/// is modified in the meantime.
+#[derive(Debug)]
+pub struct Watch {
+    /// Names of the keys to watch
+    keys: Vec<String>,
+}
+
+/// Stop watching every key watched by the connection.
+#[derive(Debug, Default)]
+pub struct Unwatch {}
+
+/// A key watched by a connection, along with its version when it started
+/// being watched.
+#[derive(Debug)]
+pub(crate) struct WatchedKey {
+    /// Index of the database holding the key
+    database: usize,
+
+    /// Name of the key
+    key: String,
+
+    /// Version of the key when `WATCH` was applied
+    version: u64,
+}
+
+impl Watch {
+    /// Create a new `Watch` command which watches `keys`.
+    pub fn new(keys: &[&str]) -> Watch {
+        Watch {
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+        }
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Parse a `Watch` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `WATCH` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Watch` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 2 entries.
+    ///
+    /// ```text
+    /// WATCH key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Watch> {
+        // At least one key is required.
+        let mut keys = vec![parse.next_string()?];
+
+        loop {
+            match parse.next_string() {
+                Ok(key) => keys.push(key),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(Watch { keys })
+    }
+
+    /// Apply the `Watch` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        for key in self.keys {
+            // Watching a key twice has no further effect.
+            let watched = dst
+                .watched_keys()
+                .iter()
+                .any(|watched| watched.database == db.index() && watched.key == key);
+
+            if !watched {
+                let version = db.watch(&key);
+
+                dst.watch_key(WatchedKey {
+                    database: db.index(),
+                    key,
+                    version,
+                });
+            }
+        }
+
+        let response = Frame::Simple("OK".to_string());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `Watch` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("watch".as_bytes()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        frame
+    }
+}
+
+impl Unwatch {
+    /// Create a new `Unwatch` command.
+    pub fn new() -> Unwatch {
+        Unwatch {}
+    }
+
+    /// Parse an `Unwatch` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `UNWATCH` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Unwatch` value on success. If the frame is malformed,
+    /// `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing a single entry.
+    ///
+    /// ```text
+    /// UNWATCH
+    /// ```
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Unwatch> {
+        Ok(Unwatch {})
+    }
+
+    /// Apply the `Unwatch` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        unwatch_all(db, dst);
+
+        let response = Frame::Simple("OK".to_string());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding an `Unwatch` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("unwatch".as_bytes()));
+        frame
+    }
+}
+
+impl WatchedKey {
+    /// Returns `true` if the key was written to since it started being
+    /// watched.
+    pub(crate) fn is_modified(&self, db: &Db) -> bool {
+        db.select(self.database).key_version(&self.key) != self.version
+    }
+}
+
+/// Stop watching every key watched by the connection.
+pub(crate) fn unwatch_all(db: &Db, dst: &mut Connection) {
+    for watched in dst.take_watched_keys() {
+        db.select(watched.database).unwatch(&watched.key);
+    }
+}
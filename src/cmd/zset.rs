@@ -0,0 +1,1843 @@
+//! Sorted set commands.
+//!
+//! Sorted sets are kept in their own keyspace in `Db`. Each member has a
+//! floating point score, and members are ordered by ascending score. Members
+//! with the same score are ordered lexicographically.
+
+use crate::cmd::{Parse, ParseError};
+use crate::db::SortedSet;
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Bound;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+
+/// Add members with their score to the sorted set stored at key.
+///
+/// If a member is already in the sorted set, its score is updated. If the key
+/// does not exist, an empty sorted set is created first. The reply is the
+/// number of members that were not already in the sorted set.
+#[derive(Debug)]
+pub struct ZAdd {
+    /// Name of the sorted set
+    key: String,
+
+    /// Members to add, each with its score
+    members: Vec<(f64, Bytes)>,
+}
+
+/// Returns the members of the sorted set stored at key between two offsets.
+///
+/// Members are ordered by ascending score. Offsets are handled as by `LRANGE`.
+/// With the `WITHSCORES` option, each member is followed by its score.
+#[derive(Debug)]
+pub struct ZRange {
+    /// Name of the sorted set
+    key: String,
+
+    /// Offset of the first member to return
+    start: i64,
+
+    /// Offset of the last member to return
+    stop: i64,
+
+    /// Whether to reply with the scores
+    with_scores: bool,
+}
+
+/// Returns the score of a member of the sorted set stored at key.
+///
+/// The score is replied as a bulk string, or nil if the key does not exist or
+/// the value is not a member.
+#[derive(Debug)]
+pub struct ZScore {
+    /// Name of the sorted set
+    key: String,
+
+    /// Member to look up
+    member: Bytes,
+}
+
+impl ZAdd {
+    /// Create a new `ZAdd` command which adds `members`, each with its score,
+    /// to the sorted set at `key`.
+    pub fn new(key: impl ToString, members: &[(f64, Bytes)]) -> ZAdd {
+        ZAdd {
+            key: key.to_string(),
+            members: members.to_vec(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the members and their score
+    pub fn members(&self) -> &[(f64, Bytes)] {
+        &self.members
+    }
+
+    /// Parse a `ZAdd` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZADD` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZAdd` value on success. If the frame is malformed or a
+    /// score is not a number, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 4 entries.
+    ///
+    /// ```text
+    /// ZADD key score member [score member ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZAdd> {
+        let key = parse.next_string()?;
+
+        // At least one member is required.
+        let score = parse_score(&parse.next_string()?)?;
+        let mut members = vec![(score, parse.next_bytes()?)];
+
+        loop {
+            let score = match parse.next_string() {
+                Ok(score) => parse_score(&score)?,
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            members.push((score, parse.next_bytes()?));
+        }
+
+        Ok(ZAdd { key, members })
+    }
+
+    /// Apply the `ZAdd` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db
+            .check_write_key(&self.key)
+            .and_then(|()| db.zadd(self.key, self.members))
+        {
+            Ok(added) => Frame::Integer(added as i64),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZAdd` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zadd".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for (score, member) in self.members {
+            frame.push_bulk(format_score(score));
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl ZRange {
+    /// Create a new `ZRange` command which returns the members of the sorted
+    /// set at `key` from offset `start` to offset `stop`.
+    pub fn new(key: impl ToString, start: i64, stop: i64, with_scores: bool) -> ZRange {
+        ZRange {
+            key: key.to_string(),
+            start,
+            stop,
+            with_scores,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the start offset
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    /// Get the stop offset
+    pub fn stop(&self) -> i64 {
+        self.stop
+    }
+
+    /// Whether the scores are requested
+    pub fn with_scores(&self) -> bool {
+        self.with_scores
+    }
+
+    /// Parse a `ZRange` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZRANGE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZRange` value on success. If the frame is malformed or an
+    /// offset is not an integer, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 4 entries.
+    ///
+    /// ```text
+    /// ZRANGE key start stop [WITHSCORES]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRange> {
+        use ParseError::EndOfStream;
+
+        let key = parse.next_string()?;
+        let start = parse.next_signed_int()?;
+        let stop = parse.next_signed_int()?;
+
+        let with_scores = match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "WITHSCORES" => true,
+            Ok(_) => return Err("currently `ZRANGE` only supports the WITHSCORES option".into()),
+            Err(EndOfStream) => false,
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(ZRange {
+            key,
+            start,
+            stop,
+            with_scores,
+        })
+    }
+
+    /// Apply the `ZRange` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zrange(&self.key, self.start, self.stop) {
+            Ok(members) => {
+                let mut frame = Frame::array();
+                for (member, score) in members {
+                    frame.push_bulk(member);
+                    if self.with_scores {
+                        frame.push_bulk(format_score(score));
+                    }
+                }
+                frame
+            }
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZRange` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrange".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.start.to_string()));
+        frame.push_bulk(Bytes::from(self.stop.to_string()));
+        if self.with_scores {
+            frame.push_bulk(Bytes::from("withscores".as_bytes()));
+        }
+        frame
+    }
+}
+
+impl ZScore {
+    /// Create a new `ZScore` command which returns the score of `member` in
+    /// the sorted set at `key`.
+    pub fn new(key: impl ToString, member: Bytes) -> ZScore {
+        ZScore {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the member
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+
+    /// Parse a `ZScore` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZSCORE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZScore` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// ZSCORE key member
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZScore> {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+
+        Ok(ZScore { key, member })
+    }
+
+    /// Apply the `ZScore` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zscore(&self.key, &self.member) {
+            Ok(Some(score)) => Frame::Double(score),
+            Ok(None) => Frame::Null,
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZScore` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zscore".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
+
+/// Add an increment to the score of a member of the sorted set stored at key.
+///
+/// A missing member is added with the increment as its score. If the key does
+/// not exist, an empty sorted set is created first. The reply is the new score
+/// as a bulk string.
+#[derive(Debug)]
+pub struct ZIncrBy {
+    /// Name of the sorted set
+    key: String,
+
+    /// Amount to add to the score
+    increment: f64,
+
+    /// Member whose score changes
+    member: Bytes,
+}
+
+/// Returns the rank of a member of the sorted set stored at key.
+///
+/// The rank is the 0-based position of the member when ordered by ascending
+/// score. Nil is replied if the key does not exist or the value is not a
+/// member.
+#[derive(Debug)]
+pub struct ZRank {
+    /// Name of the sorted set
+    key: String,
+
+    /// Member to look up
+    member: Bytes,
+}
+
+/// Remove members from the sorted set stored at key.
+///
+/// The reply is the number of members that were removed. The key is removed
+/// once the sorted set is empty.
+#[derive(Debug)]
+pub struct ZRem {
+    /// Name of the sorted set
+    key: String,
+
+    /// Members to remove
+    members: Vec<Bytes>,
+}
+
+/// Remove the members of the sorted set stored at key whose rank is between a
+/// start and a stop offset, both inclusive.
+///
+/// Ranks are 0-based, in ascending order of score. Negative offsets count back
+/// from the highest ranked member. The reply is the number of members that
+/// were removed. The key is removed once the sorted set is empty.
+#[derive(Debug)]
+pub struct ZRemRangeByRank {
+    /// Name of the sorted set
+    key: String,
+
+    /// Rank of the first member to remove
+    start: i64,
+
+    /// Rank of the last member to remove
+    stop: i64,
+}
+
+/// Remove the members of the sorted set stored at key whose score is between
+/// a minimum and a maximum.
+///
+/// Bounds are handled as by `ZCOUNT`. The reply is the number of members that
+/// were removed. The key is removed once the sorted set is empty.
+#[derive(Debug)]
+pub struct ZRemRangeByScore {
+    /// Name of the sorted set
+    key: String,
+
+    /// Lowest score removed
+    min: ScoreBound,
+
+    /// Highest score removed
+    max: ScoreBound,
+}
+
+impl ZIncrBy {
+    /// Create a new `ZIncrBy` command which adds `increment` to the score of
+    /// `member` in the sorted set at `key`.
+    pub fn new(key: impl ToString, increment: f64, member: Bytes) -> ZIncrBy {
+        ZIncrBy {
+            key: key.to_string(),
+            increment,
+            member,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the increment
+    pub fn increment(&self) -> f64 {
+        self.increment
+    }
+
+    /// Get the member
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+
+    /// Parse a `ZIncrBy` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZINCRBY` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZIncrBy` value on success. If the frame is malformed or
+    /// the increment is not a number, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing four entries.
+    ///
+    /// ```text
+    /// ZINCRBY key increment member
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZIncrBy> {
+        let key = parse.next_string()?;
+        let increment = parse_score(&parse.next_string()?)?;
+        let member = parse.next_bytes()?;
+
+        Ok(ZIncrBy {
+            key,
+            increment,
+            member,
+        })
+    }
+
+    /// Apply the `ZIncrBy` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db
+            .check_write_key(&self.key)
+            .and_then(|()| db.zincrby(self.key, self.member, self.increment))
+        {
+            Ok(score) => Frame::Double(score),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZIncrBy` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zincrby".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(format_score(self.increment));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
+
+impl ZRank {
+    /// Create a new `ZRank` command which returns the rank of `member` in the
+    /// sorted set at `key`.
+    pub fn new(key: impl ToString, member: Bytes) -> ZRank {
+        ZRank {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the member
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+
+    /// Parse a `ZRank` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZRANK` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZRank` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// ZRANK key member
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRank> {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+
+        Ok(ZRank { key, member })
+    }
+
+    /// Apply the `ZRank` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zrank(&self.key, &self.member) {
+            Ok(Some(rank)) => Frame::Integer(rank as i64),
+            Ok(None) => Frame::Null,
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZRank` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrank".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
+
+impl ZRem {
+    /// Create a new `ZRem` command which removes `members` from the sorted set
+    /// at `key`.
+    pub fn new(key: impl ToString, members: &[Bytes]) -> ZRem {
+        ZRem {
+            key: key.to_string(),
+            members: members.to_vec(),
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the members
+    pub fn members(&self) -> &[Bytes] {
+        &self.members
+    }
+
+    /// Parse a `ZRem` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZREM` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZRem` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 3 entries.
+    ///
+    /// ```text
+    /// ZREM key member [member ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRem> {
+        let key = parse.next_string()?;
+
+        // At least one member is required.
+        let mut members = vec![parse.next_bytes()?];
+
+        loop {
+            match parse.next_bytes() {
+                Ok(member) => members.push(member),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(ZRem { key, members })
+    }
+
+    /// Apply the `ZRem` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zrem(&self.key, &self.members) {
+            Ok(removed) => Frame::Integer(removed as i64),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZRem` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrem".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for member in self.members {
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl ZRemRangeByRank {
+    /// Create a new `ZRemRangeByRank` command which removes the members of the
+    /// sorted set at `key` ranked between `start` and `stop`.
+    pub fn new(key: impl ToString, start: i64, stop: i64) -> ZRemRangeByRank {
+        ZRemRangeByRank {
+            key: key.to_string(),
+            start,
+            stop,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the rank of the first member to remove
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    /// Get the rank of the last member to remove
+    pub fn stop(&self) -> i64 {
+        self.stop
+    }
+
+    /// Parse a `ZRemRangeByRank` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZREMRANGEBYRANK` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZRemRangeByRank` value on success. If the frame is
+    /// malformed, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing four entries.
+    ///
+    /// ```text
+    /// ZREMRANGEBYRANK key start stop
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRemRangeByRank> {
+        let key = parse.next_string()?;
+        let start = parse.next_signed_int()?;
+        let stop = parse.next_signed_int()?;
+
+        Ok(ZRemRangeByRank { key, start, stop })
+    }
+
+    /// Apply the `ZRemRangeByRank` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = count_frame(db.zremrangebyrank(&self.key, self.start, self.stop));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZRemRangeByRank` command
+    /// to send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zremrangebyrank".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.start.to_string()));
+        frame.push_bulk(Bytes::from(self.stop.to_string()));
+        frame
+    }
+}
+
+impl ZRemRangeByScore {
+    /// Create a new `ZRemRangeByScore` command which removes the members of
+    /// the sorted set at `key` scoring between `min` and `max`.
+    pub fn new(key: impl ToString, min: ScoreBound, max: ScoreBound) -> ZRemRangeByScore {
+        ZRemRangeByScore {
+            key: key.to_string(),
+            min,
+            max,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the lowest score removed
+    pub fn min(&self) -> ScoreBound {
+        self.min
+    }
+
+    /// Get the highest score removed
+    pub fn max(&self) -> ScoreBound {
+        self.max
+    }
+
+    /// Parse a `ZRemRangeByScore` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZREMRANGEBYSCORE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZRemRangeByScore` value on success. If the frame is
+    /// malformed, `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing four entries.
+    ///
+    /// ```text
+    /// ZREMRANGEBYSCORE key min max
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRemRangeByScore> {
+        let key = parse.next_string()?;
+        let min = parse.next_string()?.parse()?;
+        let max = parse.next_string()?.parse()?;
+
+        Ok(ZRemRangeByScore { key, min, max })
+    }
+
+    /// Apply the `ZRemRangeByScore` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let removed = db.zremrangebyscore(&self.key, self.min.into(), self.max.into());
+        let response = count_frame(removed);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZRemRangeByScore` command
+    /// to send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zremrangebyscore".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.min.to_string()));
+        frame.push_bulk(Bytes::from(self.max.to_string()));
+        frame
+    }
+}
+
+/// Stores the union of the sorted sets stored at the given keys into a
+/// destination key.
+///
+/// Plain sets are accepted as sources, each member scoring `1`. The scores of
+/// each source are multiplied by its weight, given with the `WEIGHTS` option
+/// and `1` by default. The scores of a member found in several sources are
+/// combined as chosen with the `AGGREGATE` option, summed by default.
+///
+/// The destination is overwritten, whatever its type, or removed if the result
+/// is empty. The reply is the number of members stored.
+#[derive(Debug)]
+pub struct ZUnionStore {
+    /// Name of the key to store the result at
+    destination: String,
+
+    /// The sorted sets to combine
+    sources: Sources,
+}
+
+/// Stores the intersection of the sorted sets stored at the given keys into a
+/// destination key.
+///
+/// Only the members found in every source are kept. Sources, weights, scores
+/// and the destination are handled as by `ZUNIONSTORE`.
+#[derive(Debug)]
+pub struct ZInterStore {
+    /// Name of the key to store the result at
+    destination: String,
+
+    /// The sorted sets to combine
+    sources: Sources,
+}
+
+/// Returns the union of the sorted sets stored at the given keys.
+///
+/// Sources, weights and scores are handled as by `ZUNIONSTORE`. Members are
+/// ordered by ascending score. With the `WITHSCORES` option, each member is
+/// followed by its score.
+#[derive(Debug)]
+pub struct ZUnion {
+    /// The sorted sets to combine
+    sources: Sources,
+
+    /// Whether to reply with the scores
+    with_scores: bool,
+}
+
+/// Returns the intersection of the sorted sets stored at the given keys.
+///
+/// Sources, weights and scores are handled as by `ZINTERSTORE`, and the reply
+/// as by `ZUNION`.
+#[derive(Debug)]
+pub struct ZInter {
+    /// The sorted sets to combine
+    sources: Sources,
+
+    /// Whether to reply with the scores
+    with_scores: bool,
+}
+
+/// Returns the members of the sorted set stored at the first key that are in
+/// none of the sorted sets stored at the other keys, with their score in the
+/// first one.
+///
+/// Plain sets are accepted as sources, as by `ZUNIONSTORE`. The reply is
+/// handled as by `ZUNION`.
+#[derive(Debug)]
+pub struct ZDiff {
+    /// Names of the sorted sets, the first one being the set to subtract from
+    keys: Vec<String>,
+
+    /// Whether to reply with the scores
+    with_scores: bool,
+}
+
+/// Returns the number of members of the sorted set stored at key whose score
+/// is between a minimum and a maximum.
+///
+/// Bounds are inclusive unless prefixed with `(`, and `-inf` and `+inf` may be
+/// used as bounds. A missing key counts no members.
+#[derive(Debug)]
+pub struct ZCount {
+    /// Name of the sorted set
+    key: String,
+
+    /// Lowest score counted
+    min: ScoreBound,
+
+    /// Highest score counted
+    max: ScoreBound,
+}
+
+/// How the weighted scores of a member found in several sorted sets are
+/// combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Aggregate {
+    /// The scores are added up
+    #[default]
+    Sum,
+
+    /// The lowest score is kept
+    Min,
+
+    /// The highest score is kept
+    Max,
+}
+
+/// A bound of a score range.
+///
+/// Bounds are written as a score, or as a score prefixed with `(` to exclude
+/// it. Scores may be `-inf` and `+inf`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScoreBound {
+    /// Members with this score are in range
+    Inclusive(f64),
+
+    /// Members with this score are out of range
+    Exclusive(f64),
+}
+
+/// Keys of the sorted sets combined by a command, along with how they are
+/// combined.
+#[derive(Debug)]
+struct Sources {
+    /// Names of the sorted sets
+    keys: Vec<String>,
+
+    /// Multiplier of the scores of each sorted set, `1` for all of them if
+    /// `None`
+    weights: Option<Vec<f64>>,
+
+    /// How the scores of a member found in several sorted sets are combined
+    aggregate: Aggregate,
+}
+
+impl ZUnionStore {
+    /// Create a new `ZUnionStore` command which stores the union of the sorted
+    /// sets at `keys` into `destination`.
+    ///
+    /// `weights`, if given, holds one weight per key.
+    pub fn new(
+        destination: impl ToString,
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Aggregate,
+    ) -> ZUnionStore {
+        ZUnionStore {
+            destination: destination.to_string(),
+            sources: Sources::new(keys, weights, aggregate),
+        }
+    }
+
+    /// Get the destination key
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.sources.keys
+    }
+
+    /// Get the weights, if given
+    pub fn weights(&self) -> Option<&[f64]> {
+        self.sources.weights.as_deref()
+    }
+
+    /// Get the aggregate function
+    pub fn aggregate(&self) -> Aggregate {
+        self.sources.aggregate
+    }
+
+    /// Parse a `ZUnionStore` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZUNIONSTORE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZUnionStore` value on success. If the frame is malformed,
+    /// `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 4 entries.
+    ///
+    /// ```text
+    /// ZUNIONSTORE destination numkeys key [key ...] [WEIGHTS weight [weight ...]]
+    ///     [AGGREGATE SUM|MIN|MAX]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZUnionStore> {
+        let destination = parse.next_string()?;
+        let sources = Sources::parse(parse, None)?;
+
+        Ok(ZUnionStore {
+            destination,
+            sources,
+        })
+    }
+
+    /// Apply the `ZUnionStore` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = count_frame(store(db, self.destination, &self.sources, false));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZUnionStore` command to
+    /// send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zunionstore".as_bytes()));
+        frame.push_bulk(Bytes::from(self.destination.into_bytes()));
+        self.sources.push_frames(&mut frame);
+        frame
+    }
+}
+
+impl ZInterStore {
+    /// Create a new `ZInterStore` command which stores the intersection of the
+    /// sorted sets at `keys` into `destination`.
+    ///
+    /// `weights`, if given, holds one weight per key.
+    pub fn new(
+        destination: impl ToString,
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Aggregate,
+    ) -> ZInterStore {
+        ZInterStore {
+            destination: destination.to_string(),
+            sources: Sources::new(keys, weights, aggregate),
+        }
+    }
+
+    /// Get the destination key
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.sources.keys
+    }
+
+    /// Get the weights, if given
+    pub fn weights(&self) -> Option<&[f64]> {
+        self.sources.weights.as_deref()
+    }
+
+    /// Get the aggregate function
+    pub fn aggregate(&self) -> Aggregate {
+        self.sources.aggregate
+    }
+
+    /// Parse a `ZInterStore` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZINTERSTORE` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZInterStore` value on success. If the frame is malformed,
+    /// `Err` is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 4 entries.
+    ///
+    /// ```text
+    /// ZINTERSTORE destination numkeys key [key ...] [WEIGHTS weight [weight ...]]
+    ///     [AGGREGATE SUM|MIN|MAX]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZInterStore> {
+        let destination = parse.next_string()?;
+        let sources = Sources::parse(parse, None)?;
+
+        Ok(ZInterStore {
+            destination,
+            sources,
+        })
+    }
+
+    /// Apply the `ZInterStore` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = count_frame(store(db, self.destination, &self.sources, true));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZInterStore` command to
+    /// send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zinterstore".as_bytes()));
+        frame.push_bulk(Bytes::from(self.destination.into_bytes()));
+        self.sources.push_frames(&mut frame);
+        frame
+    }
+}
+
+impl ZUnion {
+    /// Create a new `ZUnion` command which returns the union of the sorted
+    /// sets at `keys`.
+    ///
+    /// `weights`, if given, holds one weight per key.
+    pub fn new(
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Aggregate,
+        with_scores: bool,
+    ) -> ZUnion {
+        ZUnion {
+            sources: Sources::new(keys, weights, aggregate),
+            with_scores,
+        }
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.sources.keys
+    }
+
+    /// Get the weights, if given
+    pub fn weights(&self) -> Option<&[f64]> {
+        self.sources.weights.as_deref()
+    }
+
+    /// Get the aggregate function
+    pub fn aggregate(&self) -> Aggregate {
+        self.sources.aggregate
+    }
+
+    /// Whether the scores are requested
+    pub fn with_scores(&self) -> bool {
+        self.with_scores
+    }
+
+    /// Parse a `ZUnion` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZUNION` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZUnion` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 3 entries.
+    ///
+    /// ```text
+    /// ZUNION numkeys key [key ...] [WEIGHTS weight [weight ...]]
+    ///     [AGGREGATE SUM|MIN|MAX] [WITHSCORES]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZUnion> {
+        let mut with_scores = false;
+        let sources = Sources::parse(parse, Some(&mut with_scores))?;
+
+        Ok(ZUnion {
+            sources,
+            with_scores,
+        })
+    }
+
+    /// Apply the `ZUnion` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let zset = db
+            .load_zsets(&self.sources.keys)
+            .map(|zsets| self.sources.combine(&zsets, false));
+        let response = scored_frame(zset, self.with_scores);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZUnion` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zunion".as_bytes()));
+        self.sources.push_frames(&mut frame);
+        if self.with_scores {
+            frame.push_bulk(Bytes::from("withscores".as_bytes()));
+        }
+        frame
+    }
+}
+
+impl ZInter {
+    /// Create a new `ZInter` command which returns the intersection of the sorted
+    /// sets at `keys`.
+    ///
+    /// `weights`, if given, holds one weight per key.
+    pub fn new(
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Aggregate,
+        with_scores: bool,
+    ) -> ZInter {
+        ZInter {
+            sources: Sources::new(keys, weights, aggregate),
+            with_scores,
+        }
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.sources.keys
+    }
+
+    /// Get the weights, if given
+    pub fn weights(&self) -> Option<&[f64]> {
+        self.sources.weights.as_deref()
+    }
+
+    /// Get the aggregate function
+    pub fn aggregate(&self) -> Aggregate {
+        self.sources.aggregate
+    }
+
+    /// Whether the scores are requested
+    pub fn with_scores(&self) -> bool {
+        self.with_scores
+    }
+
+    /// Parse a `ZInter` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZINTER` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZInter` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 3 entries.
+    ///
+    /// ```text
+    /// ZINTER numkeys key [key ...] [WEIGHTS weight [weight ...]]
+    ///     [AGGREGATE SUM|MIN|MAX] [WITHSCORES]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZInter> {
+        let mut with_scores = false;
+        let sources = Sources::parse(parse, Some(&mut with_scores))?;
+
+        Ok(ZInter {
+            sources,
+            with_scores,
+        })
+    }
+
+    /// Apply the `ZInter` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let zset = db
+            .load_zsets(&self.sources.keys)
+            .map(|zsets| self.sources.combine(&zsets, true));
+        let response = scored_frame(zset, self.with_scores);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZInter` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zinter".as_bytes()));
+        self.sources.push_frames(&mut frame);
+        if self.with_scores {
+            frame.push_bulk(Bytes::from("withscores".as_bytes()));
+        }
+        frame
+    }
+}
+
+impl ZDiff {
+    /// Create a new `ZDiff` command which subtracts the sorted sets at the
+    /// other `keys` from the sorted set at the first one.
+    pub fn new(keys: &[&str], with_scores: bool) -> ZDiff {
+        ZDiff {
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+            with_scores,
+        }
+    }
+
+    /// Get the keys
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Whether the scores are requested
+    pub fn with_scores(&self) -> bool {
+        self.with_scores
+    }
+
+    /// Parse a `ZDiff` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZDIFF` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZDiff` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least 3 entries.
+    ///
+    /// ```text
+    /// ZDIFF numkeys key [key ...] [WITHSCORES]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZDiff> {
+        use ParseError::EndOfStream;
+
+        let keys = parse_numkeys(parse)?;
+
+        let with_scores = match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "WITHSCORES" => true,
+            Ok(_) => return Err("syntax error".into()),
+            Err(EndOfStream) => false,
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(ZDiff { keys, with_scores })
+    }
+
+    /// Apply the `ZDiff` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let zset = db.load_zsets(&self.keys).map(|zsets| diff(&zsets));
+        let response = scored_frame(zset, self.with_scores);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZDiff` command to send to
+    /// the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zdiff".as_bytes()));
+        frame.push_bulk(Bytes::from(self.keys.len().to_string()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        if self.with_scores {
+            frame.push_bulk(Bytes::from("withscores".as_bytes()));
+        }
+        frame
+    }
+}
+
+impl ZCount {
+    /// Create a new `ZCount` command which counts the members of the sorted
+    /// set at `key` scoring between `min` and `max`.
+    pub fn new(key: impl ToString, min: ScoreBound, max: ScoreBound) -> ZCount {
+        ZCount {
+            key: key.to_string(),
+            min,
+            max,
+        }
+    }
+
+    /// Get the key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the lowest score counted
+    pub fn min(&self) -> ScoreBound {
+        self.min
+    }
+
+    /// Get the highest score counted
+    pub fn max(&self) -> ScoreBound {
+        self.max
+    }
+
+    /// Parse a `ZCount` instance from a received frame.
+    ///
+    /// The `Parse` argument provides a cursor-like API to read fields from the
+    /// `Frame`. At this point, the entire frame has already been received from
+    /// the socket.
+    ///
+    /// The `ZCOUNT` string has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZCount` value on success. If the frame is malformed, `Err`
+    /// is returned.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing four entries.
+    ///
+    /// ```text
+    /// ZCOUNT key min max
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZCount> {
+        let key = parse.next_string()?;
+        let min = parse.next_string()?.parse()?;
+        let max = parse.next_string()?.parse()?;
+
+        Ok(ZCount { key, min, max })
+    }
+
+    /// Apply the `ZCount` command to the specified `Db` instance.
+    ///
+    /// The response is written to `dst`. This is called by the server in order
+    /// to execute a received command.
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = count_frame(db.zcount(&self.key, self.min.into(), self.max.into()));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `ZCount` command to send
+    /// to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zcount".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.min.to_string()));
+        frame.push_bulk(Bytes::from(self.max.to_string()));
+        frame
+    }
+}
+
+impl Aggregate {
+    /// Returns the name of the function, as sent with the `AGGREGATE` option.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Aggregate::Sum => "sum",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+        }
+    }
+
+    /// Combine two scores of the same member.
+    fn apply(self, a: f64, b: f64) -> f64 {
+        let score = match self {
+            Aggregate::Sum => a + b,
+            Aggregate::Min => a.min(b),
+            Aggregate::Max => a.max(b),
+        };
+
+        // Adding opposite infinities gives NaN, which Redis stores as 0.
+        if score.is_nan() {
+            0.0
+        } else {
+            score
+        }
+    }
+}
+
+impl FromStr for Aggregate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Aggregate, String> {
+        match &s.to_lowercase()[..] {
+            "sum" => Ok(Aggregate::Sum),
+            "min" => Ok(Aggregate::Min),
+            "max" => Ok(Aggregate::Max),
+            _ => Err(format!(
+                "invalid aggregate '{}', expected sum, min or max",
+                s
+            )),
+        }
+    }
+}
+
+impl FromStr for ScoreBound {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ScoreBound, String> {
+        let (bound, score): (fn(f64) -> ScoreBound, _) = match s.strip_prefix('(') {
+            Some(score) => (ScoreBound::Exclusive, score),
+            None => (ScoreBound::Inclusive, s),
+        };
+
+        match score.parse::<f64>() {
+            Ok(score) if !score.is_nan() => Ok(bound(score)),
+            _ => Err("min or max is not a float".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ScoreBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScoreBound::Inclusive(score) => write!(f, "{}", score),
+            ScoreBound::Exclusive(score) => write!(f, "({}", score),
+        }
+    }
+}
+
+impl From<ScoreBound> for Bound<f64> {
+    fn from(bound: ScoreBound) -> Bound<f64> {
+        match bound {
+            ScoreBound::Inclusive(score) => Bound::Included(score),
+            ScoreBound::Exclusive(score) => Bound::Excluded(score),
+        }
+    }
+}
+
+impl Sources {
+    fn new(keys: &[&str], weights: Option<&[f64]>, aggregate: Aggregate) -> Sources {
+        Sources {
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+            weights: weights.map(<[f64]>::to_vec),
+            aggregate,
+        }
+    }
+
+    /// Parse the keys and options, starting with the number of keys.
+    ///
+    /// ```text
+    /// numkeys key [key ...] [WEIGHTS weight [weight ...]] [AGGREGATE SUM|MIN|MAX]
+    /// ```
+    ///
+    /// The `WITHSCORES` option is also accepted if `with_scores` is given, and
+    /// sets it.
+    fn parse(parse: &mut Parse, mut with_scores: Option<&mut bool>) -> crate::Result<Sources> {
+        let keys = parse_numkeys(parse)?;
+        let numkeys = keys.len();
+
+        let mut sources = Sources {
+            keys,
+            weights: None,
+            aggregate: Aggregate::default(),
+        };
+
+        loop {
+            let option = match parse.next_string() {
+                Ok(option) => option.to_uppercase(),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            match &option[..] {
+                "WEIGHTS" => {
+                    let mut weights = vec![];
+                    for _ in 0..numkeys {
+                        weights.push(parse_score(&parse.next_string()?)?);
+                    }
+                    sources.weights = Some(weights);
+                }
+                "AGGREGATE" => sources.aggregate = parse.next_string()?.parse()?,
+                "WITHSCORES" => match with_scores.as_deref_mut() {
+                    Some(with_scores) => *with_scores = true,
+                    None => return Err("syntax error".into()),
+                },
+                _ => return Err("syntax error".into()),
+            }
+        }
+
+        Ok(sources)
+    }
+
+    /// Encode the keys and options, as parsed by `parse`.
+    fn push_frames(self, frame: &mut Frame) {
+        frame.push_bulk(Bytes::from(self.keys.len().to_string()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+
+        if let Some(weights) = self.weights {
+            frame.push_bulk(Bytes::from("weights".as_bytes()));
+            for weight in weights {
+                frame.push_bulk(Bytes::from(weight.to_string()));
+            }
+        }
+
+        if self.aggregate != Aggregate::Sum {
+            frame.push_bulk(Bytes::from("aggregate".as_bytes()));
+            frame.push_bulk(Bytes::from(self.aggregate.as_str().as_bytes()));
+        }
+    }
+
+    /// Combine `zsets`, loaded from `keys`, into a new sorted set. With
+    /// `inter`, only the members of every sorted set are kept.
+    fn combine(&self, zsets: &[Arc<SortedSet>], inter: bool) -> SortedSet {
+        let weight = |i: usize| self.weights.as_ref().map_or(1.0, |weights| weights[i]);
+
+        // Multiplying an infinite score by a zero weight gives NaN, which
+        // Redis scores 0.
+        let weighted = |score: f64, i: usize| {
+            let score = score * weight(i);
+            if score.is_nan() {
+                0.0
+            } else {
+                score
+            }
+        };
+
+        let mut scores: HashMap<&Bytes, f64> = HashMap::new();
+
+        for (i, zset) in zsets.iter().enumerate() {
+            for (member, score) in zset.iter() {
+                let score = weighted(score, i);
+                scores
+                    .entry(member)
+                    .and_modify(|total| *total = self.aggregate.apply(*total, score))
+                    .or_insert(score);
+            }
+        }
+
+        let mut combined = SortedSet::default();
+
+        for (member, score) in scores {
+            if !inter || zsets.iter().all(|zset| zset.score(member).is_some()) {
+                combined.insert(member.clone(), score);
+            }
+        }
+
+        combined
+    }
+}
+
+/// Returns the members of the first of `zsets` that are in none of the others.
+fn diff(zsets: &[Arc<SortedSet>]) -> SortedSet {
+    let (first, others) = zsets.split_first().unwrap();
+    let mut diff = SortedSet::default();
+
+    for (member, score) in first.iter() {
+        if others.iter().all(|zset| zset.score(member).is_none()) {
+            diff.insert(member.clone(), score);
+        }
+    }
+
+    diff
+}
+
+/// Combine the sorted sets of `sources` and store the result at
+/// `destination`, returning its number of members.
+///
+/// The work is done while holding the `Db` lock, so that no other write
+/// happens between reading the sorted sets and storing the result.
+fn store(
+    db: &Db,
+    destination: String,
+    sources: &Sources,
+    inter: bool,
+) -> Result<u64, &'static str> {
+    db.check_write_key(&destination)?;
+    db.store_zset(destination, &sources.keys, |zsets| {
+        sources.combine(zsets, inter)
+    })
+}
+
+/// Parse a number of keys followed by the keys. At least one key is required.
+fn parse_numkeys(parse: &mut Parse) -> crate::Result<Vec<String>> {
+    let numkeys = parse.next_int()?;
+
+    if numkeys == 0 {
+        return Err("at least 1 input key is needed".into());
+    }
+
+    let mut keys = vec![];
+    for _ in 0..numkeys {
+        keys.push(parse.next_string()?);
+    }
+
+    Ok(keys)
+}
+
+/// Build the reply of a command returning the members of a sorted set, in
+/// order, each followed by its score if `with_scores` is set.
+fn scored_frame(zset: Result<SortedSet, &'static str>, with_scores: bool) -> Frame {
+    match zset {
+        Ok(zset) => {
+            let mut frame = Frame::array();
+            for (member, score) in zset.iter() {
+                frame.push_bulk(member.clone());
+                if with_scores {
+                    frame.push_bulk(format_score(score));
+                }
+            }
+            frame
+        }
+        Err(msg) => Frame::Error(msg.to_string()),
+    }
+}
+
+/// Build the reply of a command returning a number of members.
+fn count_frame(count: Result<u64, &'static str>) -> Frame {
+    match count {
+        Ok(count) => Frame::Integer(count as i64),
+        Err(msg) => Frame::Error(msg.to_string()),
+    }
+}
+
+/// Parse a score. `inf`, `+inf` and `-inf` are accepted, NaN is not.
+fn parse_score(src: &str) -> crate::Result<f64> {
+    match src.parse::<f64>() {
+        Ok(score) if !score.is_nan() => Ok(score),
+        _ => Err("value is not a valid float".into()),
+    }
+}
+
+/// Encode a score the way it is replied to clients.
+fn format_score(score: f64) -> Bytes {
+    Bytes::from(score.to_string())
+}
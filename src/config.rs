@@ -0,0 +1,226 @@
+//! Server configuration.
+//!
+//! Most of mini-redis is hard coded, but a few limits protect the server from
+//! misbehaving clients and are worth tuning. They are grouped in `Config`,
+//! which is passed to `server::run_with_config`.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Default maximum number of elements accepted in a single array frame.
+///
+/// This is the same default Redis uses.
+pub const DEFAULT_MAX_MULTIBULK_LEN: u64 = 1024 * 1024;
+
+/// Default maximum number of pub/sub channels keeping a backlog.
+pub const DEFAULT_PUBSUB_BACKLOG_CHANNELS: usize = 1024;
+
+/// Default number of logical databases.
+///
+/// This is the same default Redis uses.
+pub const DEFAULT_DATABASES: usize = 16;
+
+/// Tunable server settings.
+///
+/// `Config::default()` returns the settings used by `server::run`. Individual
+/// fields may be overridden using struct update syntax:
+///
+/// ```
+/// use mini_redis::Config;
+///
+/// let config = Config {
+///     max_multibulk_len: 1024,
+///     ..Config::default()
+/// };
+/// # drop(config);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum number of elements a client may send in a single array
+    /// (multibulk) frame.
+    ///
+    /// The length is validated as soon as the array header is read, before any
+    /// storage for the elements is allocated. Connections sending a larger
+    /// array receive a protocol error and are closed.
+    pub max_multibulk_len: u64,
+
+    /// Maximum number of channels a single connection may be subscribed to.
+    ///
+    /// Once the limit is reached, further `SUBSCRIBE` requests are answered
+    /// with an error frame. Existing subscriptions are not affected.
+    pub max_subscriptions: usize,
+
+    /// Serve plain `GET` and `SET` requests without going through the generic
+    /// command parsing path.
+    ///
+    /// Replies are identical either way. This only exists to allow comparing
+    /// the two paths.
+    pub command_fast_path: bool,
+
+    /// Reject empty key names on write commands.
+    ///
+    /// When set, commands which store a value, such as `SET`, `HSET`,
+    /// `INCR`, `LPUSH`, `SADD` or `ZADD`, are answered with
+    /// `ERR empty key is not allowed` if given an empty key, and nothing is
+    /// stored. Keys are otherwise binary safe either way.
+    pub strict_keys: bool,
+
+    /// Number of recent messages kept for each pub/sub channel.
+    ///
+    /// The messages are delivered to clients subscribing with
+    /// `SUBSCRIBE-REPLAY` before any new message. Messages are kept even if
+    /// nobody is subscribed to the channel. `0` disables the backlog, in which
+    /// case `SUBSCRIBE-REPLAY` behaves like `SUBSCRIBE`.
+    pub pubsub_backlog: usize,
+
+    /// Maximum number of channels keeping a backlog of `pubsub_backlog`
+    /// messages.
+    ///
+    /// Publishing on a new channel once the limit is reached drops the
+    /// backlog of the channel published on least recently.
+    pub pubsub_backlog_channels: usize,
+
+    /// Password clients must send with `AUTH` before issuing other commands.
+    ///
+    /// Until a connection has authenticated, every command other than `AUTH`
+    /// and `HELLO` is answered with `NOAUTH Authentication required`. `None`
+    /// disables authentication.
+    pub requirepass: Option<String>,
+
+    /// Only serve clients speaking RESP3.
+    ///
+    /// When set, every command other than `HELLO` is answered with
+    /// `NOPROTO unsupported protocol version` until the connection switched to
+    /// RESP3 with `HELLO 3`, and `HELLO 2` is rejected the same way. Clients
+    /// relying on RESP3 replies, such as maps, then never receive RESP2 ones.
+    pub protocol_required: bool,
+
+    /// Number of logical databases, selected with `SELECT`.
+    ///
+    /// Each database is an independent keyspace. Connections start on
+    /// database `0`. Must be at least `1`.
+    pub databases: usize,
+
+    /// Dump file holding the keys of every database, written by `SAVE`.
+    ///
+    /// The file is loaded when the server starts, if it exists, and written
+    /// again once the server shut down gracefully. If the file exists but
+    /// cannot be loaded, the server does not start, rather than overwrite it
+    /// on shutdown. `None` disables persistence.
+    pub dbfile: Option<PathBuf>,
+
+    /// Append-only file logging every command which modifies keys.
+    ///
+    /// When the server starts, the commands in the file are applied again to
+    /// rebuild the keyspace, instead of loading `dbfile`. Commands are then
+    /// appended to the file as they are applied. If the file exists but
+    /// cannot be replayed, the server does not start. `None` disables the
+    /// log.
+    pub appendonly: Option<PathBuf>,
+
+    /// When the append-only file is synced to disk.
+    pub appendfsync: AppendFsync,
+
+    /// Approximate number of bytes values may use, across all databases.
+    ///
+    /// Each key counts for the length of its name and value, plus a fixed
+    /// overhead. Each element of a hash, list, set or sorted set adds its
+    /// length plus a smaller overhead. When a write would exceed the limit,
+    /// `maxmemory_policy` decides what happens. `0` disables the limit.
+    pub maxmemory: usize,
+
+    /// What happens when a write would exceed `maxmemory`.
+    pub maxmemory_policy: MaxmemoryPolicy,
+}
+
+/// When the append-only file is synced to disk, see `Config::appendfsync`.
+///
+/// Commands are always written to the file before their reply is sent. This
+/// only sets when the operating system is asked to persist the written data,
+/// which bounds what is lost if the machine crashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppendFsync {
+    /// Sync after every command. Nothing replied to is lost, but each write
+    /// command waits for the disk.
+    Always,
+
+    /// Sync once per second, in the background. Up to a second of commands
+    /// may be lost.
+    EverySec,
+
+    /// Never sync, the operating system flushes the data when it sees fit.
+    No,
+}
+
+impl FromStr for AppendFsync {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AppendFsync, String> {
+        match &s.to_lowercase()[..] {
+            "always" => Ok(AppendFsync::Always),
+            "everysec" => Ok(AppendFsync::EverySec),
+            "no" => Ok(AppendFsync::No),
+            _ => Err(format!(
+                "invalid fsync policy '{}', expected always, everysec or no",
+                s
+            )),
+        }
+    }
+}
+
+/// What happens when a write would exceed `Config::maxmemory`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaxmemoryPolicy {
+    /// Reject the write with an `OOM` error.
+    NoEviction,
+
+    /// Evict the keys accessed least recently, of any type and database, until
+    /// the write fits. Reading or writing a key counts as an access.
+    AllKeysLru,
+}
+
+impl MaxmemoryPolicy {
+    /// Returns the name of the policy, as parsed by `from_str`.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            MaxmemoryPolicy::NoEviction => "noeviction",
+            MaxmemoryPolicy::AllKeysLru => "allkeys-lru",
+        }
+    }
+}
+
+impl FromStr for MaxmemoryPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MaxmemoryPolicy, String> {
+        match &s.to_lowercase()[..] {
+            "noeviction" => Ok(MaxmemoryPolicy::NoEviction),
+            "allkeys-lru" => Ok(MaxmemoryPolicy::AllKeysLru),
+            _ => Err(format!(
+                "invalid maxmemory policy '{}', expected noeviction or allkeys-lru",
+                s
+            )),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            max_multibulk_len: DEFAULT_MAX_MULTIBULK_LEN,
+            max_subscriptions: usize::MAX,
+            command_fast_path: true,
+            strict_keys: false,
+            pubsub_backlog: 0,
+            pubsub_backlog_channels: DEFAULT_PUBSUB_BACKLOG_CHANNELS,
+            requirepass: None,
+            protocol_required: false,
+            databases: DEFAULT_DATABASES,
+            dbfile: None,
+            appendonly: None,
+            appendfsync: AppendFsync::EverySec,
+            maxmemory: 0,
+            maxmemory_policy: MaxmemoryPolicy::NoEviction,
+        }
+    }
+}
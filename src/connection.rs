@@ -1,15 +1,17 @@
+use crate::cmd::{Transaction, WatchedKey};
 use crate::frame::{self, Frame};
 
 use bytes::{Buf, BytesMut};
+use std::fmt::Debug;
 use std::io::{self, Cursor};
-use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
-use tokio::net::TcpStream;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
 
 /// Send and receive `Frame` values from a remote peer.
 ///
 /// When implementing networking protocols, a message on that protocol is
 /// often composed of several smaller messages known as frames. The purpose of
-/// `Connection` is to read and write frames on the underlying `TcpStream`.
+/// `Connection` is to read and write frames on the underlying socket, such as
+/// a `TcpStream` or a `UnixStream`.
 ///
 /// To read frames, the `Connection` uses an internal buffer, which is filled
 /// up until there are enough bytes to create a full frame. Once this happens,
@@ -19,29 +21,186 @@ use tokio::net::TcpStream;
 /// The contents of the write buffer are then written to the socket.
 #[derive(Debug)]
 pub struct Connection {
-    // The `TcpStream`. It is decorated with a `BufWriter`, which provides write
+    // The socket. It is decorated with a `BufWriter`, which provides write
     // level buffering. The `BufWriter` implementation provided by Tokio is
-    // sufficient for our needs.
-    stream: BufWriter<TcpStream>,
+    // sufficient for our needs. The socket is boxed so that `Connection` is the
+    // same type whichever kind of socket backs it.
+    stream: BufWriter<Box<dyn Socket>>,
 
     // The buffer for reading frames.
     buffer: BytesMut,
+
+    // Maximum number of elements accepted in a received array frame.
+    max_array_len: u64,
+
+    // Protocol version used to encode frames, 2 for RESP2 or 3 for RESP3.
+    protocol: u8,
+
+    // Whether the peer may issue commands other than `AUTH` and `HELLO`.
+    authenticated: bool,
+
+    // Index of the database commands are applied to.
+    database: usize,
+
+    // Commands queued since `MULTI`, if a transaction is in progress.
+    transaction: Option<Transaction>,
+
+    // Keys watched with `WATCH`, checked by the next `EXEC`.
+    watched_keys: Vec<WatchedKey>,
+
+    // Frames written while `EXEC` collects the replies of queued commands.
+    captured: Option<Vec<Frame>>,
+
+    // Whether the last frame was read while requests were left in the socket,
+    // see `take_backpressured`.
+    backpressured: bool,
 }
 
 impl Connection {
     /// Create a new `Connection`, backed by `socket`. Read and write buffers
     /// are initialized.
-    pub fn new(socket: TcpStream) -> Connection {
+    ///
+    /// `socket` may be any bidirectional byte stream, such as a `TcpStream` or
+    /// a `UnixStream`.
+    pub fn new<S>(socket: S) -> Connection
+    where
+        S: AsyncRead + AsyncWrite + Debug + Send + Unpin + 'static,
+    {
         Connection {
-            stream: BufWriter::new(socket),
+            stream: BufWriter::new(Box::new(socket)),
             // Default to a 4KB read buffer. For the use case of mini redis,
             // this is fine. However, real applications will want to tune this
             // value to their specific use case. There is a high likelihood that
             // a larger read buffer will work better.
             buffer: BytesMut::with_capacity(4 * 1024),
+            // By default, no limit is placed on received arrays. The server
+            // lowers this to the configured `max_multibulk_len`.
+            max_array_len: u64::MAX,
+            // Connections start with RESP2, `HELLO 3` switches to RESP3.
+            protocol: 2,
+            // The server clears this when a password is required.
+            authenticated: true,
+            // Connections start on the first database, `SELECT` changes it.
+            database: 0,
+            transaction: None,
+            watched_keys: Vec::new(),
+            captured: None,
+            backpressured: false,
         }
     }
 
+    /// Set the maximum number of elements accepted in a received array frame.
+    ///
+    /// Reading a frame that declares a larger array fails with
+    /// `frame::Error::InvalidMultibulkLength`.
+    pub fn set_max_array_len(&mut self, len: u64) {
+        self.max_array_len = len;
+    }
+
+    /// Set the protocol version used to encode written frames, 2 for RESP2 or
+    /// 3 for RESP3.
+    ///
+    /// RESP3 frames written to a RESP2 connection are sent as their closest
+    /// RESP2 equivalent, so commands may reply with RESP3 frames regardless of
+    /// the protocol in use.
+    pub fn set_protocol(&mut self, protocol: u8) {
+        self.protocol = protocol;
+    }
+
+    /// Returns the protocol version used to encode written frames.
+    pub fn protocol(&self) -> u8 {
+        self.protocol
+    }
+
+    /// Set whether the peer has authenticated.
+    ///
+    /// Until it has, the server only accepts `AUTH` and `HELLO` on the
+    /// connection.
+    pub fn set_authenticated(&mut self, authenticated: bool) {
+        self.authenticated = authenticated;
+    }
+
+    /// Returns `true` if the peer has authenticated, or no password is
+    /// required.
+    pub fn is_authenticated(&self) -> bool {
+        self.authenticated
+    }
+
+    /// Set the index of the database the server applies the peer's commands
+    /// to.
+    pub fn select_database(&mut self, index: usize) {
+        self.database = index;
+    }
+
+    /// Returns the index of the selected database.
+    pub fn database(&self) -> usize {
+        self.database
+    }
+
+    /// Start a transaction. Commands are queued until it is taken back with
+    /// `take_transaction`.
+    pub(crate) fn begin_transaction(&mut self) {
+        self.transaction = Some(Transaction::default());
+    }
+
+    /// Returns `true` if a transaction is in progress.
+    pub(crate) fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    /// Returns the transaction in progress, if any.
+    pub(crate) fn transaction_mut(&mut self) -> Option<&mut Transaction> {
+        self.transaction.as_mut()
+    }
+
+    /// End the transaction in progress, if any, and return it.
+    pub(crate) fn take_transaction(&mut self) -> Option<Transaction> {
+        self.transaction.take()
+    }
+
+    /// Add a key to the keys watched by the peer.
+    pub(crate) fn watch_key(&mut self, key: WatchedKey) {
+        self.watched_keys.push(key);
+    }
+
+    /// Returns the keys watched by the peer.
+    pub(crate) fn watched_keys(&self) -> &[WatchedKey] {
+        &self.watched_keys
+    }
+
+    /// Clear the keys watched by the peer, and return them.
+    pub(crate) fn take_watched_keys(&mut self) -> Vec<WatchedKey> {
+        std::mem::take(&mut self.watched_keys)
+    }
+
+    /// Collect written frames instead of sending them, until `finish_capture`
+    /// is called.
+    pub(crate) fn start_capture(&mut self) {
+        self.captured = Some(Vec::new());
+    }
+
+    /// Stop collecting written frames, and return the ones collected since
+    /// `start_capture`.
+    pub(crate) fn finish_capture(&mut self) -> Vec<Frame> {
+        self.captured.take().unwrap_or_default()
+    }
+
+    /// Returns the last frame collected since `start_capture`, if any.
+    pub(crate) fn last_captured(&self) -> Option<&Frame> {
+        self.captured.as_ref()?.last()
+    }
+
+    /// Returns `true` if the frame last returned by `read_frame` was read
+    /// while the peer had more data waiting in the socket, and clears the
+    /// flag.
+    ///
+    /// The read buffer is only refilled once every frame it holds has been
+    /// read, so a peer pipelining requests faster than they are processed is
+    /// held back by TCP flow control. This reports each time it happens.
+    pub(crate) fn take_backpressured(&mut self) -> bool {
+        std::mem::take(&mut self.backpressured)
+    }
+
     /// Read a single `Frame` value from the underlying stream.
     ///
     /// The function waits until it has retrieved enough data to parse a frame.
@@ -50,14 +209,21 @@ impl Connection {
     ///
     /// # Returns
     ///
-    /// On success, the received frame is returned. If the `TcpStream`
+    /// On success, the received frame is returned. If the socket
     /// is closed in a way that doesn't break a frame in half, it returns
     /// `None`. Otherwise, an error is returned.
     pub async fn read_frame(&mut self) -> crate::Result<Option<Frame>> {
+        // Whether the last read filled the read buffer.
+        let mut full = false;
+
         loop {
             // Attempt to parse a frame from the buffered data. If enough data
             // has been buffered, the frame is returned.
             if let Some(frame) = self.parse_frame()? {
+                // A read limited by the size of the buffer may have left data
+                // in the socket. It stays there until the frames already
+                // buffered are read.
+                self.backpressured = full;
                 return Ok(Some(frame));
             }
 
@@ -74,9 +240,13 @@ impl Connection {
                 if self.buffer.is_empty() {
                     return Ok(None);
                 } else {
-                    return Err("connection reset by peer".into());
+                    let err =
+                        io::Error::new(io::ErrorKind::ConnectionReset, "connection reset by peer");
+                    return Err(err.into());
                 }
             }
+
+            full = self.buffer.len() == self.buffer.capacity();
         }
     }
 
@@ -98,7 +268,7 @@ impl Connection {
         // parse of the frame, and allows us to skip allocating data structures
         // to hold the frame data unless we know the full frame has been
         // received.
-        match Frame::check(&mut buf) {
+        match Frame::check_bounded(&mut buf, self.max_array_len) {
             Ok(_) => {
                 // The `check` function will have advanced the cursor until the
                 // end of the frame. Since the cursor had position set to zero
@@ -154,37 +324,37 @@ impl Connection {
     /// write stream. The data will be written to the buffer. Once the buffer is
     /// full, it is flushed to the underlying socket.
     pub async fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
-        // Arrays are encoded by encoding each entry. All other frame types are
-        // considered literals. For now, mini-redis is not able to encode
-        // recursive frame structures. See below for more details.
-        match frame {
-            Frame::Array(val) => {
-                // Encode the frame type prefix. For an array, it is `*`.
-                self.stream.write_u8(b'*').await?;
-
-                // Encode the length of the array.
-                self.write_decimal(val.len() as u64).await?;
-
-                //print val data
-                println!("val.len() = {}", val.len());
-                println!("val = {:?}", val);
-
-                // Iterate and encode each entry in the array.
-                for entry in &**val {
-                    self.write_value(entry).await?;
-                }
-            }
-            // The frame type is a literal. Encode the value directly.
-            _ => self.write_value(frame).await?,
-        }
+        self.feed_frame(frame).await?;
 
         // Ensure the encoded frame is written to the socket. The calls above
         // are to the buffered stream and writes. Calling `flush` writes the
         // remaining contents of the buffer to the socket.
+        self.flush().await
+    }
+
+    /// Write a single `Frame` value to the write buffer without flushing it.
+    ///
+    /// This allows encoding several frames before sending them with a single
+    /// call to `flush`. Data is only written to the socket once the buffer
+    /// fills up or `flush` is called.
+    ///
+    /// Between `start_capture` and `finish_capture`, the frame is collected
+    /// instead.
+    pub async fn feed_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        if let Some(captured) = &mut self.captured {
+            captured.push(frame.clone());
+            return Ok(());
+        }
+
+        self.write_value(frame).await
+    }
+
+    /// Flush any buffered frames to the socket.
+    pub async fn flush(&mut self) -> io::Result<()> {
         self.stream.flush().await
     }
 
-    /// Write a frame literal to the stream
+    /// Write a frame to the stream
     async fn write_value(&mut self, frame: &Frame) -> io::Result<()> {
         match frame {
             Frame::Simple(val) => {
@@ -201,29 +371,97 @@ impl Connection {
                 self.stream.write_u8(b':').await?;
                 self.write_decimal(*val).await?;
             }
+            // RESP3 has a single null type.
+            Frame::Null | Frame::NullArray if self.protocol >= 3 => {
+                self.stream.write_all(b"_\r\n").await?;
+            }
             Frame::Null => {
                 self.stream.write_all(b"$-1\r\n").await?;
             }
+            Frame::NullArray => {
+                self.stream.write_all(b"*-1\r\n").await?;
+            }
+            Frame::Double(val) => {
+                let val = if val.is_nan() {
+                    "nan".to_string()
+                } else {
+                    val.to_string()
+                };
+
+                if self.protocol >= 3 {
+                    self.stream.write_u8(b',').await?;
+                } else {
+                    self.stream.write_u8(b'$').await?;
+                    self.write_decimal(val.len() as i64).await?;
+                }
+
+                self.stream.write_all(val.as_bytes()).await?;
+                self.stream.write_all(b"\r\n").await?;
+            }
+            Frame::Boolean(val) if self.protocol >= 3 => {
+                let val: &[u8] = if *val { b"#t\r\n" } else { b"#f\r\n" };
+                self.stream.write_all(val).await?;
+            }
+            Frame::Boolean(val) => {
+                self.stream.write_u8(b':').await?;
+                self.write_decimal(*val as i64).await?;
+            }
+            Frame::Map(entries) => {
+                // RESP2 has no maps, keys and values are sent as a flat array.
+                if self.protocol >= 3 {
+                    self.stream.write_u8(b'%').await?;
+                    self.write_decimal(entries.len() as i64).await?;
+                } else {
+                    self.stream.write_u8(b'*').await?;
+                    self.write_decimal(entries.len() as i64 * 2).await?;
+                }
+
+                for (key, value) in entries {
+                    Box::pin(self.write_value(key)).await?;
+                    Box::pin(self.write_value(value)).await?;
+                }
+            }
+            Frame::Push(val) => {
+                if self.protocol >= 3 {
+                    self.stream.write_u8(b'>').await?;
+                } else {
+                    self.stream.write_u8(b'*').await?;
+                }
+
+                self.write_decimal(val.len() as i64).await?;
+
+                for entry in val {
+                    Box::pin(self.write_value(entry)).await?;
+                }
+            }
             Frame::Bulk(val) => {
                 let len = val.len();
-                println!("val write_all = {:?}", val);
                 self.stream.write_u8(b'$').await?;
-                self.write_decimal(len as u64).await?;
+                self.write_decimal(len as i64).await?;
                 self.stream.write_all(val).await?;
                 self.stream.write_all(b"\r\n").await?;
             }
-            // Encoding an `Array` from within a value cannot be done using a
-            // recursive strategy. In general, async fns do not support
-            // recursion. Mini-redis has not needed to encode nested arrays yet,
-            // so for now it is skipped.
-            Frame::Array(_val) => unreachable!(),
+            Frame::Array(val) => {
+                // Encode the frame type prefix. For an array, it is `*`.
+                self.stream.write_u8(b'*').await?;
+
+                // Encode the length of the array.
+                self.write_decimal(val.len() as i64).await?;
+
+                // Iterate and encode each entry in the array. Entries may be
+                // arrays themselves. A recursive async call must be boxed, as
+                // the future would otherwise contain itself.
+                for entry in &**val {
+                    Box::pin(self.write_value(entry)).await?;
+                }
+            }
         }
 
         Ok(())
     }
 
     /// Write a decimal frame to the stream
-    async fn write_decimal(&mut self, val: u64) -> io::Result<()> {
+    async fn write_decimal(&mut self, val: i64) -> io::Result<()> {
         use std::io::Write;
 
         // Convert the value to a string
@@ -238,3 +476,10 @@ impl Connection {
         Ok(())
     }
 }
+
+/// A socket a `Connection` can be backed by.
+///
+/// Implemented for every type meeting the bounds of `Connection::new`.
+trait Socket: AsyncRead + AsyncWrite + Debug + Send + Unpin {}
+
+impl<S: AsyncRead + AsyncWrite + Debug + Send + Unpin> Socket for S {}
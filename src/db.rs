@@ -1,9 +1,19 @@
-use tokio::sync::{broadcast, Notify};
+use crate::aof::{self, Aof};
+use crate::dump::{self, Value};
+use crate::storage::{self, Encoding, Entry, Lru, MemoryStorage, Storage, Tracked};
+use crate::{glob, Config, Frame, MaxmemoryPolicy};
+
+use tokio::sync::{broadcast, oneshot, Notify, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use tokio::time::{self, Duration, Instant};
 
 use bytes::Bytes;
-use std::collections::{BTreeSet, HashMap};
-use std::sync::{Arc, Mutex};
+use indexmap::IndexMap;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::ops::{Bound, ControlFlow, Deref, DerefMut, Range};
+use std::path::Path;
+use std::str::{self, Utf8Error};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::SystemTime;
 use tracing::debug;
 
 /// A wrapper around a `Db` instance. This exists to allow orderly cleanup
@@ -18,7 +28,7 @@ pub(crate) struct DbDropGuard {
 
 /// Server state shared across all connections.
 ///
-/// `Db` contains a `HashMap` storing the key/value data and all
+/// `Db` contains a `Storage` holding the key/value data and all
 /// `broadcast::Sender` values for active pub/sub channels.
 ///
 /// A `Db` instance is a handle to shared state. Cloning `Db` is shallow and
@@ -33,6 +43,9 @@ pub(crate) struct Db {
     /// Handle to shared state. The background task will also have an
     /// `Arc<Shared>`.
     shared: Arc<Shared>,
+
+    /// Index of the logical database this handle operates on.
+    index: usize,
 }
 
 #[derive(Debug)]
@@ -55,17 +68,86 @@ struct Shared {
     /// task waits on this to be notified, then checks for expired values or the
     /// shutdown signal.
     background_task: Notify,
+
+    /// Orders transactions with respect to other commands. Commands are
+    /// applied holding a read guard, while `EXEC` applies its queued commands
+    /// holding the write guard, so no other command is applied in between.
+    ///
+    /// This is a Tokio lock as the guards are held while replies are written.
+    exec_gate: RwLock<()>,
+
+    /// Held while writing a dump file, so that concurrent saves do not write
+    /// to the same temporary file.
+    saving: tokio::sync::Mutex<()>,
+
+    /// The append-only file logging write commands, attached once the server
+    /// replayed it. Unset when `Config::appendonly` is not.
+    aof: OnceLock<Aof>,
+
+    /// Server configuration. It does not change once the server is running,
+    /// so it is stored outside of the mutex.
+    config: Config,
+
+    /// Random 40 character hex id of this server, generated at startup.
+    node_id: String,
 }
 
 #[derive(Debug)]
 struct State {
-    /// The key-value data. We are not trying to do anything fancy so a
-    /// `std::collections::HashMap` works fine.
-    entries: HashMap<String, Entry>,
+    /// The logical databases, selected with `SELECT`. Each one is an
+    /// independent keyspace.
+    databases: Vec<Keyspace>,
 
     /// The pub/sub key-space. Redis uses a **separate** key space for key-value
     /// and pub/sub. `mini-redis` handles this by using a separate `HashMap`.
-    pub_sub: HashMap<String, broadcast::Sender<Bytes>>,
+    ///
+    /// Channel names are binary safe, so they are `Bytes` rather than
+    /// `String`. Channels are shared by all databases.
+    pub_sub: HashMap<Bytes, broadcast::Sender<Bytes>>,
+
+    /// Pattern subscriptions made with `PSUBSCRIBE`, keyed by glob-style
+    /// pattern. Messages are sent along with the channel they were published
+    /// on, which the pattern matched.
+    patterns: HashMap<Bytes, broadcast::Sender<(Bytes, Bytes)>>,
+
+    /// The most recent messages published on each channel, oldest first,
+    /// replayed by `SUBSCRIBE-REPLAY`. Each backlog holds at most
+    /// `Config::pubsub_backlog` messages. The map stays empty when the backlog
+    /// is disabled.
+    ///
+    /// Channels are ordered by their last publish, least recent first, so
+    /// that the oldest backlog is dropped once more than
+    /// `Config::pubsub_backlog_channels` channels have one.
+    backlogs: IndexMap<Bytes, VecDeque<Bytes>>,
+
+    /// True when the Db instance is shutting down. This happens when all `Db`
+    /// values drop. Setting this to `true` signals to the background task to
+    /// exit.
+    shutdown: bool,
+
+    /// Active client connections, keyed by connection id. The value is used to
+    /// ask the connection to close.
+    clients: HashMap<u64, Arc<Notify>>,
+
+    /// Id assigned to the next registered connection.
+    next_client_id: u64,
+
+    /// Per command call statistics, keyed by command name. Reported by
+    /// `INFO commandstats`.
+    command_stats: HashMap<String, CommandStats>,
+
+    /// Number of times a connection stopped reading pipelined requests from
+    /// its socket until the buffered ones were processed. Reported by
+    /// `INFO stats`.
+    backpressure_events: u64,
+}
+
+/// The data of a logical database.
+#[derive(Debug)]
+struct Keyspace {
+    /// The key-value data. `MemoryStorage` is used unless another `Storage`
+    /// is given to `Db::with_storage`.
+    entries: Tracked,
 
     /// Tracks key TTLs.
     ///
@@ -79,34 +161,188 @@ struct State {
     /// break these ties.
     expirations: BTreeSet<(Instant, String)>,
 
-    /// True when the Db instance is shutting down. This happens when all `Db`
-    /// values drop. Setting this to `true` signals to the background task to
-    /// exit.
-    shutdown: bool,
-
     /// key-value data for hashset type
     /// key: hashset name
     /// value: hashmap
-    hashes: HashMap<String, HashMap<String, Bytes>>,
+    ///
+    /// An `IndexMap` is used for the fields so they are returned in insertion
+    /// order, as Redis does, rather than in an arbitrary order that changes
+    /// from run to run.
+    ///
+    /// Like `entries`, the map is copied on write when shared with a
+    /// `Snapshot`. Each hash is behind its own `Arc`, so copying the map does
+    /// not copy the hashes, and only the hash being modified is copied.
+    hashes: Arc<HashMap<String, Arc<IndexMap<String, Bytes>>>>,
+
+    /// List values, copied on write like `hashes`.
+    lists: Arc<HashMap<String, Arc<VecDeque<Bytes>>>>,
 
+    /// Set values, copied on write like `hashes`.
+    sets: Arc<HashMap<String, Arc<HashSet<Bytes>>>>,
+
+    /// Sorted set values, copied on write like `hashes`.
+    zsets: Arc<HashMap<String, Arc<SortedSet>>>,
+
+    /// Instant at which the hashes, lists, sets and sorted sets expire, copied
+    /// on write like `hashes`. String values keep theirs in their `Entry`.
+    /// Both are tracked in `expirations` too.
+    collection_expirations: Arc<HashMap<String, Instant>>,
+
+    /// Approximate number of bytes used by the hashes, lists, sets and sorted
+    /// sets, as returned by `key_size`. `entries` tracks the strings.
+    collections_memory: usize,
+
+    /// Last access of each key, of any type. Every write to a key counts as
+    /// an access, see `touch`, as do reads through `access`.
+    ///
+    /// `None` unless keys are evicted least recently used first.
+    lru: Option<Lru>,
+
+    /// Clients blocked in `BLPOP`, per list key. Each queue holds waiter ids,
+    /// longest waiting first, so pushed elements are handed out in the order
+    /// the clients blocked.
+    ///
+    /// A client blocked on several keys is queued on each of them. Its id may
+    /// stay in the other queues once it is served, those are skipped.
+    blocked: HashMap<String, VecDeque<u64>>,
+
+    /// Channels used to hand an element to a blocked client, keyed by waiter
+    /// id. A waiter is removed from this map as soon as it is served or gives
+    /// up.
+    waiters: HashMap<u64, oneshot::Sender<(String, Bytes)>>,
+
+    /// Id assigned to the next blocked client.
+    next_waiter_id: u64,
+
+    /// Keys watched by connections with `WATCH`. Every write to a watched key
+    /// bumps its version, which lets `EXEC` tell the key was modified since
+    /// it was watched.
+    ///
+    /// Only watched keys are tracked, so writes to other keys only cost a
+    /// lookup in this map, and it does not grow with the keyspace.
+    watched: HashMap<String, KeyVersion>,
+
+    /// Writes made outside of the commands modifying the keys, which are to
+    /// be logged to the append-only file: elements popped by `BLPOP`,
+    /// elements put back when the client they were handed to went away, and
+    /// keys evicted to make room under `Config::maxmemory`.
+    ///
+    /// `None` unless an append-only file is attached.
+    implicit_writes: Option<Vec<Frame>>,
+}
+
+/// Version of a watched key, see `Keyspace::watched`.
+#[derive(Debug, Default)]
+struct KeyVersion {
+    /// Number of writes to the key since it started being watched.
+    version: u64,
+
+    /// Number of connections watching the key. It stops being tracked once
+    /// this drops to zero.
+    watchers: usize,
 }
 
-/// Entry in the key-value store
+/// Clients blocked in `BLPOP`, across all databases.
+#[derive(Debug, Default)]
+pub(crate) struct BlockedClients {
+    /// Number of clients blocked
+    pub(crate) clients: usize,
+
+    /// Keys clients are blocked on, as the index of their database, the key
+    /// and the number of clients blocked on it, sorted by database then key
+    pub(crate) keys: Vec<(usize, String, usize)>,
+}
+
+/// Accumulated statistics of a command.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct CommandStats {
+    /// Number of times the command was called
+    pub(crate) calls: u64,
+
+    /// Total time spent in the command, in microseconds
+    pub(crate) usec: u64,
+}
+
+/// A set of members ordered by score. Members with the same score are ordered
+/// lexicographically.
+///
+/// The members are indexed both by name, to look up their score, and by
+/// score, to walk them in order. Adding or updating a member is `O(log n)`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SortedSet {
+    /// Score of each member
+    scores: HashMap<Bytes, f64>,
+
+    /// Members ordered by score, then by name
+    ordered: BTreeSet<(Score, Bytes)>,
+}
+
+/// A sorted set score, totally ordered so it can be used in a `BTreeSet`.
+///
+/// NaN is never stored, commands reject it.
+#[derive(Debug, Clone, Copy)]
+struct Score(f64);
+
+/// A read-only view of the keyspace at the time `Db::snapshot` was called.
+///
+/// Taking a snapshot only increments reference counts. The snapshot is not
+/// affected by later writes to the `Db` and can be read without holding the
+/// `Db` lock, so long running tasks can iterate it without blocking
+/// connections.
 #[derive(Debug)]
-struct Entry {
-    /// Stored data
-    data: Bytes,
+pub(crate) struct Snapshot {
+    /// String values
+    entries: Box<dyn Storage>,
 
-    /// Instant at which the entry expires and should be removed from the
-    /// database.
-    expires_at: Option<Instant>,
+    /// Hash values
+    hashes: Arc<HashMap<String, Arc<IndexMap<String, Bytes>>>>,
+
+    /// List values
+    lists: Arc<HashMap<String, Arc<VecDeque<Bytes>>>>,
+
+    /// Set values
+    sets: Arc<HashMap<String, Arc<HashSet<Bytes>>>>,
+
+    /// Sorted set values
+    zsets: Arc<HashMap<String, Arc<SortedSet>>>,
+
+    /// Expirations of the values other than strings
+    collection_expirations: Arc<HashMap<String, Instant>>,
+}
+
+/// A client blocked in `Db::blpop`. Dropping it unregisters the client from
+/// the lists it is blocked on.
+struct Blocked<'a> {
+    /// The `Db` the client is blocked in
+    db: &'a Db,
+
+    /// Id of the waiter
+    id: u64,
+
+    /// Lists the client is blocked on
+    keys: &'a [String],
+
+    /// Receives the popped element when the client is served
+    rx: oneshot::Receiver<(String, Bytes)>,
+}
+
+/// The locked state, giving access to the keyspace of the database selected
+/// by a `Db` handle.
+struct KeyspaceGuard<'a> {
+    /// The locked shared state
+    state: MutexGuard<'a, State>,
+
+    /// Index of the selected database
+    index: usize,
 }
 
 impl DbDropGuard {
     /// Create a new `DbHolder`, wrapping a `Db` instance. When this is dropped
     /// the `Db`'s purge task will be shut down.
-    pub(crate) fn new() -> DbDropGuard {
-        DbDropGuard { db: Db::new() }
+    pub(crate) fn new(config: Config) -> DbDropGuard {
+        DbDropGuard {
+            db: Db::new(config),
+        }
     }
 
     /// Get the shared database. Internally, this is an
@@ -116,6 +352,32 @@ impl DbDropGuard {
     }
 }
 
+impl Drop for Blocked<'_> {
+    fn drop(&mut self) {
+        let mut state = self.db.keyspace();
+
+        state.waiters.remove(&self.id);
+
+        for key in self.keys {
+            if let Some(queue) = state.blocked.get_mut(key) {
+                queue.retain(|id| *id != self.id);
+
+                if queue.is_empty() {
+                    state.blocked.remove(key);
+                }
+            }
+        }
+
+        // An element handed over but never received, because the client went
+        // away, is put back at the head of its list.
+        if let Ok((key, value)) = self.rx.try_recv() {
+            state.push_front(&key, value);
+            state.touch(&key);
+            state.serve_blocked(&key);
+        }
+    }
+}
+
 impl Drop for DbDropGuard {
     fn drop(&mut self) {
         // Signal the 'Db' instance to shut down the task that purges expired keys
@@ -126,22 +388,209 @@ impl Drop for DbDropGuard {
 impl Db {
     /// Create a new, empty, `Db` instance. Allocates shared state and spawns a
     /// background task to manage key expiration.
-    pub(crate) fn new() -> Db {
+    pub(crate) fn new(config: Config) -> Db {
+        let storage = (0..config.databases)
+            .map(|_| Box::new(MemoryStorage::default()) as Box<dyn Storage>)
+            .collect();
+
+        Db::with_storage(config, storage)
+    }
+
+    /// Create a new `Db` instance storing the key/value data of each database
+    /// in the matching element of `storage`.
+    ///
+    /// `storage` may already contain keys. Those with an expiration are purged
+    /// once it is reached.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `storage` is empty.
+    pub(crate) fn with_storage(config: Config, storage: Vec<Box<dyn Storage>>) -> Db {
+        assert!(!storage.is_empty(), "at least one database is required");
+
+        let lru = config.maxmemory > 0 && config.maxmemory_policy == MaxmemoryPolicy::AllKeysLru;
+        let databases = storage
+            .into_iter()
+            .map(|entries| Keyspace::new(entries, lru))
+            .collect();
+
         let shared = Arc::new(Shared {
             state: Mutex::new(State {
-                entries: HashMap::new(),
+                databases,
                 pub_sub: HashMap::new(),
-                expirations: BTreeSet::new(),
+                patterns: HashMap::new(),
+                backlogs: IndexMap::new(),
                 shutdown: false,
-                hashes: HashMap::new(),
+                clients: HashMap::new(),
+                next_client_id: 0,
+                command_stats: HashMap::new(),
+                backpressure_events: 0,
             }),
             background_task: Notify::new(),
+            exec_gate: RwLock::new(()),
+            saving: tokio::sync::Mutex::new(()),
+            aof: OnceLock::new(),
+            config,
+            node_id: generate_node_id(),
         });
 
         // Start the background task.
         tokio::spawn(purge_expired_tasks(shared.clone()));
 
-        Db { shared }
+        Db { shared, index: 0 }
+    }
+
+    /// Returns a handle to the database at `index`, sharing the state of this
+    /// one.
+    ///
+    /// `index` must be lower than `Db::databases`.
+    pub(crate) fn select(&self, index: usize) -> Db {
+        assert!(index < self.databases());
+
+        Db {
+            shared: self.shared.clone(),
+            index,
+        }
+    }
+
+    /// Returns the index of the database this handle operates on.
+    pub(crate) fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the number of databases.
+    pub(crate) fn databases(&self) -> usize {
+        self.shared.state.lock().unwrap().databases.len()
+    }
+
+    /// Locks the shared state and returns the keyspace of the selected
+    /// database.
+    fn keyspace(&self) -> KeyspaceGuard<'_> {
+        KeyspaceGuard {
+            state: self.shared.state.lock().unwrap(),
+            index: self.index,
+        }
+    }
+
+    /// Returns the server configuration.
+    pub(crate) fn config(&self) -> &Config {
+        &self.shared.config
+    }
+
+    /// Waits for any running transaction to complete.
+    ///
+    /// No transaction starts until the returned guard is dropped.
+    pub(crate) async fn command_guard(&self) -> RwLockReadGuard<'_, ()> {
+        self.shared.exec_gate.read().await
+    }
+
+    /// Waits for the commands being applied to complete.
+    ///
+    /// No other command is applied until the returned guard is dropped.
+    pub(crate) async fn transaction_guard(&self) -> RwLockWriteGuard<'_, ()> {
+        self.shared.exec_gate.write().await
+    }
+
+    /// Returns the append-only file write commands are logged to, if any.
+    pub(crate) fn aof(&self) -> Option<&Aof> {
+        self.shared.aof.get()
+    }
+
+    /// Log write commands to `aof` from now on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an append-only file is already attached.
+    pub(crate) fn set_aof(&self, aof: Aof) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        for keyspace in &mut state.databases {
+            keyspace.implicit_writes = Some(vec![]);
+        }
+
+        self.shared
+            .aof
+            .set(aof)
+            .expect("append-only file already attached");
+    }
+
+    /// Returns the implicit writes to log to the append-only file, see
+    /// `Keyspace::implicit_writes`, along with the index of their database.
+    pub(crate) fn take_implicit_writes(&self) -> Vec<(usize, Frame)> {
+        let mut state = self.shared.state.lock().unwrap();
+        let mut writes = vec![];
+
+        for (index, keyspace) in state.databases.iter_mut().enumerate() {
+            if let Some(implicit_writes) = &mut keyspace.implicit_writes {
+                writes.extend(implicit_writes.drain(..).map(|frame| (index, frame)));
+            }
+        }
+
+        writes
+    }
+
+    /// Returns the id of this server, as reported by `CLUSTER MYID`.
+    pub(crate) fn node_id(&self) -> &str {
+        &self.shared.node_id
+    }
+
+    /// Checks that `key` may be used as the key of a write command.
+    ///
+    /// Empty keys are rejected when `Config::strict_keys` is set. On failure,
+    /// the error message to send to the client is returned.
+    pub(crate) fn check_write_key(&self, key: &str) -> Result<(), &'static str> {
+        if self.shared.config.strict_keys && key.is_empty() {
+            return Err("ERR empty key is not allowed");
+        }
+
+        Ok(())
+    }
+
+    /// Returns the approximate number of bytes used by the values, across all
+    /// databases, as counted for `Config::maxmemory`.
+    pub(crate) fn used_memory(&self) -> usize {
+        self.shared.state.lock().unwrap().used_memory()
+    }
+
+    /// Checks that a write to `keys`, freeing `freed` bytes and using `size`
+    /// more, does not exceed `Config::maxmemory`.
+    ///
+    /// Writes replacing values free the size of the values they replace, see
+    /// `Keyspace::key_size`. Writes adding to a hash, list, set or sorted set
+    /// free nothing. Under `MaxmemoryPolicy::AllKeysLru`, the least recently
+    /// used keys of any database and type, other than `keys`, are evicted
+    /// until the write fits. On failure, the error message to send to the
+    /// client is returned.
+    fn make_room(
+        &self,
+        state: &mut KeyspaceGuard<'_>,
+        keys: &[&str],
+        freed: usize,
+        size: usize,
+    ) -> Result<(), &'static str> {
+        let config = &self.shared.config;
+
+        // While the append-only file is replayed, keys are not evicted, the
+        // file holds the keys which were.
+        if config.maxmemory == 0 || (config.appendonly.is_some() && self.aof().is_none()) {
+            return Ok(());
+        }
+
+        loop {
+            if state.state.used_memory().saturating_sub(freed) + size <= config.maxmemory {
+                return Ok(());
+            }
+
+            let victim = match config.maxmemory_policy {
+                MaxmemoryPolicy::NoEviction => None,
+                MaxmemoryPolicy::AllKeysLru => state.state.least_recently_used(state.index, keys),
+            };
+
+            match victim {
+                Some((index, key)) => state.state.evict(index, &key),
+                None => return Err("OOM command not allowed when used memory > 'maxmemory'."),
+            }
+        }
     }
 
     /// Get the value associated with a key.
@@ -154,213 +603,2886 @@ impl Db {
         //
         // Because data is stored using `Bytes`, a clone here is a shallow
         // clone. Data is not copied.
-        let state = self.shared.state.lock().unwrap();
+        let mut state = self.keyspace();
+        state.access(key);
         state.entries.get(key).map(|entry| entry.data.clone())
     }
 
-    /// Set the value associated with a key along with an optional expiration
-    /// Duration.
+    /// Returns the name of the type of the value associated with a key, as
+    /// reported by `TYPE`.
     ///
-    /// If a value is already associated with the key, it is removed.
-    pub(crate) fn set(&self, key: String, value: Bytes, expire: Option<Duration>) {
-        let mut state = self.shared.state.lock().unwrap();
+    /// `"none"` is returned if there is no such key.
+    pub(crate) fn key_type(&self, key: &str) -> &'static str {
+        let state = self.keyspace();
+        state.key_type(key)
+    }
 
-        // If this `set` becomes the key that expires **next**, the background
-        // task needs to be notified so it can update its state.
-        //
-        // Whether or not the task needs to be notified is computed during the
-        // `set` routine.
-        let mut notify = false;
+    /// Returns `true` if a value of any type is associated with the key.
+    pub(crate) fn exists(&self, key: &str) -> bool {
+        let state = self.keyspace();
+        state.key_type(key) != "none"
+    }
 
-        let expires_at = expire.map(|duration| {
-            // `Instant` at which the key expires.
-            let when = Instant::now() + duration;
+    /// Returns the time left before the key expires.
+    ///
+    /// The outer `Option` is `None` if there is no such key, the inner one is
+    /// `None` if the key exists but does not expire.
+    pub(crate) fn ttl(&self, key: &str) -> Option<Option<Duration>> {
+        let state = self.keyspace();
 
-            // Only notify the worker task if the newly inserted expiration is the
-            // **next** key to evict. In this case, the worker needs to be woken up
-            // to update its state.
-            notify = state
-                .next_expiration()
-                .map(|expiration| expiration > when)
-                .unwrap_or(true);
+        if state.key_type(key) == "none" {
+            return None;
+        }
 
-            // Track the expiration.
-            state.expirations.insert((when, key.clone()));
-            when
-        });
+        let now = Instant::now();
+        let ttl = state
+            .expires_at(key)
+            .map(|when| when.saturating_duration_since(now));
+        Some(ttl)
+    }
 
-        // Insert the entry into the `HashMap`.
-        let prev = state.entries.insert(
-            key.clone(),
-            Entry {
-                data: value,
-                expires_at,
-            },
-        );
+    /// Make the key, of any type, expire after `duration`, replacing any
+    /// previous expiration.
+    ///
+    /// Returns `false` if there is no such key.
+    pub(crate) fn expire(&self, key: &str, duration: Duration) -> bool {
+        let mut state = self.keyspace();
 
-        // If there was a value previously associated with the key **and** it
-        // had an expiration time. The associated entry in the `expirations` map
-        // must also be removed. This avoids leaking data.
-        if let Some(prev) = prev {
-            if let Some(when) = prev.expires_at {
-                // clear expiration
-                state.expirations.remove(&(when, key));
-            }
+        if state.key_type(key) == "none" {
+            return false;
         }
 
-        // Release the mutex before notifying the background task. This helps
-        // reduce contention by avoiding the background task waking up only to
-        // be unable to acquire the mutex due to this function still holding it.
+        let when = Instant::now() + duration;
+
+        // As in `set`, the background task only needs to be notified if the
+        // key now expires before any other.
+        let notify = state
+            .next_expiration()
+            .map(|expiration| expiration > when)
+            .unwrap_or(true);
+
+        state.set_expires_at(key, Some(when));
+        state.touch(key);
+
         drop(state);
 
         if notify {
-            // Finally, only notify the background task if it needs to update
-            // its state to reflect a new expiration.
             self.shared.background_task.notify_one();
         }
+
+        true
     }
 
-    /// Returns a `Receiver` for the requested channel.
+    /// Remove the expiration of the key.
     ///
-    /// The returned `Receiver` is used to receive values broadcast by `PUBLISH`
-    /// commands.
-    pub(crate) fn subscribe(&self, key: String) -> broadcast::Receiver<Bytes> {
-        use std::collections::hash_map::Entry;
-
-        // Acquire the mutex
-        let mut state = self.shared.state.lock().unwrap();
+    /// Returns `true` if the key existed and had an expiration.
+    pub(crate) fn persist(&self, key: &str) -> bool {
+        let mut state = self.keyspace();
 
-        // If there is no entry for the requested channel, then create a new
-        // broadcast channel and associate it with the key. If one already
-        // exists, return an associated receiver.
-        match state.pub_sub.entry(key) {
-            Entry::Occupied(e) => e.get().subscribe(),
-            Entry::Vacant(e) => {
-                // No broadcast channel exists yet, so create one.
-                //
-                // The channel is created with a capacity of `1024` messages. A
-                // message is stored in the channel until **all** subscribers
-                // have seen it. This means that a slow subscriber could result
-                // in messages being held indefinitely.
-                //
-                // When the channel's capacity fills up, publishing will result
-                // in old messages being dropped. This prevents slow consumers
-                // from blocking the entire system.
-                let (tx, rx) = broadcast::channel(1024);
-                e.insert(tx);
-                rx
-            }
+        if state.expires_at(key).is_none() {
+            return false;
         }
-    }
 
-    /// Publish a message to the channel. Returns the number of subscribers
-    /// listening on the channel.
-    pub(crate) fn publish(&self, key: &str, value: Bytes) -> usize {
-        let state = self.shared.state.lock().unwrap();
+        state.set_expires_at(key, None);
+        state.touch(key);
 
-        state
-            .pub_sub
-            .get(key)
-            // On a successful message send on the broadcast channel, the number
-            // of subscribers is returned. An error indicates there are no
-            // receivers, in which case, `0` should be returned.
-            .map(|tx| tx.send(value).unwrap_or(0))
-            // If there is no entry for the channel key, then there are no
-            // subscribers. In this case, return `0`.
-            .unwrap_or(0)
+        // Removing an expiration never makes the background task wake up
+        // earlier, so there is no need to notify it.
+        true
     }
 
-    /// Signals the purge background task to shut down. This is called by the
-    /// `DbShutdown`s `Drop` implementation.
-    fn shutdown_purge_task(&self) {
-        // The background task must be signaled to shut down. This is done by
-        // setting `State::shutdown` to `true` and signalling the task.
-        let mut state = self.shared.state.lock().unwrap();
-        state.shutdown = true;
-
-        // Drop the lock before signalling the background task. This helps
-        // reduce lock contention by ensuring the background task doesn't
-        // wake up only to be unable to acquire the mutex.
-        drop(state);
-        self.shared.background_task.notify_one();
-    }
+    /// Returns the encoding of the value associated with a key, as reported
+    /// by `OBJECT ENCODING`, or `None` if there is no such key.
+    pub(crate) fn object_encoding(&self, key: &str) -> Option<&'static str> {
+        let state = self.keyspace();
 
-    /// hashset implementation
-    pub(crate) fn hset(&self, key: String, field: String, value: Bytes) -> bool {
-        let mut state = self.shared.state.lock().unwrap();
+        if let Some(entry) = state.entries.get(key) {
+            return Some(entry.encoding.as_str());
+        }
 
-        let hash = state.hashes.entry(key).or_insert_with(HashMap::new);
-        // This returns `None` if the field is new, otherwise returns the old value.
-        hash.insert(field, value).is_none()
+        match state.key_type(key) {
+            "hash" => Some("hashtable"),
+            "list" => Some("quicklist"),
+            "set" => Some("hashtable"),
+            "zset" => Some("skiplist"),
+            _ => None,
+        }
     }
 
+    /// Get a substring of the value associated with a key.
+    ///
+    /// `start` and `end` are inclusive. Negative offsets count back from the
+    /// end of the value, `-1` being the last element. Offsets out of range are
+    /// clamped, so a missing key or an empty range returns an empty value.
+    ///
+    /// By default, offsets are byte indices. When `chars` is `true`, they are
+    /// character indices over the value decoded as UTF-8, and the returned
+    /// bytes never split a multibyte character. An error is returned if the
+    /// value is not valid UTF-8.
+    pub(crate) fn getrange(
+        &self,
+        key: &str,
+        start: i64,
+        end: i64,
+        chars: bool,
+    ) -> Result<Bytes, Utf8Error> {
+        let value = match self.get(key) {
+            Some(value) => value,
+            None => return Ok(Bytes::new()),
+        };
+
+        if !chars {
+            return Ok(match normalize_range(start, end, value.len()) {
+                Some(range) => value.slice(range),
+                None => Bytes::new(),
+            });
+        }
+
+        let text = str::from_utf8(&value)?;
+
+        let range = match normalize_range(start, end, text.chars().count()) {
+            Some(range) => range,
+            None => return Ok(Bytes::new()),
+        };
+
+        // Map the character indices to byte offsets in the value.
+        let from = char_to_byte_offset(text, range.start);
+        let to = char_to_byte_offset(text, range.end);
+
+        Ok(value.slice(from..to))
+    }
+
+    /// Set the value associated with a key along with an optional expiration
+    /// Duration.
+    ///
+    /// If a value of any type is already associated with the key, it is
+    /// removed. On failure, the error message to send to the client is
+    /// returned.
+    pub(crate) fn set(
+        &self,
+        key: String,
+        value: Bytes,
+        expire: Option<Duration>,
+    ) -> Result<(), &'static str> {
+        let mut state = self.keyspace();
+        let freed = state.key_size(&key);
+        self.make_room(
+            &mut state,
+            &[&key],
+            freed,
+            storage::entry_size(&key, &value),
+        )?;
+        let notify = state.set(key, value, expire);
+
+        // Release the mutex before notifying the background task. This helps
+        // reduce contention by avoiding the background task waking up only to
+        // be unable to acquire the mutex due to this function still holding it.
+        drop(state);
+
+        if notify {
+            // Finally, only notify the background task if it needs to update
+            // its state to reflect a new expiration.
+            self.shared.background_task.notify_one();
+        }
+
+        Ok(())
+    }
+
+    /// Set the value associated with a key and return the value it replaces.
+    ///
+    /// As with `set` without an expiration, any previous expiration of the key
+    /// is removed. Both happen while holding the lock, so no other write can
+    /// happen in between. On failure, the error message to send to the client
+    /// is returned.
+    pub(crate) fn getset(&self, key: String, value: Bytes) -> Result<Option<Bytes>, &'static str> {
+        let mut state = self.keyspace();
+
+        if !matches!(state.key_type(&key), "string" | "none") {
+            return Err("WRONGTYPE Operation against a key holding the wrong kind of value");
+        }
+
+        let freed = state.key_size(&key);
+        self.make_room(
+            &mut state,
+            &[&key],
+            freed,
+            storage::entry_size(&key, &value),
+        )?;
+
+        let prev = state.entries.get(&key).map(|entry| entry.data.clone());
+
+        // Without an expiration, there is never a need to notify the
+        // background task.
+        state.set(key, value, None);
+
+        Ok(prev)
+    }
+
+    /// Set the value associated with a key, only if the key does not exist.
+    ///
+    /// Returns `true` if the value was set. Keys of any type count as
+    /// existing. A key which expired but was not purged yet by the background
+    /// task is treated as absent and is replaced, without an expiration. On
+    /// failure, the error message to send to the client is returned.
+    pub(crate) fn set_nx(&self, key: String, value: Bytes) -> Result<bool, &'static str> {
+        let mut state = self.keyspace();
+
+        if !matches!(state.key_type(&key), "string" | "none") {
+            return Ok(false);
+        }
+
+        if let Some(entry) = state.entries.get(&key) {
+            let expired = matches!(entry.expires_at, Some(when) if when <= Instant::now());
+
+            if !expired {
+                return Ok(false);
+            }
+        }
+
+        let freed = state.key_size(&key);
+        self.make_room(
+            &mut state,
+            &[&key],
+            freed,
+            storage::entry_size(&key, &value),
+        )?;
+
+        // Without an expiration, there is never a need to notify the
+        // background task. The expiration of a replaced key is removed by
+        // `set`.
+        state.set(key, value, None);
+
+        Ok(true)
+    }
+
+    /// Append `value` to the value associated with a key and return the new
+    /// length of the value.
+    ///
+    /// A missing key is set to `value`. The expiration of the key, if any, is
+    /// kept. On failure, the error message to send to the client is returned.
+    pub(crate) fn append(&self, key: String, value: Bytes) -> Result<u64, &'static str> {
+        let mut state = self.keyspace();
+
+        if !matches!(state.key_type(&key), "string" | "none") {
+            return Err("WRONGTYPE Operation against a key holding the wrong kind of value");
+        }
+
+        // The value grows by the appended bytes, or is created.
+        let size = match state.entries.get(&key) {
+            Some(_) => value.len(),
+            None => storage::entry_size(&key, &value),
+        };
+        self.make_room(&mut state, &[&key], 0, size)?;
+
+        let entry = match state.entries.remove(&key) {
+            Some(prev) => {
+                // Unless readers or snapshots share the stored `Bytes`, the
+                // conversion takes its buffer back without copying. The
+                // buffer grows geometrically, so appending repeatedly to the
+                // same key does not copy the whole value each time.
+                let mut data = Vec::from(prev.data);
+                data.extend_from_slice(&value);
+
+                // Redis stores values modified in place as raw strings.
+                Entry {
+                    data: Bytes::from(data),
+                    expires_at: prev.expires_at,
+                    encoding: Encoding::Raw,
+                }
+            }
+            None => Entry::new(value, None),
+        };
+
+        let len = entry.data.len() as u64;
+
+        // The expiration is unchanged, so `expirations` does not need to be
+        // updated.
+        state.touch(&key);
+        state.entries.set(key, entry);
+
+        Ok(len)
+    }
+
+    /// Set the values associated with several keys.
+    ///
+    /// All the values are set while holding the lock, so other clients see
+    /// either none or all of them. As with `set` without an expiration, any
+    /// previous expiration of the keys is removed. On failure, the error
+    /// message to send to the client is returned, and none of the values are
+    /// set.
+    pub(crate) fn mset(&self, pairs: Vec<(String, Bytes)>) -> Result<(), &'static str> {
+        let mut state = self.keyspace();
+
+        // A key set more than once ends up holding its last value, and only
+        // replaces its previous value once.
+        let mut last = HashMap::new();
+        for (key, value) in &pairs {
+            last.insert(&key[..], value);
+        }
+
+        let keys: Vec<&str> = last.keys().copied().collect();
+        let freed = keys.iter().map(|key| state.key_size(key)).sum();
+        let size = last
+            .iter()
+            .map(|(key, value)| storage::entry_size(key, value))
+            .sum();
+        self.make_room(&mut state, &keys, freed, size)?;
+
+        for (key, value) in pairs {
+            // Without an expiration, there is never a need to notify the
+            // background task.
+            state.set(key, value, None);
+        }
+
+        Ok(())
+    }
+
+    /// Create `count` string keys named `prefix:n`, holding `value:n`, for
+    /// `DEBUG POPULATE`.
+    ///
+    /// If `size` is given, the values are padded with zero bytes, or
+    /// truncated, to that length. Keys which already exist, of any type, are
+    /// left untouched. All the keys are created while holding the lock.
+    pub(crate) fn populate(&self, count: u64, prefix: &str, size: Option<usize>) {
+        let mut state = self.keyspace();
+
+        for n in 0..count {
+            let key = format!("{}:{}", prefix, n);
+
+            if state.key_type(&key) != "none" {
+                continue;
+            }
+
+            let mut value = format!("value:{}", n).into_bytes();
+            if let Some(size) = size {
+                value.resize(size, 0);
+            }
+
+            // Without an expiration, there is never a need to notify the
+            // background task.
+            state.set(key, Bytes::from(value), None);
+        }
+    }
+
+    /// Returns the number of keys, of any type.
+    ///
+    /// Keys which expired but were not purged yet are included. A key is held
+    /// by a single map, the one of its type, so the sizes of the maps add up.
+    pub(crate) fn len(&self) -> usize {
+        let state = self.keyspace();
+        state.entries.len()
+            + state.hashes.len()
+            + state.lists.len()
+            + state.sets.len()
+            + state.zsets.len()
+    }
+
+    /// Remove `keys`, of any type, and return the number of keys that
+    /// existed.
+    ///
+    /// A key listed more than once is only counted once.
+    pub(crate) fn del(&self, keys: &[String]) -> u64 {
+        let mut state = self.keyspace();
+        let state = &mut *state;
+
+        let removed = keys.iter().filter(|key| state.remove(key)).count();
+
+        // Removing expirations never makes the background task wake up
+        // earlier, so there is no need to notify it.
+        removed as u64
+    }
+
+    /// Rename `key` to `new_key`, whatever the type of its value, and return
+    /// `true` if it was renamed.
+    ///
+    /// The expiration of `key`, if any, moves with the value. A value already
+    /// stored at `new_key` is replaced, unless `nx` is set, in which case
+    /// nothing happens and `false` is returned. Renaming a key to itself
+    /// leaves it untouched.
+    ///
+    /// Returns an error if `key` does not exist.
+    pub(crate) fn rename(&self, key: &str, new_key: &str, nx: bool) -> Result<bool, &'static str> {
+        let mut state = self.keyspace();
+        let state = &mut *state;
+
+        if state.key_type(key) == "none" {
+            return Err("ERR no such key");
+        }
+
+        if nx && state.key_type(new_key) != "none" {
+            return Ok(false);
+        }
+
+        if key == new_key {
+            return Ok(true);
+        }
+
+        state.remove(new_key);
+        state.touch(key);
+        state.touch(new_key);
+
+        if let Some(lru) = &mut state.lru {
+            lru.forget(key);
+        }
+
+        // The size of a collection only depends on the length of its name.
+        if !matches!(state.key_type(key), "string" | "none") {
+            state.collections_memory += new_key.len();
+            state.collections_memory -= key.len();
+        }
+
+        if let Some(entry) = state.entries.remove(key) {
+            // The key expires at the same instant under its new name, so
+            // there is no need to notify the background task.
+            if let Some(when) = entry.expires_at {
+                state.expirations.remove(&(when, key.to_string()));
+                state.expirations.insert((when, new_key.to_string()));
+            }
+
+            state.entries.set(new_key.to_string(), entry);
+            return Ok(true);
+        }
+
+        // Likewise for the expiration of the other types.
+        if let Some(when) = state.collection_expirations.get(key).copied() {
+            let expirations = Arc::make_mut(&mut state.collection_expirations);
+            expirations.remove(key);
+            expirations.insert(new_key.to_string(), when);
+            state.expirations.remove(&(when, key.to_string()));
+            state.expirations.insert((when, new_key.to_string()));
+        }
+
+        if rename_in(&mut state.lists, key, new_key) {
+            // Clients blocked on the new name are served, as with a push.
+            state.serve_blocked(new_key);
+        } else {
+            // Each type is kept in its own map. Writing a value replaces a
+            // value of any other type, so only one of them holds the key.
+            rename_in(&mut state.hashes, key, new_key);
+            rename_in(&mut state.sets, key, new_key);
+            rename_in(&mut state.zsets, key, new_key);
+        }
+
+        Ok(true)
+    }
+
+    /// Add `delta` to the integer stored at `key` and return the new value.
+    ///
+    /// A missing key is treated as `0`. The expiration of the key, if any, is
+    /// kept. On failure, the value is left untouched and the error message to
+    /// send to the client is returned.
+    pub(crate) fn incr_by(&self, key: &str, delta: i64) -> Result<i64, &'static str> {
+        let mut state = self.keyspace();
+
+        if !matches!(state.key_type(key), "string" | "none") {
+            return Err("WRONGTYPE Operation against a key holding the wrong kind of value");
+        }
+
+        let (current, expires_at) = match state.entries.get(key) {
+            Some(entry) => {
+                let current = str::from_utf8(&entry.data)
+                    .ok()
+                    .and_then(|text| text.parse::<i64>().ok())
+                    .ok_or("ERR value is not an integer or out of range")?;
+
+                (current, entry.expires_at)
+            }
+            None => (0, None),
+        };
+
+        let value = current
+            .checked_add(delta)
+            .ok_or("ERR increment or decrement would overflow")?;
+
+        // The expiration is unchanged, so `expirations` does not need to be
+        // updated.
+        state.touch(key);
+        state.entries.set(
+            key.to_string(),
+            Entry::new(Bytes::from(value.to_string()), expires_at),
+        );
+
+        Ok(value)
+    }
+
+    /// Start watching `key`, and return its current version.
+    ///
+    /// The version changes whenever the key is written to, until every
+    /// watcher calls `unwatch`.
+    pub(crate) fn watch(&self, key: &str) -> u64 {
+        let mut state = self.keyspace();
+
+        let watched = state.watched.entry(key.to_string()).or_default();
+        watched.watchers += 1;
+        watched.version
+    }
+
+    /// Stop watching `key`, which must have been watched with `watch`.
+    pub(crate) fn unwatch(&self, key: &str) {
+        let mut state = self.keyspace();
+
+        if let Some(watched) = state.watched.get_mut(key) {
+            watched.watchers -= 1;
+
+            if watched.watchers == 0 {
+                state.watched.remove(key);
+            }
+        }
+    }
+
+    /// Returns the version of `key`, which must be watched.
+    pub(crate) fn key_version(&self, key: &str) -> u64 {
+        let state = self.keyspace();
+        state.watched.get(key).map_or(0, |watched| watched.version)
+    }
+
+    /// Returns a `Receiver` for the requested channel.
+    ///
+    /// The returned `Receiver` is used to receive values broadcast by `PUBLISH`
+    /// commands.
+    pub(crate) fn subscribe(&self, key: Bytes) -> broadcast::Receiver<Bytes> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.subscribe(key)
+    }
+
+    /// Returns a `Receiver` for the requested channel along with, oldest first,
+    /// up to `count` of the messages last published on it.
+    ///
+    /// Both are taken while holding the lock, so every message published on
+    /// the channel is either in the backlog or received by the `Receiver`, but
+    /// never both.
+    pub(crate) fn subscribe_replay(
+        &self,
+        key: Bytes,
+        count: usize,
+    ) -> (broadcast::Receiver<Bytes>, Vec<Bytes>) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let backlog = match state.backlogs.get(&key) {
+            Some(backlog) => {
+                let skip = backlog.len().saturating_sub(count);
+                backlog.iter().skip(skip).cloned().collect()
+            }
+            None => vec![],
+        };
+
+        (state.subscribe(key), backlog)
+    }
+
+    /// Returns a `Receiver` for the messages published on channels matching
+    /// `pattern`, along with the channel each was published on.
+    pub(crate) fn psubscribe(&self, pattern: Bytes) -> broadcast::Receiver<(Bytes, Bytes)> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        match state.patterns.get(&pattern) {
+            Some(tx) => tx.subscribe(),
+            None => {
+                // Same capacity as the channels created by `subscribe`.
+                let (tx, rx) = broadcast::channel(1024);
+                state.patterns.insert(pattern, tx);
+                rx
+            }
+        }
+    }
+
+    /// Returns the number of subscribers of each channel, leaving out the
+    /// channels without any.
+    pub(crate) fn channel_subscribers(&self) -> HashMap<Bytes, usize> {
+        let state = self.shared.state.lock().unwrap();
+
+        // Channels are not removed when their last subscriber leaves, so
+        // their sender remains with no receivers.
+        state
+            .pub_sub
+            .iter()
+            .map(|(channel, tx)| (channel.clone(), tx.receiver_count()))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    /// Publish a message to the channel. Returns the number of subscribers
+    /// listening on the channel, including the pattern subscribers whose
+    /// pattern matches it.
+    pub(crate) fn publish(&self, key: &Bytes, value: Bytes) -> usize {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let backlog_len = self.shared.config.pubsub_backlog;
+
+        if backlog_len > 0 {
+            // The backlog is taken out and inserted again to move the channel
+            // last.
+            let mut backlog = state.backlogs.shift_remove(key).unwrap_or_default();
+
+            if backlog.len() == backlog_len {
+                backlog.pop_front();
+            }
+
+            backlog.push_back(value.clone());
+            state.backlogs.insert(key.clone(), backlog);
+
+            if state.backlogs.len() > self.shared.config.pubsub_backlog_channels {
+                state.backlogs.shift_remove_index(0);
+            }
+        }
+
+        let mut receivers = state
+            .pub_sub
+            .get(key)
+            // On a successful message send on the broadcast channel, the number
+            // of subscribers is returned. An error indicates there are no
+            // receivers, in which case, `0` should be returned.
+            .map(|tx| tx.send(value.clone()).unwrap_or(0))
+            // If there is no entry for the channel key, then there are no
+            // subscribers. In this case, return `0`.
+            .unwrap_or(0);
+
+        // Every pattern is tested against the channel, so patterns left
+        // without subscribers are dropped along the way.
+        state.patterns.retain(|pattern, tx| {
+            if tx.receiver_count() == 0 {
+                return false;
+            }
+
+            if glob::matches(pattern, key) {
+                receivers += tx.send((key.clone(), value.clone())).unwrap_or(0);
+            }
+
+            true
+        });
+
+        receivers
+    }
+
+    /// Registers a new client connection.
+    ///
+    /// Returns the id assigned to the connection and the `Notify` used to ask
+    /// it to close. The connection must call `unregister_client` once it
+    /// terminates.
+    pub(crate) fn register_client(&self) -> (u64, Arc<Notify>) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let id = state.next_client_id;
+        state.next_client_id += 1;
+
+        let close = Arc::new(Notify::new());
+        state.clients.insert(id, close.clone());
+
+        (id, close)
+    }
+
+    /// Removes a client connection from the registry.
+    pub(crate) fn unregister_client(&self, id: u64) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.clients.remove(&id);
+    }
+
+    /// Asks every client connection except `id` to close. Returns the number
+    /// of connections notified.
+    ///
+    /// The connections are removed from the registry immediately, so calling
+    /// this again does not count them twice while they are closing.
+    pub(crate) fn close_clients_except(&self, id: u64) -> usize {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let ids: Vec<u64> = state
+            .clients
+            .keys()
+            .copied()
+            .filter(|other| *other != id)
+            .collect();
+
+        for other in &ids {
+            if let Some(close) = state.clients.remove(other) {
+                // `notify_one` stores a permit if the connection is not
+                // currently waiting, so the request is not lost.
+                close.notify_one();
+            }
+        }
+
+        ids.len()
+    }
+
+    /// Records a call to the command `name` which took `elapsed`.
+    pub(crate) fn record_command(&self, name: &str, elapsed: Duration) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        // Avoid allocating the name once the command has been seen.
+        let stats = match state.command_stats.get_mut(name) {
+            Some(stats) => stats,
+            None => state.command_stats.entry(name.to_string()).or_default(),
+        };
+
+        stats.calls += 1;
+        stats.usec += elapsed.as_micros() as u64;
+    }
+
+    /// Returns the statistics of every command called since the server started
+    /// or the statistics were last reset, sorted by command name.
+    pub(crate) fn command_stats(&self) -> Vec<(String, CommandStats)> {
+        let state = self.shared.state.lock().unwrap();
+
+        let mut stats: Vec<_> = state
+            .command_stats
+            .iter()
+            .map(|(name, stats)| (name.clone(), *stats))
+            .collect();
+        stats.sort_by(|a, b| a.0.cmp(&b.0));
+
+        stats
+    }
+
+    /// Records a connection holding back pipelined requests, see
+    /// `Connection::take_backpressured`.
+    pub(crate) fn record_backpressure(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.backpressure_events += 1;
+    }
+
+    /// Returns the clients currently blocked in `BLPOP` and the keys they are
+    /// blocked on.
+    pub(crate) fn blocked_clients(&self) -> BlockedClients {
+        let state = self.shared.state.lock().unwrap();
+        let mut blocked = BlockedClients::default();
+
+        for (index, keyspace) in state.databases.iter().enumerate() {
+            blocked.clients += keyspace.waiters.len();
+
+            let mut keys: Vec<_> = keyspace
+                .blocked
+                .iter()
+                .map(|(key, queue)| {
+                    // Clients served through another key stay queued on this
+                    // one, they are not counted.
+                    let clients = queue
+                        .iter()
+                        .filter(|id| keyspace.waiters.contains_key(id))
+                        .count();
+                    (index, key.clone(), clients)
+                })
+                .filter(|(_, _, clients)| *clients > 0)
+                .collect();
+            keys.sort_by(|a, b| a.1.cmp(&b.1));
+
+            blocked.keys.extend(keys);
+        }
+
+        blocked
+    }
+
+    /// Returns the number of backpressure events recorded since the server
+    /// started or the statistics were last reset.
+    pub(crate) fn backpressure_events(&self) -> u64 {
+        let state = self.shared.state.lock().unwrap();
+        state.backpressure_events
+    }
+
+    /// Clears the statistics of every command, and the number of
+    /// backpressure events.
+    pub(crate) fn reset_stats(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.command_stats.clear();
+        state.backpressure_events = 0;
+    }
+
+    /// Signals the purge background task to shut down. This is called by the
+    /// `DbShutdown`s `Drop` implementation.
+    fn shutdown_purge_task(&self) {
+        // The background task must be signaled to shut down. This is done by
+        // setting `State::shutdown` to `true` and signalling the task.
+        let mut state = self.shared.state.lock().unwrap();
+        state.shutdown = true;
+
+        // Drop the lock before signalling the background task. This helps
+        // reduce lock contention by ensuring the background task doesn't
+        // wake up only to be unable to acquire the mutex.
+        drop(state);
+        self.shared.background_task.notify_one();
+    }
+
+    /// hashset implementation
+    ///
+    /// On failure, the error message to send to the client is returned.
+    pub(crate) fn hset(
+        &self,
+        key: String,
+        field: String,
+        value: Bytes,
+    ) -> Result<bool, &'static str> {
+        let mut state = self.keyspace();
+
+        if !matches!(state.key_type(&key), "hash" | "none") {
+            return Err("WRONGTYPE Operation against a key holding the wrong kind of value");
+        }
+
+        let (freed, size) = hash_write_size(&state, &key, &field, &value);
+        self.make_room(&mut state, &[&key], freed, size)?;
+
+        state.touch(&key);
+        state.collections_memory = state.collections_memory - freed + size;
+
+        let hash = Arc::make_mut(&mut state.hashes).entry(key).or_default();
+        let hash = Arc::make_mut(hash);
+        // This returns `None` if the field is new, otherwise returns the old value.
+        Ok(hash.insert(field, value).is_none())
+    }
+
+
+    /// hget implementation
+    pub(crate) fn hget(&self, key: &str, field: &str) -> Option<Bytes> {
+        let mut state = self.keyspace();
+        state.access(key);
+        state.hashes.get(key).and_then(|hash| hash.get(field)).cloned()
+    }
+
+    /// hgetall implementation
+    ///
+    /// Fields are returned in the order they were first set.
+    pub fn hgetall(&self, key: &str) -> Option<IndexMap<String, Bytes>> {
+        let mut state = self.keyspace();
+        state.access(key);
+        state.hashes.get(key).map(|hash| (**hash).clone())
+    }
+
+    /// Remove `fields` from the hash stored at `key` and return the number of
+    /// fields that existed.
+    ///
+    /// A field listed more than once is only counted once. Hashes are never
+    /// empty, so the key is removed along with its last field.
+    pub(crate) fn hdel(&self, key: &str, fields: &[String]) -> u64 {
+        let mut state = self.keyspace();
+
+        // Avoid copying a shared map when there is nothing to remove.
+        let existing = match state.hashes.get(key) {
+            Some(hash) => fields.iter().any(|field| hash.contains_key(field)),
+            None => false,
+        };
+
+        if !existing {
+            return 0;
+        }
+
+        let hashes = Arc::make_mut(&mut state.hashes);
+        let hash = Arc::make_mut(hashes.get_mut(key).unwrap());
+
+        let mut removed = 0;
+        let mut freed = 0;
+
+        for field in fields {
+            // `shift_remove` keeps the remaining fields in insertion order.
+            if let Some(value) = hash.shift_remove(&field[..]) {
+                removed += 1;
+                freed += hash_field_size(field, &value);
+            }
+        }
+
+        let empty = hash.is_empty();
+        state.collections_memory -= freed;
+
+        if removed > 0 {
+            state.touch(key);
+        }
+
+        if empty {
+            state.remove(key);
+        }
+
+        removed
+    }
+
+    /// Returns `true` if the hash stored at `key` has `field`.
+    pub(crate) fn hexists(&self, key: &str, field: &str) -> bool {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        match state.hashes.get(key) {
+            Some(hash) => hash.contains_key(field),
+            None => false,
+        }
+    }
+
+    /// Returns the number of fields of the hash stored at `key`, `0` if there
+    /// is no such hash.
+    pub(crate) fn hlen(&self, key: &str) -> u64 {
+        let mut state = self.keyspace();
+        state.access(key);
+        state.hashes.get(key).map_or(0, |hash| hash.len() as u64)
+    }
+
+    /// Returns the fields of the hash stored at `key`.
+    ///
+    /// Fields are returned in the order they were first set, as with
+    /// `hgetall`.
+    pub(crate) fn hkeys(&self, key: &str) -> Vec<String> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        match state.hashes.get(key) {
+            Some(hash) => hash.keys().cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns the values of the hash stored at `key`.
+    ///
+    /// Values are returned in the order their fields were first set, as with
+    /// `hgetall`.
+    pub(crate) fn hvals(&self, key: &str) -> Vec<Bytes> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        match state.hashes.get(key) {
+            Some(hash) => hash.values().cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns the values of `fields` in the hash stored at `key`, in the same
+    /// order.
+    ///
+    /// Missing fields are `None`. If there is no such hash, every field is
+    /// missing.
+    pub(crate) fn hmget(&self, key: &str, fields: &[String]) -> Vec<Option<Bytes>> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        match state.hashes.get(key) {
+            Some(hash) => fields
+                .iter()
+                .map(|field| hash.get(field).cloned())
+                .collect(),
+            None => vec![None; fields.len()],
+        }
+    }
+
+    /// Increment the integer stored in `field` of the hash at `key` by `delta`
+    /// and return the new value.
+    ///
+    /// A missing field is treated as `0` and a missing hash is created. On
+    /// failure, the error message to send to the client is returned.
+    pub(crate) fn hincrby(
+        &self,
+        key: String,
+        field: String,
+        delta: i64,
+    ) -> Result<i64, &'static str> {
+        let mut state = self.keyspace();
+
+        if !matches!(state.key_type(&key), "hash" | "none") {
+            return Err("WRONGTYPE Operation against a key holding the wrong kind of value");
+        }
+
+        let current = match state.hashes.get(&key).and_then(|hash| hash.get(&field)) {
+            Some(value) => str::from_utf8(value)
+                .ok()
+                .and_then(|text| text.parse::<i64>().ok())
+                .ok_or("ERR hash value is not an integer")?,
+            None => 0,
+        };
+
+        let value = current
+            .checked_add(delta)
+            .ok_or("ERR increment or decrement would overflow")?;
+        let data = Bytes::from(value.to_string());
+
+        let (freed, size) = hash_write_size(&state, &key, &field, &data);
+        self.make_room(&mut state, &[&key], freed, size)?;
+
+        state.touch(&key);
+        state.collections_memory = state.collections_memory - freed + size;
+
+        let hash = Arc::make_mut(&mut state.hashes).entry(key).or_default();
+        Arc::make_mut(hash).insert(field, data);
+
+        Ok(value)
+    }
+
+    /// Set `field` in the hash stored at `key` to `value`, only if the field
+    /// does not exist.
+    ///
+    /// Returns `true` if the field was set. A missing hash is created. On
+    /// failure, the error message to send to the client is returned.
+    pub(crate) fn hsetnx(
+        &self,
+        key: String,
+        field: String,
+        value: Bytes,
+    ) -> Result<bool, &'static str> {
+        let mut state = self.keyspace();
+
+        if !matches!(state.key_type(&key), "hash" | "none") {
+            return Err("WRONGTYPE Operation against a key holding the wrong kind of value");
+        }
+
+        // Avoid copying a shared map when the field is not set.
+        if let Some(hash) = state.hashes.get(&key) {
+            if hash.contains_key(&field) {
+                return Ok(false);
+            }
+        }
+
+        let (freed, size) = hash_write_size(&state, &key, &field, &value);
+        self.make_room(&mut state, &[&key], freed, size)?;
+
+        state.touch(&key);
+        state.collections_memory = state.collections_memory - freed + size;
+
+        let hash = Arc::make_mut(&mut state.hashes).entry(key).or_default();
+        Arc::make_mut(hash).insert(field, value);
+
+        Ok(true)
+    }
+
+    /// Insert `values` at the head of the list stored at `key`, one after the
+    /// other, and return the length of the list.
+    ///
+    /// The last value ends up first. A missing list is created. On failure,
+    /// the error message to send to the client is returned.
+    pub(crate) fn lpush(&self, key: String, values: Vec<Bytes>) -> Result<u64, &'static str> {
+        self.push(key, values, true, None)
+    }
+
+    /// Insert `value` at the head of the list stored at `key`, then drop the
+    /// elements past the first `maxlen`, and return the length of the list.
+    ///
+    /// A missing list is created, and the key is removed if `maxlen` is `0`.
+    /// On failure, the error message to send to the client is returned.
+    pub(crate) fn lpush_capped(
+        &self,
+        key: String,
+        value: Bytes,
+        maxlen: usize,
+    ) -> Result<u64, &'static str> {
+        self.push(key, vec![value], true, Some(maxlen))
+    }
+
+    /// Insert `values` at the tail of the list stored at `key` and return the
+    /// length of the list.
+    ///
+    /// A missing list is created. On failure, the error message to send to
+    /// the client is returned.
+    pub(crate) fn rpush(&self, key: String, values: Vec<Bytes>) -> Result<u64, &'static str> {
+        self.push(key, values, false, None)
+    }
+
+    /// Push `values` at the head of the list if `front` is `true`, at its tail
+    /// otherwise, then keep its first `maxlen` elements if given.
+    fn push(
+        &self,
+        key: String,
+        values: Vec<Bytes>,
+        front: bool,
+        maxlen: Option<usize>,
+    ) -> Result<u64, &'static str> {
+        let mut state = self.keyspace();
+
+        if !matches!(state.key_type(&key), "list" | "none") {
+            return Err("WRONGTYPE Operation against a key holding the wrong kind of value");
+        }
+
+        let mut size = values
+            .iter()
+            .map(|value| storage::element_size(value))
+            .sum();
+        if !state.lists.contains_key(&key) {
+            size += storage::entry_size(&key, &[]);
+        }
+
+        self.make_room(&mut state, &[&key], 0, size)?;
+
+        state.touch(&key);
+        state.collections_memory += size;
+
+        let list = Arc::make_mut(&mut state.lists)
+            .entry(key.clone())
+            .or_default();
+        let list = Arc::make_mut(list);
+
+        for value in values {
+            if front {
+                list.push_front(value);
+            } else {
+                list.push_back(value);
+            }
+        }
+
+        let mut trimmed = 0;
+        if let Some(maxlen) = maxlen.filter(|&maxlen| maxlen < list.len()) {
+            trimmed = list
+                .drain(maxlen..)
+                .map(|value| storage::element_size(&value))
+                .sum();
+        }
+
+        let len = list.len() as u64;
+        state.collections_memory -= trimmed;
+
+        if len == 0 {
+            state.remove(&key);
+        }
+
+        // The pushed elements may be handed to clients blocked on the list.
+        state.serve_blocked(&key);
+
+        Ok(len)
+    }
+
+    /// Remove and return the first element of the first non-empty list among
+    /// `keys`, along with the key it was popped from.
+    ///
+    /// If all the lists are empty, waits until an element is pushed to one of
+    /// them, or until `timeout` elapses. `None` waits forever. When several
+    /// clients are blocked on a list, the one that has been waiting for the
+    /// longest is served first. `None` is returned on timeout. On failure, the
+    /// error message to send to the client is returned.
+    pub(crate) async fn blpop(
+        &self,
+        keys: &[String],
+        timeout: Option<Duration>,
+    ) -> Result<Option<(String, Bytes)>, &'static str> {
+        // An element popped right away is logged before another command is,
+        // as if `BLPOP` was a write command.
+        let mut log = match self.aof() {
+            Some(aof) => Some(
+                aof.lock(self)
+                    .await
+                    .map_err(|_| "ERR failed to write to the append-only file")?,
+            ),
+            None => None,
+        };
+
+        let waiter = 'waiter: {
+            let mut state = self.keyspace();
+
+            for key in keys {
+                match state.key_type(key) {
+                    "list" => {
+                        let value = state.pop_front(key).unwrap();
+                        break 'waiter ControlFlow::Break((key.clone(), value));
+                    }
+                    "none" => {}
+                    _ => {
+                        return Err(
+                            "WRONGTYPE Operation against a key holding the wrong kind of value",
+                        )
+                    }
+                }
+            }
+
+            // All the lists are empty, block on each of them.
+            let id = state.next_waiter_id;
+            state.next_waiter_id += 1;
+
+            let (tx, rx) = oneshot::channel();
+            state.waiters.insert(id, tx);
+
+            for key in keys {
+                state.blocked.entry(key.clone()).or_default().push_back(id);
+            }
+
+            ControlFlow::Continue((id, rx))
+        };
+
+        let (id, rx) = match waiter {
+            ControlFlow::Continue(waiter) => waiter,
+            ControlFlow::Break(popped) => {
+                if let Some(log) = &mut log {
+                    log.append_implicit_writes(self)
+                        .await
+                        .map_err(|_| "ERR failed to write to the append-only file")?;
+                }
+
+                return Ok(Some(popped));
+            }
+        };
+
+        // Elements handed to the client once blocked are logged along with
+        // the command pushing them.
+        drop(log);
+
+        // The guard unregisters the waiter once done, including when this
+        // future is dropped before completing.
+        let mut guard = Blocked {
+            db: self,
+            id,
+            keys,
+            rx,
+        };
+
+        let served = match timeout {
+            Some(timeout) => time::timeout(timeout, &mut guard.rx).await.ok(),
+            None => Some((&mut guard.rx).await),
+        };
+
+        if let Some(Ok(served)) = served {
+            return Ok(Some(served));
+        }
+
+        // Timed out. An element may have been handed over right before the
+        // waiter is unregistered, it is returned rather than lost.
+        let timed_out = {
+            let mut state = self.keyspace();
+            state.waiters.remove(&id).is_some()
+        };
+
+        if timed_out {
+            return Ok(None);
+        }
+
+        Ok(guard.rx.try_recv().ok())
+    }
+
+    /// Returns the elements of the list stored at `key` between `start` and
+    /// `stop`, both inclusive.
+    ///
+    /// Negative offsets count back from the end of the list, `-1` being the
+    /// last element. A missing list is empty. On failure, the error message
+    /// to send to the client is returned.
+    pub(crate) fn lrange(
+        &self,
+        key: &str,
+        start: i64,
+        stop: i64,
+    ) -> Result<Vec<Bytes>, &'static str> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        let list = match state.key_type(key) {
+            "list" => &state.lists[key],
+            "none" => return Ok(vec![]),
+            _ => return Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        };
+
+        Ok(match normalize_list_range(start, stop, list.len()) {
+            Some(range) => list.range(range).cloned().collect(),
+            None => vec![],
+        })
+    }
+
+    /// Returns the length of the list stored at `key`, `0` if there is no such
+    /// list.
+    ///
+    /// On failure, the error message to send to the client is returned.
+    pub(crate) fn llen(&self, key: &str) -> Result<u64, &'static str> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        match state.key_type(key) {
+            "list" => Ok(state.lists[key].len() as u64),
+            "none" => Ok(0),
+            _ => Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        }
+    }
+
+    /// Returns the element at `index` in the list stored at `key`.
+    ///
+    /// A negative index counts back from the end of the list, `-1` being the
+    /// last element. `None` is returned if the index is out of range or there
+    /// is no such list. On failure, the error message to send to the client is
+    /// returned.
+    pub(crate) fn lindex(&self, key: &str, index: i64) -> Result<Option<Bytes>, &'static str> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        let list = match state.key_type(key) {
+            "list" => &state.lists[key],
+            "none" => return Ok(None),
+            _ => return Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        };
+
+        let index = if index < 0 {
+            list.len() as i64 + index
+        } else {
+            index
+        };
+
+        if index < 0 {
+            return Ok(None);
+        }
+
+        Ok(list.get(index as usize).cloned())
+    }
+
+    /// Add `members` to the set stored at `key` and return how many were not
+    /// already members.
+    ///
+    /// A missing set is created. On failure, the error message to send to the
+    /// client is returned.
+    pub(crate) fn sadd(&self, key: String, members: Vec<Bytes>) -> Result<u64, &'static str> {
+        let mut state = self.keyspace();
+
+        if !matches!(state.key_type(&key), "set" | "none") {
+            return Err("WRONGTYPE Operation against a key holding the wrong kind of value");
+        }
+
+        let size = match state.sets.get(&key) {
+            Some(set) => added_size(members.iter().filter(|member| !set.contains(*member))),
+            None => storage::entry_size(&key, &[]) + added_size(members.iter()),
+        };
+        self.make_room(&mut state, &[&key], 0, size)?;
+
+        state.touch(&key);
+        state.collections_memory += size;
+
+        let set = Arc::make_mut(&mut state.sets).entry(key).or_default();
+        let set = Arc::make_mut(set);
+
+        let added = members
+            .into_iter()
+            .filter(|member| set.insert(member.clone()))
+            .count();
+
+        Ok(added as u64)
+    }
+
+    /// Returns the members of the set stored at `key`, in no particular order.
+    ///
+    /// A missing set is empty. On failure, the error message to send to the
+    /// client is returned.
+    pub(crate) fn smembers(&self, key: &str) -> Result<Vec<Bytes>, &'static str> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        match state.key_type(key) {
+            "set" => Ok(state.sets[key].iter().cloned().collect()),
+            "none" => Ok(vec![]),
+            _ => Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        }
+    }
+
+    /// Returns `true` if `member` is a member of the set stored at `key`.
+    ///
+    /// On failure, the error message to send to the client is returned.
+    pub(crate) fn sismember(&self, key: &str, member: &[u8]) -> Result<bool, &'static str> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        match state.key_type(key) {
+            "set" => Ok(state.sets[key].contains(member)),
+            "none" => Ok(false),
+            _ => Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        }
+    }
+
+    /// Remove `members` from the set stored at `key` and return how many were
+    /// members.
+    ///
+    /// The key is removed once the set is empty. On failure, the error message
+    /// to send to the client is returned.
+    pub(crate) fn srem(&self, key: &str, members: &[Bytes]) -> Result<u64, &'static str> {
+        let mut state = self.keyspace();
+
+        match state.key_type(key) {
+            "set" => {}
+            "none" => return Ok(0),
+            _ => return Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        }
+
+        let sets = Arc::make_mut(&mut state.sets);
+        let set = Arc::make_mut(sets.get_mut(key).unwrap());
+
+        let removed: Vec<_> = members
+            .iter()
+            .filter(|member| set.remove(*member))
+            .collect();
+        let empty = set.is_empty();
+        state.collections_memory -= added_size(removed.iter().copied());
+
+        if !removed.is_empty() {
+            state.touch(key);
+        }
+
+        if empty {
+            state.remove(key);
+        }
+
+        Ok(removed.len() as u64)
+    }
+
+    /// Returns the number of members of the set stored at `key`, `0` if there
+    /// is no such set.
+    ///
+    /// On failure, the error message to send to the client is returned.
+    pub(crate) fn scard(&self, key: &str) -> Result<u64, &'static str> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        match state.key_type(key) {
+            "set" => Ok(state.sets[key].len() as u64),
+            "none" => Ok(0),
+            _ => Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        }
+    }
+
+    /// Remove and return up to `count` members of the set stored at `key`,
+    /// picked at random.
+    ///
+    /// The order of the returned members is random too. The key is removed
+    /// once the set is empty. On failure, the error message to send to the
+    /// client is returned.
+    pub(crate) fn spop(&self, key: &str, count: usize) -> Result<Vec<Bytes>, &'static str> {
+        let mut state = self.keyspace();
+
+        match state.key_type(key) {
+            "set" if count > 0 => {}
+            "set" | "none" => return Ok(vec![]),
+            _ => return Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        }
+
+        let sets = Arc::make_mut(&mut state.sets);
+        let set = Arc::make_mut(sets.get_mut(key).unwrap());
+
+        let mut popped = Vec::with_capacity(count.min(set.len()));
+
+        // `HashSet` has no random access, so the members are walked to reach
+        // the picked one. This is linear, which is fine for the sizes
+        // `mini-redis` deals with.
+        while popped.len() < count && !set.is_empty() {
+            let member = set.iter().nth(random_index(set.len())).unwrap().clone();
+            set.remove(&member);
+            popped.push(member);
+        }
+
+        let empty = set.is_empty();
+        state.collections_memory -= added_size(popped.iter());
+        state.touch(key);
+
+        if empty {
+            state.remove(key);
+        }
+
+        Ok(popped)
+    }
+
+    /// Returns the sets stored at `keys`, in the same order. Missing keys are
+    /// returned as empty sets.
+    ///
+    /// The sets are shared with the `Db` rather than copied. On failure, the
+    /// error message to send to the client is returned.
+    pub(crate) fn load_sets(
+        &self,
+        keys: &[String],
+    ) -> Result<Vec<Arc<HashSet<Bytes>>>, &'static str> {
+        let mut state = self.keyspace();
+        state.load_sets(keys)
+    }
+
+    /// Store the members computed by `op` from the sets stored at `keys` into
+    /// `destination`, and return how many were stored.
+    ///
+    /// The sets are passed to `op` as by `load_sets`. Whatever `destination`
+    /// holds is replaced, along with its expiration, and it is removed if
+    /// there are no members. The sets are read and the result stored under
+    /// the same lock. On failure, the error message to send to the client is
+    /// returned.
+    pub(crate) fn store_set(
+        &self,
+        destination: String,
+        keys: &[String],
+        op: impl FnOnce(&[Arc<HashSet<Bytes>>]) -> Vec<Bytes>,
+    ) -> Result<u64, &'static str> {
+        let mut state = self.keyspace();
+
+        let sets = state.load_sets(keys)?;
+        let set: HashSet<Bytes> = op(&sets).into_iter().collect();
+
+        let freed = state.key_size(&destination);
+        let size = if set.is_empty() {
+            0
+        } else {
+            storage::entry_size(&destination, &[]) + added_size(set.iter())
+        };
+        self.make_room(&mut state, &[&destination], freed, size)?;
+
+        state.remove(&destination);
+
+        let stored = set.len();
+
+        if !set.is_empty() {
+            state.touch(&destination);
+            state.collections_memory += size;
+            Arc::make_mut(&mut state.sets).insert(destination, Arc::new(set));
+        }
+
+        Ok(stored as u64)
+    }
+
+    /// Returns the sorted sets stored at `keys`, in the same order. Plain sets
+    /// are returned as sorted sets whose members all score `1`, and missing
+    /// keys as empty sorted sets.
+    ///
+    /// Sorted sets are shared with the `Db` rather than copied. On failure,
+    /// the error message to send to the client is returned.
+    pub(crate) fn load_zsets(&self, keys: &[String]) -> Result<Vec<Arc<SortedSet>>, &'static str> {
+        let mut state = self.keyspace();
+        state.load_zsets(keys)
+    }
+
+    /// Store the sorted set computed by `op` from the sorted sets stored at
+    /// `keys` into `destination`, and return its number of members.
+    ///
+    /// The sorted sets are passed to `op` as by `load_zsets`. `destination` is
+    /// handled as by `store_set`. On failure, the error message to send to the client is
+    /// returned.
+    pub(crate) fn store_zset(
+        &self,
+        destination: String,
+        keys: &[String],
+        op: impl FnOnce(&[Arc<SortedSet>]) -> SortedSet,
+    ) -> Result<u64, &'static str> {
+        let mut state = self.keyspace();
+
+        let zsets = state.load_zsets(keys)?;
+        let zset = op(&zsets);
+
+        let freed = state.key_size(&destination);
+        let size = if zset.is_empty() {
+            0
+        } else {
+            storage::entry_size(&destination, &[]) + added_size(zset.iter().map(|(m, _)| m))
+        };
+        self.make_room(&mut state, &[&destination], freed, size)?;
+
+        state.remove(&destination);
+
+        let stored = zset.len();
+
+        if !zset.is_empty() {
+            state.touch(&destination);
+            state.collections_memory += size;
+            Arc::make_mut(&mut state.zsets).insert(destination, Arc::new(zset));
+        }
+
+        Ok(stored as u64)
+    }
+
+    /// Add `members` to the sorted set stored at `key`, each with its score,
+    /// and return how many were not already members.
+    ///
+    /// The score of existing members is updated. A missing sorted set is
+    /// created. On failure, the error message to send to the client is
+    /// returned.
+    pub(crate) fn zadd(
+        &self,
+        key: String,
+        members: Vec<(f64, Bytes)>,
+    ) -> Result<u64, &'static str> {
+        let mut state = self.keyspace();
+
+        if !matches!(state.key_type(&key), "zset" | "none") {
+            return Err("WRONGTYPE Operation against a key holding the wrong kind of value");
+        }
+
+        let members_added = members.iter().map(|(_, member)| member);
+        let size = match state.zsets.get(&key) {
+            Some(zset) => added_size(members_added.filter(|member| zset.score(member).is_none())),
+            None => storage::entry_size(&key, &[]) + added_size(members_added),
+        };
+        self.make_room(&mut state, &[&key], 0, size)?;
+
+        state.touch(&key);
+        state.collections_memory += size;
+
+        let zset = Arc::make_mut(&mut state.zsets).entry(key).or_default();
+        let zset = Arc::make_mut(zset);
+
+        let added = members
+            .into_iter()
+            .filter(|(score, member)| zset.insert(member.clone(), *score))
+            .count();
+
+        Ok(added as u64)
+    }
+
+    /// Returns the members of the sorted set stored at `key` between `start`
+    /// and `stop`, both inclusive, with their score.
+    ///
+    /// Members are ordered by ascending score. Offsets are handled as by
+    /// `lrange`. A missing sorted set is empty. On failure, the error message
+    /// to send to the client is returned.
+    pub(crate) fn zrange(
+        &self,
+        key: &str,
+        start: i64,
+        stop: i64,
+    ) -> Result<Vec<(Bytes, f64)>, &'static str> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        let zset = match state.key_type(key) {
+            "zset" => &state.zsets[key],
+            "none" => return Ok(vec![]),
+            _ => return Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        };
+
+        Ok(match normalize_list_range(start, stop, zset.len()) {
+            Some(range) => zset
+                .iter()
+                .skip(range.start)
+                .take(range.len())
+                .map(|(member, score)| (member.clone(), score))
+                .collect(),
+            None => vec![],
+        })
+    }
+
+    /// Returns the score of `member` in the sorted set stored at `key`.
+    ///
+    /// `None` is returned if it is not a member or there is no such sorted
+    /// set. On failure, the error message to send to the client is returned.
+    pub(crate) fn zscore(&self, key: &str, member: &[u8]) -> Result<Option<f64>, &'static str> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        match state.key_type(key) {
+            "zset" => Ok(state.zsets[key].score(member)),
+            "none" => Ok(None),
+            _ => Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        }
+    }
+
+    /// Returns the number of members of the sorted set stored at `key` whose
+    /// score is within `min` and `max`.
+    ///
+    /// A missing sorted set is empty. On failure, the error message to send to
+    /// the client is returned.
+    pub(crate) fn zcount(
+        &self,
+        key: &str,
+        min: Bound<f64>,
+        max: Bound<f64>,
+    ) -> Result<u64, &'static str> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        match state.key_type(key) {
+            "zset" => Ok(state.zsets[key].range_by_score(min, max).count() as u64),
+            "none" => Ok(0),
+            _ => Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        }
+    }
+
+    /// Add `increment` to the score of `member` in the sorted set stored at
+    /// `key` and return the new score.
+    ///
+    /// A missing member is added with `increment` as its score, and a missing
+    /// sorted set is created. The member is moved to its new position in the
+    /// ordering. On failure, the error message to send to the client is
+    /// returned.
+    pub(crate) fn zincrby(
+        &self,
+        key: String,
+        member: Bytes,
+        increment: f64,
+    ) -> Result<f64, &'static str> {
+        let mut state = self.keyspace();
+
+        let current = match state.key_type(&key) {
+            "zset" => state.zsets[&key].score(&member).unwrap_or(0.0),
+            "none" => 0.0,
+            _ => return Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        };
+
+        // Adding opposite infinities is the only way to get NaN here.
+        let score = current + increment;
+        if score.is_nan() {
+            return Err("ERR resulting score is not a number (NaN)");
+        }
+
+        let size = match state.zsets.get(&key) {
+            Some(zset) if zset.score(&member).is_some() => 0,
+            Some(_) => storage::element_size(&member),
+            None => storage::entry_size(&key, &[]) + storage::element_size(&member),
+        };
+        self.make_room(&mut state, &[&key], 0, size)?;
+
+        state.touch(&key);
+        state.collections_memory += size;
+
+        let zset = Arc::make_mut(&mut state.zsets).entry(key).or_default();
+        Arc::make_mut(zset).insert(member, score);
+
+        Ok(score)
+    }
+
+    /// Returns the rank of `member` in the sorted set stored at `key`, `0`
+    /// being the member with the lowest score.
+    ///
+    /// `None` is returned if it is not a member or there is no such sorted
+    /// set. On failure, the error message to send to the client is returned.
+    pub(crate) fn zrank(&self, key: &str, member: &[u8]) -> Result<Option<u64>, &'static str> {
+        let mut state = self.keyspace();
+        state.access(key);
+
+        match state.key_type(key) {
+            "zset" => Ok(state.zsets[key].rank(member).map(|rank| rank as u64)),
+            "none" => Ok(None),
+            _ => Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        }
+    }
+
+    /// Remove `members` from the sorted set stored at `key` and return how
+    /// many were members.
+    ///
+    /// The key is removed once the sorted set is empty. On failure, the error
+    /// message to send to the client is returned.
+    pub(crate) fn zrem(&self, key: &str, members: &[Bytes]) -> Result<u64, &'static str> {
+        let mut state = self.keyspace();
+
+        match state.key_type(key) {
+            "zset" => {}
+            "none" => return Ok(0),
+            _ => return Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        }
+
+        let zsets = Arc::make_mut(&mut state.zsets);
+        let zset = Arc::make_mut(zsets.get_mut(key).unwrap());
+
+        let removed: Vec<_> = members
+            .iter()
+            .filter(|member| zset.remove(member))
+            .collect();
+        let empty = zset.is_empty();
+        state.collections_memory -= added_size(removed.iter().copied());
+
+        if !removed.is_empty() {
+            state.touch(key);
+        }
+
+        if empty {
+            state.remove(key);
+        }
+
+        Ok(removed.len() as u64)
+    }
+
+    /// Remove the members of the sorted set stored at `key` ranked between
+    /// `start` and `stop`, both inclusive, and return how many were removed.
+    ///
+    /// Offsets are handled as by `zrange`. The key is removed once the sorted
+    /// set is empty. On failure, the error message to send to the client is
+    /// returned.
+    pub(crate) fn zremrangebyrank(
+        &self,
+        key: &str,
+        start: i64,
+        stop: i64,
+    ) -> Result<u64, &'static str> {
+        self.zrem_range(key, |zset| {
+            match normalize_list_range(start, stop, zset.len()) {
+                Some(range) => zset
+                    .iter()
+                    .skip(range.start)
+                    .take(range.len())
+                    .map(|(member, _)| member.clone())
+                    .collect(),
+                None => vec![],
+            }
+        })
+    }
+
+    /// Remove the members of the sorted set stored at `key` whose score is
+    /// within `min` and `max`, and return how many were removed.
+    ///
+    /// The key is removed once the sorted set is empty. On failure, the error
+    /// message to send to the client is returned.
+    pub(crate) fn zremrangebyscore(
+        &self,
+        key: &str,
+        min: Bound<f64>,
+        max: Bound<f64>,
+    ) -> Result<u64, &'static str> {
+        self.zrem_range(key, |zset| {
+            zset.range_by_score(min, max)
+                .map(|(member, _)| member.clone())
+                .collect()
+        })
+    }
+
+    /// Remove the members `range` selects from the sorted set stored at
+    /// `key`, and return how many were removed.
+    ///
+    /// The sorted set is only copied or touched if there is something to
+    /// remove. The key is removed once the sorted set is empty.
+    fn zrem_range(
+        &self,
+        key: &str,
+        range: impl FnOnce(&SortedSet) -> Vec<Bytes>,
+    ) -> Result<u64, &'static str> {
+        let mut state = self.keyspace();
+
+        let removed = match state.key_type(key) {
+            "zset" => range(&state.zsets[key]),
+            "none" => return Ok(0),
+            _ => return Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        };
+
+        if removed.is_empty() {
+            return Ok(0);
+        }
+
+        let zsets = Arc::make_mut(&mut state.zsets);
+        let zset = Arc::make_mut(zsets.get_mut(key).unwrap());
+
+        for member in &removed {
+            zset.remove(member);
+        }
+        let empty = zset.is_empty();
+        state.collections_memory -= added_size(removed.iter());
+
+        state.touch(key);
+
+        if empty {
+            state.remove(key);
+        }
+
+        Ok(removed.len() as u64)
+    }
+
+    /// Remove every key from the selected database.
+    ///
+    /// Clients blocked in `BLPOP` stay blocked.
+    pub(crate) fn flush(&self) {
+        self.keyspace().clear();
+    }
+
+    /// Remove every key from every database.
+    pub(crate) fn flush_all(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        for keyspace in &mut state.databases {
+            keyspace.clear();
+        }
+    }
+
+    /// Returns a consistent, read-only view of the keyspace.
+    ///
+    /// This is cheap, but the first write to the `Db` after a snapshot is
+    /// taken copies the top level map of the keyspace it modifies, as long as
+    /// the snapshot is alive. Snapshots should be dropped once they are no
+    /// longer needed.
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        self.keyspace().snapshot()
+    }
+
+    /// Write every database to the dump file at `path`, replacing it.
+    ///
+    /// The databases are snapshotted together, then written without holding
+    /// the lock. The file is written under a temporary name first, then
+    /// renamed, so that a failed save leaves the previous file intact.
+    pub(crate) async fn save_to(&self, path: &Path) -> crate::Result<()> {
+        let _saving = self.shared.saving.lock().await;
+
+        let snapshots: Vec<Snapshot> = {
+            let state = self.shared.state.lock().unwrap();
+            state.databases.iter().map(Keyspace::snapshot).collect()
+        };
+
+        let data = dump::encode(&snapshots);
+
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        tokio::fs::write(&tmp, data).await?;
+        tokio::fs::rename(&tmp, path).await?;
+
+        Ok(())
+    }
+
+    /// Replace the content of every database with the keys of the dump file
+    /// at `path`.
+    ///
+    /// Keys keep the expiration time they were saved with. Those which
+    /// expired in the meantime are not loaded. Nothing is modified if the file
+    /// cannot be read, or holds a database beyond `Db::databases`.
+    pub(crate) async fn load_from(&self, path: &Path) -> crate::Result<()> {
+        let data = tokio::fs::read(path).await?;
+        let databases = dump::decode(data.into())?;
+
+        let mut state = self.shared.state.lock().unwrap();
+
+        let configured = state.databases.len();
+        if let Some((index, _)) = databases.iter().find(|(index, _)| *index >= configured) {
+            return Err(format!(
+                "dump file holds database {}, but only {} are configured",
+                index, configured
+            )
+            .into());
+        }
+
+        for keyspace in &mut state.databases {
+            keyspace.clear();
+        }
+
+        let now = Instant::now();
+        let system_now = SystemTime::now();
+
+        for (index, keys) in databases {
+            for (key, value, expires_at) in keys {
+                state.databases[index].load(key, value, expires_at, now, system_now);
+            }
+        }
+
+        drop(state);
+
+        // Loaded keys may expire before any previous one.
+        self.shared.background_task.notify_one();
+
+        Ok(())
+    }
+}
+
+impl Snapshot {
+    /// Returns the number of keys, of any type.
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+            + self.hashes.len()
+            + self.lists.len()
+            + self.sets.len()
+            + self.zsets.len()
+    }
+
+    /// Returns `true` if there are no keys.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the names of the keys, of any type, in no particular
+    /// order.
+    ///
+    /// Keys which expired at `now` but were not purged yet are skipped.
+    pub(crate) fn keys(&self, now: Instant) -> impl Iterator<Item = &str> {
+        let strings = self
+            .entries
+            .iter()
+            .filter(move |(_, entry)| entry.expires_at.is_none_or(|when| when > now))
+            .map(|(key, _)| key);
+
+        let collections = self
+            .hashes
+            .keys()
+            .chain(self.lists.keys())
+            .chain(self.sets.keys())
+            .chain(self.zsets.keys())
+            .filter(move |key| {
+                let expires_at = self.collection_expirations.get(*key);
+                expires_at.is_none_or(|when| *when > now)
+            })
+            .map(String::as_str);
+
+        strings.chain(collections)
+    }
+
+    /// Returns the instant at which `key`, of any type, expires, or `None` if
+    /// it does not expire or there is no such key.
+    pub(crate) fn expires_at(&self, key: &str) -> Option<Instant> {
+        match self.entries.get(key) {
+            Some(entry) => entry.expires_at,
+            None => self.collection_expirations.get(key).copied(),
+        }
+    }
+
+    /// Iterates over the instants at which the keys of any type expire, for
+    /// those which do.
+    ///
+    /// Keys which expired but were not purged yet are included.
+    pub(crate) fn expirations(&self) -> impl Iterator<Item = Instant> + '_ {
+        let strings = self
+            .entries
+            .iter()
+            .filter_map(|(_, entry)| entry.expires_at);
+        strings.chain(self.collection_expirations.values().copied())
+    }
+
+    /// Iterates over the string values, yielding the key, the value and the
+    /// instant at which the key expires, if any.
+    ///
+    /// Keys which expired but were not purged yet are included.
+    pub(crate) fn strings(&self) -> impl Iterator<Item = (&str, &Bytes, Option<Instant>)> {
+        self.entries
+            .iter()
+            .map(|(key, entry)| (key, &entry.data, entry.expires_at))
+    }
+
+    /// Iterates over the hash values, yielding the key and the fields.
+    pub(crate) fn hashes(&self) -> impl Iterator<Item = (&str, &IndexMap<String, Bytes>)> {
+        self.hashes.iter().map(|(key, hash)| (&key[..], &**hash))
+    }
+
+    /// Iterates over the list values, yielding the key and the elements.
+    pub(crate) fn lists(&self) -> impl Iterator<Item = (&str, &VecDeque<Bytes>)> {
+        self.lists.iter().map(|(key, list)| (&key[..], &**list))
+    }
+
+    /// Iterates over the set values, yielding the key and the members.
+    pub(crate) fn sets(&self) -> impl Iterator<Item = (&str, &HashSet<Bytes>)> {
+        self.sets.iter().map(|(key, set)| (&key[..], &**set))
+    }
+
+    /// Iterates over the sorted set values, yielding the key and the members.
+    pub(crate) fn zsets(&self) -> impl Iterator<Item = (&str, &SortedSet)> {
+        self.zsets.iter().map(|(key, zset)| (&key[..], &**zset))
+    }
+}
+
+impl Shared {
+    /// Purge all expired keys and return the `Instant` at which the **next**
+    /// key will expire. The background task will sleep until this instant.
+    fn purge_expired_keys(&self) -> Option<Instant> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.shutdown {
+            // The database is shutting down. All handles to the shared state
+            // have dropped. The background task should exit.
+            return None;
+        }
+
+        // Find all keys scheduled to expire **before** now.
+        let now = Instant::now();
+
+        // The worker task waits until the earliest of the next expirations of
+        // each database.
+        state
+            .databases
+            .iter_mut()
+            .filter_map(|keyspace| keyspace.purge_expired_keys(now))
+            .min()
+    }
+
+    /// Returns `true` if the database is shutting down
+    ///
+    /// The `shutdown` flag is set when all `Db` values have dropped, indicating
+    /// that the shared state can no longer be accessed.
+    fn is_shutdown(&self) -> bool {
+        self.state.lock().unwrap().shutdown
+    }
+}
+
+impl Deref for KeyspaceGuard<'_> {
+    type Target = Keyspace;
+
+    fn deref(&self) -> &Keyspace {
+        &self.state.databases[self.index]
+    }
+}
+
+impl DerefMut for KeyspaceGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Keyspace {
+        &mut self.state.databases[self.index]
+    }
+}
+
+impl State {
+    /// Returns the approximate number of bytes used by the values of every
+    /// type, across all databases.
+    fn used_memory(&self) -> usize {
+        self.databases
+            .iter()
+            .map(|keyspace| keyspace.entries.used_memory() + keyspace.collections_memory)
+            .sum()
+    }
+
+    /// Returns the least recently used key of any database, along with the
+    /// index of its database, leaving out `keys` in the database at `index`.
+    fn least_recently_used(&self, index: usize, keys: &[&str]) -> Option<(usize, String)> {
+        self.databases
+            .iter()
+            .enumerate()
+            .filter_map(|(i, keyspace)| {
+                keyspace
+                    .lru
+                    .as_ref()?
+                    .least_recently_used()
+                    .find(|(_, key)| i != index || !keys.contains(key))
+                    .map(|(when, key)| (when, i, key))
+            })
+            .min()
+            .map(|(_, i, key)| (i, key.to_string()))
+    }
+
+    /// Remove `key` from the database at `index`, to make room for other
+    /// values.
+    fn evict(&mut self, index: usize, key: &str) {
+        let keyspace = &mut self.databases[index];
+        keyspace.remove(key);
+
+        if let Some(implicit_writes) = &mut keyspace.implicit_writes {
+            implicit_writes.push(aof::del_frame(key));
+        }
+
+        debug!(index, key, "evicted key");
+    }
+
+    /// Returns a `Receiver` for the requested channel, creating the broadcast
+    /// channel if needed.
+    fn subscribe(&mut self, key: Bytes) -> broadcast::Receiver<Bytes> {
+        use std::collections::hash_map::Entry;
+
+        // If there is no entry for the requested channel, then create a new
+        // broadcast channel and associate it with the key. If one already
+        // exists, return an associated receiver.
+        match self.pub_sub.entry(key) {
+            Entry::Occupied(e) => e.get().subscribe(),
+            Entry::Vacant(e) => {
+                // No broadcast channel exists yet, so create one.
+                //
+                // The channel is created with a capacity of `1024` messages. A
+                // message is stored in the channel until **all** subscribers
+                // have seen it. This means that a slow subscriber could result
+                // in messages being held indefinitely.
+                //
+                // When the channel's capacity fills up, publishing will result
+                // in old messages being dropped. This prevents slow consumers
+                // from blocking the entire system.
+                let (tx, rx) = broadcast::channel(1024);
+                e.insert(tx);
+                rx
+            }
+        }
+    }
+}
+
+impl Keyspace {
+    /// Create a keyspace holding the keys in `entries`, tracking accesses to
+    /// keys if `lru` is set. The existing keys are accessed now.
+    fn new(entries: Box<dyn Storage>, lru: bool) -> Keyspace {
+        let expirations = entries
+            .iter()
+            .filter_map(|(key, entry)| Some((entry.expires_at?, key.to_string())))
+            .collect();
+
+        let lru = lru.then(|| {
+            let mut lru = Lru::default();
+            let now = Instant::now();
+            for (key, _) in entries.iter() {
+                lru.access(key, now);
+            }
+            lru
+        });
+
+        Keyspace {
+            entries: Tracked::new(entries),
+            expirations,
+            hashes: Arc::new(HashMap::new()),
+            lists: Arc::new(HashMap::new()),
+            sets: Arc::new(HashMap::new()),
+            zsets: Arc::new(HashMap::new()),
+            collection_expirations: Arc::new(HashMap::new()),
+            collections_memory: 0,
+            lru,
+            blocked: HashMap::new(),
+            waiters: HashMap::new(),
+            next_waiter_id: 0,
+            watched: HashMap::new(),
+            implicit_writes: None,
+        }
+    }
+
+    /// Remove `key`, of any type, and return `true` if it existed.
+    fn remove(&mut self, key: &str) -> bool {
+        let mut existed = false;
+
+        if !matches!(self.key_type(key), "string" | "none") {
+            self.collections_memory -= self.key_size(key);
+        }
+
+        if let Some(prev) = self.entries.remove(key) {
+            // The expiration must be removed along with the entry, as in
+            // `set`.
+            if let Some(when) = prev.expires_at {
+                self.expirations.remove(&(when, key.to_string()));
+            }
+
+            existed = true;
+        }
+
+        // Only copy the hashes if a snapshot holds them and the key is
+        // actually a hash.
+        if self.hashes.contains_key(key) {
+            Arc::make_mut(&mut self.hashes).remove(key);
+            existed = true;
+        }
+
+        // Likewise for the other types.
+        if self.lists.contains_key(key) {
+            Arc::make_mut(&mut self.lists).remove(key);
+            existed = true;
+        }
+
+        if self.sets.contains_key(key) {
+            Arc::make_mut(&mut self.sets).remove(key);
+            existed = true;
+        }
+
+        if self.zsets.contains_key(key) {
+            Arc::make_mut(&mut self.zsets).remove(key);
+            existed = true;
+        }
+
+        if let Some(when) = self.collection_expirations.get(key).copied() {
+            Arc::make_mut(&mut self.collection_expirations).remove(key);
+            self.expirations.remove(&(when, key.to_string()));
+        }
+
+        if existed {
+            self.touch(key);
+        }
+
+        // Always forgotten, so that a key evicted although it no longer
+        // exists is not picked again.
+        if let Some(lru) = &mut self.lru {
+            lru.forget(key);
+        }
+
+        existed
+    }
+
+    /// Returns the approximate number of bytes used by the value of `key`, of
+    /// any type, or `0` if there is no such key.
+    ///
+    /// The elements of the collections are summed, so this is linear in their
+    /// size.
+    fn key_size(&self, key: &str) -> usize {
+        let key_size = storage::entry_size(key, &[]);
+
+        if let Some(entry) = self.entries.get(key) {
+            storage::entry_size(key, &entry.data)
+        } else if let Some(hash) = self.hashes.get(key) {
+            let fields = hash
+                .iter()
+                .map(|(field, value)| hash_field_size(field, value));
+            key_size + fields.sum::<usize>()
+        } else if let Some(list) = self.lists.get(key) {
+            let values = list.iter().map(|value| storage::element_size(value));
+            key_size + values.sum::<usize>()
+        } else if let Some(set) = self.sets.get(key) {
+            let members = set.iter().map(|member| storage::element_size(member));
+            key_size + members.sum::<usize>()
+        } else if let Some(zset) = self.zsets.get(key) {
+            let members = zset.iter().map(|(member, _)| storage::element_size(member));
+            key_size + members.sum::<usize>()
+        } else {
+            0
+        }
+    }
+
+    /// Returns the sets stored at `keys`, as `Db::load_sets` does.
+    fn load_sets(&mut self, keys: &[String]) -> Result<Vec<Arc<HashSet<Bytes>>>, &'static str> {
+        for key in keys {
+            self.access(key);
+        }
+
+        keys.iter()
+            .map(|key| match self.key_type(key) {
+                "set" => Ok(self.sets[key].clone()),
+                "none" => Ok(Arc::default()),
+                _ => Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+            })
+            .collect()
+    }
+
+    /// Returns the sorted sets stored at `keys`, as `Db::load_zsets` does.
+    fn load_zsets(&mut self, keys: &[String]) -> Result<Vec<Arc<SortedSet>>, &'static str> {
+        for key in keys {
+            self.access(key);
+        }
+
+        keys.iter()
+            .map(|key| match self.key_type(key) {
+                "zset" => Ok(self.zsets[key].clone()),
+                "set" => {
+                    let mut zset = SortedSet::default();
+                    for member in self.sets[key].iter() {
+                        zset.insert(member.clone(), 1.0);
+                    }
+                    Ok(Arc::new(zset))
+                }
+                "none" => Ok(Arc::default()),
+                _ => Err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+            })
+            .collect()
+    }
+
+    /// Returns the instant at which `key`, of any type, expires, or `None` if
+    /// it does not expire or there is no such key.
+    fn expires_at(&self, key: &str) -> Option<Instant> {
+        match self.entries.get(key) {
+            Some(entry) => entry.expires_at,
+            None => self.collection_expirations.get(key).copied(),
+        }
+    }
+
+    /// Make the existing `key`, of any type, expire at `when`, or never if
+    /// `when` is `None`, replacing any previous expiration.
+    ///
+    /// The background task is not notified.
+    fn set_expires_at(&mut self, key: &str, when: Option<Instant>) {
+        if let Some(prev) = self.expires_at(key) {
+            self.expirations.remove(&(prev, key.to_string()));
+        }
+
+        if let Some(when) = when {
+            self.expirations.insert((when, key.to_string()));
+        }
+
+        if let Some(entry) = self.entries.get(key) {
+            let mut entry = entry.clone();
+            entry.expires_at = when;
+            self.entries.set(key.to_string(), entry);
+            return;
+        }
+
+        let expirations = Arc::make_mut(&mut self.collection_expirations);
+        match when {
+            Some(when) => expirations.insert(key.to_string(), when),
+            None => expirations.remove(key),
+        };
+    }
+
+    /// Record a read of `key`, if it exists and accesses are tracked.
+    fn access(&mut self, key: &str) {
+        let exists = self.key_type(key) != "none";
+
+        if let Some(lru) = self.lru.as_mut().filter(|_| exists) {
+            lru.access(key, Instant::now());
+        }
+    }
+
+    /// Returns a read-only view of the keyspace, as described in
+    /// `Db::snapshot`.
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            entries: self.entries.snapshot(),
+            hashes: self.hashes.clone(),
+            lists: self.lists.clone(),
+            sets: self.sets.clone(),
+            zsets: self.zsets.clone(),
+            collection_expirations: self.collection_expirations.clone(),
+        }
+    }
+
+    /// Store `key`, read from a dump file, replacing any value it holds.
+    ///
+    /// The expiration is converted from wall clock time, `now` and
+    /// `system_now` being the same point in time. A key which already expired
+    /// is not stored.
+    fn load(
+        &mut self,
+        key: String,
+        value: Value,
+        expires_at: Option<SystemTime>,
+        now: Instant,
+        system_now: SystemTime,
+    ) {
+        self.remove(&key);
+
+        let expires_at = match expires_at {
+            Some(when) => match when.duration_since(system_now) {
+                Ok(ttl) => Some(now + ttl),
+                Err(_) => return,
+            },
+            None => None,
+        };
+
+        match value {
+            Value::String(data) => {
+                self.entries.set(key.clone(), Entry::new(data, None));
+            }
+            Value::Hash(fields) => {
+                Arc::make_mut(&mut self.hashes).insert(key.clone(), Arc::new(fields));
+            }
+            Value::List(list) => {
+                Arc::make_mut(&mut self.lists).insert(key.clone(), Arc::new(list));
+            }
+            Value::Set(set) => {
+                Arc::make_mut(&mut self.sets).insert(key.clone(), Arc::new(set));
+            }
+            Value::SortedSet(zset) => {
+                Arc::make_mut(&mut self.zsets).insert(key.clone(), Arc::new(zset));
+            }
+        }
 
-    /// hget implementation
-    pub(crate) fn hget(&self, key: &str, field: &str) -> Option<Bytes> {
-        let state = self.shared.state.lock().unwrap();
-        state.hashes.get(key).and_then(|hash| hash.get(field)).cloned()
-    }
+        if !matches!(self.key_type(&key), "string" | "none") {
+            self.collections_memory += self.key_size(&key);
+        }
 
-    /// hgetall implementation
-    pub fn hgetall(&self, key: &str) -> Option<HashMap<String, Bytes>> {
-        let state = self.shared.state.lock().unwrap();
-        state.hashes.get(key).cloned()
+        self.set_expires_at(&key, expires_at);
+        self.touch(&key);
     }
-}
 
-impl Shared {
-    /// Purge all expired keys and return the `Instant` at which the **next**
-    /// key will expire. The background task will sleep until this instant.
-    fn purge_expired_keys(&self) -> Option<Instant> {
-        let mut state = self.state.lock().unwrap();
+    /// Remove every key, along with their expirations.
+    ///
+    /// Snapshots keep the maps they share, so they are not copied.
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.expirations.clear();
+        self.hashes = Arc::default();
+        self.lists = Arc::default();
+        self.sets = Arc::default();
+        self.zsets = Arc::default();
+        self.collection_expirations = Arc::default();
+        self.collections_memory = 0;
 
-        if state.shutdown {
-            // The database is shutting down. All handles to the shared state
-            // have dropped. The background task should exit.
-            return None;
+        if let Some(lru) = &mut self.lru {
+            *lru = Lru::default();
         }
 
-        // This is needed to make the borrow checker happy. In short, `lock()`
-        // returns a `MutexGuard` and not a `&mut State`. The borrow checker is
-        // not able to see "through" the mutex guard and determine that it is
-        // safe to access both `state.expirations` and `state.entries` mutably,
-        // so we get a "real" mutable reference to `State` outside of the loop.
-        let state = &mut *state;
-
-        // Find all keys scheduled to expire **before** now.
-        let now = Instant::now();
+        // Every watched key is considered modified, whether it existed or not.
+        for watched in self.watched.values_mut() {
+            watched.version += 1;
+        }
+    }
 
-        while let Some(&(when, ref key)) = state.expirations.iter().next() {
+    /// Purge the keys which expired at `now` and return the `Instant` at which
+    /// the **next** key will expire.
+    fn purge_expired_keys(&mut self, now: Instant) -> Option<Instant> {
+        while let Some(&(when, ref key)) = self.expirations.iter().next() {
             if when > now {
                 // Done purging, `when` is the instant at which the next key
-                // expires. The worker task will wait until this instant.
+                // expires.
                 return Some(when);
             }
 
-            // The key expired, remove it
-            state.entries.remove(key);
-            state.expirations.remove(&(when, key.clone()));
+            // The key expired, remove it along with its expiration, whatever
+            // its type.
+            let key = key.clone();
+            self.remove(&key);
+            self.expirations.remove(&(when, key));
         }
 
         None
     }
 
-    /// Returns `true` if the database is shutting down
+    /// Returns the name of the type of the value associated with a key, as
+    /// described in `Db::key_type`.
     ///
-    /// The `shutdown` flag is set when all `Db` values have dropped, indicating
-    /// that the shared state can no longer be accessed.
-    fn is_shutdown(&self) -> bool {
-        self.state.lock().unwrap().shutdown
+    /// Each type is kept in its own map, so the type is the map holding the
+    /// key.
+    fn key_type(&self, key: &str) -> &'static str {
+        if self.entries.get(key).is_some() {
+            "string"
+        } else if self.hashes.contains_key(key) {
+            "hash"
+        } else if self.lists.contains_key(key) {
+            "list"
+        } else if self.sets.contains_key(key) {
+            "set"
+        } else if self.zsets.contains_key(key) {
+            "zset"
+        } else {
+            "none"
+        }
+    }
+
+    /// Remove and return the first element of the list stored at `key`. The
+    /// key is removed once the list is empty.
+    fn pop_front(&mut self, key: &str) -> Option<Bytes> {
+        let lists = Arc::make_mut(&mut self.lists);
+        let list = Arc::make_mut(lists.get_mut(key)?);
+        let value = list.pop_front();
+        let empty = list.is_empty();
+
+        if let Some(value) = &value {
+            self.collections_memory -= storage::element_size(value);
+        }
+
+        self.touch(key);
+
+        if empty {
+            self.remove(key);
+        }
+
+        if let Some(implicit_writes) = &mut self.implicit_writes {
+            implicit_writes.push(aof::pop_frame(key));
+        }
+
+        value
+    }
+
+    /// Put `value` back at the head of the list stored at `key`, after it was
+    /// popped for a client which went away.
+    fn push_front(&mut self, key: &str, value: Bytes) {
+        if let Some(implicit_writes) = &mut self.implicit_writes {
+            implicit_writes.push(aof::push_front_frame(key, value.clone()));
+        }
+
+        if !self.lists.contains_key(key) {
+            self.collections_memory += storage::entry_size(key, &[]);
+        }
+
+        self.collections_memory += storage::element_size(&value);
+        self.touch(key);
+
+        let lists = Arc::make_mut(&mut self.lists);
+        let list = lists.entry(key.to_string()).or_default();
+        Arc::make_mut(list).push_front(value);
+    }
+
+    /// Hand the elements of the list stored at `key` to the clients blocked on
+    /// it, longest waiting first, until either runs out.
+    fn serve_blocked(&mut self, key: &str) {
+        while self.lists.contains_key(key) {
+            let id = match self.blocked.get_mut(key).and_then(VecDeque::pop_front) {
+                Some(id) => id,
+                None => break,
+            };
+
+            // The client may have been served through another key already.
+            let tx = match self.waiters.remove(&id) {
+                Some(tx) => tx,
+                None => continue,
+            };
+
+            let value = self.pop_front(key).unwrap();
+
+            if let Err((_, value)) = tx.send((key.to_string(), value)) {
+                // The client is gone, the element goes back to the list.
+                self.push_front(key, value);
+            }
+        }
+
+        if self.blocked.get(key).is_some_and(VecDeque::is_empty) {
+            self.blocked.remove(key);
+        }
+    }
+
+    /// Set the value associated with a key, as described in `Db::set`.
+    ///
+    /// Returns `true` if the background task must be notified, because the key
+    /// is now the next one to expire.
+    fn set(&mut self, key: String, value: Bytes, expire: Option<Duration>) -> bool {
+        // If this `set` becomes the key that expires **next**, the background
+        // task needs to be notified so it can update its state.
+        //
+        // Whether or not the task needs to be notified is computed during the
+        // `set` routine.
+        let mut notify = false;
+
+        // A string replaces a value of any other type.
+        if !matches!(self.key_type(&key), "string" | "none") {
+            self.remove(&key);
+        }
+
+        let expires_at = expire.map(|duration| {
+            // `Instant` at which the key expires.
+            let when = Instant::now() + duration;
+
+            // Only notify the worker task if the newly inserted expiration is the
+            // **next** key to evict. In this case, the worker needs to be woken up
+            // to update its state.
+            notify = self
+                .next_expiration()
+                .map(|expiration| expiration > when)
+                .unwrap_or(true);
+
+            // Track the expiration.
+            self.expirations.insert((when, key.clone()));
+            when
+        });
+
+        self.touch(&key);
+
+        // Insert the entry into the `HashMap`.
+        let prev = self.entries.set(key.clone(), Entry::new(value, expires_at));
+
+        // If there was a value previously associated with the key **and** it
+        // had an expiration time. The associated entry in the `expirations` map
+        // must also be removed. This avoids leaking data.
+        if let Some(prev) = prev {
+            if let Some(when) = prev.expires_at {
+                // clear expiration
+                self.expirations.remove(&(when, key));
+            }
+        }
+
+        notify
     }
-}
 
-impl State {
     fn next_expiration(&self) -> Option<Instant> {
         self.expirations
             .iter()
             .next()
             .map(|expiration| expiration.0)
     }
+
+    /// Record a write to `key`, bumping its version if it is watched. The
+    /// write counts as an access to the key.
+    ///
+    /// Every method modifying a key calls this, whatever the type of its
+    /// value. Methods removing the key stop tracking its accesses afterwards.
+    fn touch(&mut self, key: &str) {
+        if let Some(watched) = self.watched.get_mut(key) {
+            watched.version += 1;
+        }
+
+        if let Some(lru) = &mut self.lru {
+            lru.access(key, Instant::now());
+        }
+    }
+}
+
+/// Returns the approximate number of bytes used by `field`, holding `value`,
+/// in a hash.
+fn hash_field_size(field: &str, value: &[u8]) -> usize {
+    storage::element_size(field.as_bytes()) + value.len()
+}
+
+/// Returns the number of bytes freed and used by setting `field` to `value` in
+/// the hash stored at `key`, creating it if needed.
+fn hash_write_size(keyspace: &Keyspace, key: &str, field: &str, value: &[u8]) -> (usize, usize) {
+    let size = hash_field_size(field, value);
+
+    match keyspace.hashes.get(key) {
+        Some(hash) => {
+            let prev = hash.get(field);
+            (prev.map_or(0, |prev| hash_field_size(field, prev)), size)
+        }
+        None => (0, storage::entry_size(key, &[]) + size),
+    }
+}
+
+/// Returns the approximate number of bytes used by `members`, distinct
+/// elements added to or removed from a list, set or sorted set.
+///
+/// A member listed more than once is only counted once.
+fn added_size<'a>(members: impl Iterator<Item = &'a Bytes>) -> usize {
+    let members: HashSet<_> = members.collect();
+    members
+        .into_iter()
+        .map(|member| storage::element_size(member))
+        .sum()
+}
+
+/// Moves the value stored at `key` in `map` to `new_key`, returning `false` if
+/// `map` has no such key.
+///
+/// The map is only copied if a snapshot holds it and the key is in it.
+fn rename_in<V: Clone>(map: &mut Arc<HashMap<String, V>>, key: &str, new_key: &str) -> bool {
+    if !map.contains_key(key) {
+        return false;
+    }
+
+    let map = Arc::make_mut(map);
+    let value = map.remove(key).unwrap();
+    map.insert(new_key.to_string(), value);
+
+    true
+}
+
+/// Generates a random 40 character hex id.
+///
+/// `RandomState` is seeded with random keys, so hashing the current time with
+/// it provides enough randomness to tell servers apart without an additional
+/// dependency.
+fn generate_node_id() -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::BuildHasher;
+    use std::time::SystemTime;
+
+    let now = SystemTime::now();
+    let state = RandomState::new();
+
+    let mut id = String::with_capacity(48);
+    for i in 0..3u8 {
+        id.push_str(&format!("{:016x}", state.hash_one((now, i))));
+    }
+
+    id.truncate(40);
+    id
+}
+
+impl SortedSet {
+    /// Add `member` with `score`, or update its score if it is already a
+    /// member. Returns `true` if `member` was added.
+    pub(crate) fn insert(&mut self, member: Bytes, score: f64) -> bool {
+        let added = match self.scores.insert(member.clone(), score) {
+            Some(prev) => {
+                self.ordered.remove(&(Score(prev), member.clone()));
+                false
+            }
+            None => true,
+        };
+
+        self.ordered.insert((Score(score), member));
+        added
+    }
+
+    /// Remove `member`. Returns `true` if it was a member.
+    fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove_entry(member) {
+            Some((member, score)) => {
+                self.ordered.remove(&(Score(score), member));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the position of `member` in the ordering, if it is a member.
+    ///
+    /// This walks the members ranked before it, so it is linear in the rank.
+    fn rank(&self, member: &[u8]) -> Option<usize> {
+        let (member, score) = self.scores.get_key_value(member)?;
+        let below = self.ordered.range(..(Score(*score), member.clone()));
+        Some(below.count())
+    }
+
+    /// Iterates over the members scoring within `min` and `max`, in order.
+    ///
+    /// This seeks to the first member in range, then walks only the members
+    /// in range.
+    fn range_by_score(
+        &self,
+        min: Bound<f64>,
+        max: Bound<f64>,
+    ) -> impl Iterator<Item = (&Bytes, f64)> {
+        // The empty name sorts before every other member with the same score,
+        // and -0 before 0 although they are equal.
+        let start = match min {
+            Bound::Included(score) | Bound::Excluded(score) => {
+                let score = if score == 0.0 { -0.0 } else { score };
+                Bound::Included((Score(score), Bytes::new()))
+            }
+            Bound::Unbounded => Bound::Unbounded,
+        };
+
+        self.ordered
+            .range((start, Bound::Unbounded))
+            .map(|(score, member)| (member, score.0))
+            .skip_while(move |(_, score)| matches!(min, Bound::Excluded(min) if *score <= min))
+            .take_while(move |(_, score)| match max {
+                Bound::Included(max) => *score <= max,
+                Bound::Excluded(max) => *score < max,
+                Bound::Unbounded => true,
+            })
+    }
+
+    /// Returns `true` if there are no members.
+    fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// Returns the score of `member`, if it is a member.
+    pub(crate) fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    /// Returns the number of members.
+    pub(crate) fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// Iterates over the members and their score, in order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Bytes, f64)> {
+        self.ordered.iter().map(|(score, member)| (member, score.0))
+    }
+}
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Score) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Score) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Score) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Returns a random index below `len`, which must not be zero.
+fn random_index(len: usize) -> usize {
+    use std::collections::hash_map::RandomState;
+    use std::hash::BuildHasher;
+
+    // Every `RandomState` is seeded with different keys, so hashing the same
+    // value with a new one gives a different number each time.
+    (RandomState::new().hash_one(()) % len as u64) as usize
+}
+
+/// Converts the inclusive, possibly negative, `start` and `end` offsets used by
+/// `GETRANGE` into a range over a value of `len` elements.
+///
+/// The steps are the ones Redis takes, so that the same offsets select the
+/// same elements. In particular, an `end` before the start of the value is
+/// clamped to the first element rather than producing an empty range, unless
+/// both offsets are negative and out of order.
+///
+/// Returns `None` if the range is empty.
+fn normalize_range(start: i64, end: i64, len: usize) -> Option<Range<usize>> {
+    let len = len as i64;
+
+    // Both offsets count from the end and are out of order.
+    if start < 0 && end < 0 && start > end {
+        return None;
+    }
+
+    // Negative offsets count back from the end of the value.
+    let mut start = if start < 0 { len + start } else { start };
+    let mut end = if end < 0 { len + end } else { end };
+
+    // Offsets still negative are before the start of the value.
+    if start < 0 {
+        start = 0;
+    }
+    if end < 0 {
+        end = 0;
+    }
+
+    // Offsets past the end of the value are clamped to the last element.
+    if end >= len {
+        end = len - 1;
+    }
+
+    // The only remaining way for the range to be empty.
+    if start > end || len == 0 {
+        return None;
+    }
+
+    Some(start as usize..end as usize + 1)
+}
+
+/// Converts the `start` and `stop` offsets, as accepted by `LRANGE`, into a
+/// range over a list of `len` elements.
+///
+/// Unlike `normalize_range`, a `stop` before the start of the list always
+/// produces an empty range, as Redis does for lists.
+///
+/// Returns `None` if the range is empty.
+fn normalize_list_range(start: i64, stop: i64, len: usize) -> Option<Range<usize>> {
+    let len = len as i64;
+
+    // Negative offsets count back from the end of the list.
+    let mut start = if start < 0 { len + start } else { start };
+    let stop = if stop < 0 { len + stop } else { stop };
+
+    // A `start` still negative is before the start of the list.
+    if start < 0 {
+        start = 0;
+    }
+
+    if start > stop || start >= len {
+        return None;
+    }
+
+    // Offsets past the end of the list are clamped to the last element.
+    Some(start as usize..stop.min(len - 1) as usize + 1)
+}
+
+/// Returns the byte offset of the character at index `idx` in `text`, or the
+/// length of `text` if `idx` is past the last character.
+fn char_to_byte_offset(text: &str, idx: usize) -> usize {
+    text.char_indices()
+        .nth(idx)
+        .map(|(offset, _)| offset)
+        .unwrap_or(text.len())
 }
 
 /// Routine executed by the background task.
@@ -391,3 +3513,218 @@ async fn purge_expired_tasks(shared: Arc<Shared>) {
 
     debug!("Purge background task shut down")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    /// A `Storage` other than the default one, to check `Db` only relies on
+    /// the trait.
+    #[derive(Debug, Default, Clone)]
+    struct BTreeStorage(BTreeMap<String, Entry>);
+
+    impl Storage for BTreeStorage {
+        fn get(&self, key: &str) -> Option<&Entry> {
+            self.0.get(key)
+        }
+
+        fn set(&mut self, key: String, entry: Entry) -> Option<Entry> {
+            self.0.insert(key, entry)
+        }
+
+        fn remove(&mut self, key: &str) -> Option<Entry> {
+            self.0.remove(key)
+        }
+
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        fn iter(&self) -> Box<dyn Iterator<Item = (&str, &Entry)> + '_> {
+            Box::new(self.0.iter().map(|(key, entry)| (&key[..], entry)))
+        }
+
+        fn clear(&mut self) {
+            self.0.clear();
+        }
+
+        fn snapshot(&self) -> Box<dyn Storage> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_storage() {
+        tokio::time::pause();
+
+        let mut storage = BTreeStorage::default();
+        storage.set(
+            "stored".to_string(),
+            Entry::new(
+                "before".into(),
+                Some(Instant::now() + Duration::from_secs(1)),
+            ),
+        );
+
+        let guard = DbDropGuard {
+            db: Db::with_storage(
+                Config::default(),
+                vec![Box::new(storage), Box::new(BTreeStorage::default())],
+            ),
+        };
+        let db = guard.db();
+
+        assert_eq!(2, db.databases());
+        assert_eq!(Some(Bytes::from("before")), db.get("stored"));
+        assert_eq!(None, db.select(1).get("stored"));
+
+        db.set("hello".to_string(), "world".into(), None).unwrap();
+        db.set(
+            "short".to_string(),
+            "lived".into(),
+            Some(Duration::from_secs(2)),
+        )
+        .unwrap();
+
+        assert_eq!(Some(Bytes::from("world")), db.get("hello"));
+        assert_eq!(
+            Bytes::from("orl"),
+            db.getrange("hello", 1, -2, false).unwrap()
+        );
+
+        let snapshot = db.snapshot();
+        db.set("hello".to_string(), "changed".into(), None).unwrap();
+
+        // Both the key stored before the `Db` was created and the one set
+        // through it expire.
+        time::advance(Duration::from_secs(3)).await;
+        tokio::task::yield_now().await;
+
+        assert_eq!(None, db.get("stored"));
+        assert_eq!(None, db.get("short"));
+        assert_eq!(Some(Bytes::from("changed")), db.get("hello"));
+
+        let keys: Vec<_> = snapshot.strings().map(|(key, ..)| key).collect();
+        assert_eq!(vec!["hello", "short", "stored"], keys);
+        assert_eq!(1, db.snapshot().len());
+    }
+
+    // `Db` is private to the crate, so the snapshot isolation is tested here
+    // rather than in `tests/`.
+    #[tokio::test]
+    async fn snapshot_is_not_affected_by_writes() {
+        let guard = DbDropGuard::new(Config::default());
+        let db = guard.db();
+
+        db.set("a".to_string(), "1".into(), None).unwrap();
+        db.set("b".to_string(), "2".into(), Some(Duration::from_secs(60)))
+            .unwrap();
+        db.hset("h".to_string(), "f".to_string(), "v".into())
+            .unwrap();
+
+        let snapshot = db.snapshot();
+
+        db.set("a".to_string(), "changed".into(), None).unwrap();
+        db.set("c".to_string(), "3".into(), None).unwrap();
+        db.hset("h".to_string(), "g".to_string(), "w".into())
+            .unwrap();
+        db.hset("h2".to_string(), "f".to_string(), "v".into())
+            .unwrap();
+
+        let mut strings: Vec<_> = snapshot
+            .strings()
+            .map(|(key, value, expires_at)| (key.to_string(), value.clone(), expires_at.is_some()))
+            .collect();
+        strings.sort();
+
+        assert_eq!(
+            vec![
+                ("a".to_string(), Bytes::from("1"), false),
+                ("b".to_string(), Bytes::from("2"), true),
+            ],
+            strings
+        );
+        assert_eq!(3, snapshot.len());
+        assert_eq!(1, snapshot.hashes["h"].len());
+
+        // The writes were applied to the `Db` itself.
+        assert_eq!(Some(Bytes::from("changed")), db.get("a"));
+        assert_eq!(2, db.hgetall("h").unwrap().len());
+        assert_eq!(5, db.snapshot().len());
+    }
+
+    #[tokio::test]
+    async fn append_many_times() {
+        let guard = DbDropGuard::new(Config::default());
+        let db = guard.db();
+
+        for _ in 0..50_000 {
+            db.append("a".to_string(), "xy".into()).unwrap();
+        }
+
+        // A value shared with a snapshot is copied rather than extended.
+        let snapshot = db.snapshot();
+
+        for _ in 0..50_000 {
+            db.append("a".to_string(), "xy".into()).unwrap();
+        }
+
+        let (_, value, _) = snapshot.strings().next().unwrap();
+        assert_eq!("xy".repeat(50_000).as_bytes(), &value[..]);
+
+        let value = db.get("a").unwrap();
+        assert_eq!("xy".repeat(100_000).as_bytes(), &value[..]);
+        assert_eq!(Some("raw"), db.object_encoding("a"));
+    }
+
+    // The memory of the collections is tracked as they are written, so it
+    // must match their size computed from scratch.
+    #[tokio::test]
+    async fn used_memory_counts_every_type() {
+        let guard = DbDropGuard::new(Config::default());
+        let db = guard.db();
+
+        db.set("s".to_string(), "value".into(), None).unwrap();
+        db.hset("h".to_string(), "f".to_string(), "1".into())
+            .unwrap();
+        db.hset("h".to_string(), "f".to_string(), "longer".into())
+            .unwrap();
+        db.hincrby("h".to_string(), "n".to_string(), 10).unwrap();
+        db.hsetnx("h".to_string(), "g".to_string(), "v".into())
+            .unwrap();
+        db.rpush("l".to_string(), vec!["a".into(), "bc".into()])
+            .unwrap();
+        db.sadd("set".to_string(), vec!["a".into(), "a".into(), "b".into()])
+            .unwrap();
+        db.sadd("set".to_string(), vec!["b".into(), "c".into()])
+            .unwrap();
+        db.zadd("z".to_string(), vec![(1.0, "a".into()), (2.0, "a".into())])
+            .unwrap();
+        db.zincrby("z".to_string(), "b".into(), 1.0).unwrap();
+
+        db.hdel("h", &["g".to_string(), "g".to_string()]);
+        db.srem("set", &["a".into(), "a".into()]).unwrap();
+        db.spop("set", 1).unwrap();
+        db.zrem("z", &["a".into()]).unwrap();
+        db.rename("l", "list", false).unwrap();
+
+        let keys = ["s", "h", "list", "set", "z"];
+        let expected = {
+            let state = db.keyspace();
+            keys.iter().map(|key| state.key_size(key)).sum::<usize>()
+        };
+        assert!(expected > 0);
+        assert_eq!(expected, db.used_memory());
+
+        // Emptying or replacing the collections frees all they used.
+        db.hdel("h", &["f".to_string(), "n".to_string()]);
+        db.spop("set", 10).unwrap();
+        db.zrem("z", &["b".into()]).unwrap();
+        db.set("list".to_string(), "x".into(), None).unwrap();
+        db.del(&["s".to_string()]);
+
+        assert_eq!(storage::entry_size("list", b"x"), db.used_memory());
+    }
+}
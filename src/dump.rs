@@ -0,0 +1,346 @@
+//! Dump files, holding a copy of every database.
+//!
+//! `SAVE` writes the keyspace to the file configured with `Config::dbfile`,
+//! and the server loads it back when starting. The format is specific to
+//! mini-redis, it is not compatible with Redis RDB files.
+//!
+//! # Format
+//!
+//! Integers are big endian. Strings are written as their length, a `u32`,
+//! followed by their bytes.
+//!
+//! The file starts with the `MINIREDIS` magic string and the format version,
+//! a `u16`. Each database holding keys follows, starting with a `SELECTDB`
+//! byte and the index of the database, a `u32`. Its keys come next, each as a
+//! type byte, the key, the value and the time at which the key expires, a
+//! `u64` of milliseconds since the Unix epoch, or `0` if it does not expire.
+//! The file ends with an `EOF` byte.
+//!
+//! Values are written as follows:
+//!
+//! * string: the string.
+//! * hash: the number of fields, a `u32`, then each field and its value.
+//! * list: the number of elements, a `u32`, then each element.
+//! * set: the number of members, a `u32`, then each member.
+//! * sorted set: the number of members, a `u32`, then each member and its
+//!   score, an `f64`.
+
+use crate::db::{Snapshot, SortedSet};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use indexmap::IndexMap;
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::Instant;
+
+/// Written at the start of every dump file.
+const MAGIC: &[u8] = b"MINIREDIS";
+
+/// Version of the format written by `encode`. It must be bumped whenever the
+/// format changes, so that files in another format are rejected rather than
+/// misread.
+const VERSION: u16 = 2;
+
+/// Starts the keys of a database.
+const SELECTDB: u8 = 0xfe;
+
+/// Ends the file.
+const EOF: u8 = 0xff;
+
+/// Type bytes of the values.
+const TYPE_STRING: u8 = 0;
+const TYPE_HASH: u8 = 1;
+const TYPE_LIST: u8 = 2;
+const TYPE_SET: u8 = 3;
+const TYPE_ZSET: u8 = 4;
+
+/// A value read from a dump file.
+#[derive(Debug)]
+pub(crate) enum Value {
+    /// String value
+    String(Bytes),
+
+    /// Hash value
+    Hash(IndexMap<String, Bytes>),
+
+    /// List value
+    List(VecDeque<Bytes>),
+
+    /// Set value
+    Set(HashSet<Bytes>),
+
+    /// Sorted set value
+    SortedSet(SortedSet),
+}
+
+/// The keys of a database read from a dump file, each with the time at which
+/// it expires, along with the index of the database.
+pub(crate) type Database = (usize, Vec<(String, Value, Option<SystemTime>)>);
+
+/// Reads the content of a dump file, checking for truncated or malformed
+/// data.
+struct Reader {
+    buf: Bytes,
+}
+
+/// Encodes `databases`, each snapshot holding the database at the same index,
+/// in the dump file format.
+///
+/// Keys which already expired are left out.
+pub(crate) fn encode(databases: &[Snapshot]) -> Bytes {
+    let mut buf = BytesMut::new();
+    buf.put_slice(MAGIC);
+    buf.put_u16(VERSION);
+
+    // Expirations are tracked as `Instant`s, which have no meaning once the
+    // process exits. They are converted to wall clock times, relative to the
+    // same point in time.
+    let now = Instant::now();
+    let system_now = SystemTime::now();
+
+    for (index, snapshot) in databases.iter().enumerate() {
+        if snapshot.is_empty() {
+            continue;
+        }
+
+        buf.put_u8(SELECTDB);
+        buf.put_u32(index as u32);
+
+        // Returns the expiration to write for `key`, `None` if it expired
+        // already.
+        let expiration = |key: &str| match snapshot.expires_at(key) {
+            Some(when) if when <= now => None,
+            Some(when) => Some(unix_millis(system_now + (when - now))),
+            None => Some(0),
+        };
+
+        for (key, value, _) in snapshot.strings() {
+            let expires_at = match expiration(key) {
+                Some(expires_at) => expires_at,
+                None => continue,
+            };
+
+            buf.put_u8(TYPE_STRING);
+            put_bytes(&mut buf, key.as_bytes());
+            put_bytes(&mut buf, value);
+            buf.put_u64(expires_at);
+        }
+
+        for (key, fields) in snapshot.hashes() {
+            let expires_at = match expiration(key) {
+                Some(expires_at) => expires_at,
+                None => continue,
+            };
+
+            buf.put_u8(TYPE_HASH);
+            put_bytes(&mut buf, key.as_bytes());
+            buf.put_u32(fields.len() as u32);
+            for (field, value) in fields {
+                put_bytes(&mut buf, field.as_bytes());
+                put_bytes(&mut buf, value);
+            }
+            buf.put_u64(expires_at);
+        }
+
+        for (key, list) in snapshot.lists() {
+            let expires_at = match expiration(key) {
+                Some(expires_at) => expires_at,
+                None => continue,
+            };
+
+            buf.put_u8(TYPE_LIST);
+            put_bytes(&mut buf, key.as_bytes());
+            buf.put_u32(list.len() as u32);
+            for element in list {
+                put_bytes(&mut buf, element);
+            }
+            buf.put_u64(expires_at);
+        }
+
+        for (key, set) in snapshot.sets() {
+            let expires_at = match expiration(key) {
+                Some(expires_at) => expires_at,
+                None => continue,
+            };
+
+            buf.put_u8(TYPE_SET);
+            put_bytes(&mut buf, key.as_bytes());
+            buf.put_u32(set.len() as u32);
+            for member in set {
+                put_bytes(&mut buf, member);
+            }
+            buf.put_u64(expires_at);
+        }
+
+        for (key, zset) in snapshot.zsets() {
+            let expires_at = match expiration(key) {
+                Some(expires_at) => expires_at,
+                None => continue,
+            };
+
+            buf.put_u8(TYPE_ZSET);
+            put_bytes(&mut buf, key.as_bytes());
+            buf.put_u32(zset.len() as u32);
+            for (member, score) in zset.iter() {
+                put_bytes(&mut buf, member);
+                buf.put_f64(score);
+            }
+            buf.put_u64(expires_at);
+        }
+    }
+
+    buf.put_u8(EOF);
+    buf.freeze()
+}
+
+/// Decodes the content of a dump file, returning the keys of each database
+/// it holds.
+///
+/// Fails if the file was written in another version of the format, or is
+/// truncated or malformed.
+pub(crate) fn decode(buf: Bytes) -> crate::Result<Vec<Database>> {
+    let mut reader = Reader { buf };
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err("not a mini-redis dump file".into());
+    }
+
+    let version = reader.u16()?;
+    if version != VERSION {
+        return Err(format!("unsupported dump file version {}", version).into());
+    }
+
+    let mut databases: Vec<Database> = vec![];
+
+    loop {
+        let kind = reader.u8()?;
+
+        let keys = match (kind, databases.last_mut()) {
+            (EOF, _) => break,
+            (SELECTDB, _) => {
+                let index = reader.u32()? as usize;
+                databases.push((index, vec![]));
+                continue;
+            }
+            (_, Some((_, keys))) => keys,
+            (_, None) => return Err("dump file holds a key outside of a database".into()),
+        };
+
+        let key = reader.string()?;
+
+        let value = match kind {
+            TYPE_STRING => Value::String(reader.bytes()?),
+            TYPE_HASH => {
+                let mut fields = IndexMap::new();
+                for _ in 0..reader.u32()? {
+                    let field = reader.string()?;
+                    fields.insert(field, reader.bytes()?);
+                }
+                Value::Hash(fields)
+            }
+            TYPE_LIST => {
+                let mut list = VecDeque::new();
+                for _ in 0..reader.u32()? {
+                    list.push_back(reader.bytes()?);
+                }
+                Value::List(list)
+            }
+            TYPE_SET => {
+                let mut set = HashSet::new();
+                for _ in 0..reader.u32()? {
+                    set.insert(reader.bytes()?);
+                }
+                Value::Set(set)
+            }
+            TYPE_ZSET => {
+                let mut zset = SortedSet::default();
+                for _ in 0..reader.u32()? {
+                    let member = reader.bytes()?;
+                    let score = reader.f64()?;
+                    if score.is_nan() {
+                        return Err("invalid sorted set score in dump file".into());
+                    }
+                    zset.insert(member, score);
+                }
+                Value::SortedSet(zset)
+            }
+            kind => return Err(format!("unknown value type {} in dump file", kind).into()),
+        };
+
+        let expires_at = match reader.u64()? {
+            0 => None,
+            millis => Some(UNIX_EPOCH + Duration::from_millis(millis)),
+        };
+
+        keys.push((key, value, expires_at));
+    }
+
+    if reader.buf.has_remaining() {
+        return Err("unexpected data after the end of the dump file".into());
+    }
+
+    Ok(databases)
+}
+
+/// Writes `data` preceded by its length.
+fn put_bytes(buf: &mut BytesMut, data: &[u8]) {
+    buf.put_u32(data.len() as u32);
+    buf.put_slice(data);
+}
+
+/// Returns the number of milliseconds between the Unix epoch and `time`.
+fn unix_millis(time: SystemTime) -> u64 {
+    // A clock set before 1970 is not worth supporting, such times are stored
+    // as the epoch itself.
+    let millis = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    // `0` means no expiration, so the earliest time stored is 1 ms after the
+    // epoch. The key has expired when loaded either way.
+    millis.max(1) as u64
+}
+
+impl Reader {
+    /// Returns the next `len` bytes.
+    fn take(&mut self, len: usize) -> crate::Result<Bytes> {
+        if self.buf.remaining() < len {
+            return Err("unexpected end of dump file".into());
+        }
+
+        Ok(self.buf.split_to(len))
+    }
+
+    fn u8(&mut self) -> crate::Result<u8> {
+        Ok(self.take(1)?.get_u8())
+    }
+
+    fn u16(&mut self) -> crate::Result<u16> {
+        Ok(self.take(2)?.get_u16())
+    }
+
+    fn u32(&mut self) -> crate::Result<u32> {
+        Ok(self.take(4)?.get_u32())
+    }
+
+    fn u64(&mut self) -> crate::Result<u64> {
+        Ok(self.take(8)?.get_u64())
+    }
+
+    fn f64(&mut self) -> crate::Result<f64> {
+        Ok(self.take(8)?.get_f64())
+    }
+
+    /// Returns the next string, as bytes.
+    fn bytes(&mut self) -> crate::Result<Bytes> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    /// Returns the next string, which must be valid UTF-8.
+    fn string(&mut self) -> crate::Result<String> {
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| "invalid string in dump file".into())
+    }
+}
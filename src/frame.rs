@@ -13,10 +13,22 @@ use std::string::FromUtf8Error;
 pub enum Frame {
     Simple(String),
     Error(String),
-    Integer(u64),
+    Integer(i64),
     Bulk(Bytes),
     Null,
+    /// A null array, `*-1`, as opposed to an empty `Array`.
+    NullArray,
     Array(Vec<Frame>),
+    /// A RESP3 map. Sent as a flat array of keys and values to RESP2
+    /// connections.
+    Map(Vec<(Frame, Frame)>),
+    /// A RESP3 double. Sent as a bulk string to RESP2 connections.
+    Double(f64),
+    /// A RESP3 boolean. Sent as `1` or `0` to RESP2 connections.
+    Boolean(bool),
+    /// A RESP3 out of band message, such as a pub/sub message. Sent as an
+    /// array to RESP2 connections.
+    Push(Vec<Frame>),
 }
 
 #[derive(Debug)]
@@ -24,6 +36,9 @@ pub enum Error {
     /// Not enough data is available to parse a message
     Incomplete,
 
+    /// An array frame declared more elements than the reader accepts
+    InvalidMultibulkLength,
+
     /// Invalid message encoding
     Other(crate::Error),
 }
@@ -34,28 +49,35 @@ impl Frame {
         Frame::Array(vec![])
     }
 
-    /// Push a "bulk" frame into the array. `self` must be an Array frame.
+    /// Returns an empty push frame
+    pub(crate) fn push() -> Frame {
+        Frame::Push(vec![])
+    }
+
+    /// Push a "bulk" frame into the array. `self` must be an Array or Push
+    /// frame.
     ///
     /// # Panics
     ///
     /// panics if `self` is not an array
     pub(crate) fn push_bulk(&mut self, bytes: Bytes) {
         match self {
-            Frame::Array(vec) => {
+            Frame::Array(vec) | Frame::Push(vec) => {
                 vec.push(Frame::Bulk(bytes));
             }
             _ => panic!("not an array frame"),
         }
     }
 
-    /// Push an "integer" frame into the array. `self` must be an Array frame.
+    /// Push an "integer" frame into the array. `self` must be an Array or Push
+    /// frame.
     ///
     /// # Panics
     ///
     /// panics if `self` is not an array
-    pub(crate) fn push_int(&mut self, value: u64) {
+    pub(crate) fn push_int(&mut self, value: i64) {
         match self {
-            Frame::Array(vec) => {
+            Frame::Array(vec) | Frame::Push(vec) => {
                 vec.push(Frame::Integer(value));
             }
             _ => panic!("not an array frame"),
@@ -64,6 +86,16 @@ impl Frame {
 
     /// Checks if an entire message can be decoded from `src`
     pub fn check(src: &mut Cursor<&[u8]>) -> Result<(), Error> {
+        Frame::check_bounded(src, u64::MAX)
+    }
+
+    /// Checks if an entire message can be decoded from `src`, rejecting any
+    /// array frame (including nested ones) that declares more than
+    /// `max_array_len` elements.
+    ///
+    /// The length is validated as soon as the array header is read, so an
+    /// oversized frame is rejected without waiting for its elements.
+    pub fn check_bounded(src: &mut Cursor<&[u8]>, max_array_len: u64) -> Result<(), Error> {
         match get_u8(src)? {
             b'+' => {
                 get_line(src)?;
@@ -74,7 +106,7 @@ impl Frame {
                 Ok(())
             }
             b':' => {
-                let _ = get_decimal(src)?;
+                let _ = get_signed_decimal(src)?;
                 Ok(())
             }
             b'$' => {
@@ -90,10 +122,51 @@ impl Frame {
                 }
             }
             b'*' => {
+                if b'-' == peek_u8(src)? {
+                    // Skip '-1\r\n'
+                    return skip(src, 4);
+                }
+
+                let len = get_decimal(src)?;
+
+                if len > max_array_len {
+                    return Err(Error::InvalidMultibulkLength);
+                }
+
+                for _ in 0..len {
+                    Frame::check_bounded(src, max_array_len)?;
+                }
+
+                Ok(())
+            }
+            b'_' | b',' | b'#' => {
+                get_line(src)?;
+                Ok(())
+            }
+            b'%' => {
+                let len = get_decimal(src)?;
+
+                if len > max_array_len {
+                    return Err(Error::InvalidMultibulkLength);
+                }
+
+                // Each entry is a key followed by a value.
+                for _ in 0..len {
+                    Frame::check_bounded(src, max_array_len)?;
+                    Frame::check_bounded(src, max_array_len)?;
+                }
+
+                Ok(())
+            }
+            b'>' => {
                 let len = get_decimal(src)?;
 
+                if len > max_array_len {
+                    return Err(Error::InvalidMultibulkLength);
+                }
+
                 for _ in 0..len {
-                    Frame::check(src)?;
+                    Frame::check_bounded(src, max_array_len)?;
                 }
 
                 Ok(())
@@ -124,8 +197,8 @@ impl Frame {
                 Ok(Frame::Error(string))
             }
             b':' => {
-                let len = get_decimal(src)?;
-                Ok(Frame::Integer(len))
+                let value = get_signed_decimal(src)?;
+                Ok(Frame::Integer(value))
             }
             b'$' => {
                 if b'-' == peek_u8(src)? {
@@ -154,6 +227,16 @@ impl Frame {
                 }
             }
             b'*' => {
+                if b'-' == peek_u8(src)? {
+                    let line = get_line(src)?;
+
+                    if line != b"-1" {
+                        return Err("protocol error; invalid frame format".into());
+                    }
+
+                    return Ok(Frame::NullArray);
+                }
+
                 let len = get_decimal(src)?.try_into()?;
                 let mut out = Vec::with_capacity(len);
 
@@ -163,6 +246,50 @@ impl Frame {
 
                 Ok(Frame::Array(out))
             }
+            b'_' => {
+                if !get_line(src)?.is_empty() {
+                    return Err("protocol error; invalid frame format".into());
+                }
+
+                Ok(Frame::Null)
+            }
+            b',' => {
+                let line = get_line(src)?;
+
+                let value = std::str::from_utf8(line)
+                    .ok()
+                    .and_then(|line| line.parse::<f64>().ok())
+                    .ok_or("protocol error; invalid frame format")?;
+
+                Ok(Frame::Double(value))
+            }
+            b'#' => match get_line(src)? {
+                b"t" => Ok(Frame::Boolean(true)),
+                b"f" => Ok(Frame::Boolean(false)),
+                _ => Err("protocol error; invalid frame format".into()),
+            },
+            b'%' => {
+                let len = get_decimal(src)?.try_into()?;
+                let mut out = Vec::with_capacity(len);
+
+                for _ in 0..len {
+                    let key = Frame::parse(src)?;
+                    let value = Frame::parse(src)?;
+                    out.push((key, value));
+                }
+
+                Ok(Frame::Map(out))
+            }
+            b'>' => {
+                let len = get_decimal(src)?.try_into()?;
+                let mut out = Vec::with_capacity(len);
+
+                for _ in 0..len {
+                    out.push(Frame::parse(src)?);
+                }
+
+                Ok(Frame::Push(out))
+            }
             _ => unimplemented!(),
         }
     }
@@ -195,8 +322,21 @@ impl fmt::Display for Frame {
                 Ok(string) => string.fmt(fmt),
                 Err(_) => write!(fmt, "{:?}", msg),
             },
-            Frame::Null => "(nil)".fmt(fmt),
-            Frame::Array(parts) => {
+            Frame::Null | Frame::NullArray => "(nil)".fmt(fmt),
+            Frame::Double(num) => num.fmt(fmt),
+            Frame::Boolean(value) => write!(fmt, "({})", value),
+            Frame::Map(entries) => {
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(fmt, " ")?;
+                    }
+
+                    write!(fmt, "{} {}", key, value)?;
+                }
+
+                Ok(())
+            }
+            Frame::Array(parts) | Frame::Push(parts) => {
                 for (i, part) in parts.iter().enumerate() {
                     if i > 0 {
                         // use space as the array element display separator
@@ -246,6 +386,15 @@ fn get_decimal(src: &mut Cursor<&[u8]>) -> Result<u64, Error> {
     atoi::<u64>(line).ok_or_else(|| "protocol error; invalid frame format".into())
 }
 
+/// Read a new-line terminated decimal which may be negative
+fn get_signed_decimal(src: &mut Cursor<&[u8]>) -> Result<i64, Error> {
+    use atoi::atoi;
+
+    let line = get_line(src)?;
+
+    atoi::<i64>(line).ok_or_else(|| "protocol error; invalid frame format".into())
+}
+
 /// Find a line
 fn get_line<'a>(src: &mut Cursor<&'a [u8]>) -> Result<&'a [u8], Error> {
     // Scan the bytes directly
@@ -296,6 +445,7 @@ impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::Incomplete => "stream ended early".fmt(fmt),
+            Error::InvalidMultibulkLength => "Protocol error: invalid multibulk length".fmt(fmt),
             Error::Other(err) => err.fmt(fmt),
         }
     }
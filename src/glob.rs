@@ -0,0 +1,96 @@
+//! Glob-style pattern matching, as used by `SCAN MATCH` and `PSUBSCRIBE`.
+//!
+//! The supported syntax is the one Redis uses:
+//!
+//! * `?` matches any single byte.
+//! * `*` matches any sequence of bytes, including an empty one.
+//! * `[abc]` matches one of the listed bytes, `[a-z]` a byte in the range and
+//!   `[^abc]` a byte not listed.
+//! * `\` escapes the next byte, which is then matched literally.
+//!
+//! Patterns and subjects are matched byte by byte, so they need not be UTF-8.
+
+/// Returns `true` if `subject` matches `pattern` as a whole.
+pub(crate) fn matches(pattern: &[u8], subject: &[u8]) -> bool {
+    // Positions to resume from when the bytes following the last `*` fail to
+    // match: the pattern after the `*`, and the subject byte it should start
+    // consuming from. Only the last `*` needs to be tracked.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    let mut p = 0;
+    let mut s = 0;
+
+    while s < subject.len() {
+        if p < pattern.len() {
+            if pattern[p] == b'*' {
+                p += 1;
+                backtrack = Some((p, s));
+                continue;
+            }
+
+            if let Some(len) = match_one(&pattern[p..], subject[s]) {
+                p += len;
+                s += 1;
+                continue;
+            }
+        }
+
+        // The byte did not match. Let the last `*` consume one more byte.
+        match backtrack {
+            Some((after_star, start)) => {
+                p = after_star;
+                s = start + 1;
+                backtrack = Some((after_star, s));
+            }
+            None => return false,
+        }
+    }
+
+    // The subject is consumed, only trailing `*` may remain in the pattern.
+    pattern[p..].iter().all(|&b| b == b'*')
+}
+
+/// Matches `byte` against the first element of `pattern`, which is not `*`.
+///
+/// Returns the length of the element in the pattern if it matches.
+fn match_one(pattern: &[u8], byte: u8) -> Option<usize> {
+    match pattern[0] {
+        b'?' => Some(1),
+        b'\\' if pattern.len() > 1 => (pattern[1] == byte).then_some(2),
+        b'[' => match_class(pattern, byte),
+        literal => (literal == byte).then_some(1),
+    }
+}
+
+/// Matches `byte` against the `[...]` class at the start of `pattern`.
+///
+/// An unterminated class extends to the end of the pattern.
+fn match_class(pattern: &[u8], byte: u8) -> Option<usize> {
+    let mut i = 1;
+
+    let negate = pattern.get(i) == Some(&b'^');
+    if negate {
+        i += 1;
+    }
+
+    let mut found = false;
+
+    while i < pattern.len() && pattern[i] != b']' {
+        if pattern[i] == b'\\' && i + 1 < pattern.len() {
+            found |= pattern[i + 1] == byte;
+            i += 2;
+        } else if i + 2 < pattern.len() && pattern[i + 1] == b'-' && pattern[i + 2] != b']' {
+            let (from, to) = (pattern[i], pattern[i + 2]);
+            found |= (from.min(to)..=from.max(to)).contains(&byte);
+            i += 3;
+        } else {
+            found |= pattern[i] == byte;
+            i += 1;
+        }
+    }
+
+    // Skip the closing `]`, if any.
+    let len = (i + 1).min(pattern.len());
+
+    (found != negate).then_some(len)
+}
@@ -13,8 +13,9 @@
 //!
 //! The major components are:
 //!
-//! * `server`: Redis server implementation. Includes a single `run` function
-//!   that takes a `TcpListener` and starts accepting redis client connections.
+//! * `server`: Redis server implementation. Includes a `run` function that
+//!   takes a `TcpListener` and starts accepting redis client connections, and
+//!   `run_unix` which does the same over a Unix domain socket.
 //!
 //! * `clients/client`: an asynchronous Redis client implementation. Demonstrates how to
 //!   build clients with Tokio.
@@ -25,12 +26,17 @@
 //!   intermediate representation between a "command" and the byte
 //!   representation.
 
+mod aof;
+
 pub mod clients;
-pub use clients::{BlockingClient, BufferedClient, Client};
+pub use clients::{BlockingClient, BufferedClient, Client, Pool, RetryClient};
 
 pub mod cmd;
 pub use cmd::Command;
 
+mod config;
+pub use config::{AppendFsync, Config, MaxmemoryPolicy};
+
 mod connection;
 pub use connection::Connection;
 
@@ -41,6 +47,10 @@ mod db;
 use db::Db;
 use db::DbDropGuard;
 
+mod dump;
+
+mod glob;
+
 mod parse;
 use parse::{Parse, ParseError};
 
@@ -49,6 +59,8 @@ pub mod server;
 mod shutdown;
 use shutdown::Shutdown;
 
+mod storage;
+
 /// Default port that a redis server listens on.
 ///
 /// Used if no port is specified.
@@ -3,17 +3,23 @@
 //! Provides an async `run` function that listens for inbound connections,
 //! spawning a task per connection.
 
-use crate::{Command, Connection, Db, DbDropGuard, Shutdown};
+use crate::aof::{self, Aof};
+use crate::cmd::{fast_path, unwatch_all};
+use crate::frame::{self, Frame};
+use crate::{Command, Config, Connection, Db, DbDropGuard, Shutdown};
 
 use std::future::Future;
+use std::io;
 use std::sync::Arc;
-use tokio::net::{TcpListener, TcpStream};
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
 use tokio::sync::{broadcast, mpsc, Semaphore};
-use tokio::time::{self, Duration};
+use tokio::time::{self, Duration, Instant};
 use tracing::{debug, error, info, instrument};
 
 /// Server listener state. Created in the `run` call. It includes a `run` method
-/// which performs the TCP listening and initialization of per-connection state.
+/// which performs the listening and initialization of per-connection state.
 #[derive(Debug)]
 struct Listener {
     /// Shared database handle.
@@ -25,8 +31,11 @@ struct Listener {
     /// retrieved and passed into the per connection state (`Handler`).
     db_holder: DbDropGuard,
 
-    /// TCP listener supplied by the `run` caller.
-    listener: TcpListener,
+    /// Server configuration supplied by the `run_with_config` caller.
+    config: Config,
+
+    /// TCP or Unix domain socket listener supplied by the `run` caller.
+    listener: Incoming,
 
     /// Limit the max number of connections.
     ///
@@ -63,6 +72,14 @@ struct Listener {
     shutdown_complete_tx: mpsc::Sender<()>,
 }
 
+/// Listener the server accepts inbound connections from.
+#[derive(Debug)]
+enum Incoming {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
 /// Per-connection handler. Reads requests from `connection` and applies the
 /// commands to `db`.
 #[derive(Debug)]
@@ -72,6 +89,9 @@ struct Handler {
     /// When a command is received from `connection`, it is applied with `db`.
     /// The implementation of the command is in the `cmd` module. Each command
     /// will need to interact with `db` in order to complete the work.
+    ///
+    /// The handle is switched to the database selected on the connection
+    /// before each command is applied.
     db: Db,
 
     /// The TCP connection decorated with the redis protocol encoder / decoder
@@ -93,6 +113,10 @@ struct Handler {
     /// which point the connection is terminated.
     shutdown: Shutdown,
 
+    /// Id of the connection in the `Db` client registry. The connection is
+    /// removed from the registry when the `Handler` is dropped.
+    client_id: u64,
+
     /// Not used directly. Instead, when `Handler` is dropped...?
     _shutdown_complete: mpsc::Sender<()>,
 }
@@ -121,6 +145,52 @@ const MAX_CONNECTIONS: usize = 250;
 /// `tokio::signal::ctrl_c()` can be used as the `shutdown` argument. This will
 /// listen for a SIGINT signal.
 pub async fn run(listener: TcpListener, shutdown: impl Future) {
+    run_with_config(listener, Config::default(), shutdown).await
+}
+
+/// Run the mini-redis server using the supplied `config`.
+///
+/// Behaves like `run`, but allows overriding the default server settings.
+pub async fn run_with_config(listener: TcpListener, config: Config, shutdown: impl Future) {
+    serve(Incoming::Tcp(listener), config, shutdown).await
+}
+
+/// Run the mini-redis server on a Unix domain socket.
+///
+/// Behaves like `run`, but accepts connections from `listener` instead of a
+/// TCP listener.
+#[cfg(unix)]
+pub async fn run_unix(listener: UnixListener, shutdown: impl Future) {
+    run_unix_with_config(listener, Config::default(), shutdown).await
+}
+
+/// Run the mini-redis server on a Unix domain socket, using the supplied
+/// `config`.
+///
+/// Once the server shut down, the socket file `listener` is bound to is
+/// removed.
+#[cfg(unix)]
+pub async fn run_unix_with_config(listener: UnixListener, config: Config, shutdown: impl Future) {
+    // The path is looked up first, as the listener is dropped by `serve`.
+    let path = listener
+        .local_addr()
+        .ok()
+        .and_then(|addr| addr.as_pathname().map(|path| path.to_path_buf()));
+
+    serve(Incoming::Unix(listener), config, shutdown).await;
+
+    // Dropping the listener does not remove the socket file, which would make
+    // binding the same path again fail.
+    if let Some(path) = path {
+        if let Err(err) = std::fs::remove_file(&path) {
+            error!(cause = %err, path = %path.display(), "failed to remove socket file");
+        }
+    }
+}
+
+/// Accept connections from `listener` until `shutdown` completes, then wait
+/// for the active connections to finish.
+async fn serve(listener: Incoming, config: Config, shutdown: impl Future) {
     // When the provided `shutdown` future completes, we must send a shutdown
     // message to all active connections. We use a broadcast channel for this
     // purpose. The call below ignores the receiver of the broadcast pair, and when
@@ -132,12 +202,46 @@ pub async fn run(listener: TcpListener, shutdown: impl Future) {
     // Initialize the listener state
     let mut server = Listener {
         listener,
-        db_holder: DbDropGuard::new(),
+        db_holder: DbDropGuard::new(config.clone()),
+        config,
         limit_connections: Arc::new(Semaphore::new(MAX_CONNECTIONS)),
         notify_shutdown,
         shutdown_complete_tx,
     };
 
+    // Load the keys saved by a previous run. The server does not start if an
+    // existing file cannot be loaded, as it would be overwritten on shutdown.
+    //
+    // The append-only file is more up to date than the dump file, so the
+    // latter is not loaded when there is one.
+    if let Some(path) = &server.config.appendonly {
+        let db = server.db_holder.db();
+
+        match aof::replay(&db, path).await {
+            Ok(count) => info!(path = %path.display(), count, "replayed append-only file"),
+            Err(err) => {
+                error!(cause = %err, path = %path.display(), "failed to replay append-only file");
+                return;
+            }
+        }
+
+        match Aof::open(path, server.config.appendfsync).await {
+            Ok(aof) => db.set_aof(aof),
+            Err(err) => {
+                error!(cause = %err, path = %path.display(), "failed to open append-only file");
+                return;
+            }
+        }
+    } else if let Some(path) = &server.config.dbfile {
+        match server.db_holder.db().load_from(path).await {
+            Ok(()) => info!(path = %path.display(), "loaded dump file"),
+            Err(err) if is_not_found(&err) => {}
+            Err(err) => {
+                error!(cause = %err, path = %path.display(), "failed to load dump file");
+                return;
+            }
+        }
+    }
 
     // Concurrently run the server and listen for the `shutdown` signal. The
     // server task runs until an error is encountered, so under normal
@@ -196,6 +300,29 @@ pub async fn run(listener: TcpListener, shutdown: impl Future) {
     // `Sender` instances are held by connection handler tasks. When those drop,
     // the `mpsc` channel will close and `recv()` will return `None`.
     let _ = shutdown_complete_rx.recv().await;
+
+    // No connection is left to modify the keys, make sure every write made
+    // it to disk.
+    let db = server.db_holder.db();
+    if let Some(aof) = db.aof() {
+        if let Err(err) = aof.close(&db).await {
+            error!(cause = %err, "failed to sync append-only file");
+        }
+    }
+
+    // No connection is left to modify the keys, save them.
+    if let Some(path) = &server.config.dbfile {
+        match server.db_holder.db().save_to(path).await {
+            Ok(()) => info!(path = %path.display(), "saved dump file"),
+            Err(err) => error!(cause = %err, path = %path.display(), "failed to save dump file"),
+        }
+    }
+}
+
+/// Returns `true` if `err` is caused by a missing file.
+fn is_not_found(err: &crate::Error) -> bool {
+    err.downcast_ref::<io::Error>()
+        .is_some_and(|err| err.kind() == io::ErrorKind::NotFound)
 }
 
 impl Listener {
@@ -236,19 +363,25 @@ impl Listener {
             // Accept a new socket. This will attempt to perform error handling.
             // The `accept` method internally attempts to recover errors, so an
             // error here is non-recoverable.
-            let socket = self.accept().await?;
+            let mut connection = self.accept().await?;
+            connection.set_max_array_len(self.config.max_multibulk_len);
+            connection.set_authenticated(self.config.requirepass.is_none());
+
+            // Register the connection, allowing it to be closed individually.
+            let db = self.db_holder.db();
+            let (client_id, close) = db.register_client();
 
             // Create the necessary per-connection handler state.
             let mut handler = Handler {
                 // Get a handle to the shared database.
-                db: self.db_holder.db(),
+                db,
 
-                // Initialize the connection state. This allocates read/write
-                // buffers to perform redis protocol frame parsing.
-                connection: Connection::new(socket),
+                connection,
 
                 // Receive shutdown notifications.
-                shutdown: Shutdown::new(self.notify_shutdown.subscribe()),
+                shutdown: Shutdown::new(self.notify_shutdown.subscribe(), close),
+
+                client_id,
 
                 // Notifies the receiver half once all clones are
                 // dropped.
@@ -276,7 +409,7 @@ impl Listener {
     /// After the second failure, the task waits for 2 seconds. Each subsequent
     /// failure doubles the wait time. If accepting fails on the 6th try after
     /// waiting for 64 seconds, then this function returns with an error.
-    async fn accept(&mut self) -> crate::Result<TcpStream> {
+    async fn accept(&mut self) -> crate::Result<Connection> {
         let mut backoff = 1;
 
         // Try to accept a few times
@@ -284,7 +417,7 @@ impl Listener {
             // Perform the accept operation. If a socket is successfully
             // accepted, return it. Otherwise, save the error.
             match self.listener.accept().await {
-                Ok((socket, _)) => return Ok(socket),
+                Ok(connection) => return Ok(connection),
                 Err(err) => {
                     if backoff > 64 {
                         // Accept has failed too many times. Return the error.
@@ -302,6 +435,36 @@ impl Listener {
     }
 }
 
+impl Incoming {
+    /// Accept an inbound connection, without retrying on error.
+    ///
+    /// The returned `Connection` has its read/write buffers allocated to
+    /// perform redis protocol frame parsing.
+    async fn accept(&self) -> io::Result<Connection> {
+        match self {
+            Incoming::Tcp(listener) => {
+                let (socket, _) = listener.accept().await?;
+
+                // Replies are small and written as soon as they are ready.
+                // Without `TCP_NODELAY`, Nagle's algorithm holds back each
+                // reply until the previous one is acknowledged, which stalls
+                // pipelined requests. Failing to disable it only affects
+                // latency.
+                if let Err(err) = socket.set_nodelay(true) {
+                    debug!(cause = %err, "failed to set TCP_NODELAY");
+                }
+
+                Ok(Connection::new(socket))
+            }
+            #[cfg(unix)]
+            Incoming::Unix(listener) => {
+                let (socket, _) = listener.accept().await?;
+                Ok(Connection::new(socket))
+            }
+        }
+    }
+}
+
 
 impl Handler {
     /// Process a single connection.
@@ -309,10 +472,11 @@ impl Handler {
     /// Request frames are read from the socket and processed. Responses are
     /// written back to the socket.
     ///
-    /// Currently, pipelining is not implemented. Pipelining is the ability to
-    /// process more than one request concurrently per connection without
-    /// interleaving frames. See for more details:
-    /// https://redis.io/topics/pipelining
+    /// Pipelined requests are processed one at a time, in order. The socket
+    /// is only read once the requests already buffered are processed, so a
+    /// client pipelining faster than the server can keep up is held back by
+    /// TCP flow control rather than growing the read buffer. See for more
+    /// details on pipelining: https://redis.io/topics/pipelining
     ///
     /// When the shutdown signal is received, the connection is processed until
     /// it reaches a safe state, at which point it is terminated.
@@ -324,7 +488,19 @@ impl Handler {
             // While reading a request frame, also listen for the shutdown
             // signal.
             let maybe_frame = tokio::select! {
-                res = self.connection.read_frame() => res?,
+                res = self.connection.read_frame() => match res {
+                    Ok(maybe_frame) => maybe_frame,
+                    Err(err) => {
+                        // Some protocol violations are reported to the peer
+                        // before the connection is closed, matching Redis.
+                        if let Some(frame::Error::InvalidMultibulkLength) = err.downcast_ref() {
+                            let response = Frame::Error(format!("ERR {}", err));
+                            self.connection.write_frame(&response).await?;
+                        }
+
+                        return Err(err);
+                    }
+                },
                 _ = self.shutdown.recv() => {
                     // If a shutdown signal is received, return from `run`.
                     // This will result in the task terminating.
@@ -340,10 +516,126 @@ impl Handler {
                 None => return Ok(()),
             };
 
+            if self.connection.take_backpressured() {
+                self.db.record_backpressure();
+            }
+
+            // Time spent in each command, including writing the response, is
+            // recorded for `INFO commandstats`.
+            let start = Instant::now();
+
+            // Route the request to the database selected on the connection.
+            if self.db.index() != self.connection.database() {
+                self.db = self.db.select(self.connection.database());
+            }
+
+            // Until the connection has authenticated, and switched to RESP3
+            // if required, requests are not served by the fast path, so that
+            // they are checked below.
+            let authenticated = self.connection.is_authenticated();
+            let negotiated = self.connection.protocol() >= 3 || !self.db.config().protocol_required;
+
+            // Plain `GET` and `SET` requests are applied directly from the
+            // frame, skipping the construction of a command struct. All other
+            // requests go through the generic path below.
+            //
+            // Inside a transaction, requests must be queued rather than
+            // applied, so none are served by the fast path either.
+            //
+            // Write requests must be logged to the append-only file, which
+            // the fast path does not do, so it is skipped when one is in use.
+            if authenticated
+                && negotiated
+                && !self.connection.in_transaction()
+                && self.db.config().command_fast_path
+                && self.db.aof().is_none()
+            {
+                let applied = {
+                    let _guard = self.db.command_guard().await;
+                    fast_path::apply(&frame, &self.db)
+                };
+
+                if let Some((name, response)) = applied {
+                    debug!(?response);
+                    self.connection.write_frame(&response).await?;
+                    self.db.record_command(name, start.elapsed());
+                    continue;
+                }
+            }
+
+            // Write commands are logged to the append-only file as received,
+            // so the frame is kept until the command is known.
+            let logged = self.db.aof().map(|_| frame.clone());
+
             // Convert the redis frame into a command struct. This returns an
             // error if the frame is not a valid redis command or it is an
             // unsupported command.
-            let cmd = Command::from_frame(frame)?;
+            //
+            // Inside a transaction, an invalid command aborts the transaction
+            // instead of closing the connection.
+            let cmd = match Command::from_frame(frame) {
+                Ok(cmd) => cmd,
+                Err(err) => match self.connection.transaction_mut() {
+                    Some(transaction) => {
+                        transaction.fail();
+
+                        let msg = err.to_string();
+                        let response = if msg.starts_with("ERR ") {
+                            Frame::Error(msg)
+                        } else {
+                            Frame::Error(format!("ERR {}", msg))
+                        };
+                        debug!(?response);
+                        self.connection.write_frame(&response).await?;
+                        continue;
+                    }
+                    None => return Err(err),
+                },
+            };
+
+            // Only `HELLO 3`, or `HELLO` to describe the server, is accepted
+            // before switching to RESP3 when it is required.
+            let rejected = match &cmd {
+                Command::Hello(hello) => hello.protocol() == Some(2),
+                _ => !negotiated,
+            };
+            if rejected && self.db.config().protocol_required {
+                let response = Frame::Error("NOPROTO unsupported protocol version".to_string());
+                debug!(?response);
+                self.connection.write_frame(&response).await?;
+                continue;
+            }
+
+            // Only `AUTH` and `HELLO` are accepted before authenticating.
+            if !authenticated && !matches!(cmd, Command::Auth(_) | Command::Hello(_)) {
+                let response = Frame::Error("NOAUTH Authentication required".to_string());
+                debug!(?response);
+                self.connection.write_frame(&response).await?;
+                continue;
+            }
+
+            let logged = logged.filter(|_| cmd.is_write());
+
+            // Inside a transaction, commands are queued until `EXEC`, which
+            // applies them, or `DISCARD`.
+            if let Some(transaction) = self.connection.transaction_mut() {
+                match cmd {
+                    Command::Multi(_) | Command::Exec(_) | Command::Discard(_) => {}
+                    cmd => {
+                        let response = transaction.queue(cmd, logged);
+                        debug!(?response);
+                        self.connection.write_frame(&response).await?;
+                        continue;
+                    }
+                }
+            }
+
+            // Unknown commands are not recorded, as Redis does. The name is
+            // copied as applying the command consumes it.
+            let name = match &cmd {
+                Command::Unknown(_) => None,
+                cmd => Some(cmd.get_name().to_string()),
+            };
 
             // Logs the `cmd` object. The syntax here is a shorthand provided by
             // the `tracing` crate. It can be thought of as similar to:
@@ -363,10 +655,68 @@ impl Handler {
             // command to write response frames directly to the connection. In
             // the case of pub/sub, multiple frames may be send back to the
             // peer.
-            cmd.apply(&self.db, &mut self.connection, &mut self.shutdown)
-                .await?;
+            //
+            // Commands are applied holding a guard which keeps transactions
+            // from running meanwhile. Their replies are collected and only
+            // written once the guard is dropped, so a peer slow to read them
+            // does not hold up transactions. `EXEC` takes its own guard.
+            // Commands which may wait on other connections do not hold the
+            // guard, as they would otherwise hold up transactions indefinitely.
+            let guard = match cmd {
+                Command::Exec(_)
+                | Command::Subscribe(_)
+                | Command::Psubscribe(_)
+                | Command::BLPop(_) => None,
+                _ => Some(self.db.command_guard().await),
+            };
+
+            // Write commands hold the append-only file log until they are
+            // logged, so that they are logged in the order they are applied.
+            let mut log = match (&logged, self.db.aof()) {
+                (Some(_), Some(aof)) => Some(aof.lock(&self.db).await?),
+                _ => None,
+            };
+
+            if guard.is_some() {
+                self.connection.start_capture();
+            }
+
+            cmd.apply(
+                &self.db,
+                &mut self.connection,
+                &mut self.shutdown,
+                self.client_id,
+            )
+            .await?;
+
+            if let Some(guard) = guard {
+                let replies = self.connection.finish_capture();
+
+                if let (Some(log), Some(frame)) = (&mut log, logged) {
+                    log.append(&self.db, frame, replies.last()).await?;
+                }
+
+                drop(log);
+                drop(guard);
+
+                for reply in &replies {
+                    self.connection.feed_frame(reply).await?;
+                }
+                self.connection.flush().await?;
+            }
+
+            if let Some(name) = name {
+                self.db.record_command(&name, start.elapsed());
+            }
         }
 
         Ok(())
     }
 }
+
+impl Drop for Handler {
+    fn drop(&mut self) {
+        unwatch_all(&self.db, &mut self.connection);
+        self.db.unregister_client(self.client_id);
+    }
+}
@@ -1,4 +1,5 @@
-use tokio::sync::broadcast;
+use std::sync::Arc;
+use tokio::sync::{broadcast, Notify};
 
 /// Listens for the server shutdown signal.
 ///
@@ -6,6 +7,9 @@ use tokio::sync::broadcast;
 /// ever sent. Once a value has been sent via the broadcast channel, the server
 /// should shutdown.
 ///
+/// A single connection may also be asked to close, without shutting down the
+/// server, using the `close` notification. Both are handled the same way.
+///
 /// The `Shutdown` struct listens for the signal and tracks that the signal has
 /// been received. Callers may query for whether the shutdown signal has been
 /// received or not.
@@ -16,14 +20,19 @@ pub(crate) struct Shutdown {
 
     /// The receive half of the channel used to listen for shutdown.
     notify: broadcast::Receiver<()>,
+
+    /// Notified when only this connection should close.
+    close: Arc<Notify>,
 }
 
 impl Shutdown {
-    /// Create a new `Shutdown` backed by the given `broadcast::Receiver`.
-    pub(crate) fn new(notify: broadcast::Receiver<()>) -> Shutdown {
+    /// Create a new `Shutdown` backed by the given `broadcast::Receiver` and
+    /// per-connection `close` notification.
+    pub(crate) fn new(notify: broadcast::Receiver<()>, close: Arc<Notify>) -> Shutdown {
         Shutdown {
             is_shutdown: false,
             notify,
+            close,
         }
     }
 
@@ -40,8 +49,11 @@ impl Shutdown {
             return;
         }
 
-        // Cannot receive a "lag error" as only one value is ever sent.
-        let _ = self.notify.recv().await;
+        tokio::select! {
+            // Cannot receive a "lag error" as only one value is ever sent.
+            _ = self.notify.recv() => {}
+            _ = self.close.notified() => {}
+        }
 
         // Remember that the signal has been received.
         self.is_shutdown = true;
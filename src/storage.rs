@@ -0,0 +1,294 @@
+//! Key-value storage backing `Db`.
+//!
+//! `Db` keeps string values in a `Storage` trait object, so different storage
+//! engines can be tried without touching the commands. `MemoryStorage` is the
+//! default. The storage is wrapped in `Tracked`, which keeps track of the
+//! memory used by the entries for `Config::maxmemory`. `Lru` records the
+//! accesses to keys of any type, to evict them least recently used first.
+
+use bytes::Bytes;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+use tokio::time::Instant;
+
+/// Longest string Redis stores in the same allocation as its object header.
+const EMBSTR_SIZE_LIMIT: usize = 44;
+
+/// Approximate number of bytes used by an entry on top of its key and data:
+/// the map slot, the `Entry` itself and the headers of the allocations.
+const ENTRY_OVERHEAD: usize = 64;
+
+/// Approximate number of bytes used by an element of a hash, list, set or
+/// sorted set on top of its data.
+const ELEMENT_OVERHEAD: usize = 16;
+
+/// Entry in the key-value store
+#[derive(Debug, Clone)]
+pub(crate) struct Entry {
+    /// Stored data
+    pub(crate) data: Bytes,
+
+    /// Instant at which the entry expires and should be removed from the
+    /// database.
+    pub(crate) expires_at: Option<Instant>,
+
+    /// Encoding reported by `OBJECT ENCODING`
+    pub(crate) encoding: Encoding,
+}
+
+impl Entry {
+    /// Create an entry holding `data`, classifying its encoding.
+    pub(crate) fn new(data: Bytes, expires_at: Option<Instant>) -> Entry {
+        Entry {
+            encoding: Encoding::of(&data),
+            data,
+            expires_at,
+        }
+    }
+}
+
+/// The encoding Redis would use to store a string value.
+///
+/// Values are always held as `Bytes`, this only mirrors what Redis reports.
+/// The encoding is picked when a value is stored whole. Commands modifying a
+/// value in place should set it to `Raw`, as Redis does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    /// The value is the canonical representation of a 64 bit signed integer
+    Int,
+
+    /// Short string, up to `EMBSTR_SIZE_LIMIT` bytes
+    Embstr,
+
+    /// Any other string
+    Raw,
+}
+
+impl Encoding {
+    /// Returns the encoding of `data` when stored whole.
+    pub(crate) fn of(data: &[u8]) -> Encoding {
+        // Only values which are formatted back to the same bytes are stored as
+        // integers, so "007" or "+1" are strings.
+        let value = std::str::from_utf8(data)
+            .ok()
+            .and_then(|text| text.parse::<i64>().ok());
+        let is_int = match value {
+            Some(value) => value.to_string().as_bytes() == data,
+            None => false,
+        };
+
+        if is_int {
+            Encoding::Int
+        } else if data.len() <= EMBSTR_SIZE_LIMIT {
+            Encoding::Embstr
+        } else {
+            Encoding::Raw
+        }
+    }
+
+    /// Returns the name of the encoding.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Int => "int",
+            Encoding::Embstr => "embstr",
+            Encoding::Raw => "raw",
+        }
+    }
+}
+
+/// The key-value operations performed by `Db`.
+///
+/// The storage is guarded by the `Db` mutex, so implementations do not need
+/// any synchronization of their own. Expirations are tracked and purged by
+/// `Db`, implementations only store `Entry::expires_at`.
+pub(crate) trait Storage: fmt::Debug + Send {
+    /// Returns the entry associated with `key`.
+    fn get(&self, key: &str) -> Option<&Entry>;
+
+    /// Associates `entry` with `key`, returning the entry it replaces.
+    fn set(&mut self, key: String, entry: Entry) -> Option<Entry>;
+
+    /// Removes the entry associated with `key`, returning it.
+    fn remove(&mut self, key: &str) -> Option<Entry>;
+
+    /// Removes every entry.
+    fn clear(&mut self);
+
+    /// Returns the number of entries.
+    fn len(&self) -> usize;
+
+    /// Iterates over the keys and their entries, in no particular order.
+    fn iter(&self) -> Box<dyn Iterator<Item = (&str, &Entry)> + '_>;
+
+    /// Returns a copy of the storage which is not affected by later writes.
+    ///
+    /// This is called by `Db::snapshot` while holding the `Db` lock and should
+    /// be cheap.
+    fn snapshot(&self) -> Box<dyn Storage>;
+}
+
+/// A `Storage`, along with the approximate number of bytes used by its
+/// entries.
+///
+/// `Db` goes through this wrapper for every access to the storage, so the
+/// figure is kept up to date whatever the `Storage` implementation.
+#[derive(Debug)]
+pub(crate) struct Tracked {
+    storage: Box<dyn Storage>,
+
+    /// Sum of `entry_size` over the entries.
+    used_memory: usize,
+}
+
+/// Last time each key was accessed, when keys are evicted least recently used
+/// first.
+#[derive(Debug, Default)]
+pub(crate) struct Lru {
+    /// Last access of each key
+    accessed: HashMap<String, Instant>,
+
+    /// Keys ordered by last access, least recent first. As with
+    /// expirations, the key breaks ties between accesses at the same instant.
+    order: BTreeSet<(Instant, String)>,
+}
+
+/// Returns the approximate number of bytes used to store `data` at `key`.
+///
+/// The values of the other types use `entry_size(key, &[])`, plus the
+/// `element_size` of each of their elements.
+pub(crate) fn entry_size(key: &str, data: &[u8]) -> usize {
+    ENTRY_OVERHEAD + key.len() + data.len()
+}
+
+/// Returns the approximate number of bytes used to store `data` as an element
+/// of a hash, list, set or sorted set. A hash field and its value count as
+/// one element.
+pub(crate) fn element_size(data: &[u8]) -> usize {
+    ELEMENT_OVERHEAD + data.len()
+}
+
+impl Tracked {
+    /// Wrap `storage`, which may already hold entries.
+    pub(crate) fn new(storage: Box<dyn Storage>) -> Tracked {
+        let used_memory = storage
+            .iter()
+            .map(|(key, entry)| entry_size(key, &entry.data))
+            .sum();
+
+        Tracked {
+            storage,
+            used_memory,
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&Entry> {
+        self.storage.get(key)
+    }
+
+    /// Associates `entry` with `key`, returning the entry it replaces.
+    pub(crate) fn set(&mut self, key: String, entry: Entry) -> Option<Entry> {
+        self.used_memory += entry_size(&key, &entry.data);
+
+        let prev = self.storage.set(key.clone(), entry);
+
+        if let Some(prev) = &prev {
+            self.used_memory -= entry_size(&key, &prev.data);
+        }
+
+        prev
+    }
+
+    pub(crate) fn remove(&mut self, key: &str) -> Option<Entry> {
+        let prev = self.storage.remove(key)?;
+        self.used_memory -= entry_size(key, &prev.data);
+        Some(prev)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.storage.clear();
+        self.used_memory = 0;
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    pub(crate) fn snapshot(&self) -> Box<dyn Storage> {
+        self.storage.snapshot()
+    }
+
+    /// Returns the approximate number of bytes used by the entries.
+    pub(crate) fn used_memory(&self) -> usize {
+        self.used_memory
+    }
+}
+
+impl Lru {
+    /// Record an access to `key` at `now`.
+    pub(crate) fn access(&mut self, key: &str, now: Instant) {
+        self.forget(key);
+        self.accessed.insert(key.to_string(), now);
+        self.order.insert((now, key.to_string()));
+    }
+
+    /// Stop tracking `key`, which was removed.
+    pub(crate) fn forget(&mut self, key: &str) {
+        if let Some(when) = self.accessed.remove(key) {
+            self.order.remove(&(when, key.to_string()));
+        }
+    }
+
+    /// Iterates over the keys, least recently accessed first, along with
+    /// their last access.
+    pub(crate) fn least_recently_used(&self) -> impl Iterator<Item = (Instant, &str)> {
+        self.order.iter().map(|(when, key)| (*when, &key[..]))
+    }
+}
+
+/// The default `Storage`, a `HashMap` held in memory.
+///
+/// The map is shared with the snapshots taken since it was last modified.
+/// Writes go through `Arc::make_mut`, which copies the map first if a snapshot
+/// still holds it. The values are `Bytes`, so the copy does not copy the data
+/// itself.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MemoryStorage {
+    entries: Arc<HashMap<String, Entry>>,
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.get(key)
+    }
+
+    fn set(&mut self, key: String, entry: Entry) -> Option<Entry> {
+        Arc::make_mut(&mut self.entries).insert(key, entry)
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        // Avoid copying a shared map when there is nothing to remove.
+        if !self.entries.contains_key(key) {
+            return None;
+        }
+
+        Arc::make_mut(&mut self.entries).remove(key)
+    }
+
+    fn clear(&mut self) {
+        // Snapshots keep the map they share, it is not copied.
+        self.entries = Arc::default();
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&str, &Entry)> + '_> {
+        Box::new(self.entries.iter().map(|(key, entry)| (&key[..], entry)))
+    }
+
+    fn snapshot(&self) -> Box<dyn Storage> {
+        Box::new(self.clone())
+    }
+}
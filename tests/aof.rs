@@ -0,0 +1,224 @@
+use bytes::Bytes;
+use mini_redis::{clients::Client, server, AppendFsync, Config};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// A server logging its write commands to an append-only file, which can be
+/// shut down and started again.
+struct Server {
+    addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+/// Write commands, including the ones applied by a transaction and in
+/// another database, are applied again after a restart. Random pops remove
+/// the same members.
+#[tokio::test]
+async fn replay_writes_after_restart() {
+    let path = aof_path("writes");
+
+    let server = Server::start(&path).await;
+    let mut client = Client::connect(server.addr).await.unwrap();
+
+    client.set("hello", "world".into()).await.unwrap();
+    client.incr("counter").await.unwrap();
+    client
+        .sadd("set", &["a".into(), "b".into(), "c".into()])
+        .await
+        .unwrap();
+    let popped = client.spop("set").await.unwrap().unwrap();
+
+    client.multi().await.unwrap();
+    client.cmd("INCR").arg("counter").query().await.unwrap();
+    client
+        .cmd("RPUSH")
+        .arg("list")
+        .arg("x")
+        .query()
+        .await
+        .unwrap();
+    client.exec().await.unwrap().unwrap();
+
+    // Errors are not logged, reads neither.
+    client.cmd("INCR").arg("hello").query().await.unwrap_err();
+    client.get("hello").await.unwrap();
+
+    client.select(1).await.unwrap();
+    client.set("other", "db".into()).await.unwrap();
+
+    drop(client);
+    server.stop().await;
+
+    let log = std::fs::read(&path).unwrap();
+    let log = String::from_utf8_lossy(&log);
+    assert!(!log.contains("SPOP"), "{}", log);
+    assert!(!log.contains("GET\r"), "{}", log);
+
+    let server = Server::start(&path).await;
+    let mut client = Client::connect(server.addr).await.unwrap();
+
+    assert_eq!(Some("world".into()), client.get("hello").await.unwrap());
+    assert_eq!(3, client.incr("counter").await.unwrap());
+    let mut members = client.smembers("set").await.unwrap();
+    members.sort();
+    let mut expected: Vec<Bytes> = vec!["a".into(), "b".into(), "c".into()];
+    expected.retain(|member| *member != popped);
+    assert_eq!(expected, members);
+    let list = client.lrange("list", 0, -1).await.unwrap();
+    assert_eq!(vec![Bytes::from("x")], list);
+    client.select(1).await.unwrap();
+    assert_eq!(Some("db".into()), client.get("other").await.unwrap());
+
+    drop(client);
+    server.stop().await;
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Elements handed to a client blocked in `BLPOP` stay popped after a
+/// restart.
+#[tokio::test]
+async fn replay_blocked_pop() {
+    let path = aof_path("blpop");
+
+    let server = Server::start(&path).await;
+    let mut blocked = Client::connect(server.addr).await.unwrap();
+    let mut client = Client::connect(server.addr).await.unwrap();
+
+    let pop = tokio::spawn(async move {
+        let popped = blocked.blpop(&["jobs"], Duration::from_secs(5)).await;
+        (blocked, popped.unwrap())
+    });
+
+    // Give the client time to block.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    client
+        .cmd("RPUSH")
+        .arg("jobs")
+        .arg("a")
+        .arg("b")
+        .query()
+        .await
+        .unwrap();
+
+    let (blocked, popped) = pop.await.unwrap();
+    assert_eq!(Some(("jobs".to_string(), Bytes::from("a"))), popped);
+
+    drop(blocked);
+    drop(client);
+    server.stop().await;
+
+    let server = Server::start(&path).await;
+    let mut client = Client::connect(server.addr).await.unwrap();
+
+    let list = client.lrange("jobs", 0, -1).await.unwrap();
+    assert_eq!(vec![Bytes::from("b")], list);
+
+    drop(client);
+    server.stop().await;
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Expirations are kept at the time they were set for, rather than armed
+/// again when the file is replayed. Keys which expired while the server was
+/// stopped stay expired.
+#[tokio::test]
+async fn replay_expirations_at_same_time() {
+    let path = aof_path("expire");
+
+    let server = Server::start(&path).await;
+    let mut client = Client::connect(server.addr).await.unwrap();
+
+    client
+        .set_expires("short", "a".into(), Duration::from_millis(300))
+        .await
+        .unwrap();
+    client.set("expired", "b".into()).await.unwrap();
+    assert!(client.expire("expired", 1).await.unwrap());
+    client
+        .set_expires("kept", "c".into(), Duration::from_secs(100))
+        .await
+        .unwrap();
+    client.lpush("list", &["d".into()]).await.unwrap();
+    assert!(client.expire("list", 100).await.unwrap());
+
+    drop(client);
+    server.stop().await;
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    let server = Server::start(&path).await;
+    let mut client = Client::connect(server.addr).await.unwrap();
+
+    assert_eq!(None, client.get("short").await.unwrap());
+    assert_eq!(None, client.get("expired").await.unwrap());
+    assert_eq!(Some("c".into()), client.get("kept").await.unwrap());
+    let ttl = client.ttl("kept").await.unwrap();
+    assert!((97..=99).contains(&ttl), "{}", ttl);
+    let ttl = client.ttl("list").await.unwrap();
+    assert!((97..=99).contains(&ttl), "{}", ttl);
+
+    drop(client);
+    server.stop().await;
+    let _ = std::fs::remove_file(&path);
+}
+
+/// The server does not start if the append-only file ends with a truncated
+/// command, leaving the file untouched.
+#[tokio::test]
+async fn refuse_truncated_file() {
+    let path = aof_path("truncated");
+    let data = b"*2\r\n$3\r\nDEL\r\n$5\r\nhel";
+    std::fs::write(&path, data).unwrap();
+
+    let server = Server::start(&path).await;
+
+    // The server returns without waiting for the shutdown signal.
+    server.handle.await.unwrap();
+    assert_eq!(&data[..], &std::fs::read(&path).unwrap()[..]);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Returns a path for the append-only file of the test `name`, removing any
+/// file left by a previous run.
+fn aof_path(name: &str) -> PathBuf {
+    let file = format!("mini-redis-{}-{}.aof", std::process::id(), name);
+    let path = std::env::temp_dir().join(file);
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+impl Server {
+    async fn start(appendonly: &Path) -> Server {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let config = Config {
+            appendonly: Some(appendonly.to_path_buf()),
+            appendfsync: AppendFsync::Always,
+            ..Config::default()
+        };
+
+        let (shutdown, rx) = oneshot::channel();
+        let handle =
+            tokio::spawn(async move { server::run_with_config(listener, config, rx).await });
+
+        Server {
+            addr,
+            shutdown,
+            handle,
+        }
+    }
+
+    /// Shut the server down, waiting for every connection to be closed and
+    /// the append-only file to be synced.
+    async fn stop(self) {
+        self.shutdown.send(()).unwrap();
+        self.handle.await.unwrap();
+    }
+}
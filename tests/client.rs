@@ -1,7 +1,15 @@
-use mini_redis::{clients::Client, server};
+use bytes::Bytes;
+use mini_redis::clients::{self, Client, LcsMatch, Subscriber};
+use mini_redis::cmd::{Aggregate, ScoreBound};
+use mini_redis::{server, Config, Connection, Frame, MaxmemoryPolicy};
 use std::net::SocketAddr;
+use std::time::Duration;
 use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
 
 /// A PING PONG test without message provided.
 /// It should return "PONG".
@@ -25,28 +33,2599 @@ async fn ping_pong_with_message() {
     assert_eq!("你好世界".as_bytes(), &pong[..]);
 }
 
+/// HELLO without a version describes the server and keeps the client on
+/// RESP2.
+#[tokio::test]
+async fn hello_describes_server() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let info = client.hello().await.unwrap();
+    assert_eq!(info["server"], "mini-redis");
+    assert!(matches!(info["proto"], Frame::Integer(2)));
+    assert_eq!(info["role"], "master");
+
+    let pong = client.ping(None).await.unwrap();
+    assert_eq!(b"PONG", &pong[..]);
+}
+
+/// With `requirepass` set, commands other than AUTH and HELLO are rejected
+/// until the connection authenticates with the right password.
+#[tokio::test]
+async fn auth_required() {
+    let config = Config {
+        requirepass: Some("secret".to_string()),
+        ..Config::default()
+    };
+    let (addr, _) = start_server_with_config(config).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let err = client.get("hello").await.unwrap_err();
+    assert_eq!("NOAUTH Authentication required", err.to_string());
+
+    let err = client.set("hello", "world".into()).await.unwrap_err();
+    assert_eq!("NOAUTH Authentication required", err.to_string());
+
+    let info = client.hello().await.unwrap();
+    assert_eq!(info["server"], "mini-redis");
+
+    let err = client.auth("wrong").await.unwrap_err();
+    assert_eq!("WRONGPASS invalid password", err.to_string());
+
+    let err = client.ping(None).await.unwrap_err();
+    assert_eq!("NOAUTH Authentication required", err.to_string());
+
+    client.auth("secret").await.unwrap();
+    client.set("hello", "world".into()).await.unwrap();
+    let value = client.get("hello").await.unwrap().unwrap();
+    assert_eq!(b"world", &value[..]);
+
+    // Other connections are not authenticated
+    let mut other = Client::connect(addr).await.unwrap();
+    assert!(other.get("hello").await.is_err());
+}
+
+/// AUTH fails when the server does not require a password.
+#[tokio::test]
+async fn auth_without_password() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    assert!(client.auth("secret").await.is_err());
+    assert_eq!(b"PONG", &client.ping(None).await.unwrap()[..]);
+}
+
+/// Each database selected with SELECT is an independent keyspace.
+#[tokio::test]
+async fn select_database() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    client.set("hello", "zero".into()).await.unwrap();
+
+    client.select(15).await.unwrap();
+    assert_eq!(None, client.get("hello").await.unwrap());
+    client.set("hello", "fifteen".into()).await.unwrap();
+    client.sadd("tags", &["a".into()]).await.unwrap();
+
+    let err = client.select(16).await.unwrap_err();
+    assert_eq!("ERR DB index is out of range", err.to_string());
+
+    // The failed SELECT left the connection on database 15.
+    let value = client.get("hello").await.unwrap().unwrap();
+    assert_eq!(b"fifteen", &value[..]);
+
+    client.select(0).await.unwrap();
+    let value = client.get("hello").await.unwrap().unwrap();
+    assert_eq!(b"zero", &value[..]);
+    assert_eq!("none", client.key_type("tags").await.unwrap());
+
+    // Other connections start on database 0.
+    let mut other = Client::connect(addr).await.unwrap();
+    let value = other.get("hello").await.unwrap().unwrap();
+    assert_eq!(b"zero", &value[..]);
+
+    let info = other.info(Some("keyspace")).await.unwrap();
+    let info = std::str::from_utf8(&info).unwrap();
+    assert!(info.contains("\r\ndb0:keys=1,"));
+    assert!(info.contains("\r\ndb15:keys=2,"));
+}
+
+/// The number of databases is configurable.
+#[tokio::test]
+async fn configured_databases() {
+    let config = Config {
+        databases: 2,
+        ..Config::default()
+    };
+    let (addr, _) = start_server_with_config(config).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    client.select(1).await.unwrap();
+    assert!(client.select(2).await.is_err());
+}
+
+/// FLUSHDB clears the selected database only, FLUSHALL clears all of them.
+/// Neither affects pub/sub subscriptions.
+#[tokio::test]
+async fn flush_databases() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let subscriber = Client::connect(addr).await.unwrap();
+    let mut subscriber = subscriber.subscribe(vec!["news".into()]).await.unwrap();
+
+    client.set("a", "1".into()).await.unwrap();
+    client.rpush("list", &["x".into()]).await.unwrap();
+    client.select(1).await.unwrap();
+    client.set("b", "2".into()).await.unwrap();
+    client.sadd("tags", &["t".into()]).await.unwrap();
+    client.zadd("scores", &[(1.0, "m".into())]).await.unwrap();
+
+    client.flushdb().await.unwrap();
+    assert_eq!(None, client.get("b").await.unwrap());
+    assert_eq!("none", client.key_type("tags").await.unwrap());
+    assert_eq!("none", client.key_type("scores").await.unwrap());
+
+    client.select(0).await.unwrap();
+    assert!(client.get("a").await.unwrap().is_some());
+
+    // A key set with an expiration before the flush does not remove the key
+    // set after it.
+    client
+        .set_expires("short", "1".into(), Duration::from_millis(50))
+        .await
+        .unwrap();
+
+    client.flushall().await.unwrap();
+    assert_eq!(None, client.get("a").await.unwrap());
+    assert_eq!("none", client.key_type("list").await.unwrap());
+
+    client.set("short", "2".into()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(client.get("short").await.unwrap().is_some());
+
+    assert_eq!(1, client.publish("news", "hi".into()).await.unwrap());
+    let message = subscriber.next_message().await.unwrap().unwrap();
+    assert_eq!(b"hi", &message.content[..]);
+}
+
+/// SCAN returns every key once across calls, and filters them with MATCH.
+#[tokio::test]
+async fn scan_keys() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let (cursor, keys) = client.scan(0, None, None).await.unwrap();
+    assert_eq!((0, vec![]), (cursor, keys));
+
+    for i in 0..50 {
+        let key = format!("k:{}", i);
+        client.set(&key, "x".into()).await.unwrap();
+    }
+    client.sadd("tags", &["a".into()]).await.unwrap();
+    client.rpush("queue", &["a".into()]).await.unwrap();
+
+    let mut seen = vec![];
+    let mut cursor = 0;
+    let mut calls = 0;
+    loop {
+        let (next, keys) = client.scan(cursor, None, Some(7)).await.unwrap();
+        assert!(keys.len() <= 7);
+        seen.extend(keys);
+        calls += 1;
+
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+
+        // Keys added or removed during the scan do not make it skip the keys
+        // present all along.
+        if calls == 2 {
+            client.set("added", "x".into()).await.unwrap();
+            client.del(&["k:0"]).await.unwrap();
+        }
+    }
+    assert!(calls >= 8);
+
+    seen.sort();
+    seen.dedup();
+    let expected = (1..50).map(|i| format!("k:{}", i));
+    for key in expected.chain(["queue".to_string(), "tags".to_string()]) {
+        assert!(seen.contains(&key), "{} was not returned", key);
+    }
+
+    let mut matched = vec![];
+    let mut cursor = 0;
+    loop {
+        let pattern = Some("k:[1-2]?");
+        let (next, keys) = client.scan(cursor, pattern, None).await.unwrap();
+        matched.extend(keys);
+
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+    }
+    matched.sort();
+    let expected: Vec<_> = (10..30).map(|i| format!("k:{}", i)).collect();
+    assert_eq!(expected, matched);
+
+    // A single call examines every key when COUNT is large enough.
+    for (pattern, expected) in [
+        ("*9", vec!["k:19", "k:29", "k:39", "k:49", "k:9"]),
+        ("k:[^0-6]", vec!["k:7", "k:8", "k:9"]),
+        ("*:4*8", vec!["k:48"]),
+        ("k:\\4[0-3]", vec!["k:40", "k:41", "k:42", "k:43"]),
+    ] {
+        let (cursor, mut keys) = client.scan(0, Some(pattern), Some(100)).await.unwrap();
+        keys.sort();
+        keys.truncate(5);
+        assert_eq!(0, cursor);
+        assert_eq!(expected, keys, "pattern {}", pattern);
+    }
+}
+
+/// RENAME moves a value of any type, with its expiration, replacing the value
+/// stored under the new name.
+#[tokio::test]
+async fn rename_keys() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let err = client.rename("missing", "other").await.unwrap_err();
+    assert_eq!("ERR no such key", err.to_string());
+
+    client
+        .set_expires("old", "value".into(), Duration::from_secs(60))
+        .await
+        .unwrap();
+    client.sadd("new", &["member".into()]).await.unwrap();
+
+    client.rename("old", "new").await.unwrap();
+    assert_eq!(None, client.get("old").await.unwrap());
+    let value = client.get("new").await.unwrap().unwrap();
+    assert_eq!(b"value", &value[..]);
+    assert!(client.pttl("new").await.unwrap() > 0);
+    assert_eq!(-2, client.pttl("old").await.unwrap());
+
+    // Renaming a key to itself leaves it untouched.
+    client.rename("new", "new").await.unwrap();
+    assert!(client.get("new").await.unwrap().is_some());
+
+    client.zadd("scores", &[(1.5, "m".into())]).await.unwrap();
+    client.rename("scores", "ranking").await.unwrap();
+    assert_eq!("none", client.key_type("scores").await.unwrap());
+    let score = client.zscore("ranking", "m".into()).await.unwrap();
+    assert_eq!(Some(1.5), score);
+
+    // RENAMENX only renames to an unused name.
+    assert!(!client.rename_nx("ranking", "new").await.unwrap());
+    assert!(!client.rename_nx("ranking", "ranking").await.unwrap());
+    assert!(client.rename_nx("ranking", "board").await.unwrap());
+    assert_eq!("zset", client.key_type("board").await.unwrap());
+    assert!(client.rename_nx("missing", "other").await.is_err());
+}
+
+/// Renaming a list to a key clients are blocked on serves them.
+#[tokio::test]
+async fn rename_serves_blocked_clients() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let mut blocked = Client::connect(addr).await.unwrap();
+    let timeout = Duration::from_secs(5);
+    let popped = tokio::spawn(async move { blocked.blpop(&["queue"], timeout).await.unwrap() });
+
+    // Let the client block
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let values = ["a".into(), "b".into()];
+    client.rpush("pending", &values).await.unwrap();
+    client.rename("pending", "queue").await.unwrap();
+
+    let popped = popped.await.unwrap();
+    assert_eq!(Some(("queue".to_string(), "a".into())), popped);
+    let remaining = client.lrange("queue", 0, -1).await.unwrap();
+    assert_eq!(vec![Bytes::from("b")], remaining);
+}
+
+/// Commands sent after MULTI are queued, and applied by EXEC, which replies
+/// with the reply to each of them.
+#[tokio::test]
+async fn transaction_applies_queued_commands() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut other = Client::connect(addr).await.unwrap();
+
+    client.multi().await.unwrap();
+    assert!(client.multi().await.is_err());
+
+    let queued = client.cmd("SET").arg("n").arg("1").query().await.unwrap();
+    assert_eq!(queued, "QUEUED");
+    client.cmd("INCR").arg("n").query().await.unwrap();
+    client.cmd("RPUSH").arg("n").arg("x").query().await.unwrap();
+    client.cmd("GET").arg("n").query().await.unwrap();
+
+    // Nothing is applied before EXEC
+    assert_eq!(None, other.get("n").await.unwrap());
+
+    let replies = client.exec().await.unwrap().unwrap();
+    assert_eq!(4, replies.len());
+    assert_eq!(replies[0], "OK");
+    assert!(matches!(replies[1], Frame::Integer(2)));
+    assert!(matches!(&replies[2], Frame::Error(msg) if msg.starts_with("WRONGTYPE")));
+    assert_eq!(replies[3], "2");
+
+    assert_eq!(Some("2".into()), other.get("n").await.unwrap());
+    assert!(client.exec().await.is_err());
+}
+
+/// DISCARD drops the queued commands.
+#[tokio::test]
+async fn transaction_discard() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    assert!(client.discard().await.is_err());
+
+    client.multi().await.unwrap();
+    client.cmd("SET").arg("k").arg("v").query().await.unwrap();
+    client.discard().await.unwrap();
+
+    assert_eq!(None, client.get("k").await.unwrap());
+    assert!(client.exec().await.is_err());
+}
+
+/// A command which cannot be queued aborts the transaction at EXEC, without
+/// closing the connection.
+#[tokio::test]
+async fn transaction_aborted_by_invalid_command() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    client.multi().await.unwrap();
+    client.cmd("SET").arg("k").arg("v").query().await.unwrap();
+    let invalid = client.cmd("EXPIRE").arg("k").arg("soon").query().await;
+    assert!(invalid.is_err());
+
+    let err = client.exec().await.unwrap_err();
+    assert!(err.to_string().starts_with("EXECABORT"));
+    assert_eq!(None, client.get("k").await.unwrap());
+
+    client.multi().await.unwrap();
+    assert!(client.cmd("NOSUCHCOMMAND").query().await.is_err());
+    let blocking = client.cmd("BLPOP").arg("k").arg("0").query().await;
+    assert!(blocking.is_err());
+    assert!(client.exec().await.is_err());
+}
+
+/// Commands from other connections are not applied between the commands of a
+/// transaction.
+#[tokio::test]
+async fn transaction_is_atomic() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut other = Client::connect(addr).await.unwrap();
+
+    let concurrent = tokio::spawn(async move {
+        for _ in 0..100 {
+            other.incr("counter").await.unwrap();
+        }
+    });
+
+    client.multi().await.unwrap();
+    for _ in 0..100 {
+        client.cmd("INCR").arg("counter").query().await.unwrap();
+    }
+    let replies = client.exec().await.unwrap().unwrap();
+    concurrent.await.unwrap();
+
+    let values: Vec<i64> = replies
+        .iter()
+        .map(|reply| match reply {
+            Frame::Integer(value) => *value,
+            frame => panic!("unexpected reply {:?}", frame),
+        })
+        .collect();
+    assert!(values.windows(2).all(|pair| pair[1] == pair[0] + 1));
+}
+
+/// EXEC does not apply the transaction if a watched key was modified since it
+/// was watched.
+#[tokio::test]
+async fn watch_aborts_modified_transaction() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut other = Client::connect(addr).await.unwrap();
+
+    client.set("k", "1".into()).await.unwrap();
+
+    client.watch(&["k", "missing"]).await.unwrap();
+    other.set("k", "2".into()).await.unwrap();
+
+    client.multi().await.unwrap();
+    assert!(client.watch(&["k"]).await.is_err());
+    client.cmd("SET").arg("k").arg("3").query().await.unwrap();
+    assert!(client.exec().await.unwrap().is_none());
+    assert_eq!(Some("2".into()), client.get("k").await.unwrap());
+
+    // EXEC stopped watching the keys.
+    other.set("k", "4".into()).await.unwrap();
+    client.multi().await.unwrap();
+    client.cmd("SET").arg("k").arg("5").query().await.unwrap();
+    assert!(client.exec().await.unwrap().is_some());
+
+    // Creating a watched key modifies it too.
+    client.watch(&["missing"]).await.unwrap();
+    other.rpush("missing", &["a".into()]).await.unwrap();
+    client.multi().await.unwrap();
+    assert!(client.exec().await.unwrap().is_none());
+}
+
+/// EXEC applies the transaction if the watched keys were not modified, or
+/// were unwatched.
+#[tokio::test]
+async fn watch_unmodified_and_unwatch() {
+    let (addr, _) = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut other = Client::connect(addr).await.unwrap();
+
+    client.set("k", "1".into()).await.unwrap();
+
+    client.watch(&["k"]).await.unwrap();
+    other.set("unrelated", "1".into()).await.unwrap();
+    client.multi().await.unwrap();
+    client.cmd("INCR").arg("k").query().await.unwrap();
+    let replies = client.exec().await.unwrap().unwrap();
+    assert!(matches!(replies[0], Frame::Integer(2)));
+
+    client.watch(&["k"]).await.unwrap();
+    other.set("k", "10".into()).await.unwrap();
+    client.unwatch().await.unwrap();
+    client.multi().await.unwrap();
+    client.cmd("INCR").arg("k").query().await.unwrap();
+    let replies = client.exec().await.unwrap().unwrap();
+    assert!(matches!(replies[0], Frame::Integer(11)));
+}
+
 /// A basic "hello world" style test. A server instance is started in a
 /// background task. A client instance is then established and set and get
 /// commands are sent to the server. The response is then evaluated
 #[tokio::test]
-async fn key_value_get_set() {
+async fn key_value_get_set() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("hello", "world".into()).await.unwrap();
+
+    let value = client.get("hello").await.unwrap().unwrap();
+    assert_eq!(b"world", &value[..])
+}
+
+/// GETRANGE uses byte offsets by default, which can split a multibyte
+/// character. With CHARS, offsets count characters instead.
+#[tokio::test]
+async fn getrange_bytes_and_chars() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("text", "a😀b你c".into()).await.unwrap();
+
+    // Bytes: the emoji is 4 bytes long and is split.
+    let value = client.getrange("text", 0, 2, false).await.unwrap();
+    assert_eq!(&"a😀".as_bytes()[..3], &value[..]);
+
+    // Characters: the emoji is a single character.
+    let value = client.getrange("text", 0, 2, true).await.unwrap();
+    assert_eq!("a😀b".as_bytes(), &value[..]);
+
+    let value = client.getrange("text", -2, -1, true).await.unwrap();
+    assert_eq!("你c".as_bytes(), &value[..]);
+
+    let value = client.getrange("text", 1, 100, true).await.unwrap();
+    assert_eq!("😀b你c".as_bytes(), &value[..]);
+
+    let value = client.getrange("text", 3, 1, true).await.unwrap();
+    assert!(value.is_empty());
+
+    let value = client.getrange("missing", 0, -1, true).await.unwrap();
+    assert!(value.is_empty());
+}
+
+/// GETRANGE offsets are normalized exactly like Redis does, including
+/// negative and out of range offsets.
+#[tokio::test]
+async fn getrange_offsets() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .set("mykey", "This is a string".into())
+        .await
+        .unwrap();
+    client.set("empty", "".into()).await.unwrap();
+
+    // (key, start, end, expected)
+    let cases: &[(&str, i64, i64, &str)] = &[
+        // The examples from the GETRANGE documentation
+        ("mykey", 0, 3, "This"),
+        ("mykey", -3, -1, "ing"),
+        ("mykey", 0, -1, "This is a string"),
+        ("mykey", 10, 100, "string"),
+        // Single elements
+        ("mykey", 0, 0, "T"),
+        ("mykey", -1, -1, "g"),
+        ("mykey", 15, 15, "g"),
+        // Out of range offsets are clamped
+        ("mykey", -100, -1, "This is a string"),
+        ("mykey", -100, 3, "This"),
+        ("mykey", 5, 1000, "is a string"),
+        ("mykey", -100, -50, "T"),
+        ("mykey", 0, -100, "T"),
+        // Empty ranges
+        ("mykey", 16, 20, ""),
+        ("mykey", 100, 200, ""),
+        ("mykey", 5, 4, ""),
+        ("mykey", -1, -2, ""),
+        ("mykey", 3, -16, ""),
+        // Empty and missing values
+        ("empty", 0, -1, ""),
+        ("empty", 0, 0, ""),
+        ("empty", -1, 10, ""),
+        ("missing", 0, -1, ""),
+    ];
+
+    for &(key, start, end, expected) in cases {
+        let value = client.getrange(key, start, end, false).await.unwrap();
+        assert_eq!(
+            expected.as_bytes(),
+            &value[..],
+            "GETRANGE {} {} {}",
+            key,
+            start,
+            end
+        );
+    }
+}
+
+/// GETRANGE CHARS reports an error for values that are not valid UTF-8, and
+/// the connection remains usable.
+#[tokio::test]
+async fn getrange_chars_invalid_utf8() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .set("binary", vec![0x61, 0xff, 0x62].into())
+        .await
+        .unwrap();
+
+    let err = client.getrange("binary", 0, -1, true).await.unwrap_err();
+    assert_eq!("ERR value is not valid UTF-8", err.to_string());
+
+    // Byte offsets are still fine.
+    let value = client.getrange("binary", 0, -1, false).await.unwrap();
+    assert_eq!(&[0x61, 0xff, 0x62][..], &value[..]);
+}
+
+/// COMMAND GETKEYS returns the key arguments of an invocation.
+#[tokio::test]
+async fn command_getkeys() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let args = vec![
+        "SET".into(),
+        "foo".into(),
+        "bar".into(),
+        "px".into(),
+        "10".into(),
+    ];
+    let keys = client.command_getkeys(args).await.unwrap();
+    assert_eq!(vec![Bytes::from("foo")], keys);
+
+    let args = vec!["hset".into(), "hash".into(), "field".into(), "value".into()];
+    let keys = client.command_getkeys(args).await.unwrap();
+    assert_eq!(vec![Bytes::from("hash")], keys);
+
+    let args = vec!["getrange".into(), "text".into(), "0".into(), "-1".into()];
+    let keys = client.command_getkeys(args).await.unwrap();
+    assert_eq!(vec![Bytes::from("text")], keys);
+
+    let err = client
+        .command_getkeys(vec!["publish".into(), "chan".into(), "msg".into()])
+        .await
+        .unwrap_err();
+    assert_eq!("ERR The command has no key arguments", err.to_string());
+
+    let err = client
+        .command_getkeys(vec!["get".into()])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        "ERR Invalid number of arguments specified for command",
+        err.to_string()
+    );
+
+    let err = client
+        .command_getkeys(vec!["nosuchcommand".into(), "foo".into()])
+        .await
+        .unwrap_err();
+    assert_eq!("ERR Invalid command specified", err.to_string());
+}
+
+/// Raw commands with too few arguments are rejected before being sent.
+/// Commands the client does not know about are sent as is.
+#[tokio::test]
+async fn raw_command_arity() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let err = client.cmd("SET").arg("k").query().await.unwrap_err();
+    assert_eq!(
+        "ERR wrong number of arguments for 'set' command",
+        err.to_string()
+    );
+
+    let err = client.cmd("get").query().await.unwrap_err();
+    assert_eq!(
+        "ERR wrong number of arguments for 'get' command",
+        err.to_string()
+    );
+
+    // The connection is still usable, nothing was sent.
+    let value = client.cmd("SET").arg("k").arg("v").query().await.unwrap();
+    assert_eq!(value, "OK");
+
+    let value = client.cmd("GET").arg("k").query().await.unwrap();
+    assert_eq!(value, "v");
+
+    let err = client.cmd("nosuchcommand").query().await.unwrap_err();
+    assert_eq!("ERR unknown command 'nosuchcommand'", err.to_string());
+}
+
+/// DEBUG PROTOCOL replies with an example of each RESP type, as decoded by the
+/// client
+#[tokio::test]
+async fn debug_protocol() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let cases = [
+        ("string", r#"Bulk(b"Hello World")"#),
+        ("integer", "Integer(12345)"),
+        ("double", r#"Bulk(b"3.141")"#),
+        (
+            "bignum",
+            r#"Bulk(b"1234567999999999999999999999999999999")"#,
+        ),
+        ("null", "Null"),
+        ("array", "Array([Integer(0), Integer(1), Integer(2)])"),
+        ("set", "Array([Integer(0), Integer(1), Integer(2)])"),
+        (
+            "map",
+            "Array([Integer(0), Integer(0), Integer(1), Integer(1), Integer(2), Integer(0)])",
+        ),
+        (
+            "attrib",
+            r#"Bulk(b"Some real reply following the attribute")"#,
+        ),
+        ("verbatim", r#"Bulk(b"This is a verbatim\nstring")"#),
+        ("true", "Integer(1)"),
+        ("false", "Integer(0)"),
+    ];
+
+    for (kind, expected) in cases {
+        let frame = client.cmd("debug").arg("protocol").arg(kind).query().await;
+        assert_eq!(expected, format!("{:?}", frame.unwrap()), "{}", kind);
+    }
+
+    let err = client.cmd("debug").arg("protocol").arg("err").query().await;
+    assert_eq!("ERR This is an error", err.unwrap_err().to_string());
+
+    let err = client
+        .cmd("debug")
+        .arg("protocol")
+        .arg("nope")
+        .query()
+        .await;
+    assert!(err
+        .unwrap_err()
+        .to_string()
+        .starts_with("ERR Wrong protocol type name"));
+}
+
+/// DEBUG POPULATE creates keys in bulk, counted by DBSIZE
+#[tokio::test]
+async fn debug_populate() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("load:3", "kept".into()).await.unwrap();
+    assert_eq!(1, client.dbsize().await.unwrap());
+
+    let frame = client
+        .cmd("debug")
+        .arg("populate")
+        .arg("1000")
+        .arg("load")
+        .arg("16")
+        .query()
+        .await
+        .unwrap();
+    assert_eq!("Simple(\"OK\")", format!("{:?}", frame));
+
+    assert_eq!(1000, client.dbsize().await.unwrap());
+    assert_eq!(
+        &b"value:999\0\0\0\0\0\0\0"[..],
+        &client.get("load:999").await.unwrap().unwrap()[..]
+    );
+    assert_eq!(16, client.strlen("load:0").await.unwrap());
+
+    // Existing keys are not overwritten.
+    let value = client.get("load:3").await.unwrap().unwrap();
+    assert_eq!(&b"kept"[..], &value[..]);
+
+    // Without a size, values are `value:n`.
+    let frame = client.cmd("debug").arg("populate").arg("2").query().await;
+    assert!(frame.is_ok());
+
+    let value = client.get("key:1").await.unwrap().unwrap();
+    assert_eq!(&b"value:1"[..], &value[..]);
+    assert_eq!(1002, client.dbsize().await.unwrap());
+}
+
+/// DEL removes keys of any type and counts the keys that existed
+#[tokio::test]
+async fn del() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("a", "1".into()).await.unwrap();
+    client.set("b", "2".into()).await.unwrap();
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+
+    // Missing and repeated keys are not counted.
+    assert_eq!(2, client.del(&["a", "h", "missing", "a"]).await.unwrap());
+    assert!(client.get("a").await.unwrap().is_none());
+    assert!(client.hgetall(&"h".to_string()).await.unwrap().is_none());
+    assert_eq!(Some(Bytes::from("2")), client.get("b").await.unwrap());
+
+    assert_eq!(0, client.del(&["a"]).await.unwrap());
+
+    // The expiration of a deleted key does not apply to a new value.
+    client
+        .set_expires("e", "old".into(), Duration::from_millis(100))
+        .await
+        .unwrap();
+    assert_eq!(1, client.del(&["e"]).await.unwrap());
+    client.set("e", "new".into()).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(Some(Bytes::from("new")), client.get("e").await.unwrap());
+}
+
+/// EXISTS counts the keys of any type that exist, including repeated ones
+#[tokio::test]
+async fn exists() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("a", "1".into()).await.unwrap();
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+
+    assert_eq!(1, client.exists(&["a"]).await.unwrap());
+    assert_eq!(0, client.exists(&["missing"]).await.unwrap());
+    assert_eq!(3, client.exists(&["a", "h", "missing", "a"]).await.unwrap());
+
+    client.del(&["a"]).await.unwrap();
+    assert_eq!(1, client.exists(&["a", "h"]).await.unwrap());
+}
+
+/// INCR and DECR treat a missing key as zero and reject values which are not
+/// integers or would overflow
+#[tokio::test]
+async fn incr_decr() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    assert_eq!(1, client.incr("counter").await.unwrap());
+    assert_eq!(2, client.incr("counter").await.unwrap());
+    assert_eq!(-1, client.decr("other").await.unwrap());
+    assert_eq!(Some("2".into()), client.get("counter").await.unwrap());
+
+    client.set("text", "bar".into()).await.unwrap();
+    let err = client.incr("text").await.unwrap_err();
+    assert_eq!(
+        "ERR value is not an integer or out of range",
+        err.to_string()
+    );
+
+    client
+        .set("max", i64::MAX.to_string().into())
+        .await
+        .unwrap();
+    let err = client.incr("max").await.unwrap_err();
+    assert_eq!("ERR increment or decrement would overflow", err.to_string());
+    assert_eq!(
+        Some(i64::MAX.to_string().into()),
+        client.get("max").await.unwrap()
+    );
+
+    client
+        .set("min", i64::MIN.to_string().into())
+        .await
+        .unwrap();
+    assert!(client.decr("min").await.is_err());
+}
+
+/// INCRBY and DECRBY apply a signed delta and guard against overflow
+#[tokio::test]
+async fn incr_by_decr_by() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    assert_eq!(10, client.incr_by("counter", 10).await.unwrap());
+    assert_eq!(7, client.incr_by("counter", -3).await.unwrap());
+    assert_eq!(2, client.decr_by("counter", 5).await.unwrap());
+    assert_eq!(3, client.incr("counter").await.unwrap());
+
+    let err = client.incr_by("counter", i64::MAX).await.unwrap_err();
+    assert_eq!("ERR increment or decrement would overflow", err.to_string());
+
+    let err = client.decr_by("counter", i64::MIN).await.unwrap_err();
+    assert_eq!("ERR decrement would overflow", err.to_string());
+    assert_eq!(Some("3".into()), client.get("counter").await.unwrap());
+}
+
+/// OBJECT ENCODING classifies strings as Redis does
+#[tokio::test]
+async fn object_encoding() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    client.set("short", "hello".into()).await.unwrap();
+    client.set("long", "x".repeat(45).into()).await.unwrap();
+    client.set("limit", "x".repeat(44).into()).await.unwrap();
+    client.set("int", "-12345".into()).await.unwrap();
+    client.set("padded", "007".into()).await.unwrap();
+    client.incr("counter").await.unwrap();
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+
+    let cases = [
+        ("short", Some("embstr")),
+        ("long", Some("raw")),
+        ("limit", Some("embstr")),
+        ("int", Some("int")),
+        ("padded", Some("embstr")),
+        ("counter", Some("int")),
+        ("h", Some("hashtable")),
+        ("missing", None),
+    ];
+
+    for (key, expected) in cases {
+        let encoding = client.object_encoding(key).await.unwrap();
+        assert_eq!(expected, encoding.as_deref(), "{}", key);
+    }
+}
+
+/// Subscribe and unsubscribe confirmations carry the number of channels the
+/// client is subscribed to
+#[tokio::test]
+async fn subscription_confirmations() {
+    let (addr, _) = start_server().await;
+
+    let client = Client::connect(addr).await.unwrap();
+    let mut subscriber = client
+        .subscribe(vec!["a".into(), "b".into()])
+        .await
+        .unwrap();
+
+    let counts = |subscriber: &Subscriber| {
+        subscriber
+            .confirmations()
+            .iter()
+            .map(|c| (c.channel.clone(), c.count))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(vec![("a".into(), 1), ("b".into(), 2)], counts(&subscriber));
+
+    subscriber.subscribe(&["c".into()]).await.unwrap();
+    assert_eq!(vec![("c".into(), 3)], counts(&subscriber));
+
+    subscriber.unsubscribe(&["a".into()]).await.unwrap();
+    assert_eq!(vec![("a".into(), 2)], counts(&subscriber));
+
+    subscriber.unsubscribe(&[]).await.unwrap();
+    assert_eq!(
+        vec![1, 0],
+        counts(&subscriber)
+            .into_iter()
+            .map(|(_, count)| count)
+            .collect::<Vec<_>>()
+    );
+}
+
+/// TTL and PTTL report the remaining lifetime, -1 without expiration and -2
+/// for missing keys
+#[tokio::test]
+async fn ttl_pttl() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    client
+        .set_expires("e", "v".into(), Duration::from_secs(100))
+        .await
+        .unwrap();
+    client.set("p", "v".into()).await.unwrap();
+
+    assert_eq!(100, client.ttl("e").await.unwrap());
+    let pttl = client.pttl("e").await.unwrap();
+    assert!(pttl > 99_000 && pttl <= 100_000, "{}", pttl);
+
+    assert_eq!(-1, client.ttl("p").await.unwrap());
+    assert_eq!(-1, client.pttl("p").await.unwrap());
+    assert_eq!(-2, client.ttl("missing").await.unwrap());
+    assert_eq!(-2, client.pttl("missing").await.unwrap());
+}
+
+/// EXPIRE and PERSIST change the expiration of an existing key
+#[tokio::test]
+async fn expire_persist() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    assert!(!client.expire("missing", 10).await.unwrap());
+    assert!(!client.persist("missing").await.unwrap());
+
+    client.set("a", "1".into()).await.unwrap();
+    client.set("b", "2".into()).await.unwrap();
+    assert!(!client.persist("a").await.unwrap());
+
+    assert!(client.expire("a", 100).await.unwrap());
+    assert_eq!(100, client.ttl("a").await.unwrap());
+    assert!(client.expire("a", 1).await.unwrap());
+    assert_eq!(1, client.ttl("a").await.unwrap());
+
+    assert!(client.expire("b", 1).await.unwrap());
+    assert!(client.persist("b").await.unwrap());
+    assert_eq!(-1, client.ttl("b").await.unwrap());
+
+    // The background task is woken up for the new, earlier, expiration.
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    assert!(client.get("a").await.unwrap().is_none());
+    assert_eq!(Some("2".into()), client.get("b").await.unwrap());
+
+    // A timeout which is not positive deletes the key.
+    assert!(client.expire("b", 0).await.unwrap());
+    assert_eq!(-2, client.ttl("b").await.unwrap());
+}
+
+/// EXPIRE, TTL and PERSIST work on keys of any type
+#[tokio::test]
+async fn expire_other_types() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+    client.lpush("l", &["a".into()]).await.unwrap();
+    client.sadd("s", &["a".into()]).await.unwrap();
+    client.zadd("z", &[(1.0, "a".into())]).await.unwrap();
+
+    for key in ["h", "l", "s", "z"] {
+        assert_eq!(-1, client.ttl(key).await.unwrap());
+        assert!(client.expire(key, 100).await.unwrap());
+        assert_eq!(100, client.ttl(key).await.unwrap());
+    }
+
+    assert!(client.persist("s").await.unwrap());
+    assert_eq!(-1, client.ttl("s").await.unwrap());
+
+    // A timeout which is not positive deletes the key.
+    assert!(client.expire("z", 0).await.unwrap());
+    assert_eq!(-2, client.ttl("z").await.unwrap());
+}
+
+/// A list expires like a string, and writing to it keeps its timeout
+#[tokio::test]
+async fn expire_list() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.lpush("l", &["a".into()]).await.unwrap();
+
+    assert!(client.expire("l", 1).await.unwrap());
+    client.lpush("l", &["b".into()]).await.unwrap();
+    assert_eq!(1, client.ttl("l").await.unwrap());
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    assert_eq!(-2, client.ttl("l").await.unwrap());
+    assert_eq!(0, client.llen("l").await.unwrap());
+    assert_eq!(0, client.dbsize().await.unwrap());
+}
+
+/// A hash expires like a string, under its new name once renamed
+#[tokio::test]
+async fn expire_hash() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+
+    assert!(client.expire("h", 1).await.unwrap());
+    client.rename("h", "renamed").await.unwrap();
+    assert_eq!(1, client.ttl("renamed").await.unwrap());
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    assert_eq!(-2, client.ttl("renamed").await.unwrap());
+    assert_eq!(None, client.hgetall(&"renamed".to_string()).await.unwrap());
+    assert_eq!(0, client.dbsize().await.unwrap());
+}
+
+/// SET refuses an expire time of zero, as Redis does, and leaves the key
+/// untouched
+#[tokio::test]
+async fn set_invalid_expire() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("k", "before".into()).await.unwrap();
+
+    let err = client
+        .set_expires("k", "v".into(), Duration::ZERO)
+        .await
+        .unwrap_err();
+    assert_eq!("ERR invalid expire time in 'set' command", err.to_string());
+
+    let err = client
+        .cmd("SET")
+        .arg("k")
+        .arg("v")
+        .arg("EX")
+        .arg("0")
+        .query()
+        .await
+        .unwrap_err();
+    assert_eq!("ERR invalid expire time in 'set' command", err.to_string());
+
+    assert_eq!(Some("before".into()), client.get("k").await.unwrap());
+    assert_eq!(-1, client.ttl("k").await.unwrap());
+
+    // The append-only file logs expirations as `PEXPIREAT`, which clients
+    // cannot send.
+    let err = client.cmd("pexpireat").arg("k").arg("1").query().await;
+    assert_eq!(
+        "ERR unknown command 'pexpireat'",
+        err.unwrap_err().to_string()
+    );
+}
+
+/// MGET returns the values in the order of the keys, nil for missing keys
+#[tokio::test]
+async fn mget() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("a", "1".into()).await.unwrap();
+    client.set("b", "2".into()).await.unwrap();
+
+    let values = client.mget(&["b", "missing", "a", "b"]).await.unwrap();
+    assert_eq!(
+        vec![
+            Some(Bytes::from("2")),
+            None,
+            Some(Bytes::from("1")),
+            Some(Bytes::from("2")),
+        ],
+        values
+    );
+}
+
+/// MSET sets all the keys, replacing existing values and their expiration
+#[tokio::test]
+async fn mset() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .set_expires("b", "old".into(), Duration::from_secs(100))
+        .await
+        .unwrap();
+
+    client
+        .mset(&[("a", "1".into()), ("b", "2".into()), ("a", "3".into())])
+        .await
+        .unwrap();
+
+    let values = client.mget(&["a", "b"]).await.unwrap();
+    assert_eq!(vec![Some(Bytes::from("3")), Some(Bytes::from("2"))], values);
+    assert_eq!(-1, client.ttl("b").await.unwrap());
+}
+
+/// APPEND concatenates to the stored value, creating it if needed
+#[tokio::test]
+async fn append() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    assert_eq!(5, client.append("log", "hello".into()).await.unwrap());
+    assert_eq!(
+        Some("embstr".to_string()),
+        client.object_encoding("log").await.unwrap()
+    );
+    assert_eq!(11, client.append("log", " world".into()).await.unwrap());
+    assert_eq!(
+        Some(Bytes::from("hello world")),
+        client.get("log").await.unwrap()
+    );
+
+    // A value modified in place is no longer an integer or embedded string.
+    assert_eq!(
+        Some("raw".to_string()),
+        client.object_encoding("log").await.unwrap()
+    );
+
+    client
+        .set_expires("e", "1".into(), Duration::from_secs(100))
+        .await
+        .unwrap();
+    assert_eq!(2, client.append("e", "2".into()).await.unwrap());
+    assert_eq!(100, client.ttl("e").await.unwrap());
+
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+    let err = client.append("h", "x".into()).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// STRLEN returns the byte length of strings, 0 for missing keys
+#[tokio::test]
+async fn strlen() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("a", "héllo".into()).await.unwrap();
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+
+    assert_eq!(6, client.strlen("a").await.unwrap());
+    assert_eq!(0, client.strlen("missing").await.unwrap());
+
+    let err = client.strlen("h").await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// GETSET replaces a value and returns the previous one
+#[tokio::test]
+async fn getset() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    assert_eq!(None, client.getset("leader", "a".into()).await.unwrap());
+    assert_eq!(
+        Some(Bytes::from("a")),
+        client.getset("leader", "b".into()).await.unwrap()
+    );
+    assert_eq!(Some(Bytes::from("b")), client.get("leader").await.unwrap());
+
+    // The expiration is removed.
+    client
+        .set_expires("lease", "a".into(), Duration::from_secs(100))
+        .await
+        .unwrap();
+    client.getset("lease", "b".into()).await.unwrap();
+    assert_eq!(-1, client.ttl("lease").await.unwrap());
+
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+    let err = client.getset("h", "b".into()).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// SETNX only sets keys which do not exist, or which expired
+#[tokio::test]
+async fn set_nx() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    assert!(client.set_nx("lock", "a".into()).await.unwrap());
+    assert!(!client.set_nx("lock", "b".into()).await.unwrap());
+    assert_eq!(Some(Bytes::from("a")), client.get("lock").await.unwrap());
+
+    // Keys of any type count as existing.
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+    assert!(!client.set_nx("h", "b".into()).await.unwrap());
+
+    client
+        .set_expires("lease", "a".into(), Duration::from_millis(10))
+        .await
+        .unwrap();
+    assert!(!client.set_nx("lease", "b".into()).await.unwrap());
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert!(client.set_nx("lease", "b".into()).await.unwrap());
+    assert_eq!(Some(Bytes::from("b")), client.get("lease").await.unwrap());
+    assert_eq!(-1, client.ttl("lease").await.unwrap());
+}
+
+/// TYPE reports the type of the value stored at a key
+#[tokio::test]
+async fn key_type() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("s", "1".into()).await.unwrap();
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+
+    assert_eq!("string", client.key_type("s").await.unwrap());
+    assert_eq!("hash", client.key_type("h").await.unwrap());
+    assert_eq!("none", client.key_type("missing").await.unwrap());
+}
+
+/// SET and MSET replace a value of any other type with a string
+#[tokio::test]
+async fn set_replaces_other_types() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+    client.lpush("l", &["a".into()]).await.unwrap();
+    client.sadd("s", &["a".into()]).await.unwrap();
+    client.zadd("z", &[(1.0, "a".into())]).await.unwrap();
+
+    client.set("h", "1".into()).await.unwrap();
+    client.set("l", "1".into()).await.unwrap();
+    client
+        .mset(&[("s", "1".into()), ("z", "1".into())])
+        .await
+        .unwrap();
+
+    for key in ["h", "l", "s", "z"] {
+        assert_eq!("string", client.key_type(key).await.unwrap());
+        assert_eq!(Some(Bytes::from("1")), client.get(key).await.unwrap());
+    }
+
+    // The previous values are gone, rather than hidden by the string.
+    let err = client.llen("l").await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+    let err = client.smembers("s").await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+    assert_eq!(4, client.dbsize().await.unwrap());
+}
+
+/// DBSIZE counts each key once, whatever the writes of different types made
+/// to it
+#[tokio::test]
+async fn dbsize_mixed_types() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .hset(&"a".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+    client.set("a", "1".into()).await.unwrap();
+    client.sadd("a", &["m".into()]).await.unwrap_err();
+    client.incr("a").await.unwrap();
+    client.append("a", "0".into()).await.unwrap();
+    assert_eq!(1, client.dbsize().await.unwrap());
+
+    client.sadd("b", &["m".into()]).await.unwrap();
+    client.lpush("b", &["x".into()]).await.unwrap_err();
+    client.zadd("b", &[(1.0, "m".into())]).await.unwrap_err();
+    client.getset("b", "1".into()).await.unwrap_err();
+    client.set("b", "1".into()).await.unwrap();
+    client.lpush("b", &["x".into()]).await.unwrap_err();
+    assert_eq!(2, client.dbsize().await.unwrap());
+    assert_eq!(Some(Bytes::from("20")), client.get("a").await.unwrap());
+}
+
+/// HDEL removes hash fields, and the key along with the last field
+#[tokio::test]
+async fn hdel() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    for field in ["a", "b", "c"] {
+        client
+            .hset(&"h".to_string(), &field.to_string(), "v".into())
+            .await
+            .unwrap();
+    }
+
+    // Missing and repeated fields are not counted.
+    assert_eq!(1, client.hdel("h", &["a", "missing", "a"]).await.unwrap());
+    assert_eq!(0, client.hdel("missing", &["a"]).await.unwrap());
+    assert_eq!("hash", client.key_type("h").await.unwrap());
+
+    assert_eq!(2, client.hdel("h", &["b", "c"]).await.unwrap());
+    assert_eq!("none", client.key_type("h").await.unwrap());
+    assert_eq!(0, client.exists(&["h"]).await.unwrap());
+}
+
+/// HEXISTS, HLEN, HKEYS and HVALS inspect a hash, in insertion order
+#[tokio::test]
+async fn hash_introspection() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    for (field, value) in [("b", "1"), ("a", "2"), ("c", "3"), ("a", "4")] {
+        client
+            .hset(&"h".to_string(), &field.to_string(), value.into())
+            .await
+            .unwrap();
+    }
+
+    assert!(client.hexists("h", "a").await.unwrap());
+    assert!(!client.hexists("h", "missing").await.unwrap());
+    assert_eq!(3, client.hlen("h").await.unwrap());
+    assert_eq!(vec!["b", "a", "c"], client.hkeys("h").await.unwrap());
+    assert_eq!(
+        vec![Bytes::from("1"), Bytes::from("4"), Bytes::from("3")],
+        client.hvals("h").await.unwrap()
+    );
+
+    assert!(!client.hexists("missing", "a").await.unwrap());
+    assert_eq!(0, client.hlen("missing").await.unwrap());
+    assert!(client.hkeys("missing").await.unwrap().is_empty());
+    assert!(client.hvals("missing").await.unwrap().is_empty());
+}
+
+/// HMGET returns the values of the requested fields, in the requested order
+#[tokio::test]
+async fn hmget() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .hset(&"h".to_string(), &"a".to_string(), "1".into())
+        .await
+        .unwrap();
+    client
+        .hset(&"h".to_string(), &"b".to_string(), "2".into())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        vec![Some(Bytes::from("2")), None, Some(Bytes::from("1"))],
+        client.hmget("h", &["b", "missing", "a"]).await.unwrap()
+    );
+    assert_eq!(
+        vec![None, None],
+        client.hmget("missing", &["a", "b"]).await.unwrap()
+    );
+}
+
+/// HINCRBY increments hash fields, guarding against overflow
+#[tokio::test]
+async fn hincrby() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    assert_eq!(5, client.hincrby("h", "n", 5).await.unwrap());
+    assert_eq!(-2, client.hincrby("h", "n", -7).await.unwrap());
+    assert_eq!(
+        Some(Bytes::from("-2")),
+        client
+            .hget(&"h".to_string(), &"n".to_string())
+            .await
+            .unwrap()
+    );
+
+    client
+        .hset(&"h".to_string(), &"s".to_string(), "abc".into())
+        .await
+        .unwrap();
+    let err = client.hincrby("h", "s", 1).await.unwrap_err();
+    assert_eq!("ERR hash value is not an integer", err.to_string());
+
+    client.hincrby("h", "max", i64::MAX).await.unwrap();
+    let err = client.hincrby("h", "max", 1).await.unwrap_err();
+    assert_eq!("ERR increment or decrement would overflow", err.to_string());
+
+    client.set("s", "1".into()).await.unwrap();
+    let err = client.hincrby("s", "n", 1).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// HSETNX only sets hash fields which do not exist
+#[tokio::test]
+async fn hsetnx() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    // The hash is created along with the field.
+    assert!(client.hsetnx("h", "f", "a".into()).await.unwrap());
+    assert!(!client.hsetnx("h", "f", "b".into()).await.unwrap());
+    assert!(client.hsetnx("h", "g", "c".into()).await.unwrap());
+    assert_eq!(
+        vec![Some(Bytes::from("a")), Some(Bytes::from("c"))],
+        client.hmget("h", &["f", "g"]).await.unwrap()
+    );
+
+    client.set("s", "1".into()).await.unwrap();
+    let err = client.hsetnx("s", "f", "a".into()).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// LPUSH and RPUSH insert at either end of a list, read back with LRANGE
+#[tokio::test]
+async fn list_push_range() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let (a, b, c, d) = ("a".into(), "b".into(), "c".into(), "d".into());
+    assert_eq!(2, client.rpush("l", &[c, d]).await.unwrap());
+    assert_eq!(4, client.lpush("l", &[b, a]).await.unwrap());
+    assert_eq!("list", client.key_type("l").await.unwrap());
+
+    let all: Vec<Bytes> = vec!["a".into(), "b".into(), "c".into(), "d".into()];
+    assert_eq!(all, client.lrange("l", 0, -1).await.unwrap());
+    assert_eq!(all[1..3], client.lrange("l", 1, 2).await.unwrap()[..]);
+    assert_eq!(all[2..], client.lrange("l", -2, 100).await.unwrap()[..]);
+    assert_eq!(all, client.lrange("l", -100, -1).await.unwrap());
+
+    // Empty ranges, and missing keys, are empty.
+    assert!(client.lrange("l", 2, 1).await.unwrap().is_empty());
+    assert!(client.lrange("l", 4, 10).await.unwrap().is_empty());
+    assert!(client.lrange("l", 0, -100).await.unwrap().is_empty());
+    assert!(client.lrange("missing", 0, -1).await.unwrap().is_empty());
+
+    client.set("s", "1".into()).await.unwrap();
+    let err = client.lpush("s", &["a".into()]).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+    let err = client.lrange("s", 0, -1).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+
+    // String commands reject lists, and lists are deleted like any key.
+    let err = client.append("l", "e".into()).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+    assert_eq!(1, client.del(&["l"]).await.unwrap());
+    assert_eq!(0, client.exists(&["l"]).await.unwrap());
+}
+
+/// LPUSHCAPPED pushes to the head of a list and drops the oldest elements past
+/// the cap
+#[tokio::test]
+async fn list_push_capped() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    for (i, value) in ["e0", "e1", "e2"].iter().enumerate() {
+        let len = client.lpushcapped("l", Bytes::from(*value), 3).await;
+        assert_eq!(i as u64 + 1, len.unwrap());
+    }
+
+    let mut used_memory = vec![];
+    for value in ["e3", "e4", "e5", "e6"] {
+        let len = client.lpushcapped("l", Bytes::from(value), 3).await;
+        assert_eq!(3, len.unwrap());
+
+        let info = client.info(Some("memory")).await.unwrap();
+        let info = std::str::from_utf8(&info).unwrap();
+        let used = info
+            .lines()
+            .find_map(|line| line.strip_prefix("used_memory:"));
+        used_memory.push(used.unwrap().to_string());
+    }
+
+    let recent: Vec<Bytes> = vec!["e6".into(), "e5".into(), "e4".into()];
+    assert_eq!(recent, client.lrange("l", 0, -1).await.unwrap());
+
+    // The dropped elements no longer count towards memory usage.
+    assert!(used_memory.windows(2).all(|used| used[0] == used[1]));
+
+    // Lowering the cap trims more, and a cap of 0 removes the key.
+    assert_eq!(1, client.lpushcapped("l", "e7".into(), 1).await.unwrap());
+    let values = client.lrange("l", 0, -1).await.unwrap();
+    assert_eq!(vec![Bytes::from("e7")], values);
+    assert_eq!(0, client.lpushcapped("l", "e8".into(), 0).await.unwrap());
+    assert_eq!(0, client.exists(&["l"]).await.unwrap());
+
+    client.set("s", "1".into()).await.unwrap();
+    let err = client.lpushcapped("s", "e".into(), 3).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// LLEN and LINDEX inspect a list
+#[tokio::test]
+async fn list_len_index() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let values = ["a".into(), "b".into(), "c".into()];
+    client.rpush("l", &values).await.unwrap();
+
+    assert_eq!(3, client.llen("l").await.unwrap());
+    assert_eq!(0, client.llen("missing").await.unwrap());
+
+    assert_eq!(Some(Bytes::from("a")), client.lindex("l", 0).await.unwrap());
+    let last = client.lindex("l", -1).await.unwrap();
+    assert_eq!(Some(Bytes::from("c")), last);
+    let first = client.lindex("l", -3).await.unwrap();
+    assert_eq!(Some(Bytes::from("a")), first);
+    assert_eq!(None, client.lindex("l", 3).await.unwrap());
+    assert_eq!(None, client.lindex("l", -4).await.unwrap());
+    assert_eq!(None, client.lindex("missing", 0).await.unwrap());
+
+    client.set("s", "1".into()).await.unwrap();
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+    for key in ["s", "h"] {
+        let err = client.llen(key).await.unwrap_err();
+        assert!(err.to_string().starts_with("WRONGTYPE"));
+        let err = client.lindex(key, 0).await.unwrap_err();
+        assert!(err.to_string().starts_with("WRONGTYPE"));
+    }
+}
+
+/// BLPOP pops right away from a non-empty list, otherwise blocks until an
+/// element is pushed or the timeout elapses. Blocked clients are served in the
+/// order they blocked.
+#[tokio::test]
+async fn blpop() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.rpush("b", &["x".into()]).await.unwrap();
+
+    let popped = client.blpop(&["a", "b"], Duration::ZERO).await.unwrap();
+    assert_eq!(Some(("b".to_string(), Bytes::from("x"))), popped);
+    assert_eq!(0, client.exists(&["b"]).await.unwrap());
+
+    let timeout = Duration::from_millis(100);
+    assert_eq!(None, client.blpop(&["a", "b"], timeout).await.unwrap());
+
+    // Two clients block on the same list, one after the other.
+    let mut waiters = vec![];
+    for _ in 0..2 {
+        let mut waiter = Client::connect(addr).await.unwrap();
+        waiters.push(tokio::spawn(async move {
+            waiter.blpop(&["q", "other"], Duration::ZERO).await.unwrap()
+        }));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    client.rpush("q", &["first".into()]).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    client.rpush("q", &["second".into()]).await.unwrap();
+
+    let first = waiters.remove(0).await.unwrap();
+    assert_eq!(Some(("q".to_string(), Bytes::from("first"))), first);
+    let second = waiters.remove(0).await.unwrap();
+    assert_eq!(Some(("q".to_string(), Bytes::from("second"))), second);
+    assert_eq!(0, client.llen("q").await.unwrap());
+
+    client.set("s", "1".into()).await.unwrap();
+    let err = client.blpop(&["s"], timeout).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// SADD adds members once, SMEMBERS and SISMEMBER read them back
+#[tokio::test]
+async fn set_add_members() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let members = ["a".into(), "b".into(), "a".into()];
+    assert_eq!(2, client.sadd("s", &members).await.unwrap());
+    let added = client.sadd("s", &["b".into(), "c".into()]).await.unwrap();
+    assert_eq!(1, added);
+
+    let mut members = client.smembers("s").await.unwrap();
+    members.sort();
+    assert_eq!(vec!["a", "b", "c"], members);
+    assert!(client.smembers("missing").await.unwrap().is_empty());
+
+    assert!(client.sismember("s", "a".into()).await.unwrap());
+    assert!(!client.sismember("s", "d".into()).await.unwrap());
+    assert!(!client.sismember("missing", "a".into()).await.unwrap());
+
+    assert_eq!("set", client.key_type("s").await.unwrap());
+    let encoding = client.object_encoding("s").await.unwrap();
+    assert_eq!(Some("hashtable".into()), encoding);
+
+    client.set("str", "1".into()).await.unwrap();
+    let err = client.sadd("str", &["a".into()]).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+    let err = client.smembers("str").await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+    let err = client.lpush("s", &["a".into()]).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+
+    assert_eq!(1, client.del(&["s"]).await.unwrap());
+    assert_eq!(0, client.exists(&["s"]).await.unwrap());
+}
+
+/// SREM, SCARD and SPOP shrink a set, removing the key once it is empty
+#[tokio::test]
+async fn set_remove_pop() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let members = ["a".into(), "b".into(), "c".into(), "d".into()];
+    client.sadd("s", &members).await.unwrap();
+    assert_eq!(4, client.scard("s").await.unwrap());
+    assert_eq!(0, client.scard("missing").await.unwrap());
+
+    let removed = client.srem("s", &["a".into(), "x".into()]).await.unwrap();
+    assert_eq!(1, removed);
+    assert_eq!(3, client.scard("s").await.unwrap());
+
+    let popped = client.spop("s").await.unwrap().unwrap();
+    assert!(!client.sismember("s", popped).await.unwrap());
+    assert_eq!(2, client.scard("s").await.unwrap());
+
+    let popped = client.spop_count("s", 5).await.unwrap();
+    assert_eq!(2, popped.len());
+    assert_eq!(0, client.exists(&["s"]).await.unwrap());
+
+    assert_eq!(None, client.spop("s").await.unwrap());
+    assert!(client.spop_count("s", 1).await.unwrap().is_empty());
+
+    client.sadd("s", &["a".into()]).await.unwrap();
+    assert_eq!(1, client.srem("s", &["a".into()]).await.unwrap());
+    assert_eq!(0, client.exists(&["s"]).await.unwrap());
+
+    client.set("str", "1".into()).await.unwrap();
+    let err = client.scard("str").await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+    let err = client.spop("str").await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// SINTER, SUNION and SDIFF combine sets, missing keys being empty sets
+#[tokio::test]
+async fn set_algebra() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let members = ["a".into(), "b".into(), "c".into()];
+    client.sadd("s1", &members).await.unwrap();
+    let members = ["b".into(), "c".into(), "d".into()];
+    client.sadd("s2", &members).await.unwrap();
+    client.sadd("s3", &["c".into(), "e".into()]).await.unwrap();
+
+    let mut inter = client.sinter(&["s1", "s2", "s3"]).await.unwrap();
+    inter.sort();
+    assert_eq!(vec!["c"], inter);
+    assert!(client.sinter(&["s1", "missing"]).await.unwrap().is_empty());
+
+    let mut union = client.sunion(&["s1", "s2", "missing"]).await.unwrap();
+    union.sort();
+    assert_eq!(vec!["a", "b", "c", "d"], union);
+
+    let mut diff = client.sdiff(&["s1", "s2"]).await.unwrap();
+    diff.sort();
+    assert_eq!(vec!["a"], diff);
+    let mut diff = client.sdiff(&["s1", "missing"]).await.unwrap();
+    diff.sort();
+    assert_eq!(vec!["a", "b", "c"], diff);
+    assert!(client.sdiff(&["missing", "s1"]).await.unwrap().is_empty());
+
+    client.set("str", "1".into()).await.unwrap();
+    let err = client.sunion(&["s1", "str"]).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// SINTERSTORE, SUNIONSTORE and SDIFFSTORE store their result in place of
+/// whatever the destination holds, and remove it when the result is empty
+#[tokio::test]
+async fn set_algebra_store() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let members = ["a".into(), "b".into(), "c".into()];
+    client.sadd("s1", &members).await.unwrap();
+    let members = ["b".into(), "c".into(), "d".into()];
+    client.sadd("s2", &members).await.unwrap();
+
+    // The destination's previous value and expiration are replaced.
+    client.set("dst", "1".into()).await.unwrap();
+    client.expire("dst", 100).await.unwrap();
+
+    assert_eq!(2, client.sinterstore("dst", &["s1", "s2"]).await.unwrap());
+    let mut members = client.smembers("dst").await.unwrap();
+    members.sort();
+    assert_eq!(vec!["b", "c"], members);
+    assert_eq!(-1, client.ttl("dst").await.unwrap());
+
+    assert_eq!(4, client.sunionstore("dst", &["s1", "s2"]).await.unwrap());
+    let mut members = client.smembers("dst").await.unwrap();
+    members.sort();
+    assert_eq!(vec!["a", "b", "c", "d"], members);
+
+    assert_eq!(1, client.sdiffstore("dst", &["s1", "s2"]).await.unwrap());
+    assert_eq!(vec!["a"], client.smembers("dst").await.unwrap());
+
+    // A destination may be one of the sources.
+    assert_eq!(3, client.sunionstore("s1", &["s1", "dst"]).await.unwrap());
+
+    let count = client.sinterstore("dst", &["s1", "missing"]).await.unwrap();
+    assert_eq!(0, count);
+    assert_eq!("none", client.key_type("dst").await.unwrap());
+
+    client.set("str", "1".into()).await.unwrap();
+    let err = client.sunionstore("dst", &["s1", "str"]).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// ZUNIONSTORE and ZINTERSTORE weight the scores of each source and combine
+/// the scores of a member with the chosen aggregate, plain sets scoring 1
+#[tokio::test]
+async fn sorted_set_algebra_store() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let members = [(1.0, "a".into()), (2.0, "b".into()), (3.0, "c".into())];
+    client.zadd("z1", &members).await.unwrap();
+    let members = [(10.0, "b".into()), (20.0, "c".into()), (30.0, "d".into())];
+    client.zadd("z2", &members).await.unwrap();
+    client.sadd("s", &["c".into(), "d".into()]).await.unwrap();
+
+    let union = [("a", 1.0), ("b", 12.0), ("c", 23.0), ("d", 30.0)];
+    let count = client
+        .zunionstore("dst", &["z1", "z2"], None, Aggregate::Sum)
+        .await
+        .unwrap();
+    assert_eq!(4, count);
+    assert_eq!(scores(&union), dst_scores(&mut client).await);
+
+    let weights = [2.0, 0.5];
+    let union = [("a", 2.0), ("b", 9.0), ("d", 15.0), ("c", 16.0)];
+    client
+        .zunionstore("dst", &["z1", "z2"], Some(&weights), Aggregate::Sum)
+        .await
+        .unwrap();
+    assert_eq!(scores(&union), dst_scores(&mut client).await);
+
+    let union = [("a", 1.0), ("b", 2.0), ("c", 3.0), ("d", 30.0)];
+    client
+        .zunionstore("dst", &["z1", "z2"], None, Aggregate::Min)
+        .await
+        .unwrap();
+    assert_eq!(scores(&union), dst_scores(&mut client).await);
+
+    let inter = [("c", 20.0)];
+    let count = client
+        .zinterstore("dst", &["z1", "z2", "s"], None, Aggregate::Max)
+        .await
+        .unwrap();
+    assert_eq!(1, count);
+    assert_eq!(scores(&inter), dst_scores(&mut client).await);
+
+    // The plain set's members score 1, times their weight.
+    let inter = [("c", 26.0), ("d", 36.0)];
+    client
+        .zinterstore("dst", &["z2", "s"], Some(&[1.0, 6.0]), Aggregate::Sum)
+        .await
+        .unwrap();
+    assert_eq!(scores(&inter), dst_scores(&mut client).await);
+
+    let count = client
+        .zinterstore("dst", &["z1", "missing"], None, Aggregate::Sum)
+        .await
+        .unwrap();
+    assert_eq!(0, count);
+    assert_eq!("none", client.key_type("dst").await.unwrap());
+
+    client.set("str", "1".into()).await.unwrap();
+    let err = client
+        .zunionstore("dst", &["z1", "str"], None, Aggregate::Sum)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// ZUNION, ZINTER and ZDIFF reply with the combined members in score order,
+/// without storing them
+#[tokio::test]
+async fn sorted_set_algebra() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let members = [(1.0, "a".into()), (2.0, "b".into()), (3.0, "c".into())];
+    client.zadd("z1", &members).await.unwrap();
+    let members = [(10.0, "b".into()), (20.0, "c".into()), (30.0, "d".into())];
+    client.zadd("z2", &members).await.unwrap();
+
+    // Weigh and sum the scores by hand, then order the members by score.
+    let weights = [3.0, 0.25];
+    let mut expected = std::collections::HashMap::new();
+    for (key, weight) in ["z1", "z2"].iter().zip(&weights) {
+        for (member, score) in client.zrange_with_scores(key, 0, -1).await.unwrap() {
+            *expected.entry(member).or_insert(0.0) += score * weight;
+        }
+    }
+    let mut expected: Vec<(Bytes, f64)> = expected.into_iter().collect();
+    expected.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(a.0.cmp(&b.0)));
+
+    let union = client
+        .zunion_with_scores(&["z1", "z2"], Some(&weights), Aggregate::Sum)
+        .await
+        .unwrap();
+    assert_eq!(expected, union);
+
+    let union = client
+        .zunion(&["z1", "z2"], None, Aggregate::Max)
+        .await
+        .unwrap();
+    assert_eq!(vec!["a", "b", "c", "d"], union);
+
+    let inter = client
+        .zinter_with_scores(&["z1", "z2"], None, Aggregate::Min)
+        .await
+        .unwrap();
+    assert_eq!(scores(&[("b", 2.0), ("c", 3.0)]), inter);
+
+    let diff = client.zdiff_with_scores(&["z2", "z1"]).await.unwrap();
+    assert_eq!(scores(&[("d", 30.0)]), diff);
+    assert!(client.zdiff(&["missing", "z1"]).await.unwrap().is_empty());
+
+    // Nothing is stored.
+    assert_eq!(2, client.dbsize().await.unwrap());
+
+    // WITHSCORES only applies to the commands that reply with members.
+    let response = client
+        .cmd("ZUNIONSTORE")
+        .arg("dst")
+        .arg("1")
+        .arg("z1")
+        .arg("WITHSCORES")
+        .query()
+        .await;
+    assert!(response.is_err());
+}
+
+/// ZCOUNT counts the members scoring within inclusive, exclusive and infinite
+/// bounds
+#[tokio::test]
+async fn sorted_set_count() {
+    use ScoreBound::{Exclusive, Inclusive};
+
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let members = [
+        (f64::NEG_INFINITY, "a".into()),
+        (1.0, "b".into()),
+        (2.0, "c".into()),
+        (2.0, "d".into()),
+        (3.0, "e".into()),
+        (f64::INFINITY, "f".into()),
+    ];
+    client.zadd("z", &members).await.unwrap();
+
+    let inf = f64::INFINITY;
+    let cases = [
+        (Inclusive(1.0), Inclusive(3.0), 4),
+        (Exclusive(1.0), Inclusive(3.0), 3),
+        (Inclusive(1.0), Exclusive(3.0), 3),
+        (Exclusive(1.0), Exclusive(3.0), 2),
+        (Exclusive(2.0), Exclusive(2.0), 0),
+        (Inclusive(3.0), Inclusive(1.0), 0),
+        (Inclusive(-inf), Inclusive(inf), 6),
+        (Exclusive(-inf), Exclusive(inf), 4),
+        (Inclusive(inf), Inclusive(inf), 1),
+    ];
+    for (min, max, expected) in cases {
+        let count = client.zcount("z", min, max).await.unwrap();
+        assert_eq!(expected, count, "{} {}", min, max);
+    }
+
+    let count = client.zcount("missing", Inclusive(-inf), Inclusive(inf));
+    assert_eq!(0, count.await.unwrap());
+
+    // Bounds are sent as `(` prefixed scores and `-inf` / `+inf`.
+    let response = client
+        .cmd("ZCOUNT")
+        .arg("z")
+        .arg("(-inf")
+        .arg("+inf")
+        .query()
+        .await
+        .unwrap();
+    assert!(matches!(response, Frame::Integer(5)));
+
+    client.set("str", "1".into()).await.unwrap();
+    let err = client
+        .zcount("str", Inclusive(1.0), Inclusive(3.0))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// ZREMRANGEBYRANK and ZREMRANGEBYSCORE remove the members in range, deleting
+/// the key once the sorted set is empty
+#[tokio::test]
+async fn sorted_set_remove_range() {
+    use ScoreBound::{Exclusive, Inclusive};
+
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let members: Vec<(f64, Bytes)> = ["a", "b", "c", "d", "e", "f"]
+        .iter()
+        .enumerate()
+        .map(|(i, member)| (i as f64, Bytes::from(*member)))
+        .collect();
+    client.zadd("z", &members).await.unwrap();
+
+    // Negative ranks count from the end, ranks past the end are clamped.
+    assert_eq!(2, client.zremrangebyrank("z", -2, 100).await.unwrap());
+    assert_eq!(0, client.zremrangebyrank("z", 3, 2).await.unwrap());
+    assert_eq!(0, client.zremrangebyrank("z", 10, 20).await.unwrap());
+    assert_eq!(1, client.zremrangebyrank("z", 1, -3).await.unwrap());
+    let zset = client.zrange_with_scores("z", 0, -1).await.unwrap();
+    assert_eq!(scores(&[("a", 0.0), ("c", 2.0), ("d", 3.0)]), zset);
+
+    assert_eq!(3, client.zremrangebyrank("z", -100, -1).await.unwrap());
+    assert_eq!("none", client.key_type("z").await.unwrap());
+    assert_eq!(0, client.zremrangebyrank("z", 0, -1).await.unwrap());
+
+    client.zadd("z", &members).await.unwrap();
+
+    let removed = client.zremrangebyscore("z", Exclusive(1.0), Exclusive(3.0));
+    assert_eq!(1, removed.await.unwrap());
+    let removed = client.zremrangebyscore("z", Inclusive(4.0), Inclusive(1.0));
+    assert_eq!(0, removed.await.unwrap());
+    let removed = client.zremrangebyscore("z", Exclusive(4.0), Inclusive(f64::INFINITY));
+    assert_eq!(1, removed.await.unwrap());
+    let expected = [("a", 0.0), ("b", 1.0), ("d", 3.0), ("e", 4.0)];
+    let zset = client.zrange_with_scores("z", 0, -1).await.unwrap();
+    assert_eq!(scores(&expected), zset);
+
+    let removed = client.zremrangebyscore("z", Inclusive(f64::NEG_INFINITY), Inclusive(4.0));
+    assert_eq!(4, removed.await.unwrap());
+    assert_eq!("none", client.key_type("z").await.unwrap());
+
+    client.set("str", "1".into()).await.unwrap();
+    let err = client.zremrangebyrank("str", 0, -1).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+fn scores(members: &[(&str, f64)]) -> Vec<(Bytes, f64)> {
+    members
+        .iter()
+        .map(|(member, score)| (Bytes::from(member.to_string()), *score))
+        .collect()
+}
+
+async fn dst_scores(client: &mut Client) -> Vec<(Bytes, f64)> {
+    client.zrange_with_scores("dst", 0, -1).await.unwrap()
+}
+
+/// ZADD adds or updates members, ZRANGE returns them by score and ZSCORE
+/// looks up a score
+#[tokio::test]
+async fn sorted_set() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let members = [(2.0, "b".into()), (1.0, "c".into()), (2.0, "a".into())];
+    assert_eq!(3, client.zadd("z", &members).await.unwrap());
+    let members = [(0.5, "c".into()), (-1.5, "d".into())];
+    assert_eq!(1, client.zadd("z", &members).await.unwrap());
+
+    // Ties are broken by member.
+    let all = client.zrange("z", 0, -1).await.unwrap();
+    assert_eq!(vec!["d", "c", "a", "b"], all);
+    assert_eq!(vec!["a", "b"], client.zrange("z", -2, 10).await.unwrap());
+    assert!(client.zrange("z", 3, 1).await.unwrap().is_empty());
+    assert!(client.zrange("missing", 0, -1).await.unwrap().is_empty());
+
+    let scored = client.zrange_with_scores("z", 0, 1).await.unwrap();
+    let expected = vec![(Bytes::from("d"), -1.5), (Bytes::from("c"), 0.5)];
+    assert_eq!(expected, scored);
+
+    assert_eq!(Some(0.5), client.zscore("z", "c".into()).await.unwrap());
+    assert_eq!(None, client.zscore("z", "x".into()).await.unwrap());
+    assert_eq!(None, client.zscore("missing", "c".into()).await.unwrap());
+
+    assert_eq!("zset", client.key_type("z").await.unwrap());
+    client.set("str", "1".into()).await.unwrap();
+    let err = client.zadd("str", &[(1.0, "a".into())]).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+    let err = client.zscore("str", "a".into()).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"));
+}
+
+/// ZINCRBY moves members in the ordering, ZRANK reports their position and
+/// ZREM removes them
+#[tokio::test]
+async fn sorted_set_update() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let members = [(1.0, "a".into()), (2.0, "b".into()), (3.0, "c".into())];
+    client.zadd("z", &members).await.unwrap();
+
+    assert_eq!(Some(0), client.zrank("z", "a".into()).await.unwrap());
+    assert_eq!(Some(2), client.zrank("z", "c".into()).await.unwrap());
+    assert_eq!(None, client.zrank("z", "x".into()).await.unwrap());
+    assert_eq!(None, client.zrank("missing", "a".into()).await.unwrap());
+
+    // "a" moves from first to last.
+    assert_eq!(3.5, client.zincrby("z", 2.5, "a".into()).await.unwrap());
+    let all = client.zrange("z", 0, -1).await.unwrap();
+    assert_eq!(vec!["b", "c", "a"], all);
+    assert_eq!(Some(2), client.zrank("z", "a".into()).await.unwrap());
+    assert_eq!(Some(3.5), client.zscore("z", "a".into()).await.unwrap());
+
+    // Ties with "c" are broken by member.
+    assert_eq!(3.0, client.zincrby("z", -0.5, "a".into()).await.unwrap());
+    let all = client.zrange("z", 0, -1).await.unwrap();
+    assert_eq!(vec!["b", "a", "c"], all);
+
+    assert_eq!(-1.0, client.zincrby("z", -1.0, "new".into()).await.unwrap());
+    assert_eq!(Some(0), client.zrank("z", "new".into()).await.unwrap());
+    assert_eq!(5.0, client.zincrby("z2", 5.0, "a".into()).await.unwrap());
+
+    let members = ["a".into(), "b".into(), "x".into()];
+    assert_eq!(2, client.zrem("z", &members).await.unwrap());
+    assert_eq!(vec!["new", "c"], client.zrange("z", 0, -1).await.unwrap());
+    let members = ["c".into(), "new".into()];
+    assert_eq!(2, client.zrem("z", &members).await.unwrap());
+    assert_eq!(0, client.exists(&["z"]).await.unwrap());
+    assert_eq!(0, client.zrem("z", &["c".into()]).await.unwrap());
+
+    let members = [(f64::INFINITY, "a".into())];
+    client.zadd("inf", &members).await.unwrap();
+    let err = client.zincrby("inf", f64::NEG_INFINITY, "a".into()).await;
+    assert!(err.unwrap_err().to_string().contains("NaN"));
+}
+
+/// LCS returns the longest common subsequence, its length or the position of
+/// its matches
+#[tokio::test]
+async fn lcs() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("key1", "ohmytext".into()).await.unwrap();
+    client.set("key2", "mynewtext".into()).await.unwrap();
+
+    assert_eq!(&b"mytext"[..], client.lcs("key1", "key2").await.unwrap());
+    assert_eq!(6, client.lcs_len("key1", "key2").await.unwrap());
+
+    let (matches, len) = client.lcs_idx("key1", "key2", 0).await.unwrap();
+    assert_eq!(
+        vec![
+            LcsMatch {
+                a: (4, 7),
+                b: (5, 8),
+                len: 4
+            },
+            LcsMatch {
+                a: (2, 3),
+                b: (0, 1),
+                len: 2
+            },
+        ],
+        matches
+    );
+    assert_eq!(6, len);
+
+    // Shorter matches are skipped, but still count in the length.
+    let (matches, len) = client.lcs_idx("key1", "key2", 4).await.unwrap();
+    assert_eq!(1, matches.len());
+    assert_eq!((4, 7), matches[0].a);
+    assert_eq!(6, len);
+
+    client.set("a", "ABCBDAB".into()).await.unwrap();
+    client.set("b", "BDCABA".into()).await.unwrap();
+    assert_eq!(&b"BDAB"[..], client.lcs("a", "b").await.unwrap());
+    assert_eq!(4, client.lcs_len("b", "a").await.unwrap());
+
+    // Missing keys are empty values.
+    assert!(client.lcs("key1", "missing").await.unwrap().is_empty());
+    assert_eq!(0, client.lcs_len("missing", "missing").await.unwrap());
+    let (matches, len) = client.lcs_idx("missing", "key2", 0).await.unwrap();
+    assert!(matches.is_empty());
+    assert_eq!(0, len);
+
+    let err = client
+        .cmd("lcs")
+        .arg("key1")
+        .arg("key2")
+        .arg("len")
+        .arg("idx")
+        .query()
+        .await
+        .unwrap_err();
+    assert_eq!(
+        "ERR If you want both the length and indexes, please just use IDX.",
+        err.to_string()
+    );
+}
+
+/// INFO commandstats counts the calls of each command, and CONFIG RESETSTAT
+/// clears the counts.
+#[tokio::test]
+async fn info_commandstats() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("hello", "world".into()).await.unwrap();
+
+    for _ in 0..7 {
+        client.get("hello").await.unwrap();
+    }
+
+    let info = client.info(Some("commandstats")).await.unwrap();
+    let info = std::str::from_utf8(&info).unwrap();
+    assert!(info.starts_with("# Commandstats\r\n"));
+    assert_eq!(Some(7), cmdstat_calls(info, "get"));
+    assert_eq!(Some(1), cmdstat_calls(info, "set"));
+
+    client.config_resetstat().await.unwrap();
+
+    client.get("hello").await.unwrap();
+
+    let info = client.info(None).await.unwrap();
+    let info = std::str::from_utf8(&info).unwrap();
+    assert_eq!(Some(1), cmdstat_calls(info, "get"));
+    assert_eq!(None, cmdstat_calls(info, "set"));
+    assert_eq!(Some(1), cmdstat_calls(info, "config"));
+}
+
+/// INFO clients reports the clients blocked in BLPOP and the keys they are
+/// blocked on, until they are served.
+#[tokio::test]
+async fn info_blocked_clients() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let info = client.info(Some("clients")).await.unwrap();
+    let expected = "# Clients\r\nblocked_clients:0\r\nblocking_keys:0\r\n";
+    assert_eq!(expected.as_bytes(), &info[..]);
+
+    let mut waiters = vec![];
+    for keys in [&["q", "r"][..], &["q"][..]] {
+        let mut waiter = Client::connect(addr).await.unwrap();
+        waiters.push(tokio::spawn(async move {
+            waiter.blpop(keys, Duration::ZERO).await.unwrap()
+        }));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    let info = client.info(Some("clients")).await.unwrap();
+    let expected = "# Clients\r\n\
+        blocked_clients:2\r\n\
+        blocking_keys:2\r\n\
+        blocked_db0:key=q,clients=2\r\n\
+        blocked_db0:key=r,clients=1\r\n";
+    assert_eq!(expected.as_bytes(), &info[..]);
+
+    // The first client is served, and no longer counts as blocked on `r`.
+    client.rpush("q", &["x".into()]).await.unwrap();
+    let popped = waiters.remove(0).await.unwrap();
+    assert_eq!(Some(("q".to_string(), Bytes::from("x"))), popped);
+
+    let info = client.info(Some("clients")).await.unwrap();
+    let expected = "# Clients\r\n\
+        blocked_clients:1\r\n\
+        blocking_keys:1\r\n\
+        blocked_db0:key=q,clients=1\r\n";
+    assert_eq!(expected.as_bytes(), &info[..]);
+
+    client.rpush("q", &["y".into()]).await.unwrap();
+    waiters.remove(0).await.unwrap();
+
+    let info = client.info(None).await.unwrap();
+    let info = std::str::from_utf8(&info).unwrap();
+    assert!(info.contains("blocked_clients:0\r\nblocking_keys:0\r\n\r\n# Memory"));
+}
+
+/// INFO keyspace reports the number of keys and of keys with a TTL.
+#[tokio::test]
+async fn info_keyspace() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let info = client.info(Some("keyspace")).await.unwrap();
+    assert_eq!(&b"# Keyspace\r\n"[..], &info[..]);
+
+    client.set("a", "1".into()).await.unwrap();
+    client
+        .set_expires("b", "2".into(), Duration::from_secs(100))
+        .await
+        .unwrap();
+    client
+        .hset(&"h".to_string(), &"f".to_string(), "v".into())
+        .await
+        .unwrap();
+
+    let info = client.info(Some("keyspace")).await.unwrap();
+    let info = std::str::from_utf8(&info).unwrap();
+    assert!(info.starts_with("# Keyspace\r\ndb0:keys=3,expires=1,avg_ttl="));
+}
+
+/// CLUSTER INFO and CLUSTER MYID describe a standalone server.
+#[tokio::test]
+async fn cluster_standalone() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let info = client.cmd("CLUSTER").arg("INFO").query().await.unwrap();
+    let info = match info {
+        Frame::Bulk(info) => String::from_utf8(info.to_vec()).unwrap(),
+        frame => panic!("unexpected frame {:?}", frame),
+    };
+    assert!(info.lines().any(|line| line == "cluster_enabled:0"));
+
+    let id = client.cmd("CLUSTER").arg("MYID").query().await.unwrap();
+    let id = match id {
+        Frame::Bulk(id) => id,
+        frame => panic!("unexpected frame {:?}", frame),
+    };
+    assert_eq!(40, id.len());
+    assert!(id.iter().all(u8::is_ascii_hexdigit));
+
+    // The id does not change while the server is running
+    let mut other = Client::connect(addr).await.unwrap();
+    let same = other.cmd("cluster").arg("myid").query().await.unwrap();
+    assert_eq!(same, std::str::from_utf8(&id).unwrap());
+}
+
+/// similar to the "hello world" style test, But this time
+/// a single channel subscription will be tested instead
+#[tokio::test]
+async fn receive_message_subscribed_channel() {
+    let (addr, _) = start_server().await;
+
+    let client = Client::connect(addr).await.unwrap();
+    let mut subscriber = client.subscribe(vec!["hello".into()]).await.unwrap();
+
+    tokio::spawn(async move {
+        let mut client = Client::connect(addr).await.unwrap();
+        client.publish("hello", "world".into()).await.unwrap()
+    });
+
+    let message = subscriber.next_message().await.unwrap().unwrap();
+    assert_eq!("hello", &message.channel);
+    assert_eq!(b"world", &message.content[..])
+}
+
+/// test that a client gets messages from multiple subscribed channels
+#[tokio::test]
+async fn receive_message_multiple_subscribed_channels() {
+    let (addr, _) = start_server().await;
+
+    let client = Client::connect(addr).await.unwrap();
+    let mut subscriber = client
+        .subscribe(vec!["hello".into(), "world".into()])
+        .await
+        .unwrap();
+
+    tokio::spawn(async move {
+        let mut client = Client::connect(addr).await.unwrap();
+        client.publish("hello", "world".into()).await.unwrap()
+    });
+
+    let message1 = subscriber.next_message().await.unwrap().unwrap();
+    assert_eq!("hello", &message1.channel);
+    assert_eq!(b"world", &message1.content[..]);
+
+    tokio::spawn(async move {
+        let mut client = Client::connect(addr).await.unwrap();
+        client.publish("world", "howdy?".into()).await.unwrap()
+    });
+
+    let message2 = subscriber.next_message().await.unwrap().unwrap();
+    assert_eq!("world", &message2.channel);
+    assert_eq!(b"howdy?", &message2.content[..])
+}
+
+/// test that channel names are binary safe: a channel name which is not valid
+/// UTF-8 is kept intact from PUBLISH to the subscriber
+#[tokio::test]
+async fn receive_message_binary_channel() {
+    let (addr, _) = start_server().await;
+
+    let channel = Bytes::from_static(b"chan\xff\xfe");
+
+    let client = Client::connect(addr).await.unwrap();
+    let mut subscriber = client.subscribe(vec![channel.clone()]).await.unwrap();
+    assert_eq!(std::slice::from_ref(&channel), subscriber.get_subscribed());
+
+    // A different channel which is the same after a lossy UTF-8 conversion.
+    let other = Bytes::from_static(b"chan\xfe\xff");
+
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_eq!(0, client.publish(&other, "wrong".into()).await.unwrap());
+    let message = Bytes::from_static(b"\x80world");
+    assert_eq!(1, client.publish(&channel, message).await.unwrap());
+
+    let message = subscriber.next_message().await.unwrap().unwrap();
+    assert_eq!(channel, message.channel);
+    assert_eq!(&b"\x80world"[..], &message.content[..]);
+
+    subscriber.unsubscribe(&[channel]).await.unwrap();
+    assert!(subscriber.get_subscribed().is_empty());
+}
+
+/// test that a pattern subscriber receives messages published on matching
+/// channels, along with the pattern and the channel, and that PUBLISH counts
+/// it as a receiver
+#[tokio::test]
+async fn receive_message_subscribed_pattern() {
+    let (addr, _) = start_server().await;
+
+    let client = Client::connect(addr).await.unwrap();
+    let mut subscriber = client
+        .psubscribe(vec!["news.*".into(), "h?llo".into()])
+        .await
+        .unwrap();
+    assert_eq!(2, subscriber.get_subscribed().len());
+    assert_eq!(2, subscriber.confirmations()[1].count);
+
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_eq!(0, client.publish("sports", "skipped".into()).await.unwrap());
+    assert_eq!(1, client.publish("news.tech", "one".into()).await.unwrap());
+    assert_eq!(1, client.publish("hallo", "two".into()).await.unwrap());
+
+    let message = subscriber.next_message().await.unwrap().unwrap();
+    assert_eq!("news.*", &message.pattern);
+    assert_eq!("news.tech", &message.channel);
+    assert_eq!(b"one", &message.content[..]);
+
+    let message = subscriber.next_message().await.unwrap().unwrap();
+    assert_eq!("h?llo", &message.pattern);
+    assert_eq!("hallo", &message.channel);
+    assert_eq!(b"two", &message.content[..]);
+
+    // A channel subscriber and a pattern subscriber both receive the message.
+    let other = Client::connect(addr).await.unwrap();
+    let _channel = other.subscribe(vec!["news.tech".into()]).await.unwrap();
+    assert_eq!(2, client.publish("news.tech", "3".into()).await.unwrap());
+
+    let message = subscriber.next_message().await.unwrap().unwrap();
+    assert_eq!(b"3", &message.content[..]);
+
+    subscriber.punsubscribe(&["news.*".into()]).await.unwrap();
+    assert_eq!(&["h?llo".to_string()], subscriber.get_subscribed());
+    assert_eq!(1, subscriber.confirmations()[0].count);
+
+    subscriber.punsubscribe(&[]).await.unwrap();
+    assert!(subscriber.get_subscribed().is_empty());
+}
+
+/// test that PUBSUB CHANNELS lists the channels with subscribers, optionally
+/// filtered by pattern, and PUBSUB NUMSUB counts their subscribers
+#[tokio::test]
+async fn pubsub_introspection() {
     let (addr, _) = start_server().await;
 
+    let client = Client::connect(addr).await.unwrap();
+    let _one = client
+        .subscribe(vec!["news.tech".into(), "sports".into()])
+        .await
+        .unwrap();
+    let client = Client::connect(addr).await.unwrap();
+    let mut two = client.subscribe(vec!["news.tech".into()]).await.unwrap();
+    let client = Client::connect(addr).await.unwrap();
+    let _pattern = client.psubscribe(vec!["news.*".into()]).await.unwrap();
+    let client = Client::connect(addr).await.unwrap();
+    let mut gone = client.subscribe(vec!["old".into()]).await.unwrap();
+    gone.unsubscribe(&[]).await.unwrap();
+
     let mut client = Client::connect(addr).await.unwrap();
-    client.set("hello", "world".into()).await.unwrap();
+    let channels = client.pubsub_channels(None).await.unwrap();
+    assert_eq!(vec!["news.tech", "sports"], channels);
+    let channels = client.pubsub_channels(Some("news.*")).await.unwrap();
+    assert_eq!(vec!["news.tech"], channels);
 
-    let value = client.get("hello").await.unwrap().unwrap();
-    assert_eq!(b"world", &value[..])
+    let counts = client
+        .pubsub_numsub(&["news.tech", "sports", "old"])
+        .await
+        .unwrap();
+    let expected = [("news.tech", 2), ("sports", 1), ("old", 0)];
+    assert_eq!(expected.len(), counts.len());
+    for ((channel, count), (name, expected)) in counts.iter().zip(expected) {
+        assert_eq!(name, channel);
+        assert_eq!(expected, *count);
+    }
+
+    two.unsubscribe(&["news.tech".into()]).await.unwrap();
+    let counts = client.pubsub_numsub(&["news.tech"]).await.unwrap();
+    assert_eq!(1, counts[0].1);
 }
 
-/// similar to the "hello world" style test, But this time
-/// a single channel subscription will be tested instead
+/// test that messages published before subscribing with SUBSCRIBE-REPLAY are
+/// delivered first, limited to the configured backlog and requested count,
+/// followed by live messages
 #[tokio::test]
-async fn receive_message_subscribed_channel() {
+async fn subscribe_replay_backlog() {
+    let config = Config {
+        pubsub_backlog: 3,
+        ..Config::default()
+    };
+    let (addr, _) = start_server_with_config(config).await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    for msg in ["one", "two", "three", "four"] {
+        assert_eq!(0, client.publish("news", msg.into()).await.unwrap());
+    }
+
+    // "one" fell out of the backlog, and only two messages are requested.
+    let client = Client::connect(addr).await.unwrap();
+    let mut subscriber = client.subscribe_replay("news".into(), 2).await.unwrap();
+    assert_eq!(&["news".to_string()], subscriber.get_subscribed());
+
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_eq!(1, client.publish("news", "five".into()).await.unwrap());
+
+    for expected in ["three", "four", "five"] {
+        let message = subscriber.next_message().await.unwrap().unwrap();
+        assert_eq!("news", &message.channel);
+        assert_eq!(expected.as_bytes(), &message.content[..]);
+    }
+}
+
+/// test that only the channels published on most recently keep a backlog, up
+/// to the configured number of channels
+#[tokio::test]
+async fn subscribe_replay_backlog_channels() {
+    let config = Config {
+        pubsub_backlog: 3,
+        pubsub_backlog_channels: 2,
+        ..Config::default()
+    };
+    let (addr, _) = start_server_with_config(config).await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.publish("one", "1".into()).await.unwrap();
+    client.publish("two", "2".into()).await.unwrap();
+    // "one" becomes the most recently published channel.
+    client.publish("one", "1 again".into()).await.unwrap();
+    // "two" is dropped to make room for "three".
+    client.publish("three", "3".into()).await.unwrap();
+
+    for (channel, expected) in [("one", "1 again"), ("three", "3")] {
+        let client = Client::connect(addr).await.unwrap();
+        let mut subscriber = client.subscribe_replay(channel.into(), 1).await.unwrap();
+        let message = subscriber.next_message().await.unwrap().unwrap();
+        assert_eq!(expected.as_bytes(), &message.content[..]);
+    }
+
+    // Nothing is replayed on "two", the next message is a live one.
+    let subscriber = Client::connect(addr).await.unwrap();
+    let mut subscriber = subscriber.subscribe_replay("two".into(), 3).await.unwrap();
+    client.publish("two", "live".into()).await.unwrap();
+    let message = subscriber.next_message().await.unwrap().unwrap();
+    assert_eq!(b"live", &message.content[..]);
+}
+
+/// test that a client accurately removes its own subscribed chanel list
+/// when unsubscribing to all subscribed channels by submitting an empty vec
+#[tokio::test]
+async fn unsubscribes_from_channels() {
     let (addr, _) = start_server().await;
 
     let client = Client::connect(addr).await.unwrap();
-    let mut subscriber = client.subscribe(vec!["hello".into()]).await.unwrap();
+    let mut subscriber = client
+        .subscribe(vec!["hello".into(), "world".into()])
+        .await
+        .unwrap();
+
+    subscriber.unsubscribe(&[]).await.unwrap();
+    assert_eq!(subscriber.get_subscribed().len(), 0);
+}
+
+/// test that unsubscribing from some channels keeps the others: the server
+/// confirms each with the remaining count, and messages keep flowing on the
+/// channels still subscribed to
+#[tokio::test]
+async fn unsubscribes_from_some_channels() {
+    let (addr, _) = start_server().await;
+
+    let client = Client::connect(addr).await.unwrap();
+    let mut subscriber = client
+        .subscribe(vec!["hello".into(), "world".into(), "foo".into()])
+        .await
+        .unwrap();
+
+    subscriber.unsubscribe(&["foo".into()]).await.unwrap();
+    assert_eq!(&["hello", "world"], subscriber.get_subscribed());
+    let confirmation = &subscriber.confirmations()[0];
+    assert_eq!("foo", &confirmation.channel);
+    assert_eq!(2, confirmation.count);
+
+    // Channels which are not subscribed to are confirmed too.
+    subscriber.unsubscribe(&["bar".into()]).await.unwrap();
+    assert_eq!(2, subscriber.get_subscribed().len());
+    assert_eq!(2, subscriber.confirmations()[0].count);
+
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_eq!(0, client.publish("foo", "dropped".into()).await.unwrap());
+    assert_eq!(1, client.publish("world", "kept".into()).await.unwrap());
+
+    let message = subscriber.next_message().await.unwrap().unwrap();
+    assert_eq!("world", &message.channel);
+    assert_eq!(b"kept", &message.content[..]);
+
+    subscriber.unsubscribe(&["hello".into()]).await.unwrap();
+    assert_eq!(&["world"], subscriber.get_subscribed());
+    assert_eq!(1, subscriber.confirmations()[0].count);
+}
+
+/// test that a connection may subscribe up to the configured limit, after
+/// which further subscriptions are rejected while existing ones keep working
+#[tokio::test]
+async fn subscription_limit() {
+    let config = Config {
+        max_subscriptions: 2,
+        ..Config::default()
+    };
+    let (addr, _) = start_server_with_config(config).await;
+
+    let client = Client::connect(addr).await.unwrap();
+    let mut subscriber = client
+        .subscribe(vec!["hello".into(), "world".into()])
+        .await
+        .unwrap();
+
+    let err = subscriber.subscribe(&["foo".into()]).await.unwrap_err();
+    assert!(err.to_string().contains("maximum number of subscriptions"));
+    assert_eq!(subscriber.get_subscribed(), &["hello", "world"]);
 
     tokio::spawn(async move {
         let mut client = Client::connect(addr).await.unwrap();
@@ -58,50 +2637,224 @@ async fn receive_message_subscribed_channel() {
     assert_eq!(b"world", &message.content[..])
 }
 
-/// test that a client gets messages from multiple subscribed channels
+/// test that a large stream of commands is loaded through a small in-flight
+/// window and every key lands
 #[tokio::test]
-async fn receive_message_multiple_subscribed_channels() {
+async fn bulk_load_sets() {
     let (addr, _) = start_server().await;
 
-    let client = Client::connect(addr).await.unwrap();
-    let mut subscriber = client
-        .subscribe(vec!["hello".into(), "world".into()])
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let commands = tokio_stream::iter(0..100_000).map(|i| {
+        Frame::Array(vec![
+            Frame::Bulk("set".into()),
+            Frame::Bulk(format!("key:{}", i).into()),
+            Frame::Bulk(format!("value:{}", i).into()),
+        ])
+    });
+
+    let errors = client.bulk_load(commands, 8).await.unwrap();
+    assert!(errors.is_empty());
+
+    for i in (0..100_000).step_by(999).chain(Some(99_999)) {
+        let value = client.get(&format!("key:{}", i)).await.unwrap().unwrap();
+        assert_eq!(format!("value:{}", i).as_bytes(), &value[..]);
+    }
+}
+
+/// test that commands rejected during a bulk load are reported with their
+/// index and do not stop the remaining commands
+#[tokio::test]
+async fn bulk_load_reports_errors() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let commands = tokio_stream::iter(0..10).map(|i| {
+        let name = if i == 3 || i == 7 { "nope" } else { "set" };
+
+        Frame::Array(vec![
+            Frame::Bulk(name.into()),
+            Frame::Bulk(format!("key:{}", i).into()),
+            Frame::Bulk("value".into()),
+        ])
+    });
+
+    let errors = client.bulk_load(commands, 2).await.unwrap();
+
+    let indices: Vec<_> = errors.iter().map(|err| err.index).collect();
+    assert_eq!(vec![3, 7], indices);
+    assert_eq!("ERR unknown command 'nope'", errors[0].message);
+
+    assert!(client.get("key:3").await.unwrap().is_none());
+    assert!(client.get("key:9").await.unwrap().is_some());
+}
+
+/// test that pipelined responses are returned in request order, with a
+/// rejected command answered in place without desynchronizing the others
+#[tokio::test]
+async fn pipeline_responses_in_order() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.rpush("list", &["a".into()]).await.unwrap();
+
+    let mut pipeline = client.pipeline();
+    for i in 0..100 {
+        pipeline = pipeline.cmd(["SET".to_string(), format!("key:{}", i), i.to_string()]);
+    }
+    let responses = pipeline
+        .cmd(["INCR", "list"])
+        .cmd(["NOPE"])
+        .cmd(["INCR", "key:41"])
+        .cmd(["GET", "key:41"])
+        .query()
         .await
         .unwrap();
 
+    assert_eq!(104, responses.len());
+    assert!(responses[..100].iter().all(|frame| *frame == "OK"));
+    assert!(matches!(&responses[100], Frame::Error(_)));
+    assert!(matches!(&responses[101], Frame::Error(msg) if msg.contains("unknown command")));
+    assert!(matches!(responses[102], Frame::Integer(42)));
+    assert_eq!(responses[103], "42");
+
+    // The connection is still usable.
+    assert_eq!(Some("0".into()), client.get("key:0").await.unwrap());
+}
+
+/// test that a pipeline holding a command with the wrong number of arguments
+/// is rejected without sending any command
+#[tokio::test]
+async fn pipeline_checks_arity() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let err = client
+        .pipeline()
+        .cmd(["SET", "a", "1"])
+        .cmd(["GET"])
+        .query()
+        .await
+        .unwrap_err();
+    let msg = "ERR wrong number of arguments for 'get' command";
+    assert_eq!(msg, err.to_string());
+    assert_eq!(None, client.get("a").await.unwrap());
+
+    assert!(client.pipeline().query().await.unwrap().is_empty());
+}
+
+/// test that a request times out when its response is late, and that the late
+/// response is discarded rather than taken for the response to the next
+/// request
+#[tokio::test]
+async fn timeout_discards_late_response() {
+    // A server replying to `GET key` with `key`, only after a delay for the
+    // key "slow".
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
     tokio::spawn(async move {
-        let mut client = Client::connect(addr).await.unwrap();
-        client.publish("hello", "world".into()).await.unwrap()
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut connection = Connection::new(socket);
+
+        while let Some(Frame::Array(request)) = connection.read_frame().await.unwrap() {
+            let key = match &request[..] {
+                [_, Frame::Bulk(key)] => key.clone(),
+                _ => panic!("unexpected request {:?}", request),
+            };
+            if key == "slow" {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+            connection.write_frame(&Frame::Bulk(key)).await.unwrap();
+        }
     });
 
-    let message1 = subscriber.next_message().await.unwrap().unwrap();
-    assert_eq!("hello", &message1.channel);
-    assert_eq!(b"world", &message1.content[..]);
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set_timeout(Some(Duration::from_millis(50)));
+
+    let err = client.get("slow").await.unwrap_err();
+    assert_eq!(Some(&clients::Error::Timeout), err.downcast_ref());
+
+    // The response to "slow" arrives first, and is skipped.
+    client.set_timeout(None);
+    assert_eq!(Some("fast".into()), client.get("fast").await.unwrap());
+    assert_eq!(Some("last".into()), client.get("last").await.unwrap());
+}
+
+/// test that with a timeout set, a subscriber receiving no message gets `None`
+/// and can keep polling
+#[tokio::test]
+async fn timeout_polls_subscriber() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set_timeout(Some(Duration::from_millis(50)));
+    let mut subscriber = client.subscribe(vec!["hello".into()]).await.unwrap();
+
+    assert!(subscriber.next_message().await.unwrap().is_none());
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.publish("hello", "world".into()).await.unwrap();
+
+    let message = subscriber.next_message().await.unwrap().unwrap();
+    assert_eq!(b"world", &message.content[..]);
+}
+
+/// test that with a timeout set, a subscription stream outlives quiet periods
+/// longer than the timeout, rather than ending at the first one
+#[tokio::test]
+async fn timeout_keeps_stream_open() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set_timeout(Some(Duration::from_millis(50)));
+    let subscriber = client.subscribe(vec!["hello".into()]).await.unwrap();
+    let messages = subscriber.into_stream();
+    tokio::pin!(messages);
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set_timeout(Some(Duration::from_millis(50)));
+    let subscriber = client.psubscribe(vec!["h*".into()]).await.unwrap();
+    let pattern_messages = subscriber.into_stream();
+    tokio::pin!(pattern_messages);
 
     tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
         let mut client = Client::connect(addr).await.unwrap();
-        client.publish("world", "howdy?".into()).await.unwrap()
+        client.publish("hello", "world".into()).await.unwrap();
     });
 
-    let message2 = subscriber.next_message().await.unwrap().unwrap();
-    assert_eq!("world", &message2.channel);
-    assert_eq!(b"howdy?", &message2.content[..])
+    let message = messages.next().await.unwrap().unwrap();
+    assert_eq!(b"world", &message.content[..]);
+
+    let message = pattern_messages.next().await.unwrap().unwrap();
+    assert_eq!(b"hello", &message.channel[..]);
 }
 
-/// test that a client accurately removes its own subscribed chanel list
-/// when unsubscribing to all subscribed channels by submitting an empty vec
+/// test that HSET and HGET report a timeout to the caller, instead of
+/// panicking
 #[tokio::test]
-async fn unsubscribes_from_channels() {
-    let (addr, _) = start_server().await;
+async fn timeout_hash_commands() {
+    // A server which never replies.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut connection = Connection::new(socket);
+        while connection.read_frame().await.unwrap().is_some() {}
+    });
 
-    let client = Client::connect(addr).await.unwrap();
-    let mut subscriber = client
-        .subscribe(vec!["hello".into(), "world".into()])
-        .await
-        .unwrap();
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set_timeout(Some(Duration::from_millis(50)));
 
-    subscriber.unsubscribe(&[]).await.unwrap();
-    assert_eq!(subscriber.get_subscribed().len(), 0);
+    let (key, field) = ("h".to_string(), "f".to_string());
+
+    let err = client.hset(&key, &field, "v".into()).await.unwrap_err();
+    assert_eq!(Some(&clients::Error::Timeout), err.downcast_ref());
+
+    let err = client.hget(&key, &field).await.unwrap_err();
+    assert_eq!(Some(&clients::Error::Timeout), err.downcast_ref());
 }
 
 /// test when hset command is sent to the server
@@ -120,7 +2873,36 @@ async fn hset_command() {
     assert_eq!("你好世界".as_bytes(), &value[..])
 }
 
-/*/// test for hgetall command
+/// HSET and HSETNX refuse keys holding another type, leaving them untouched
+#[tokio::test]
+async fn hset_wrong_type() {
+    let (addr, _) = start_server().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("s", "1".into()).await.unwrap();
+    client.lpush("l", &["a".into()]).await.unwrap();
+
+    for key in ["s", "l"] {
+        let err = client
+            .cmd("HSET")
+            .arg(key)
+            .arg("f")
+            .arg("v")
+            .query()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("WRONGTYPE"));
+
+        let err = client.hsetnx(key, "f", "v".into()).await.unwrap_err();
+        assert!(err.to_string().starts_with("WRONGTYPE"));
+    }
+
+    assert_eq!("string", client.key_type("s").await.unwrap());
+    assert_eq!("list", client.key_type("l").await.unwrap());
+    assert_eq!(2, client.dbsize().await.unwrap());
+}
+
+/// test for hgetall command
 /// the server will return all the key-value pairs
 #[tokio::test]
 async fn hgetall_c() {
@@ -128,17 +2910,230 @@ async fn hgetall_c() {
 
     let mut client = Client::connect(addr).await.unwrap();
     client
-        .hset(&"hello".to_string(), &"world".to_string(), "你好世界".into()).await.unwrap();
+        .hset(
+            &"hello".to_string(),
+            &"world".to_string(),
+            "你好世界".into(),
+        )
+        .await
+        .unwrap();
 
     //return all the key-value pairs
     let value = client.hgetall(&"hello".to_string()).await.unwrap().unwrap();
-    println!("value {:?}", value);
 
     //assert the key-value pairs
-    assert_eq!(value.get(&"world".to_string()).unwrap().as_ref(), "你好世界".as_bytes());
+    assert_eq!(value.get("world").unwrap().as_ref(), "你好世界".as_bytes());
+}
+
+/// A server listening on a Unix domain socket serves clients connecting to its
+/// path, and removes the socket file once shut down.
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_socket_connection() {
+    let path = std::env::temp_dir().join(format!("mini-redis-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path).unwrap();
+    let (shutdown, rx) = oneshot::channel::<()>();
+    let handle = tokio::spawn(server::run_unix(listener, rx));
+
+    let mut client = Client::connect_unix(&path).await.unwrap();
+    client.set("hello", "world".into()).await.unwrap();
+    let value = client.get("hello").await.unwrap().unwrap();
+    assert_eq!(b"world", &value[..]);
+    drop(client);
+
+    shutdown.send(()).unwrap();
+    handle.await.unwrap();
+    assert!(!path.exists());
+}
+
+/// Under `allkeys-lru`, writing past `maxmemory` evicts the keys accessed
+/// least recently, reads counting as accesses.
+#[tokio::test]
+async fn maxmemory_evicts_least_recently_used() {
+    // Each key uses about 170 bytes, so 5 of them fit.
+    let config = Config {
+        maxmemory: 1000,
+        maxmemory_policy: MaxmemoryPolicy::AllKeysLru,
+        ..Config::default()
+    };
+    let (addr, _) = start_server_with_config(config).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let value = Bytes::from(vec![b'x'; 100]);
+
+    for n in 0..5 {
+        client
+            .set(&format!("key:{}", n), value.clone())
+            .await
+            .unwrap();
+    }
+
+    // `key:0` becomes the most recently used key.
+    client.get("key:0").await.unwrap().unwrap();
+
+    for n in 5..8 {
+        client
+            .set(&format!("key:{}", n), value.clone())
+            .await
+            .unwrap();
+    }
+
+    for n in 1..4 {
+        let key = format!("key:{}", n);
+        assert_eq!(None, client.get(&key).await.unwrap(), "{}", key);
+    }
+
+    for n in [0, 4, 5, 6, 7] {
+        let key = format!("key:{}", n);
+        assert_eq!(
+            Some(value.clone()),
+            client.get(&key).await.unwrap(),
+            "{}",
+            key
+        );
+    }
+
+    let info = client.info(Some("memory")).await.unwrap();
+    let info = std::str::from_utf8(&info).unwrap();
+    let used: usize = info
+        .lines()
+        .find_map(|line| line.strip_prefix("used_memory:"))
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(used <= 1000, "used_memory = {}", used);
+    assert!(info.contains("maxmemory_policy:allkeys-lru\r\n"));
+}
+
+/// Under `noeviction`, writes which would exceed `maxmemory` are rejected,
+/// while writes which fit are still applied.
+#[tokio::test]
+async fn maxmemory_rejects_writes() {
+    // Each key uses about 165 bytes, so 2 of them fit.
+    let config = Config {
+        maxmemory: 400,
+        ..Config::default()
+    };
+    let (addr, _) = start_server_with_config(config).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let value = Bytes::from(vec![b'x'; 100]);
+
+    client.set("a", value.clone()).await.unwrap();
+    client.set("b", value.clone()).await.unwrap();
+
+    let err = client.set("c", value.clone()).await.unwrap_err();
+    assert_eq!(
+        "OOM command not allowed when used memory > 'maxmemory'.",
+        err.to_string()
+    );
+    assert_eq!(None, client.get("c").await.unwrap());
+
+    // Replacing a value frees the memory it used.
+    client.set("a", "small".into()).await.unwrap();
+    client.set("c", value.clone()).await.unwrap();
+    assert_eq!(Some(value), client.get("b").await.unwrap());
+}
+
+/// Hashes, lists, sets and sorted sets count against `maxmemory` too, so
+/// adding to them is rejected under `noeviction` once the limit is reached.
+#[tokio::test]
+async fn maxmemory_rejects_collection_writes() {
+    // A string uses about 165 bytes, a collection holding a single element
+    // about 180, so only two of them fit.
+    let config = Config {
+        maxmemory: 400,
+        ..Config::default()
+    };
+    let (addr, _) = start_server_with_config(config).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let value = Bytes::from(vec![b'x'; 100]);
+    let values = std::slice::from_ref(&value);
+
+    client.set("a", value.clone()).await.unwrap();
+    client
+        .cmd("HSET")
+        .arg("h")
+        .arg("f")
+        .arg(value.clone())
+        .query()
+        .await
+        .unwrap();
+
+    let errors = [
+        client
+            .cmd("HSET")
+            .arg("h")
+            .arg("g")
+            .arg(value.clone())
+            .query()
+            .await
+            .unwrap_err(),
+        client.lpush("l", values).await.unwrap_err(),
+        client.sadd("s", values).await.unwrap_err(),
+        client.zadd("z", &[(1.0, value.clone())]).await.unwrap_err(),
+    ];
+
+    for err in errors {
+        assert_eq!(
+            "OOM command not allowed when used memory > 'maxmemory'.",
+            err.to_string()
+        );
+    }
+
+    assert_eq!(2, client.dbsize().await.unwrap());
+
+    // Removing the hash frees the memory it used.
+    client.del(&["h"]).await.unwrap();
+    assert_eq!(1, client.lpush("l", values).await.unwrap());
+}
+
+/// Under `allkeys-lru`, collections are evicted like strings, in the order
+/// they were accessed.
+#[tokio::test]
+async fn maxmemory_evicts_collections() {
+    let config = Config {
+        maxmemory: 1000,
+        maxmemory_policy: MaxmemoryPolicy::AllKeysLru,
+        ..Config::default()
+    };
+    let (addr, _) = start_server_with_config(config).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let value = Bytes::from(vec![b'x'; 100]);
+
+    client
+        .cmd("HSET")
+        .arg("h")
+        .arg("f")
+        .arg(value.clone())
+        .query()
+        .await
+        .unwrap();
+    client
+        .lpush("l", std::slice::from_ref(&value))
+        .await
+        .unwrap();
+
+    for n in 0..3 {
+        client
+            .set(&format!("key:{}", n), value.clone())
+            .await
+            .unwrap();
+    }
 
+    // The list becomes the least recently used key.
+    client.cmd("HGET").arg("h").arg("f").query().await.unwrap();
 
-}*/
+    client.set("key:3", value).await.unwrap();
+
+    assert_eq!("none", client.key_type("l").await.unwrap());
+    assert_eq!("hash", client.key_type("h").await.unwrap());
+    assert_eq!(5, client.dbsize().await.unwrap());
+}
 
 async fn start_server() -> (SocketAddr, JoinHandle<()>) {
     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -148,3 +3143,21 @@ async fn start_server() -> (SocketAddr, JoinHandle<()>) {
 
     (addr, handle)
 }
+
+async fn start_server_with_config(config: Config) -> (SocketAddr, JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let handle = tokio::spawn(async move {
+        server::run_with_config(listener, config, tokio::signal::ctrl_c()).await
+    });
+
+    (addr, handle)
+}
+
+/// Returns the number of calls reported for `name` in an `INFO` reply.
+fn cmdstat_calls(info: &str, name: &str) -> Option<u64> {
+    let prefix = format!("cmdstat_{}:calls=", name);
+    let line = info.lines().find(|line| line.starts_with(&prefix))?;
+    line[prefix.len()..].split(',').next()?.parse().ok()
+}
@@ -0,0 +1,37 @@
+use bytes::Bytes;
+use mini_redis::{Command, Frame};
+
+/// The command name is read from the first entry of the frame and lower cased,
+/// regardless of the arguments that follow.
+#[test]
+fn peek_command_name() {
+    let frame = Frame::Array(vec![
+        Frame::Bulk(Bytes::from_static(b"SeT")),
+        Frame::Bulk(Bytes::from_static(b"hello")),
+        Frame::Bulk(Bytes::from_static(b"world")),
+    ]);
+    assert_eq!("set", Command::peek_name(&frame).unwrap());
+
+    let frame = Frame::Array(vec![Frame::Simple("PING".into())]);
+    assert_eq!("ping", Command::peek_name(&frame).unwrap());
+
+    // Arguments are not validated, so unknown and malformed commands still
+    // report their name.
+    let frame = Frame::Array(vec![
+        Frame::Bulk(Bytes::from_static(b"get")),
+        Frame::Integer(1),
+        Frame::Integer(2),
+    ]);
+    assert_eq!("get", Command::peek_name(&frame).unwrap());
+}
+
+/// Frames that cannot carry a command are rejected.
+#[test]
+fn peek_command_name_invalid_frame() {
+    assert!(Command::peek_name(&Frame::Simple("GET".into())).is_err());
+    assert!(Command::peek_name(&Frame::Array(vec![])).is_err());
+    assert!(Command::peek_name(&Frame::Array(vec![Frame::Integer(1)])).is_err());
+
+    let frame = Frame::Array(vec![Frame::Bulk(Bytes::from_static(b"\xff\xfe"))]);
+    assert!(Command::peek_name(&frame).is_err());
+}
@@ -0,0 +1,177 @@
+use bytes::Bytes;
+use mini_redis::{clients::Client, server, Config};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// A server persisting its keys to a dump file, which can be shut down and
+/// started again.
+struct Server {
+    addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+/// Keys of every type, in several databases, are restored after a restart,
+/// and keys keep their expiration.
+#[tokio::test]
+async fn save_and_load_every_type() {
+    let path = dump_path("every_type");
+
+    let server = Server::start(&path).await;
+    let mut client = Client::connect(server.addr).await.unwrap();
+
+    client.set("string", "value".into()).await.unwrap();
+    client
+        .set_expires("expiring", "value".into(), Duration::from_secs(100))
+        .await
+        .unwrap();
+    client
+        .set_expires("expired", "value".into(), Duration::from_millis(200))
+        .await
+        .unwrap();
+    let hash = "hash".to_string();
+    client
+        .hset(&hash, &"field".to_string(), "value".into())
+        .await
+        .unwrap();
+    client
+        .lpush("list", &["b".into(), "a".into()])
+        .await
+        .unwrap();
+    client.expire("list", 100).await.unwrap();
+    client.sadd("set", &["member".into()]).await.unwrap();
+    client.sadd("gone", &["member".into()]).await.unwrap();
+    client
+        .zadd("zset", &[(1.5, "one".into()), (2.5, "two".into())])
+        .await
+        .unwrap();
+    client.select(1).await.unwrap();
+    client.set("other", "db".into()).await.unwrap();
+    client.select(0).await.unwrap();
+    client.expire("gone", 1).await.unwrap();
+
+    client.save().await.unwrap();
+    drop(client);
+    server.stop().await;
+
+    // Let `expired` and `gone` expire while the server is down.
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    let server = Server::start(&path).await;
+    let mut client = Client::connect(server.addr).await.unwrap();
+
+    assert_eq!(Some("value".into()), client.get("string").await.unwrap());
+    let ttl = client.ttl("expiring").await.unwrap();
+    assert!((90..=100).contains(&ttl), "ttl = {}", ttl);
+    assert_eq!(None, client.get("expired").await.unwrap());
+    let fields = client.hgetall(&hash).await.unwrap().unwrap();
+    assert_eq!(b"value", &fields["field"][..]);
+    let list = client.lrange("list", 0, -1).await.unwrap();
+    assert_eq!(vec![Bytes::from("a"), Bytes::from("b")], list);
+    let ttl = client.ttl("list").await.unwrap();
+    assert!((90..=100).contains(&ttl), "ttl = {}", ttl);
+    let members = client.smembers("set").await.unwrap();
+    assert_eq!(vec![Bytes::from("member")], members);
+    assert_eq!(-2, client.ttl("gone").await.unwrap());
+    assert_eq!(
+        Some(2.5),
+        client.zscore("zset", "two".into()).await.unwrap()
+    );
+    client.select(1).await.unwrap();
+    assert_eq!(Some("db".into()), client.get("other").await.unwrap());
+
+    drop(client);
+    server.stop().await;
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Keys written since the last `SAVE` are saved when the server shuts down.
+#[tokio::test]
+async fn save_on_shutdown() {
+    let path = dump_path("shutdown");
+
+    let server = Server::start(&path).await;
+    let mut client = Client::connect(server.addr).await.unwrap();
+    client.set("hello", "world".into()).await.unwrap();
+    drop(client);
+    server.stop().await;
+
+    let server = Server::start(&path).await;
+    let mut client = Client::connect(server.addr).await.unwrap();
+    assert_eq!(Some("world".into()), client.get("hello").await.unwrap());
+
+    drop(client);
+    server.stop().await;
+    let _ = std::fs::remove_file(&path);
+}
+
+/// `SAVE` fails when no dump file is configured.
+#[tokio::test]
+async fn save_without_dbfile() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { server::run(listener, tokio::signal::ctrl_c()).await });
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let err = client.save().await.unwrap_err();
+    assert_eq!("ERR no dbfile configured", err.to_string());
+}
+
+/// The server does not start if the dump file was written in another version
+/// of the format, leaving the file untouched.
+#[tokio::test]
+async fn refuse_unknown_version() {
+    let path = dump_path("version");
+    let data = b"MINIREDIS\x00\x03\xff";
+    std::fs::write(&path, data).unwrap();
+
+    let server = Server::start(&path).await;
+
+    // The server returns without waiting for the shutdown signal.
+    server.handle.await.unwrap();
+    assert_eq!(&data[..], &std::fs::read(&path).unwrap()[..]);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Returns a path for the dump file of the test `name`, removing any file
+/// left by a previous run.
+fn dump_path(name: &str) -> PathBuf {
+    let file = format!("mini-redis-{}-{}.dump", std::process::id(), name);
+    let path = std::env::temp_dir().join(file);
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+impl Server {
+    async fn start(dbfile: &Path) -> Server {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let config = Config {
+            dbfile: Some(dbfile.to_path_buf()),
+            ..Config::default()
+        };
+
+        let (shutdown, rx) = oneshot::channel();
+        let handle =
+            tokio::spawn(async move { server::run_with_config(listener, config, rx).await });
+
+        Server {
+            addr,
+            shutdown,
+            handle,
+        }
+    }
+
+    /// Shut the server down, waiting for every connection to be closed and
+    /// the dump file to be written.
+    async fn stop(self) {
+        self.shutdown.send(()).unwrap();
+        self.handle.await.unwrap();
+    }
+}
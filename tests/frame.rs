@@ -0,0 +1,68 @@
+use mini_redis::{Connection, Frame};
+use std::io::Cursor;
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream};
+
+/// A null array, `*-1`, is decoded as `NullArray`, distinct from both the null
+/// bulk string and the empty array.
+#[test]
+fn parse_null_array() {
+    assert!(matches!(parse(b"*-1\r\n"), Frame::NullArray));
+    assert!(matches!(parse(b"$-1\r\n"), Frame::Null));
+
+    match parse(b"*0\r\n") {
+        Frame::Array(parts) => assert!(parts.is_empty()),
+        frame => panic!("unexpected frame: {:?}", frame),
+    }
+
+    match parse(b"*2\r\n*-1\r\n:1\r\n") {
+        Frame::Array(parts) => {
+            assert!(matches!(parts[..], [Frame::NullArray, Frame::Integer(1)]));
+        }
+        frame => panic!("unexpected frame: {:?}", frame),
+    }
+}
+
+/// Integers are signed 64 bit values.
+#[test]
+fn parse_signed_integer() {
+    assert!(matches!(parse(b":-1\r\n"), Frame::Integer(-1)));
+    assert!(matches!(
+        parse(b":-9223372036854775808\r\n"),
+        Frame::Integer(i64::MIN)
+    ));
+    assert!(Frame::check(&mut Cursor::new(&b":9223372036854775808\r\n"[..])).is_err());
+}
+
+/// `NullArray` is encoded as `*-1`, both on its own and nested in an array.
+#[tokio::test]
+async fn write_null_array() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let (socket, _) = listener.accept().await.unwrap();
+    let mut connection = Connection::new(socket);
+
+    connection.write_frame(&Frame::NullArray).await.unwrap();
+    connection
+        .write_frame(&Frame::Array(vec![Frame::NullArray, Frame::Null]))
+        .await
+        .unwrap();
+
+    let expected = b"*-1\r\n*2\r\n*-1\r\n$-1\r\n";
+    let mut response = [0; 19];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(expected, &response);
+}
+
+/// Checks and parses a complete frame, which must span all of `src`.
+fn parse(src: &[u8]) -> Frame {
+    Frame::check(&mut Cursor::new(src)).unwrap();
+
+    let mut cursor = Cursor::new(src);
+    let frame = Frame::parse(&mut cursor).unwrap();
+    assert_eq!(src.len() as u64, cursor.position());
+
+    frame
+}
@@ -0,0 +1,208 @@
+use mini_redis::{clients::Pool, server, Config};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio::time;
+
+/// Many tasks share a pool with fewer connections than tasks. Each request
+/// waits for a connection to be returned to the pool, and every task gets its
+/// response.
+#[tokio::test]
+async fn pool_concurrent_gets() {
+    let (addr, _) = start_server().await;
+
+    let pool = Pool::connect(addr, 4).await.unwrap();
+    pool.get().await.set("hello", "world".into()).await.unwrap();
+
+    let mut tasks = vec![];
+    for _ in 0..100 {
+        let pool = pool.clone();
+        tasks.push(tokio::spawn(async move {
+            let mut client = pool.get().await;
+            client.get("hello").await.unwrap()
+        }));
+    }
+
+    for task in tasks {
+        let value = task.await.unwrap().unwrap();
+        assert_eq!(b"world", &value[..]);
+    }
+}
+
+/// A connection which was closed under a command is discarded rather than
+/// reused, and replaced by a new connection on the next command.
+#[tokio::test]
+async fn pool_replaces_failed_connection() {
+    let (addr, _) = start_server().await;
+    let proxy = Proxy::start(addr).await;
+
+    let pool = Pool::connect(proxy.addr, 1).await.unwrap();
+
+    let mut client = pool.get().await;
+    client.set("key", "value".into()).await.unwrap();
+
+    proxy.kill_connections();
+    assert!(client.get("key").await.is_err());
+
+    assert_eq!(Some("value".into()), client.get("key").await.unwrap());
+    drop(client);
+
+    let mut client = pool.get().await;
+    assert_eq!(Some("value".into()), client.get("key").await.unwrap());
+    assert_eq!(2, proxy.connections());
+}
+
+/// An error reply from the server leaves the connection usable, so it is kept.
+#[tokio::test]
+async fn pool_keeps_connection_on_error_reply() {
+    let config = Config {
+        strict_keys: true,
+        ..Config::default()
+    };
+    let (addr, _) = start_server_with_config(config).await;
+    let proxy = Proxy::start(addr).await;
+
+    let pool = Pool::connect(proxy.addr, 1).await.unwrap();
+
+    let mut client = pool.get().await;
+    client.set("key", "value".into()).await.unwrap();
+    assert!(client.set("", "value".into()).await.is_err());
+    assert_eq!(Some("value".into()), client.get("key").await.unwrap());
+    drop(client);
+
+    let mut client = pool.get().await;
+    assert_eq!(Some("value".into()), client.get("key").await.unwrap());
+    assert_eq!(1, proxy.connections());
+}
+
+/// With a heartbeat, idle connections are checked periodically. Live ones are
+/// kept, and ones closed while idle are replaced before they are handed out.
+/// Connections handed out are left alone.
+#[tokio::test]
+async fn pool_heartbeat_replaces_idle_connection() {
+    let (addr, _) = start_server().await;
+    let proxy = Proxy::start(addr).await;
+
+    let interval = Duration::from_millis(100);
+    let pool = Pool::connect_with_heartbeat(proxy.addr, 1, interval)
+        .await
+        .unwrap();
+    pool.get().await.set("key", "value".into()).await.unwrap();
+
+    time::sleep(interval * 3).await;
+    let mut client = pool.get().await;
+    assert_eq!(Some("value".into()), client.get("key").await.unwrap());
+    drop(client);
+    assert_eq!(1, proxy.connections());
+
+    proxy.kill_connections();
+    time::sleep(interval * 3).await;
+
+    // The first command after idling succeeds.
+    let mut client = pool.get().await;
+    assert_eq!(Some("value".into()), client.get("key").await.unwrap());
+    assert_eq!(2, proxy.connections());
+
+    // A connection handed out is not checked, nor replaced.
+    time::sleep(interval * 3).await;
+    assert_eq!(Some("value".into()), client.get("key").await.unwrap());
+    assert_eq!(2, proxy.connections());
+}
+
+/// A connection left on another database by its borrower is handed to the
+/// next borrower on database 0.
+#[tokio::test]
+async fn pool_resets_selected_database() {
+    let (addr, _) = start_server().await;
+    let proxy = Proxy::start(addr).await;
+
+    let pool = Pool::connect(proxy.addr, 1).await.unwrap();
+
+    let mut client = pool.get().await;
+    client.select(5).await.unwrap();
+    client.set("key", "five".into()).await.unwrap();
+    drop(client);
+
+    let mut client = pool.get().await;
+    assert_eq!(None, client.get("key").await.unwrap());
+    client.set("key", "zero".into()).await.unwrap();
+    drop(client);
+
+    let mut client = pool.get().await;
+    client.select(5).await.unwrap();
+    assert_eq!(Some("five".into()), client.get("key").await.unwrap());
+
+    // The database is selected again on a replaced connection.
+    proxy.kill_connections();
+    assert!(client.get("key").await.is_err());
+    assert_eq!(Some("five".into()), client.get("key").await.unwrap());
+    drop(client);
+
+    let mut client = pool.get().await;
+    assert_eq!(Some("zero".into()), client.get("key").await.unwrap());
+    assert_eq!(2, proxy.connections());
+}
+
+/// Forwards connections to a server, and closes them on request.
+struct Proxy {
+    addr: SocketAddr,
+
+    /// Tasks forwarding each connection accepted so far.
+    tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+impl Proxy {
+    async fn start(server: SocketAddr) -> Proxy {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let tasks = Arc::new(Mutex::new(vec![]));
+
+        let accepted = tasks.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut inbound, _) = listener.accept().await.unwrap();
+                let task = tokio::spawn(async move {
+                    let mut outbound = TcpStream::connect(server).await.unwrap();
+                    let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+                });
+                accepted.lock().unwrap().push(task);
+            }
+        });
+
+        Proxy { addr, tasks }
+    }
+
+    /// Number of connections accepted so far.
+    fn connections(&self) -> usize {
+        self.tasks.lock().unwrap().len()
+    }
+
+    /// Close every connection accepted so far, on both sides.
+    fn kill_connections(&self) {
+        for task in self.tasks.lock().unwrap().iter() {
+            task.abort();
+        }
+    }
+}
+
+async fn start_server() -> (SocketAddr, JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let handle = tokio::spawn(async move { server::run(listener, tokio::signal::ctrl_c()).await });
+
+    (addr, handle)
+}
+
+async fn start_server_with_config(config: Config) -> (SocketAddr, JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let handle = tokio::spawn(async move {
+        server::run_with_config(listener, config, tokio::signal::ctrl_c()).await
+    });
+
+    (addr, handle)
+}
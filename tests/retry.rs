@@ -0,0 +1,153 @@
+use mini_redis::{
+    clients::{Client, RetryConfig},
+    server, Config,
+};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// A server which can be shut down, closing every connection, and started
+/// again on the same address.
+struct Server {
+    addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+fn retry_config() -> RetryConfig {
+    RetryConfig {
+        max_retries: 10,
+        base_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(100),
+    }
+}
+
+/// Commands sent after the server restarted succeed on a new connection.
+#[tokio::test]
+async fn retry_reconnects_after_restart() {
+    let server = Server::start("127.0.0.1:0").await;
+    let addr = server.addr;
+
+    let mut client = Client::connect_with_retry(addr, retry_config())
+        .await
+        .unwrap();
+    client.set("hello", "world".into()).await.unwrap();
+
+    server.stop().await;
+    let _server = Server::start(addr).await;
+
+    // The restarted server starts with an empty database.
+    assert_eq!(None, client.get("hello").await.unwrap());
+    client.set("hello", "again".into()).await.unwrap();
+    assert_eq!(Some("again".into()), client.get("hello").await.unwrap());
+}
+
+/// Without retries, the error of the lost connection is returned.
+#[tokio::test]
+async fn retry_disabled() {
+    let server = Server::start("127.0.0.1:0").await;
+    let addr = server.addr;
+
+    let config = RetryConfig {
+        max_retries: 0,
+        ..retry_config()
+    };
+    let mut client = Client::connect_with_retry(addr, config).await.unwrap();
+
+    server.stop().await;
+    let _server = Server::start(addr).await;
+
+    assert!(client.get("hello").await.is_err());
+}
+
+/// A subscriber subscribes to its channels again after the server restarted.
+#[tokio::test]
+async fn retry_resubscribes_after_restart() {
+    let server = Server::start("127.0.0.1:0").await;
+    let addr = server.addr;
+
+    let client = Client::connect_with_retry(addr, retry_config())
+        .await
+        .unwrap();
+    let mut subscriber = client
+        .subscribe(vec!["hello".into(), "world".into()])
+        .await
+        .unwrap();
+
+    server.stop().await;
+    let _server = Server::start(addr).await;
+
+    let message = tokio::spawn(async move {
+        let message = subscriber.next_message().await.unwrap().unwrap();
+        (message, subscriber)
+    });
+
+    // Publish until the subscriber is subscribed again.
+    let mut client = Client::connect(addr).await.unwrap();
+    while client.publish("world", "back".into()).await.unwrap() == 0 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let (message, subscriber) = message.await.unwrap();
+    assert_eq!("world", &message.channel);
+    assert_eq!(b"back", &message.content[..]);
+    assert_eq!(&["hello", "world"], subscriber.get_subscribed());
+}
+
+/// The password and database of the lost connection are restored on the new
+/// one before retrying the command.
+#[tokio::test]
+async fn retry_restores_auth_and_database() {
+    let config = Config {
+        requirepass: Some("secret".to_string()),
+        ..Config::default()
+    };
+    let server = Server::start_with_config("127.0.0.1:0", config.clone()).await;
+    let addr = server.addr;
+
+    let mut client = Client::connect_with_retry(addr, retry_config())
+        .await
+        .unwrap();
+    client.auth("secret").await.unwrap();
+    client.select(1).await.unwrap();
+
+    server.stop().await;
+    let _server = Server::start_with_config(addr, config).await;
+
+    client.set("hello", "world".into()).await.unwrap();
+
+    let mut other = Client::connect(addr).await.unwrap();
+    other.auth("secret").await.unwrap();
+    assert_eq!(None, other.get("hello").await.unwrap());
+    other.select(1).await.unwrap();
+    assert_eq!(Some("world".into()), other.get("hello").await.unwrap());
+}
+
+impl Server {
+    async fn start(addr: impl tokio::net::ToSocketAddrs) -> Server {
+        Server::start_with_config(addr, Config::default()).await
+    }
+
+    async fn start_with_config(addr: impl tokio::net::ToSocketAddrs, config: Config) -> Server {
+        let listener = TcpListener::bind(addr).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let (shutdown, rx) = oneshot::channel();
+        let handle =
+            tokio::spawn(async move { server::run_with_config(listener, config, rx).await });
+
+        Server {
+            addr,
+            shutdown,
+            handle,
+        }
+    }
+
+    /// Shut the server down, waiting for every connection to be closed.
+    async fn stop(self) {
+        self.shutdown.send(()).unwrap();
+        self.handle.await.unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use mini_redis::server;
+use mini_redis::{server, Config};
 
 use std::net::SocketAddr;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -416,6 +416,594 @@ async fn hset_server() {
     assert_eq!(b"+OK\r\n", &response);
 }
 
+// A client announcing an enormous array is rejected as soon as the header is
+// read, without the server waiting for (or allocating room for) the elements.
+#[tokio::test]
+async fn reject_oversized_multibulk() {
+    let addr = start_server().await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    stream.write_all(b"*4294967296\r\n").await.unwrap();
+
+    let mut response = [0; 47];
+
+    time::timeout(Duration::from_secs(1), stream.read_exact(&mut response))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        &b"-ERR Protocol error: invalid multibulk length\r\n"[..],
+        &response[..]
+    );
+
+    // The server closes the connection
+    assert_eq!(0, stream.read(&mut response).await.unwrap());
+}
+
+// The array length limit is configurable
+#[tokio::test]
+async fn configured_multibulk_limit() {
+    let config = Config {
+        max_multibulk_len: 2,
+        ..Config::default()
+    };
+    let addr = start_server_with_config(config).await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    // A two element request is accepted
+    stream
+        .write_all(b"*2\r\n$4\r\nPING\r\n$5\r\nhello\r\n")
+        .await
+        .unwrap();
+
+    let mut response = [0; 11];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(b"$5\r\nhello\r\n", &response);
+
+    // A three element request is not
+    stream
+        .write_all(b"*3\r\n$3\r\nSET\r\n$5\r\nhello\r\n$5\r\nworld\r\n")
+        .await
+        .unwrap();
+
+    let mut response = [0; 47];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(
+        &b"-ERR Protocol error: invalid multibulk length\r\n"[..],
+        &response[..]
+    );
+}
+
+// In strict mode, write commands reject an empty key. Other keys are still
+// accepted on the same connection.
+#[tokio::test]
+async fn strict_keys_reject_empty_key() {
+    let config = Config {
+        strict_keys: true,
+        ..Config::default()
+    };
+    let addr = start_server_with_config(config).await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    let requests: [&[u8]; 2] = [
+        b"*3\r\n$3\r\nSET\r\n$0\r\n\r\n$5\r\nworld\r\n",
+        b"*4\r\n$4\r\nHSET\r\n$0\r\n\r\n$5\r\nfield\r\n$5\r\nworld\r\n",
+    ];
+
+    for request in requests {
+        stream.write_all(request).await.unwrap();
+
+        let mut response = [0; 31];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(b"-ERR empty key is not allowed\r\n", &response);
+    }
+
+    // Nothing was stored
+    stream
+        .write_all(b"*2\r\n$3\r\nGET\r\n$0\r\n\r\n")
+        .await
+        .unwrap();
+
+    let mut response = [0; 5];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(b"$-1\r\n", &response);
+
+    stream
+        .write_all(b"*3\r\n$3\r\nSET\r\n$1\r\n \r\n$5\r\nworld\r\n")
+        .await
+        .unwrap();
+
+    let mut response = [0; 5];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(b"+OK\r\n", &response);
+}
+
+// In strict mode, every command storing a value rejects an empty key, whatever
+// the type of the value.
+#[tokio::test]
+async fn strict_keys_reject_empty_key_of_any_type() {
+    let config = Config {
+        strict_keys: true,
+        ..Config::default()
+    };
+    let addr = start_server_with_config(config).await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    let requests: [&[u8]; 16] = [
+        b"*2\r\n$4\r\nINCR\r\n$0\r\n\r\n",
+        b"*2\r\n$4\r\nDECR\r\n$0\r\n\r\n",
+        b"*3\r\n$6\r\nINCRBY\r\n$0\r\n\r\n$1\r\n2\r\n",
+        b"*3\r\n$6\r\nDECRBY\r\n$0\r\n\r\n$1\r\n2\r\n",
+        b"*3\r\n$6\r\nAPPEND\r\n$0\r\n\r\n$1\r\na\r\n",
+        b"*3\r\n$6\r\nGETSET\r\n$0\r\n\r\n$1\r\na\r\n",
+        b"*3\r\n$5\r\nSETNX\r\n$0\r\n\r\n$1\r\na\r\n",
+        b"*3\r\n$4\r\nMSET\r\n$0\r\n\r\n$1\r\na\r\n",
+        b"*4\r\n$7\r\nHINCRBY\r\n$0\r\n\r\n$1\r\nf\r\n$1\r\n1\r\n",
+        b"*4\r\n$6\r\nHSETNX\r\n$0\r\n\r\n$1\r\nf\r\n$1\r\na\r\n",
+        b"*3\r\n$5\r\nLPUSH\r\n$0\r\n\r\n$1\r\na\r\n",
+        b"*3\r\n$5\r\nRPUSH\r\n$0\r\n\r\n$1\r\na\r\n",
+        b"*3\r\n$4\r\nSADD\r\n$0\r\n\r\n$1\r\na\r\n",
+        b"*4\r\n$4\r\nZADD\r\n$0\r\n\r\n$1\r\n1\r\n$1\r\na\r\n",
+        b"*4\r\n$7\r\nZINCRBY\r\n$0\r\n\r\n$1\r\n1\r\n$1\r\na\r\n",
+        b"*4\r\n$4\r\nHSET\r\n$0\r\n\r\n$1\r\nf\r\n$1\r\na\r\n",
+    ];
+
+    for request in requests {
+        stream.write_all(request).await.unwrap();
+
+        let mut response = [0; 31];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(
+            b"-ERR empty key is not allowed\r\n",
+            &response,
+            "{}",
+            String::from_utf8_lossy(request)
+        );
+    }
+
+    // Nothing was stored
+    stream.write_all(b"*1\r\n$6\r\nDBSIZE\r\n").await.unwrap();
+
+    let mut response = [0; 4];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(b":0\r\n", &response);
+}
+
+// By default, an empty key is a valid key.
+#[tokio::test]
+async fn empty_key_accepted_by_default() {
+    let addr = start_server().await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    stream
+        .write_all(b"*3\r\n$3\r\nSET\r\n$0\r\n\r\n$5\r\nworld\r\n")
+        .await
+        .unwrap();
+
+    let mut response = [0; 5];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(b"+OK\r\n", &response);
+
+    stream
+        .write_all(b"*2\r\n$3\r\nGET\r\n$0\r\n\r\n")
+        .await
+        .unwrap();
+
+    let mut response = [0; 11];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(b"$5\r\nworld\r\n", &response);
+}
+
+// DEBUG QUIT-ALL closes every other connection, including subscribers, and
+// replies with the number of connections closed. The issuing connection
+// remains usable.
+#[tokio::test]
+async fn debug_quit_all() {
+    let addr = start_server().await;
+
+    let mut others = vec![];
+
+    for _ in 0..3 {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        // Wait for the server to accept the connection
+        stream.write_all(b"*1\r\n$4\r\nPING\r\n").await.unwrap();
+
+        let mut response = [0; 7];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(b"+PONG\r\n", &response);
+
+        others.push(stream);
+    }
+
+    let mut subscriber = TcpStream::connect(addr).await.unwrap();
+    subscriber
+        .write_all(b"*2\r\n$9\r\nSUBSCRIBE\r\n$5\r\nhello\r\n")
+        .await
+        .unwrap();
+
+    let mut response = [0; 34];
+    subscriber.read_exact(&mut response).await.unwrap();
+    others.push(subscriber);
+
+    let mut issuer = TcpStream::connect(addr).await.unwrap();
+    issuer
+        .write_all(b"*2\r\n$5\r\nDEBUG\r\n$8\r\nQUIT-ALL\r\n")
+        .await
+        .unwrap();
+
+    let mut response = [0; 4];
+    issuer.read_exact(&mut response).await.unwrap();
+    assert_eq!(b":4\r\n", &response);
+
+    // The other connections are closed by the server
+    for mut stream in others {
+        let mut response = [0; 1];
+        let n = time::timeout(Duration::from_secs(1), stream.read(&mut response))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(0, n);
+    }
+
+    // The issuer is still connected
+    issuer.write_all(b"*1\r\n$4\r\nPING\r\n").await.unwrap();
+
+    let mut response = [0; 7];
+    issuer.read_exact(&mut response).await.unwrap();
+    assert_eq!(b"+PONG\r\n", &response);
+}
+
+// HGETALL on a missing key replies with an empty array, not a null.
+#[tokio::test]
+async fn hgetall_missing_key() {
+    let addr = start_server().await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    stream
+        .write_all(b"*2\r\n$7\r\nHGETALL\r\n$7\r\nmissing\r\n")
+        .await
+        .unwrap();
+
+    let mut response = [0; 4];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(b"*0\r\n", &response);
+}
+
+// HGETALL returns fields in the order they were first set. Updating an
+// existing field does not move it.
+#[tokio::test]
+async fn hgetall_insertion_order() {
+    let addr = start_server().await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    for field in ["zebra", "apple", "mango", "kiwi", "apple"] {
+        let request = format!(
+            "*4\r\n$4\r\nHSET\r\n$4\r\nhash\r\n${}\r\n{}\r\n$1\r\n{}\r\n",
+            field.len(),
+            field,
+            &field[..1]
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = [0; 5];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(b"+OK\r\n", &response);
+    }
+
+    stream
+        .write_all(b"*2\r\n$7\r\nHGETALL\r\n$4\r\nhash\r\n")
+        .await
+        .unwrap();
+
+    let expected = b"*8\r\n\
+        $5\r\nzebra\r\n$1\r\nz\r\n\
+        $5\r\napple\r\n$1\r\na\r\n\
+        $5\r\nmango\r\n$1\r\nm\r\n\
+        $4\r\nkiwi\r\n$1\r\nk\r\n";
+
+    let mut response = vec![0; expected.len()];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(&expected[..], &response[..]);
+}
+
+// Replies stay RESP2 until HELLO 3 switches the connection to RESP3, after
+// which maps, doubles and nulls use their RESP3 encoding.
+#[tokio::test]
+async fn hello_switches_to_resp3() {
+    let addr = start_server().await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    let setup: &[(&[u8], &[u8])] = &[
+        (
+            b"*4\r\n$4\r\nHSET\r\n$4\r\nhash\r\n$1\r\nf\r\n$1\r\nv\r\n",
+            b"+OK\r\n",
+        ),
+        (
+            b"*4\r\n$4\r\nZADD\r\n$4\r\nzset\r\n$3\r\n1.5\r\n$1\r\nm\r\n",
+            b":1\r\n",
+        ),
+        // RESP2 replies before HELLO
+        (
+            b"*2\r\n$7\r\nHGETALL\r\n$4\r\nhash\r\n",
+            b"*2\r\n$1\r\nf\r\n$1\r\nv\r\n",
+        ),
+        (
+            b"*3\r\n$6\r\nZSCORE\r\n$4\r\nzset\r\n$1\r\nm\r\n",
+            b"$3\r\n1.5\r\n",
+        ),
+        // Unsupported versions are rejected and leave the protocol unchanged
+        (
+            b"*2\r\n$5\r\nHELLO\r\n$1\r\n4\r\n",
+            b"-NOPROTO unsupported protocol version\r\n",
+        ),
+        (b"*2\r\n$3\r\nGET\r\n$7\r\nmissing\r\n", b"$-1\r\n"),
+    ];
+
+    for (request, expected) in setup {
+        stream.write_all(request).await.unwrap();
+
+        let mut response = vec![0; expected.len()];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(*expected, &response[..]);
+    }
+
+    stream
+        .write_all(b"*2\r\n$5\r\nHELLO\r\n$1\r\n3\r\n")
+        .await
+        .unwrap();
+
+    let version = env!("CARGO_PKG_VERSION");
+    let expected = format!(
+        "%6\r\n\
+        $6\r\nserver\r\n$10\r\nmini-redis\r\n\
+        $7\r\nversion\r\n${}\r\n{}\r\n\
+        $5\r\nproto\r\n:3\r\n\
+        $2\r\nid\r\n:",
+        version.len(),
+        version
+    );
+
+    let mut response = vec![0; expected.len()];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(expected.as_bytes(), &response[..]);
+
+    // Skip the connection id
+    let mut byte = [0; 1];
+    while byte != *b"\n" {
+        stream.read_exact(&mut byte).await.unwrap();
+    }
+
+    let expected = b"$4\r\nmode\r\n$10\r\nstandalone\r\n\
+        $4\r\nrole\r\n$6\r\nmaster\r\n";
+
+    let mut response = vec![0; expected.len()];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(&expected[..], &response[..]);
+
+    let resp3: &[(&[u8], &[u8])] = &[
+        (
+            b"*2\r\n$7\r\nHGETALL\r\n$4\r\nhash\r\n",
+            b"%1\r\n$1\r\nf\r\n$1\r\nv\r\n",
+        ),
+        (
+            b"*3\r\n$6\r\nZSCORE\r\n$4\r\nzset\r\n$1\r\nm\r\n",
+            b",1.5\r\n",
+        ),
+        (b"*2\r\n$3\r\nGET\r\n$7\r\nmissing\r\n", b"_\r\n"),
+    ];
+
+    for (request, expected) in resp3 {
+        stream.write_all(request).await.unwrap();
+
+        let mut response = vec![0; expected.len()];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(*expected, &response[..]);
+    }
+}
+
+// With `protocol_required`, commands are rejected until HELLO 3 switches the
+// connection to RESP3, then served as usual.
+#[tokio::test]
+async fn protocol_required() {
+    let config = Config {
+        protocol_required: true,
+        ..Config::default()
+    };
+    let addr = start_server_with_config(config).await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    let noproto: &[u8] = b"-NOPROTO unsupported protocol version\r\n";
+    let requests: &[&[u8]] = &[
+        // Served by the fast path otherwise
+        b"*3\r\n$3\r\nSET\r\n$5\r\nhello\r\n$5\r\nworld\r\n",
+        b"*2\r\n$3\r\nGET\r\n$5\r\nhello\r\n",
+        b"*1\r\n$4\r\nPING\r\n",
+        b"*1\r\n$5\r\nMULTI\r\n",
+        b"*2\r\n$5\r\nHELLO\r\n$1\r\n2\r\n",
+    ];
+
+    for request in requests {
+        stream.write_all(request).await.unwrap();
+
+        let mut response = vec![0; noproto.len()];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(noproto, &response[..]);
+    }
+
+    stream
+        .write_all(b"*2\r\n$5\r\nHELLO\r\n$1\r\n3\r\n")
+        .await
+        .unwrap();
+
+    // Skip the server description, ending with the role.
+    let mut response = vec![];
+    while !response.ends_with(b"$6\r\nmaster\r\n") {
+        let mut byte = [0; 1];
+        stream.read_exact(&mut byte).await.unwrap();
+        response.push(byte[0]);
+    }
+    assert!(response.starts_with(b"%6\r\n"));
+
+    let resp3: &[(&[u8], &[u8])] = &[
+        (
+            b"*3\r\n$3\r\nSET\r\n$5\r\nhello\r\n$5\r\nworld\r\n",
+            b"+OK\r\n",
+        ),
+        (b"*2\r\n$3\r\nGET\r\n$5\r\nhello\r\n", b"$5\r\nworld\r\n"),
+        (b"*2\r\n$3\r\nGET\r\n$7\r\nmissing\r\n", b"_\r\n"),
+        (b"*2\r\n$5\r\nHELLO\r\n$1\r\n2\r\n", noproto),
+        (b"*1\r\n$4\r\nPING\r\n", b"+PONG\r\n"),
+    ];
+
+    for (request, expected) in resp3 {
+        stream.write_all(request).await.unwrap();
+
+        let mut response = vec![0; expected.len()];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(*expected, &response[..]);
+    }
+}
+
+// The GET/SET fast path must produce exactly the same bytes as the generic
+// command path, including for requests it hands off to the generic path.
+#[tokio::test]
+async fn fast_path_matches_generic_path() {
+    let requests: &[&[u8]] = &[
+        // GET a missing key
+        b"*2\r\n$3\r\nGET\r\n$5\r\nhello\r\n",
+        // SET and GET with mixed case command names
+        b"*3\r\n$3\r\nsEt\r\n$5\r\nhello\r\n$5\r\nworld\r\n",
+        b"*2\r\n$3\r\ngEt\r\n$5\r\nhello\r\n",
+        // Simple string arguments
+        b"*3\r\n+SET\r\n+foo\r\n+bar\r\n",
+        b"*2\r\n+GET\r\n+foo\r\n",
+        // Binary value
+        b"*3\r\n$3\r\nSET\r\n$3\r\nbin\r\n$2\r\n\xff\x00\r\n",
+        b"*2\r\n$3\r\nGET\r\n$3\r\nbin\r\n",
+        // SET with an option is left to the generic path
+        b"*5\r\n$3\r\nSET\r\n$5\r\nhello\r\n$5\r\nagain\r\n$2\r\nPX\r\n:100000\r\n",
+        b"*2\r\n$3\r\nGET\r\n$5\r\nhello\r\n",
+        // Too many arguments is a protocol error which closes the connection
+        b"*3\r\n$3\r\nGET\r\n$5\r\nhello\r\n$5\r\nextra\r\n",
+        b"*2\r\n$3\r\nGET\r\n$5\r\nhello\r\n",
+    ];
+
+    let mut outputs = vec![];
+
+    for command_fast_path in [true, false] {
+        let config = Config {
+            command_fast_path,
+            ..Config::default()
+        };
+        let addr = start_server_with_config(config).await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        for request in requests {
+            stream.write_all(request).await.unwrap();
+        }
+
+        let mut response = vec![];
+        stream.read_to_end(&mut response).await.unwrap();
+        outputs.push(response);
+    }
+
+    assert_eq!(
+        &b"$-1\r\n+OK\r\n$5\r\nworld\r\n+OK\r\n$3\r\nbar\r\n\
+           +OK\r\n$2\r\n\xff\x00\r\n+OK\r\n$5\r\nagain\r\n"[..],
+        &outputs[0][..]
+    );
+    assert_eq!(outputs[0], outputs[1]);
+}
+
+// A large burst of pipelined requests is answered in order. The server only
+// reads from the socket once every buffered request is processed, so the
+// writer is held back by TCP flow control instead of filling a buffer. Each
+// time this happens is counted in `INFO stats`.
+#[tokio::test]
+async fn pipelined_burst() {
+    const REQUESTS: usize = 100_000;
+
+    let addr = start_server().await;
+
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let (mut rd, mut wr) = stream.into_split();
+
+    // The replies must be read while the requests are written, otherwise both
+    // sides end up blocked on full socket buffers.
+    let writer = tokio::spawn(async move {
+        let burst = b"*1\r\n$4\r\nPING\r\n".repeat(REQUESTS);
+        wr.write_all(&burst).await.unwrap();
+        wr.shutdown().await.unwrap();
+    });
+
+    let mut response = vec![];
+    rd.read_to_end(&mut response).await.unwrap();
+    writer.await.unwrap();
+
+    assert_eq!(b"+PONG\r\n".repeat(REQUESTS), response);
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"*2\r\n$4\r\nINFO\r\n$5\r\nstats\r\n")
+        .await
+        .unwrap();
+
+    // Read until the end of the counter line.
+    let mut info = vec![];
+    while !info.ends_with(b"\r\n\r\n") {
+        assert_ne!(0, stream.read_buf(&mut info).await.unwrap());
+    }
+
+    let info = std::str::from_utf8(&info).unwrap();
+    let events: u64 = info
+        .lines()
+        .find_map(|line| line.strip_prefix("pipeline_backpressure_events:"))
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(events > 0);
+}
+
+// MSET with a key missing its value is a protocol error, nothing is set.
+#[tokio::test]
+async fn mset_odd_arguments() {
+    let addr = start_server().await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    stream
+        .write_all(b"*4\r\n$4\r\nMSET\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n")
+        .await
+        .unwrap();
+
+    // The connection is closed without a response.
+    let mut response = vec![];
+    stream.read_to_end(&mut response).await.unwrap();
+    assert!(response.is_empty());
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    stream
+        .write_all(b"*2\r\n$3\r\nGET\r\n$1\r\na\r\n")
+        .await
+        .unwrap();
+
+    let mut response = [0; 5];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(b"$-1\r\n", &response);
+}
+
 async fn start_server() -> SocketAddr {
     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
     let addr = listener.local_addr().unwrap();
@@ -424,3 +1012,14 @@ async fn start_server() -> SocketAddr {
 
     addr
 }
+
+async fn start_server_with_config(config: Config) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        server::run_with_config(listener, config, tokio::signal::ctrl_c()).await
+    });
+
+    addr
+}